@@ -124,6 +124,7 @@ impl Display<&'static str> for Terminal {
     fn display(&self) -> &'static str {
         match *self {
             Terminal::Svg => "svg dynamic",
+            Terminal::Png => "pngcairo",
         }
     }
 }
@@ -5,7 +5,7 @@ use std::iter::IntoIterator;
 
 use data::Matrix;
 use traits::{self, Data, Set};
-use {Axes, Color, Default, Display, Figure, Label, Opacity, Plot, Script};
+use {Axes, Color, Default, Display, FillPattern, Figure, Label, Opacity, Plot, Script};
 
 /// Properties common to filled curve plots
 pub struct Properties {
@@ -13,6 +13,7 @@ pub struct Properties {
     color: Option<Color>,
     label: Option<Cow<'static, str>>,
     opacity: Option<f64>,
+    pattern: Option<u8>,
 }
 
 impl Default for Properties {
@@ -22,6 +23,7 @@ impl Default for Properties {
             color: None,
             label: None,
             opacity: None,
+            pattern: None,
         }
     }
 }
@@ -37,7 +39,9 @@ impl Script for Properties {
 
         script.push_str("fillstyle ");
 
-        if let Some(opacity) = self.opacity {
+        if let Some(pattern) = self.pattern {
+            script.push_str(&format!("pattern {} ", pattern))
+        } else if let Some(opacity) = self.opacity {
             script.push_str(&format!("solid {} ", opacity))
         }
 
@@ -100,6 +104,16 @@ impl Set<Opacity> for Properties {
     }
 }
 
+impl Set<FillPattern> for Properties {
+    /// Fills the area with a pattern instead of a solid color
+    ///
+    /// **Note** The pattern takes precedence over the opacity
+    fn set(&mut self, pattern: FillPattern) -> &mut Properties {
+        self.pattern = Some(pattern.0);
+        self
+    }
+}
+
 /// Fills the area between two curves
 pub struct FilledCurve<X, Y1, Y2> {
     /// X coordinate of the data points of both curves
@@ -1,16 +1,21 @@
 //! Gridline
 
-use {Axis, Default, Display, Grid, Script};
+use traits::Set;
+use {Axis, Color, Default, Display, Grid, Script};
 
 /// Gridline properties
 #[derive(Clone, Copy)]
 pub struct Properties {
+    color: Option<Color>,
     hidden: bool,
 }
 
 impl Default for Properties {
     fn default() -> Properties {
-        Properties { hidden: true }
+        Properties {
+            color: None,
+            hidden: true,
+        }
     }
 }
 
@@ -31,6 +36,17 @@ impl Properties {
     }
 }
 
+impl Set<Color> for Properties {
+    /// Changes the color of the gridlines
+    ///
+    /// **Note** gnuplot draws all the major gridlines in one color, and all the minor ones in
+    /// another, so the last color set for either kind applies to every axis
+    fn set(&mut self, color: Color) -> &mut Properties {
+        self.color = Some(color);
+        self
+    }
+}
+
 impl<'a> Script for (Axis, Grid, &'a Properties) {
     fn script(&self) -> String {
         let &(axis, grid, properties) = self;
@@ -38,9 +54,23 @@ impl<'a> Script for (Axis, Grid, &'a Properties) {
         let grid = grid.display();
 
         if properties.hidden {
-            String::new()
-        } else {
-            format!("set grid {}{}tics\n", grid, axis)
+            return String::new();
         }
+
+        // gnuplot takes the line properties of the major gridlines, then those of the minor ones
+        // after a comma
+        let color = match (grid, properties.color) {
+            ("", Some(color)) => format!(" lc rgb '{}'", color.display()),
+            (_, Some(color)) => format!(" , lc rgb '{}'", color.display()),
+            (_, None) => String::new(),
+        };
+        // gnuplot only draws the minor tics of logarithmic axes unless asked to, and the minor
+        // gridlines follow the minor tics
+        let tics = if grid == "m" {
+            format!("set m{}tics\n", axis)
+        } else {
+            String::new()
+        };
+        format!("{}set grid {}{}tics{}\n", tics, grid, axis, color)
     }
 }
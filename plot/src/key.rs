@@ -3,12 +3,14 @@
 use std::borrow::Cow;
 
 use traits::Set;
-use {Default, Display, Script, Title};
+use {Default, Display, Font, FontSize, Script, Title};
 
 /// Properties of the key
 #[derive(Clone)]
 pub struct Properties {
     boxed: bool,
+    font: Option<Cow<'static, str>>,
+    font_size: Option<f64>,
     hidden: bool,
     justification: Option<Justification>,
     order: Option<Order>,
@@ -21,6 +23,8 @@ impl Default for Properties {
     fn default() -> Properties {
         Properties {
             boxed: false,
+            font: None,
+            font_size: None,
             hidden: false,
             justification: None,
             order: None,
@@ -84,6 +88,13 @@ impl Script for Properties {
             script.push_str(&format!("title '{}' ", title))
         }
 
+        match (&self.font, self.font_size) {
+            (&Some(ref name), Some(size)) => script.push_str(&format!("font '{},{}' ", name, size)),
+            (&Some(ref name), None) => script.push_str(&format!("font '{}' ", name)),
+            (&None, Some(size)) => script.push_str(&format!("font ',{}' ", size)),
+            (&None, None) => {}
+        }
+
         if self.boxed {
             script.push_str("box ")
         }
@@ -107,6 +118,32 @@ impl Set<Boxed> for Properties {
     }
 }
 
+impl Set<Font> for Properties {
+    /// Changes the font of the entries
+    ///
+    /// **Note** The key uses the font of the figure by default
+    fn set(&mut self, font: Font) -> &mut Properties {
+        self.font = Some(font.0);
+        self
+    }
+}
+
+impl Set<FontSize> for Properties {
+    /// Changes the size of the font of the entries
+    ///
+    /// # Panics
+    ///
+    /// Panics if `size` is a non-positive value
+    fn set(&mut self, size: FontSize) -> &mut Properties {
+        let size = size.0;
+
+        assert!(size > 0.);
+
+        self.font_size = Some(size);
+        self
+    }
+}
+
 impl Set<Justification> for Properties {
     /// Changes the justification of the text of each entry
     ///
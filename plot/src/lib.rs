@@ -437,10 +437,13 @@ pub mod traits;
 pub struct Figure {
     alpha: Option<f64>,
     axes: map::axis::Map<axis::Properties>,
+    background: Option<Color>,
     box_width: Option<f64>,
     font: Option<Cow<'static, str>>,
     font_size: Option<f64>,
+    foreground: Option<Color>,
     key: Option<key::Properties>,
+    labels: Vec<(f64, f64, Cow<'static, str>)>,
     output: Cow<'static, Path>,
     plots: Vec<Plot>,
     size: Option<(usize, usize)>,
@@ -455,10 +458,13 @@ impl Figure {
         Figure {
             alpha: None,
             axes: map::axis::Map::new(),
+            background: None,
             box_width: None,
             font: None,
             font_size: None,
+            foreground: None,
             key: None,
+            labels: Vec::new(),
             output: Cow::Borrowed(Path::new("output.plot")),
             plots: Vec::new(),
             size: None,
@@ -481,6 +487,26 @@ impl Figure {
             s.push_str(&format!("set title '{}'\n", title))
         }
 
+        if let Some(color) = self.foreground {
+            let color = color.display();
+            s.push_str(&format!("set border lc rgb '{}'\n", color));
+            s.push_str(&format!("set title textcolor rgb '{}'\n", color));
+            s.push_str(&format!("set key textcolor rgb '{}'\n", color));
+            for &axis in &[Axis::BottomX, Axis::LeftY, Axis::RightY, Axis::TopX] {
+                let axis_ = axis.display();
+                s.push_str(&format!("set {}label textcolor rgb '{}'\n", axis_, color));
+                // Coloring the tics shows them, so only the tics which are shown by default or
+                // configured are colored; the configuration of the axes follows and may hide them.
+                let shown = match axis {
+                    Axis::BottomX | Axis::LeftY => true,
+                    _ => self.axes.contains_key(axis),
+                };
+                if shown {
+                    s.push_str(&format!("set {}tics textcolor rgb '{}'\n", axis_, color));
+                }
+            }
+        }
+
         for axis in self.axes.iter() {
             s.push_str(&axis.script());
         }
@@ -497,12 +523,30 @@ impl Figure {
             s.push_str(&format!("set style fill transparent solid {}\n", alpha))
         }
 
+        let (x_factor, y_factor) = scale_factor(&self.axes, Axes::BottomXLeftY);
+        for &(x, y, ref text) in &self.labels {
+            s.push_str(&format!(
+                "set label '{}' at {}, {} center offset 0, 1",
+                text,
+                x * x_factor,
+                y * y_factor
+            ));
+            if let Some(color) = self.foreground {
+                s.push_str(&format!(" textcolor rgb '{}'", color.display()));
+            }
+            s.push('\n');
+        }
+
         s.push_str(&format!("set terminal {} dashed", self.terminal.display()));
 
         if let Some((width, height)) = self.size {
             s.push_str(&format!(" size {}, {}", width, height))
         }
 
+        if let Some(color) = self.background {
+            s.push_str(&format!(" background rgb '{}'", color.display()))
+        }
+
         if let Some(ref name) = self.font {
             if let Some(size) = self.font_size {
                 s.push_str(&format!(" font '{},{}'", name, size))
@@ -561,6 +605,15 @@ impl Figure {
         buffer
     }
 
+    /// Places a text label one line above the point `(x, y)` of the bottom X and left Y axes
+    pub fn annotate<S>(&mut self, x: f64, y: f64, text: S) -> &mut Figure
+    where
+        S: Into<Cow<'static, str>>,
+    {
+        self.labels.push((x, y, text.into()));
+        self
+    }
+
     /// Spawns a drawing child process
     ///
     /// NOTE: stderr, stdin, and stdout are piped
@@ -634,6 +687,17 @@ impl Configure<Key> for Figure {
     }
 }
 
+impl Set<Background> for Figure {
+    /// Changes the background color
+    ///
+    /// **Note** By default, the background is left to the terminal. The SVG terminal doesn't draw
+    /// a background, so the figure is transparent
+    fn set(&mut self, background: Background) -> &mut Figure {
+        self.background = Some(background.0);
+        self
+    }
+}
+
 impl Set<BoxWidth> for Figure {
     /// Changes the box width of all the box related plots (bars, candlesticks, etc)
     ///
@@ -676,6 +740,17 @@ impl Set<FontSize> for Figure {
     }
 }
 
+impl Set<Foreground> for Figure {
+    /// Changes the color of the text, the border and the tics, eg. to stand out from a dark
+    /// background
+    ///
+    /// **Note** By default, the terminal draws them in black
+    fn set(&mut self, foreground: Foreground) -> &mut Figure {
+        self.foreground = Some(foreground.0);
+        self
+    }
+}
+
 impl Set<Output> for Figure {
     /// Changes the output file
     ///
@@ -718,6 +793,10 @@ impl Default for Figure {
     }
 }
 
+/// Background color of the figure
+#[derive(Clone, Copy)]
+pub struct Background(pub Color);
+
 /// Box width for box-related plots: bars, candlesticks, etc
 #[derive(Clone, Copy)]
 pub struct BoxWidth(pub f64);
@@ -729,6 +808,10 @@ pub struct Font(Cow<'static, str>);
 #[derive(Clone, Copy)]
 pub struct FontSize(pub f64);
 
+/// The color of the text, the border and the tics of a figure
+#[derive(Clone, Copy)]
+pub struct Foreground(pub Color);
+
 /// The key or legend
 #[derive(Clone, Copy)]
 pub struct Key;
@@ -744,6 +827,10 @@ pub struct LineWidth(pub f64);
 #[derive(Clone, Copy)]
 pub struct Opacity(pub f64);
 
+/// Fill pattern, using gnuplot's numbering of the patterns
+#[derive(Clone, Copy)]
+pub struct FillPattern(pub u8);
+
 /// Output file path
 pub struct Output(Cow<'static, Path>);
 
@@ -897,6 +984,8 @@ pub struct ScaleFactor(pub f64);
 #[derive(Clone, Copy)]
 pub enum Terminal {
     Svg,
+    /// PNG images, drawn with cairo
+    Png,
 }
 
 /// Not public version of `std::default::Default`, used to not leak default constructors into the
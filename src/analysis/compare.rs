@@ -8,8 +8,13 @@ use benchmark::BenchmarkConfig;
 use error::Result;
 use estimate::Statistic;
 use estimate::{Distributions, Estimates};
-use report::BenchmarkId;
-use {format, fs, Criterion, Estimate};
+use report::{compare_with_hysteresis, BenchmarkId, ComparisonResult, TailChange};
+use {format, fs, ConfidenceInterval, Criterion, Estimate};
+
+// Relative changes smaller than this are rounding errors, eg. between the times saved in the
+// baseline and the same times recomputed, and are treated as no change at all. It is the last
+// digit shown, 0.0001%.
+const CHANGE_EPSILON: f64 = 1e-6;
 
 // Common comparison procedure
 #[cfg_attr(feature = "cargo-clippy", allow(type_complexity))]
@@ -45,6 +50,14 @@ pub(crate) fn common(
     let base_estimates: Estimates = fs::load(&estimates_file)
         .map_err(|e| e.context(format!("Failed to load {}!", &estimates_file)))?;
 
+    let base_fingerprint: Option<String> = fs::load(&format!(
+        "{}/{}/{}/fingerprint.json",
+        criterion.output_directory,
+        id.as_directory_name(),
+        criterion.baseline_directory
+    )).ok();
+    check_fingerprint(id, base_fingerprint.as_ref().map(String::as_str));
+
     let base_avg_times: Vec<f64> = iters
         .iter()
         .zip(times.iter())
@@ -52,11 +65,23 @@ pub(crate) fn common(
         .collect();
     let base_avg_time_sample = Sample::new(&base_avg_times);
 
-    fs::mkdirp(&format!(
-        "{}/{}/change",
-        criterion.output_directory,
-        id.as_directory_name()
-    ))?;
+    if criterion.saves_output() {
+        fs::mkdirp(&format!(
+            "{}/{}/change",
+            criterion.output_directory,
+            id.as_directory_name()
+        ))?;
+        // Keep the baseline sample next to the change, for the summary plots
+        log_if_err!(fs::save_sample(
+            &(&iters, &times),
+            &format!(
+                "{}/{}/change/base_sample.json",
+                criterion.output_directory,
+                id.as_directory_name()
+            ),
+            criterion.compress_samples,
+        ));
+    }
     let (t_statistic, t_distribution) = t_test(avg_times, base_avg_time_sample, config);
 
     let (estimates, relative_distributions) =
@@ -73,6 +98,25 @@ pub(crate) fn common(
     ))
 }
 
+// Baselines are found by the directory name of the benchmark, which doesn't capture everything
+// about its definition: the throughput isn't part of it, and different ids may have the same
+// filename-safe directory name. Warns if the baseline was saved by a benchmark with a different
+// definition, and returns whether the definitions match. Baselines saved without a fingerprint
+// are assumed to match.
+fn check_fingerprint(id: &BenchmarkId, base_fingerprint: Option<&str>) -> bool {
+    match base_fingerprint {
+        Some(fingerprint) if fingerprint != id.fingerprint() => {
+            warn!(
+                "{}: the baseline was saved by a benchmark with a different id, parameter or \
+                 throughput; the comparison may be meaningless",
+                id
+            );
+            false
+        }
+        _ => true,
+    }
+}
+
 // Performs a two sample t-test
 fn t_test(
     avg_times: &Sample<f64>,
@@ -134,9 +178,12 @@ fn estimates(
     point_estimates.insert(Statistic::Mean, mean);
     point_estimates.insert(Statistic::Median, median);
 
-    let estimates = Estimate::new(&distributions, &point_estimates, cl);
+    let mut estimates = Estimate::new(&distributions, &point_estimates, cl);
+    for estimate in estimates.values_mut() {
+        round_to_zero(estimate);
+    }
 
-    {
+    if criterion.saves_output() {
         log_if_err!(fs::save(
             &estimates,
             &format!(
@@ -148,3 +195,195 @@ fn estimates(
     }
     (estimates, distributions)
 }
+
+// Estimates the relative change in a percentile of the population
+pub(crate) fn tail_change(
+    avg_times: &Sample<f64>,
+    base_avg_times: &Sample<f64>,
+    percentile: f64,
+    config: &BenchmarkConfig,
+) -> TailChange {
+    let stats = |a: &Sample<f64>, b: &Sample<f64>| {
+        (a.percentiles().at(percentile) / b.percentiles().at(percentile) - 1.,)
+    };
+
+    let cl = config.confidence_level;
+    let nresamples = config.nresamples;
+
+    let distribution = elapsed!(
+        "Bootstrapping the relative tail percentile",
+        univariate::bootstrap(avg_times, base_avg_times, nresamples, stats)
+    ).0;
+    let (lb, ub) = distribution.confidence_interval(cl);
+
+    let mut change = TailChange {
+        percentile,
+        estimate: Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: cl,
+                lower_bound: lb,
+                upper_bound: ub,
+            },
+            point_estimate: stats(avg_times, base_avg_times).0,
+            standard_error: distribution.std_dev(None),
+        },
+    };
+    round_to_zero(&mut change.estimate);
+    change
+}
+
+// Snaps the bounds and point of a relative change within `CHANGE_EPSILON` of zero to exactly zero.
+fn round_to_zero(estimate: &mut Estimate) {
+    let round = |change: &mut f64| {
+        if change.abs() < CHANGE_EPSILON {
+            *change = 0.;
+        }
+    };
+    round(&mut estimate.point_estimate);
+    round(&mut estimate.confidence_interval.lower_bound);
+    round(&mut estimate.confidence_interval.upper_bound);
+}
+
+// Decides whether the benchmark has improved or regressed, taking the verdict of the comparison
+// which produced the baseline into account. The verdict is saved for the next comparison.
+pub(crate) fn verdict(
+    id: &BenchmarkId,
+    relative_estimates: &Estimates,
+    p_value: f64,
+    config: &BenchmarkConfig,
+    criterion: &Criterion,
+) -> ComparisonResult {
+    let dir = format!("{}/{}", criterion.output_directory, id.as_directory_name());
+    let previous = fs::load(&format!(
+        "{}/{}/verdict.json",
+        dir, criterion.baseline_directory
+    )).ok();
+
+    let verdict = if p_value < config.significance_level {
+        compare_with_hysteresis(
+            &relative_estimates[&Statistic::Mean],
+            config.noise_threshold,
+            config.change_hysteresis,
+            previous,
+        )
+    } else {
+        ComparisonResult::NonSignificant
+    };
+
+    if criterion.saves_output() {
+        log_if_err!(fs::save(&verdict, &format!("{}/new/verdict.json", dir)));
+    }
+    verdict
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use report::compare_to_threshold;
+    use std::time::Duration;
+    use {SampleWeighting, Throughput};
+
+    fn config() -> BenchmarkConfig {
+        BenchmarkConfig {
+            change_hysteresis: 0.0,
+            confidence_level: 0.95,
+            max_measurement_time: Duration::from_secs(30),
+            measurement_repeats: 1,
+            measurement_time: Duration::from_secs(5),
+            min_iters_per_sample: 1,
+            noise_threshold: 0.01,
+            nresamples: 10_000,
+            sample_size: 100,
+            sample_weighting: SampleWeighting::Uniform,
+            significance_level: 0.05,
+            tail_percentile: Some(95.),
+            target_ci_width: None,
+            timeout: None,
+            warm_up_time: Duration::from_secs(3),
+        }
+    }
+
+    #[test]
+    fn fingerprint_mismatch() {
+        let id = |value: &str, throughput| {
+            BenchmarkId::new(
+                "group".to_owned(),
+                Some("function".to_owned()),
+                Some(value.to_owned()),
+                throughput,
+            )
+        };
+        let base = id("1024", Some(Throughput::Bytes(1024)));
+
+        assert!(check_fingerprint(&base, Some(&base.fingerprint())));
+        assert!(check_fingerprint(&base, None));
+        assert!(!check_fingerprint(
+            &id("2048", Some(Throughput::Bytes(1024))),
+            Some(&base.fingerprint())
+        ));
+        assert!(!check_fingerprint(
+            &id("1024", Some(Throughput::Elements(1024))),
+            Some(&base.fingerprint())
+        ));
+    }
+
+    #[test]
+    fn sub_epsilon_change_is_no_change() {
+        let mut estimate = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: -3e-7,
+                upper_bound: 8e-7,
+            },
+            point_estimate: 2e-7,
+            standard_error: 1e-7,
+        };
+        round_to_zero(&mut estimate);
+
+        assert_eq!(0., estimate.point_estimate);
+        assert_eq!("0% [0%, 0%]", format::change_interval(&estimate));
+        assert_eq!(" +0.00%", format::change(estimate.point_estimate));
+        for &noise in &[0., 0.01] {
+            assert_eq!(
+                ComparisonResult::NonSignificant,
+                compare_with_hysteresis(&estimate, noise, 0., None)
+            );
+        }
+
+        // Changes above the epsilon are kept.
+        let mut estimate = Estimate {
+            point_estimate: 2e-6,
+            ..estimate
+        };
+        round_to_zero(&mut estimate);
+        assert_eq!(2e-6, estimate.point_estimate);
+    }
+
+    #[test]
+    fn tail_regression_with_unchanged_mean() {
+        let base: Vec<f64> = (0..100).map(|i| 95. + f64::from(i % 11)).collect();
+        // Most iterations got a little faster, but a few got a lot slower, leaving the mean as is.
+        let new: Vec<f64> = base.iter()
+            .enumerate()
+            .map(|(i, &t)| if i % 5 == 0 { t + 20. } else { t - 5. })
+            .collect();
+        let (base, new) = (Sample::new(&base), Sample::new(&new));
+        let config = config();
+
+        // The mean hasn't changed, so its confidence interval can't exclude the noise threshold.
+        let mean_change = univariate::bootstrap(new, base, config.nresamples, |a, b| {
+            (a.mean() / b.mean() - 1.,)
+        }).0;
+        let (lb, ub) = mean_change.confidence_interval(config.confidence_level);
+        assert!((new.mean() / base.mean() - 1.).abs() < 1e-9);
+        assert!(lb < 0. && ub > 0.);
+
+        let tail = tail_change(new, base, 95., &config);
+        assert_eq!(95., tail.percentile);
+        assert!(tail.estimate.point_estimate > 0.05);
+        assert_eq!(
+            ComparisonResult::Regressed,
+            compare_to_threshold(&tail.estimate, config.noise_threshold)
+        );
+    }
+}
@@ -4,14 +4,15 @@ use std::path::Path;
 use stats::bivariate::Data;
 use stats::bivariate::regression::Slope;
 use stats::univariate::Sample;
-use stats::univariate::outliers::tukey::{self, LabeledSample};
+use stats::univariate::outliers::tukey;
 use stats::{Distribution, Tails};
 
 use benchmark::BenchmarkConfig;
 use estimate::{Distributions, Estimates, Statistic};
+use machine_info::git_info;
 use report::{BenchmarkId, ReportContext};
 use routine::Routine;
-use {Baseline, ConfidenceInterval, Criterion, Estimate, Throughput};
+use {Baseline, ConfidenceInterval, Criterion, Estimate, SampleWeighting, Throughput};
 use {format, fs};
 
 macro_rules! elapsed {
@@ -31,6 +32,11 @@ macro_rules! elapsed {
 }
 
 mod compare;
+mod paired;
+mod shuffled;
+
+pub(crate) use self::paired::paired;
+pub(crate) use self::shuffled::shuffled;
 
 // Common analysis procedure
 pub(crate) fn common<T>(
@@ -55,6 +61,39 @@ pub(crate) fn common<T>(
         return;
     }
 
+    // In profiling mode, run the benchmark for the given time and skip all measurement.
+    if let Some(profile_time) = criterion.profile_time {
+        routine.profile(id, criterion, report_context, profile_time, parameter);
+        criterion.report.terminated(id, report_context);
+        return;
+    }
+
+    check_baseline(id, criterion);
+
+    let (iters, times) =
+        match routine.sample(id, config, &criterion.report, report_context, parameter) {
+            Ok(sample) => sample,
+            Err(e) => {
+                ::error::log_error(&e);
+                criterion.report.terminated(id, report_context);
+                return;
+            }
+        };
+
+    analyze(
+        id,
+        routine,
+        config,
+        criterion,
+        report_context,
+        throughput,
+        iters,
+        times,
+    );
+}
+
+// Panics if the benchmarks are to be compared against a baseline which `id` doesn't have
+fn check_baseline(id: &BenchmarkId, criterion: &Criterion) {
     if let Baseline::Compare = criterion.baseline {
         if !base_dir_exists(
             id,
@@ -67,8 +106,26 @@ pub(crate) fn common<T>(
             ));
         }
     }
+}
 
-    let (iters, times) = routine.sample(id, config, criterion, report_context, parameter);
+// Analyzes, reports and saves the measurements of a benchmark: the iteration counts `iters` of
+// its samples and their elapsed times `times`
+#[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
+fn analyze<T>(
+    id: &BenchmarkId,
+    routine: &mut Routine<T>,
+    config: &BenchmarkConfig,
+    criterion: &Criterion,
+    report_context: &ReportContext,
+    throughput: Option<Throughput>,
+    iters: Box<[f64]>,
+    times: Box<[f64]>,
+) {
+    // Routines which count the elements they process determine their own throughput.
+    let throughput = routine
+        .elements_per_iteration()
+        .map(|elements| Throughput::Elements(elements.round() as u32))
+        .or(throughput);
 
     // In profiling mode, skip all of the analysis.
     if criterion.measure_only {
@@ -78,6 +135,13 @@ pub(crate) fn common<T>(
 
     criterion.report.analysis(id, report_context);
 
+    let times = match routine.control_variate() {
+        Some((control_times, control_ns)) => {
+            control_variate(&iters, &times, control_times, control_ns).into_boxed_slice()
+        }
+        None => times,
+    };
+
     let avg_times = iters
         .iter()
         .zip(times.iter())
@@ -85,36 +149,42 @@ pub(crate) fn common<T>(
         .collect::<Vec<f64>>();
     let avg_times = Sample::new(&avg_times);
 
-    log_if_err!(fs::mkdirp(&format!(
-        "{}/{}/new",
-        criterion.output_directory,
-        id.as_directory_name()
-    )));
-
     let data = Data::new(&iters, &times);
-    let labeled_sample = outliers(id, &criterion.output_directory, avg_times);
-    let (distribution, slope) = regression(data, config);
-    let (mut distributions, mut estimates) = estimates(avg_times, config);
+    let labeled_sample = tukey::classify(avg_times);
+    let (distributions, estimates) = absolute_estimates(id, &iters, &times, avg_times, config);
 
-    estimates.insert(Statistic::Slope, slope);
-    distributions.insert(Statistic::Slope, distribution);
-
-    log_if_err!(fs::save(
-        &(data.x().as_slice(), data.y().as_slice()),
-        &format!(
-            "{}/{}/new/sample.json",
+    if criterion.saves_output() {
+        log_if_err!(fs::mkdirp(&format!(
+            "{}/{}/new",
             criterion.output_directory,
             id.as_directory_name()
-        ),
-    ));
-    log_if_err!(fs::save(
-        &estimates,
-        &format!(
-            "{}/{}/new/estimates.json",
-            criterion.output_directory,
-            id.as_directory_name()
-        )
-    ));
+        )));
+        log_if_err!(fs::save(
+            &labeled_sample.fences(),
+            &format!(
+                "{}/{}/new/tukey.json",
+                criterion.output_directory,
+                id.as_directory_name()
+            )
+        ));
+        log_if_err!(fs::save_sample(
+            &(data.x().as_slice(), data.y().as_slice()),
+            &format!(
+                "{}/{}/new/sample.json",
+                criterion.output_directory,
+                id.as_directory_name()
+            ),
+            criterion.compress_samples,
+        ));
+        log_if_err!(fs::save(
+            &estimates,
+            &format!(
+                "{}/{}/new/estimates.json",
+                criterion.output_directory,
+                id.as_directory_name()
+            )
+        ));
+    }
 
     let compare_data = if base_dir_exists(
         id,
@@ -134,6 +204,15 @@ pub(crate) fn common<T>(
                 base_estimates,
             )) => {
                 let p_value = t_distribution.p_value(t_value, &Tails::Two);
+                let verdict = compare::verdict(id, &relative_estimates, p_value, config, criterion);
+                let tail_change = config.tail_percentile.map(|percentile| {
+                    compare::tail_change(
+                        avg_times,
+                        Sample::new(&base_avg_times),
+                        percentile,
+                        config,
+                    )
+                });
                 Some(::report::ComparisonData {
                     p_value,
                     t_distribution,
@@ -146,6 +225,8 @@ pub(crate) fn common<T>(
                     base_sample_times,
                     base_avg_times,
                     base_estimates,
+                    tail_change,
+                    verdict,
                 })
             }
             Err(e) => {
@@ -171,6 +252,10 @@ pub(crate) fn common<T>(
         .report
         .measurement_complete(id, report_context, &measurement_data);
 
+    if !criterion.saves_output() {
+        return;
+    }
+
     log_if_err!(fs::save(
         &id,
         &format!(
@@ -179,12 +264,31 @@ pub(crate) fn common<T>(
             id.as_directory_name()
         )
     ));
+    log_if_err!(fs::save(
+        &id.fingerprint(),
+        &format!(
+            "{}/{}/new/fingerprint.json",
+            criterion.output_directory,
+            id.as_directory_name()
+        )
+    ));
+    if let Some(git) = git_info() {
+        log_if_err!(fs::save(
+            &git,
+            &format!(
+                "{}/{}/new/git.json",
+                criterion.output_directory,
+                id.as_directory_name()
+            )
+        ));
+    }
 
     if let Baseline::Save = criterion.baseline {
         copy_new_dir_to_base(
             id.as_directory_name(),
             &criterion.baseline_directory,
             &criterion.output_directory,
+            &report_context.result_file_name(),
         );
     }
 }
@@ -198,6 +302,68 @@ fn base_dir_exists(id: &BenchmarkId, baseline: &str, output_directory: &str) ->
     )).exists()
 }
 
+// Estimates the absolute statistics of the time per iteration, including the slope of the
+// regression, from the measurements. Neither saves nor reports anything.
+pub(crate) fn absolute_estimates(
+    id: &BenchmarkId,
+    iters: &[f64],
+    times: &[f64],
+    avg_times: &Sample<f64>,
+    config: &BenchmarkConfig,
+) -> (Distributions, Estimates) {
+    let (weighted_iters, weighted_times) = weighted(iters, times, config.sample_weighting);
+    let weighted_data = Data::new(&weighted_iters, &weighted_times);
+    let (distribution, slope) = regression(weighted_data, config);
+    let (mut distributions, mut estimates) = estimates(avg_times, config);
+
+    estimates.insert(Statistic::Slope, slope);
+    distributions.insert(Statistic::Slope, distribution);
+    replace_unreliable_slope(id, &mut distributions, &mut estimates);
+    (distributions, estimates)
+}
+
+// The regression line passes through the origin, but noisy samples of a very fast routine can
+// still make it slope downwards once they are adjusted (eg. by a control variate) to the point
+// that some of their times are negative. A time per iteration which isn't positive makes no sense
+// in the reports and the plots, nor when formatted, so this replaces such a slope with the mean
+// of the sample averages, warning that the regression was unreliable. Returns whether it did.
+fn replace_unreliable_slope(
+    id: &BenchmarkId,
+    distributions: &mut Distributions,
+    estimates: &mut Estimates,
+) -> bool {
+    if estimates[&Statistic::Slope].point_estimate > 0. {
+        return false;
+    }
+
+    warn!(
+        "{}: the linear regression estimated a time per iteration of {}, which is unreliable; \
+         using the mean instead. Consider increasing the measurement time.",
+        id,
+        format::time(estimates[&Statistic::Slope].point_estimate)
+    );
+    let mean = estimates[&Statistic::Mean];
+    let mean_distribution = Distribution::from(distributions[&Statistic::Mean].as_slice().into());
+    estimates.insert(Statistic::Slope, mean);
+    distributions.insert(Statistic::Slope, mean_distribution);
+    true
+}
+
+// Applies the sample weighting to the data for the regression. Least squares with weights `w` is
+// ordinary least squares on the data scaled by `sqrt(w)`, so the regression and its bootstrap work
+// unchanged.
+fn weighted(iters: &[f64], times: &[f64], weighting: SampleWeighting) -> (Vec<f64>, Vec<f64>) {
+    iters
+        .iter()
+        .zip(times)
+        .enumerate()
+        .map(|(i, (&iters, &time))| {
+            let scale = weighting.weight(i).sqrt();
+            (iters * scale, time * scale)
+        })
+        .unzip()
+}
+
 // Performs a simple linear regression on the sample
 fn regression(data: Data<f64, f64>, config: &BenchmarkConfig) -> (Distribution<f64>, Estimate) {
     let cl = config.confidence_level;
@@ -225,24 +391,6 @@ fn regression(data: Data<f64, f64>, config: &BenchmarkConfig) -> (Distribution<f
     )
 }
 
-// Classifies the outliers in the sample
-fn outliers<'a>(
-    id: &BenchmarkId,
-    output_directory: &str,
-    avg_times: &'a Sample<f64>,
-) -> LabeledSample<'a, f64> {
-    let sample = tukey::classify(avg_times);
-    log_if_err!(fs::save(
-        &sample.fences(),
-        &format!(
-            "{}/{}/new/tukey.json",
-            output_directory,
-            id.as_directory_name()
-        )
-    ));
-    sample
-}
-
 // Estimates the statistics of the population from the sample
 fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distributions, Estimates) {
     fn stats(sample: &Sample<f64>) -> (f64, f64, f64, f64) {
@@ -280,7 +428,53 @@ fn estimates(avg_times: &Sample<f64>, config: &BenchmarkConfig) -> (Distribution
     (distributions, estimates)
 }
 
-fn copy_new_dir_to_base(id: &str, baseline: &str, output_directory: &str) {
+// Adjusts the sample times with a control variate: a routine measured alongside each sample, whose
+// expected time per iteration is `control_ns`. The deviation of the control from its expected
+// time is subtracted from the time per iteration, scaled by the coefficient which minimizes the
+// variance of the result, `cov(main, control) / var(control)`. Returns the adjusted sample times.
+pub(crate) fn control_variate(
+    iters: &[f64],
+    times: &[f64],
+    control_times: &[f64],
+    control_ns: f64,
+) -> Vec<f64> {
+    let per_iter = |times: &[f64]| -> Vec<f64> {
+        iters
+            .iter()
+            .zip(times)
+            .map(|(&iters, &elapsed)| elapsed / iters)
+            .collect()
+    };
+    let avg_times = per_iter(times);
+    let control_avg_times = per_iter(control_times);
+
+    let n = avg_times.len() as f64;
+    let mean = avg_times.iter().sum::<f64>() / n;
+    let control_mean = control_avg_times.iter().sum::<f64>() / n;
+    let (covariance, control_variance) = avg_times.iter().zip(&control_avg_times).fold(
+        (0., 0.),
+        |(cov, var), (&t, &c)| {
+            (
+                cov + (t - mean) * (c - control_mean),
+                var + (c - control_mean).powi(2),
+            )
+        },
+    );
+    let beta = if control_variance > 0. {
+        covariance / control_variance
+    } else {
+        0.
+    };
+
+    avg_times
+        .iter()
+        .zip(&control_avg_times)
+        .zip(iters)
+        .map(|((&t, &c), &iters)| (t - beta * (c - control_ns)) * iters)
+        .collect()
+}
+
+fn copy_new_dir_to_base(id: &str, baseline: &str, output_directory: &str, result_file: &str) {
     let root_dir = Path::new(output_directory).join(id);
     let base_dir = root_dir.join(baseline);
     let new_dir = root_dir.join("new");
@@ -310,4 +504,110 @@ fn copy_new_dir_to_base(id: &str, baseline: &str, output_directory: &str) {
         &base_dir.join("benchmark.json")
     ));
     try_else_return!(fs::cp(&new_dir.join("raw.csv"), &base_dir.join("raw.csv")));
+    try_else_return!(fs::cp(
+        &new_dir.join(result_file),
+        &base_dir.join(result_file)
+    ));
+    try_else_return!(fs::cp(
+        &new_dir.join("fingerprint.json"),
+        &base_dir.join("fingerprint.json")
+    ));
+    if new_dir.join("verdict.json").exists() {
+        try_else_return!(fs::cp(
+            &new_dir.join("verdict.json"),
+            &base_dir.join("verdict.json")
+        ));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn downweighting_early_samples() {
+        // The first three samples are twice as slow per iteration as the rest.
+        let iters: Vec<f64> = (1..11).map(f64::from).collect();
+        let times: Vec<f64> = iters
+            .iter()
+            .map(|&i| if i <= 3. { 20. * i } else { 10. * i })
+            .collect();
+        let slope = |weighting| {
+            let (iters, times) = weighted(&iters, &times, weighting);
+            Slope::fit(Data::new(&iters, &times)).0
+        };
+
+        let uniform = slope(SampleWeighting::Uniform);
+        let downweighted = slope(SampleWeighting::DownweightFirst {
+            count: 3,
+            weight: 0.1,
+        });
+
+        assert!((uniform - 3990. / 385.).abs() < 1e-9);
+        assert!(downweighted < uniform);
+        assert!(downweighted - 10. < (uniform - 10.) / 5.);
+    }
+
+    #[test]
+    fn non_positive_slope_falls_back_to_mean() {
+        // Noise dominates, and an over-eager adjustment left the later samples with negative times.
+        let iters = [1., 2., 3., 4., 5.];
+        let times = [5., 2., -3., -8., -10.];
+        let avg_times: Vec<f64> = iters.iter().zip(&times).map(|(&n, &t)| t / n).collect();
+        let avg_times = Sample::new(&avg_times);
+        let id = BenchmarkId::new("group".to_owned(), None, None, None);
+        let mut criterion = Criterion::default();
+        criterion.config.nresamples = 1000;
+        let config = &criterion.config;
+
+        let (mut distributions, mut estimates) = estimates(avg_times, config);
+        let (distribution, slope) = regression(Data::new(&iters, &times), config);
+        assert!(slope.point_estimate < 0.);
+        estimates.insert(Statistic::Slope, slope);
+        distributions.insert(Statistic::Slope, distribution);
+
+        assert!(replace_unreliable_slope(&id, &mut distributions, &mut estimates));
+        assert_eq!(estimates[&Statistic::Mean], estimates[&Statistic::Slope]);
+        assert_eq!(
+            distributions[&Statistic::Mean].as_slice(),
+            distributions[&Statistic::Slope].as_slice()
+        );
+        // A sensible slope is left alone.
+        assert!(!replace_unreliable_slope(&id, &mut distributions, &mut estimates));
+
+        let (_, estimates) = absolute_estimates(&id, &iters, &times, avg_times, config);
+        assert_eq!(0.2, estimates[&Statistic::Slope].point_estimate);
+    }
+
+    #[test]
+    fn control_variate_reduces_variance() {
+        // Load on the machine slows down both routines, the main one three times as much.
+        let load = |i: usize| ((i * 7919) % 101) as f64 / 10. - 5.;
+        let jitter = |i: usize| ((i * 104_729) % 11) as f64 / 100.;
+        let iters: Vec<f64> = (1..101).map(f64::from).collect();
+        let times: Vec<f64> = iters
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (100. + 3. * load(i) + jitter(i)) * n)
+            .collect();
+        let control_times: Vec<f64> = iters
+            .iter()
+            .enumerate()
+            .map(|(i, &n)| (20. + load(i)) * n)
+            .collect();
+
+        let per_iter = |times: &[f64]| -> Vec<f64> {
+            iters.iter().zip(times).map(|(&n, &t)| t / n).collect()
+        };
+        let uncorrected = per_iter(&times);
+        let corrected = per_iter(&control_variate(&iters, &times, &control_times, 20.));
+        let (uncorrected, corrected) = (Sample::new(&uncorrected), Sample::new(&corrected));
+
+        assert!(corrected.std_dev(None) < uncorrected.std_dev(None) / 10.);
+        assert!((corrected.mean() - 100.05).abs() < 0.1);
+
+        // A control which doesn't vary leaves the samples as they are.
+        let constant: Vec<f64> = iters.iter().map(|&n| 20. * n).collect();
+        assert_eq!(times, control_variate(&iters, &times, &constant, 20.));
+    }
 }
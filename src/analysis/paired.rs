@@ -0,0 +1,280 @@
+use stats::univariate::Sample;
+
+use benchmark::NamedRoutine;
+use error::TooSlowError;
+use report::{BenchmarkId, PairedComparison, ReportContext};
+use routine::iteration_counts;
+use {format, Criterion, DurationExt, PlotConfiguration};
+
+// Benchmarks two routines in the same measurement window, alternating between them sample by
+// sample, so that transient load on the machine affects both of them alike.
+pub(crate) fn paired<T>(
+    group_id: &str,
+    a: NamedRoutine<T>,
+    b: NamedRoutine<T>,
+    criterion: &Criterion,
+    plot_config: &PlotConfiguration,
+    parameter: &T,
+) {
+    let id_a = BenchmarkId::new(group_id.to_owned(), Some(a.id.clone()), None, None);
+    let id_b = BenchmarkId::new(group_id.to_owned(), Some(b.id.clone()), None, None);
+    let id = BenchmarkId::new(
+        group_id.to_owned(),
+        Some(format!("{} vs {}", a.id, b.id)),
+        None,
+        None,
+    );
+
+    if criterion.list_mode {
+        println!("{}: bench", id_a);
+        println!("{}: bench", id_b);
+        return;
+    }
+    if !criterion.filter_matches(id_a.id()) && !criterion.filter_matches(id_b.id()) {
+        return;
+    }
+
+    let report_context = ReportContext {
+        output_directory: criterion.output_directory.clone(),
+        plotting: criterion.plotting,
+        plot_config: plot_config.clone(),
+        test_mode: criterion.test_mode,
+        report_only_changed: criterion.report_only_changed,
+        run_label: criterion.run_label.clone(),
+    };
+    let routine_a = &mut *a.f.borrow_mut();
+    let routine_b = &mut *b.f.borrow_mut();
+
+    // Testing and profiling don't measure anything, so there is nothing to interleave.
+    if criterion.test_mode || criterion.profile_time.is_some() {
+        for &mut (ref id, ref mut routine) in &mut [(id_a, routine_a), (id_b, routine_b)] {
+            criterion.report.benchmark_start(id, &report_context);
+            match criterion.profile_time {
+                Some(profile_time) => {
+                    routine.profile(id, criterion, &report_context, profile_time, parameter)
+                }
+                None => routine.test(parameter),
+            }
+            criterion.report.terminated(id, &report_context);
+        }
+        if !criterion.quiet {
+            println!();
+        }
+        return;
+    }
+
+    let config = &criterion.config;
+    criterion.report.benchmark_start(&id, &report_context);
+    criterion
+        .report
+        .warmup(&id, &report_context, config.warm_up_time.to_nanos() as f64);
+
+    // Each routine gets half of the warm-up and measurement time.
+    let wu = config.warm_up_time / 2;
+    let mut m_a = routine_a.start(parameter);
+    let mut m_b = routine_b.start(parameter);
+    let (wu_elapsed_a, wu_iters_a) = routine_a.warm_up(&mut m_a, wu, parameter);
+    let (wu_elapsed_b, wu_iters_b) = routine_b.warm_up(&mut m_b, wu, parameter);
+    for &(slow_id, wu_iters) in &[(&id_a, wu_iters_a), (&id_b, wu_iters_b)] {
+        if wu_iters <= 1 {
+            let e = TooSlowError {
+                id: slow_id.id().to_owned(),
+                warm_up: format::time(wu.to_nanos() as f64),
+            };
+            ::error::log_error(&e.into());
+            criterion.report.terminated(&id, &report_context);
+            return;
+        }
+    }
+
+    let m_ns = config.measurement_time.to_nanos() as f64 / 2.;
+    let met_a = wu_elapsed_a as f64 / wu_iters_a as f64;
+    let met_b = wu_elapsed_b as f64 / wu_iters_b as f64;
+    let iters_a = iteration_counts(met_a, m_ns, config);
+    let iters_b = iteration_counts(met_b, m_ns, config);
+
+    let total_a: u64 = iters_a.iter().sum();
+    let total_b: u64 = iters_b.iter().sum();
+    criterion.report.measurement_start(
+        &id,
+        &report_context,
+        config.sample_size as u64,
+        total_a as f64 * met_a + total_b as f64 * met_b,
+        total_a + total_b,
+    );
+    let (times_a, times_b) = interleave(
+        &iters_a,
+        &iters_b,
+        |iters| routine_a.bench(&mut m_a, &[iters], parameter)[0],
+        |iters| routine_b.bench(&mut m_b, &[iters], parameter)[0],
+    );
+
+    criterion.report.analysis(&id, &report_context);
+
+    let avg_times = |iters: &[u64], times: &[f64]| {
+        iters
+            .iter()
+            .zip(times)
+            .map(|(&iters, &elapsed)| elapsed / iters as f64)
+            .collect::<Vec<f64>>()
+    };
+    let avg_a = avg_times(&iters_a, &times_a);
+    let avg_b = avg_times(&iters_b, &times_b);
+
+    let comparison = PairedComparison {
+        mean_a: Sample::new(&avg_a).mean(),
+        mean_b: Sample::new(&avg_b).mean(),
+        p_value: wilcoxon_signed_rank(&avg_a, &avg_b),
+        significance_threshold: config.significance_level,
+        id_a,
+        id_b,
+    };
+    criterion
+        .report
+        .paired_comparison(&id, &report_context, &comparison);
+    if !criterion.quiet {
+        println!();
+    }
+}
+
+// Runs one sample of A, then one of B, and so on. Returns the elapsed times of the samples of A
+// and B, in the order they were taken.
+fn interleave<A, B>(iters_a: &[u64], iters_b: &[u64], mut a: A, mut b: B) -> (Vec<f64>, Vec<f64>)
+where
+    A: FnMut(u64) -> f64,
+    B: FnMut(u64) -> f64,
+{
+    iters_a
+        .iter()
+        .zip(iters_b)
+        .map(|(&iters_a, &iters_b)| {
+            let elapsed_a = a(iters_a);
+            let elapsed_b = b(iters_b);
+            (elapsed_a, elapsed_b)
+        })
+        .unzip()
+}
+
+// Two-sided p-value of the Wilcoxon signed-rank test of the paired samples `a` and `b`, using the
+// normal approximation, which is accurate for the sample sizes Criterion.rs collects.
+fn wilcoxon_signed_rank(a: &[f64], b: &[f64]) -> f64 {
+    let mut differences: Vec<f64> = a
+        .iter()
+        .zip(b)
+        .map(|(&a, &b)| a - b)
+        .filter(|&d| d != 0.)
+        .collect();
+    if differences.is_empty() {
+        return 1.;
+    }
+    differences.sort_by(|x, y| x.abs().partial_cmp(&y.abs()).unwrap());
+
+    // Tied absolute differences share the average of their ranks, which also reduces the variance
+    // of the statistic.
+    let n = differences.len();
+    let mut w_plus = 0.;
+    let mut tie_correction = 0.;
+    let mut start = 0;
+    while start < n {
+        let mut end = start + 1;
+        while end < n && differences[end].abs() == differences[start].abs() {
+            end += 1;
+        }
+
+        let rank = (start + end + 1) as f64 / 2.;
+        w_plus += rank * differences[start..end].iter().filter(|&&d| d > 0.).count() as f64;
+
+        let ties = (end - start) as f64;
+        tie_correction += ties * ties * ties - ties;
+        start = end;
+    }
+
+    let n = n as f64;
+    let mean = n * (n + 1.) / 4.;
+    let variance = n * (n + 1.) * (2. * n + 1.) / 24. - tie_correction / 48.;
+    if variance == 0. {
+        return 1.;
+    }
+
+    let z = (w_plus - mean).abs() / variance.sqrt();
+    erfc(z / 2f64.sqrt()).min(1.)
+}
+
+// Complementary error function, with a fractional error below 1.2e-7 (Numerical Recipes, 6.2).
+fn erfc(x: f64) -> f64 {
+    let z = x.abs();
+    let t = 1. / (1. + 0.5 * z);
+    let r = t * (-z * z - 1.265_512_23
+        + t * (1.000_023_68
+            + t * (0.374_091_96
+                + t * (0.096_784_18
+                    + t * (-0.186_288_06
+                        + t * (0.278_868_07
+                            + t * (-1.135_203_98
+                                + t * (1.488_515_87 + t * (-0.822_152_23 + t * 0.170_872_77)))))))))
+        .exp();
+
+    if x >= 0. {
+        r
+    } else {
+        2. - r
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::cell::RefCell;
+
+    #[test]
+    fn interleaves_samples() {
+        let order = RefCell::new(vec![]);
+        let iters: Vec<u64> = (1..6).collect();
+
+        let (times_a, times_b) = interleave(
+            &iters,
+            &iters,
+            |iters| {
+                order.borrow_mut().push('A');
+                iters as f64
+            },
+            |iters| {
+                order.borrow_mut().push('B');
+                2. * iters as f64
+            },
+        );
+
+        assert_eq!(
+            vec!['A', 'B', 'A', 'B', 'A', 'B', 'A', 'B', 'A', 'B'],
+            order.into_inner()
+        );
+        assert_eq!(iters.len(), times_a.len());
+        assert_eq!(times_a.len(), times_b.len());
+        assert_eq!(vec![2., 4., 6., 8., 10.], times_b);
+    }
+
+    #[test]
+    fn wilcoxon_detects_shift() {
+        let a: Vec<f64> = (0..50).map(|i| f64::from(i % 7)).collect();
+
+        let shifted: Vec<f64> = a.iter().map(|x| x + 0.5).collect();
+        assert!(wilcoxon_signed_rank(&a, &shifted) < 1e-6);
+
+        // Half of the pairs differ in each direction by the same amounts.
+        let mixed: Vec<f64> = a
+            .iter()
+            .enumerate()
+            .map(|(i, x)| if i % 2 == 0 { x + 0.5 } else { x - 0.5 })
+            .collect();
+        assert!(wilcoxon_signed_rank(&a, &mixed) > 0.5);
+
+        assert_eq!(1., wilcoxon_signed_rank(&a, &a));
+    }
+
+    #[test]
+    fn erfc_known_values() {
+        assert!((erfc(0.) - 1.).abs() < 1e-7);
+        assert!((erfc(1.) - 0.157_299_207).abs() < 1e-7);
+        assert!((erfc(-1.) - 1.842_700_793).abs() < 1e-7);
+    }
+}
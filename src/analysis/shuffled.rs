@@ -0,0 +1,151 @@
+use rand::{Rng, SeedableRng, XorShiftRng};
+
+use super::{analyze, check_baseline};
+use benchmark::BenchmarkConfig;
+use error::TooSlowError;
+use report::{BenchmarkId, ReportContext};
+use routine::{iteration_counts, Routine};
+use {format, Criterion, DurationExt, Throughput};
+
+// The order of the inputs is shuffled with a fixed seed, so that it's the same on every run.
+const SEED: [u32; 4] = [0x193a_6754, 0xa8a7_d469, 0x9783_0e05, 0x113b_a7bb];
+
+// Benchmarks a routine over several inputs in the same measurement window, taking one sample of
+// each input after the other in a new random order every time, so that effects of the order of
+// the inputs on the caches and branch predictors average out.
+pub(crate) fn shuffled<T>(
+    inputs: &[(BenchmarkId, &T, Option<Throughput>)],
+    routine: &mut Routine<T>,
+    config: &BenchmarkConfig,
+    criterion: &Criterion,
+    report_context: &ReportContext,
+) {
+    for &(ref id, _, _) in inputs {
+        check_baseline(id, criterion);
+    }
+
+    // Warms up each input, leaving out those which are too slow to measure.
+    let wu = config.warm_up_time;
+    let m_ns = config.measurement_time.to_nanos() as f64;
+    let mut measured = vec![];
+    let mut programs = vec![];
+    let mut ramps = vec![];
+    let mut estimate_ns = 0.;
+    for &(ref id, parameter, ref throughput) in inputs {
+        criterion.report.benchmark_start(id, report_context);
+        criterion
+            .report
+            .warmup(id, report_context, wu.to_nanos() as f64);
+
+        let mut m = routine.start(parameter);
+        let (wu_elapsed, wu_iters) = routine.warm_up(&mut m, wu, parameter);
+        if wu_iters <= 1 {
+            let e = TooSlowError {
+                id: id.id().to_owned(),
+                warm_up: format::time(wu.to_nanos() as f64),
+            };
+            ::error::log_error(&e.into());
+            criterion.report.terminated(id, report_context);
+            continue;
+        }
+
+        let met = wu_elapsed as f64 / wu_iters as f64;
+        let ramp = iteration_counts(met, m_ns, config);
+        estimate_ns += ramp.iter().sum::<u64>() as f64 * met;
+        measured.push((id, parameter, throughput));
+        programs.push(m);
+        ramps.push(ramp);
+    }
+    if measured.is_empty() {
+        return;
+    }
+
+    let first = measured[0].0;
+    let total_iters = ramps.iter().flat_map(|ramp| ramp.iter()).sum();
+    criterion.report.measurement_start(
+        &BenchmarkId::new(first.group_id.clone(), first.function_id.clone(), None, None),
+        report_context,
+        (config.sample_size * measured.len()) as u64,
+        estimate_ns,
+        total_iters,
+    );
+
+    let mut rng = XorShiftRng::from_seed(SEED);
+    let times = shuffle_samples(
+        &ramps,
+        |order| rng.shuffle(order),
+        |input, iters| {
+            let parameter = measured[input].1;
+            routine.bench(&mut programs[input], &[iters], parameter)[0]
+        },
+    );
+
+    for ((&(id, _, throughput), ramp), times) in measured.iter().zip(ramps).zip(times) {
+        let iters: Vec<f64> = ramp.iter().map(|&n| n as f64).collect();
+
+        analyze(
+            id,
+            routine,
+            config,
+            criterion,
+            report_context,
+            throughput.clone(),
+            iters.into_boxed_slice(),
+            times.into_boxed_slice(),
+        );
+    }
+}
+
+// Takes the samples of all inputs, whose iteration counts are `ramps`, one sample of each input
+// at a time, in the order which `shuffle` puts the inputs in for that round. Returns the elapsed
+// times of the samples of each input.
+fn shuffle_samples<S, B>(ramps: &[Vec<u64>], mut shuffle: S, mut bench: B) -> Vec<Vec<f64>>
+where
+    S: FnMut(&mut [usize]),
+    B: FnMut(usize, u64) -> f64,
+{
+    let mut times = vec![vec![]; ramps.len()];
+    let mut order: Vec<usize> = (0..ramps.len()).collect();
+    for sample in 0..ramps[0].len() {
+        shuffle(&mut order);
+        for &input in &order {
+            times[input].push(bench(input, ramps[input][sample]));
+        }
+    }
+    times
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn shuffles_inputs_of_each_sample() {
+        let ramps: Vec<Vec<u64>> = (1..5).map(|input| vec![input; 20]).collect();
+        let mut rng = XorShiftRng::from_seed(SEED);
+        let mut order = vec![];
+
+        let times = shuffle_samples(
+            &ramps,
+            |inputs| rng.shuffle(inputs),
+            |input, iters| {
+                order.push(input);
+                iters as f64
+            },
+        );
+
+        // Every sample measures each input once...
+        let rounds: Vec<&[usize]> = order.chunks(ramps.len()).collect();
+        assert_eq!(20, rounds.len());
+        for round in &rounds {
+            let mut inputs = round.to_vec();
+            inputs.sort();
+            assert_eq!(vec![0, 1, 2, 3], inputs);
+        }
+        // ...but not always in the same order.
+        assert!(rounds.iter().any(|&round| round != rounds[0]));
+
+        // The times are collected per input, in the order of the samples.
+        assert_eq!(vec![4.; 20], times[3]);
+    }
+}
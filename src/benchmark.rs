@@ -1,23 +1,32 @@
 use analysis;
 use program::CommandFactory;
 use report::{BenchmarkId, ReportContext};
-use routine::{Function, Routine};
+use routine::{Controlled, Fixture, Function, Routine};
 use std::cell::RefCell;
 use std::collections::HashSet;
 use std::fmt::Debug;
 use std::marker::Sized;
 use std::process::Command;
+use std::rc::Rc;
 use std::time::Duration;
-use {Bencher, Criterion, DurationExt, PlotConfiguration, Throughput};
+use {Bencher, Criterion, DurationExt, PlotConfiguration, SampleWeighting, Throughput};
 
 /// Struct containing all of the configuration options for a benchmark.
 pub struct BenchmarkConfig {
+    pub change_hysteresis: f64,
     pub confidence_level: f64,
+    pub max_measurement_time: Duration,
+    pub measurement_repeats: usize,
     pub measurement_time: Duration,
+    pub min_iters_per_sample: u64,
     pub noise_threshold: f64,
     pub nresamples: usize,
     pub sample_size: usize,
+    pub sample_weighting: SampleWeighting,
     pub significance_level: f64,
+    pub tail_percentile: Option<f64>,
+    pub target_ci_width: Option<f64>,
+    pub timeout: Option<Duration>,
     pub warm_up_time: Duration,
 }
 
@@ -29,6 +38,7 @@ struct PartialBenchmarkConfig {
     nresamples: Option<usize>,
     sample_size: Option<usize>,
     significance_level: Option<f64>,
+    timeout: Option<Duration>,
     warm_up_time: Option<Duration>,
     plot_config: PlotConfiguration,
 }
@@ -42,6 +52,7 @@ impl Default for PartialBenchmarkConfig {
             nresamples: None,
             sample_size: None,
             significance_level: None,
+            timeout: None,
             warm_up_time: None,
             plot_config: PlotConfiguration::default(),
         }
@@ -51,13 +62,21 @@ impl Default for PartialBenchmarkConfig {
 impl PartialBenchmarkConfig {
     fn to_complete(&self, defaults: &BenchmarkConfig) -> BenchmarkConfig {
         BenchmarkConfig {
+            change_hysteresis: defaults.change_hysteresis,
             confidence_level: self.confidence_level.unwrap_or(defaults.confidence_level),
+            max_measurement_time: defaults.max_measurement_time,
+            measurement_repeats: defaults.measurement_repeats,
             measurement_time: self.measurement_time.unwrap_or(defaults.measurement_time),
+            min_iters_per_sample: defaults.min_iters_per_sample,
             noise_threshold: self.noise_threshold.unwrap_or(defaults.noise_threshold),
             nresamples: self.nresamples.unwrap_or(defaults.nresamples),
             sample_size: self.sample_size.unwrap_or(defaults.sample_size),
+            sample_weighting: defaults.sample_weighting,
             significance_level: self.significance_level
                 .unwrap_or(defaults.significance_level),
+            tail_percentile: defaults.tail_percentile,
+            target_ci_width: defaults.target_ci_width,
+            timeout: self.timeout.or(defaults.timeout),
             warm_up_time: self.warm_up_time.unwrap_or(defaults.warm_up_time),
         }
     }
@@ -73,6 +92,8 @@ pub struct NamedRoutine<T> {
 pub struct ParameterizedBenchmark<T: Debug> {
     config: PartialBenchmarkConfig,
     values: Vec<T>,
+    // Formatted values of the parameters of each value of a Cartesian product of parameters
+    axes: Vec<Vec<String>>,
     routines: Vec<NamedRoutine<T>>,
     throughput: Option<Box<Fn(&T) -> Throughput>>,
 }
@@ -206,6 +227,30 @@ macro_rules! benchmark_config {
             self
         }
 
+        /// Fails this benchmark if an iteration of the routine takes longer than `timeout`, so that
+        /// a routine which is far slower than expected doesn't hold up the other benchmarks.
+        ///
+        /// The timeout is checked against the mean time per iteration of each sample, so a single
+        /// slow iteration among many fast ones may go unnoticed. The benchmark is reported as
+        /// failed and skipped, and the run continues with the next benchmark.
+        ///
+        /// A routine which hangs, eg. because of a deadlock, is noticed by a watchdog thread once
+        /// its sample runs for ten times as long as the timeout allows. A thread can't be stopped
+        /// from the outside in Rust, and the stuck routine may hold locks or have left shared
+        /// state inconsistent, so the watchdog logs an error and exits the process instead; the
+        /// remaining benchmarks don't run. The routine itself never runs on another thread, so it
+        /// needn't be `Send`.
+        ///
+        /// # Panics
+        ///
+        /// Panics if the timeout is zero
+        pub fn timeout(mut self, timeout: Duration) -> Self {
+            assert!(timeout.to_nanos() > 0);
+
+            self.config.timeout = Some(timeout);
+            self
+        }
+
         /// Changes the plot configuration for this benchmark.
         pub fn plot_config(mut self, new_config: PlotConfiguration) -> Self {
             self.config.plot_config = new_config;
@@ -324,6 +369,137 @@ impl Benchmark {
         self
     }
 
+    /// Add a function which needs an expensive context, such as an open file or a running server,
+    /// to the benchmark group. `setup` creates the context once before the function is first run,
+    /// and the context is dropped once the function has been benchmarked. Unlike
+    /// `Bencher::iter_with_setup`, the same context is used by every iteration.
+    ///
+    /// # Example:
+    /// ```
+    /// # use criterion::Benchmark;
+    /// Benchmark::new("return 10", |b| b.iter(|| 10))
+    ///     .with_setup("sum", || vec![1u64; 1024], |b, v| b.iter(|| v.iter().sum::<u64>()));
+    /// ```
+    pub fn with_setup<S, C, F, T>(self, id: S, setup: C, f: F) -> Benchmark
+    where
+        S: Into<String>,
+        C: FnOnce() -> T + 'static,
+        F: FnMut(&mut Bencher, &mut T) + 'static,
+        T: 'static,
+    {
+        self.with_setup_and_teardown(id, setup, f, drop)
+    }
+
+    /// Like `with_setup`, but the context is passed to `teardown` once the function has been
+    /// benchmarked, instead of just being dropped.
+    ///
+    /// # Example:
+    /// ```
+    /// # use criterion::Benchmark;
+    /// # use std::fs::{self, File};
+    /// # use std::io::Write;
+    /// Benchmark::new("return 10", |b| b.iter(|| 10)).with_setup_and_teardown(
+    ///     "write",
+    ///     || File::create("scratch.txt").unwrap(),
+    ///     |b, file| b.iter(|| file.write_all(b"hello").unwrap()),
+    ///     |file| {
+    ///         drop(file);
+    ///         fs::remove_file("scratch.txt").unwrap();
+    ///     },
+    /// );
+    /// ```
+    pub fn with_setup_and_teardown<S, C, F, D, T>(
+        mut self,
+        id: S,
+        setup: C,
+        mut f: F,
+        teardown: D,
+    ) -> Benchmark
+    where
+        S: Into<String>,
+        C: FnOnce() -> T + 'static,
+        F: FnMut(&mut Bencher, &mut T) + 'static,
+        D: FnOnce(T) + 'static,
+        T: 'static,
+    {
+        // The context is shared between the routine and the hooks which create and tear it down
+        let context: Rc<RefCell<Option<T>>> = Rc::new(RefCell::new(None));
+        let routine_context = context.clone();
+        let setup_context = context.clone();
+        let mut setup = Some(setup);
+        let mut teardown = Some(teardown);
+
+        let function = Function::new(move |b: &mut Bencher, _: &()| {
+            let mut context = routine_context.borrow_mut();
+            f(b, context.as_mut().expect("Benchmark context was not set up"))
+        });
+        let fixture = Fixture::new(
+            function,
+            move || {
+                if let Some(setup) = setup.take() {
+                    *setup_context.borrow_mut() = Some(setup());
+                }
+            },
+            move || {
+                if let (Some(context), Some(teardown)) = (context.borrow_mut().take(), teardown.take())
+                {
+                    teardown(context);
+                }
+            },
+        );
+        let routine = NamedRoutine {
+            id: id.into(),
+            f: Box::new(RefCell::new(fixture)),
+        };
+        self.routines.push(routine);
+        self
+    }
+
+    /// Add a function to the benchmark group, along with a control function whose time per
+    /// iteration is known to be `control_ns` nanoseconds, eg. from a long calibration run.
+    ///
+    /// The control runs after each sample of the function, with the same number of iterations.
+    /// Noise which affects both alike, such as other load on the machine, shows up as a deviation
+    /// of the control from its known time, and is subtracted from the function's samples in
+    /// proportion to how strongly the two are correlated. This narrows the confidence intervals
+    /// of the estimates, at the cost of the time spent running the control. If the two are not
+    /// correlated, the samples are left nearly as they are.
+    ///
+    /// # Example:
+    /// ```
+    /// # use criterion::Benchmark;
+    /// Benchmark::new("return 10", |b| b.iter(|| 10)).with_control_variate(
+    ///     "sum",
+    ///     |b| b.iter(|| (0..1000u64).sum::<u64>()),
+    ///     |b| b.iter(|| (0..100u64).sum::<u64>()),
+    ///     25.0,
+    /// );
+    /// ```
+    pub fn with_control_variate<S, F, C>(
+        mut self,
+        id: S,
+        mut f: F,
+        mut control: C,
+        control_ns: f64,
+    ) -> Benchmark
+    where
+        S: Into<String>,
+        F: FnMut(&mut Bencher) + 'static,
+        C: FnMut(&mut Bencher) + 'static,
+    {
+        let controlled = Controlled::new(
+            Function::new(move |b: &mut Bencher, _: &()| f(b)),
+            Function::new(move |b: &mut Bencher, _: &()| control(b)),
+            control_ns,
+        );
+        let routine = NamedRoutine {
+            id: id.into(),
+            f: Box::new(RefCell::new(controlled)),
+        };
+        self.routines.push(routine);
+        self
+    }
+
     /// Add an external program to the benchmark group.
     ///
     /// # Example:
@@ -367,6 +543,8 @@ impl BenchmarkDefinition for Benchmark {
             plotting: c.plotting,
             plot_config: self.config.plot_config.clone(),
             test_mode: c.test_mode,
+            report_only_changed: c.report_only_changed,
+            run_label: c.run_label.clone(),
         };
 
         let config = self.config.to_complete(&c.config);
@@ -395,24 +573,31 @@ impl BenchmarkDefinition for Benchmark {
 
             if c.filter_matches(id.id()) {
                 any_matched = true;
+                let routine = &mut *routine.f.borrow_mut();
                 analysis::common(
                     &id,
-                    &mut *routine.f.borrow_mut(),
+                    routine,
                     &config,
                     c,
                     &report_context,
                     &(),
                     self.throughput.clone(),
                 );
+                routine.teardown();
             }
 
             all_ids.push(id);
         }
 
-        if all_ids.len() > 1 && any_matched && !c.measure_only && !c.test_mode {
+        if all_ids.len() > 1
+            && any_matched
+            && !c.measure_only
+            && !c.test_mode
+            && c.profile_time.is_none()
+        {
             c.report.summarize(&report_context, &all_ids);
         }
-        if any_matched {
+        if any_matched && !c.quiet {
             println!();
         }
     }
@@ -461,6 +646,7 @@ where
         ParameterizedBenchmark {
             config: PartialBenchmarkConfig::default(),
             values: parameters.into_iter().collect(),
+            axes: vec![],
             routines: vec![],
             throughput: None,
         }.with_function(id, f)
@@ -524,6 +710,7 @@ where
             config: PartialBenchmarkConfig::default(),
             routines: vec![],
             values: parameters.into_iter().collect(),
+            axes: vec![],
             throughput: None,
         }.with_program(id, program)
     }
@@ -535,6 +722,7 @@ where
         ParameterizedBenchmark {
             config: PartialBenchmarkConfig::default(),
             values: parameters,
+            axes: vec![],
             routines: functions,
             throughput: None,
         }
@@ -608,6 +796,67 @@ where
         self
     }
 }
+impl<X, Y> ParameterizedBenchmark<(X, Y)>
+where
+    X: Clone + Debug + 'static,
+    Y: Clone + Debug + 'static,
+{
+    /// Create a new parameterized benchmark group which benchmarks the given function with every
+    /// combination of the two given sets of parameters, for example every input size with every
+    /// number of threads.
+    ///
+    /// The value of each benchmark ID consists of the values of both parameters, such as
+    /// `1024/4`. In addition to the usual summary, the HTML report draws line charts of each
+    /// numeric parameter for every fixed value of the other one.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![macro_use] extern crate criterion;
+    /// # use criterion::*;
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     c.bench(
+    ///         "my_group",
+    ///         ParameterizedBenchmark::new_product(
+    ///             "my_function",
+    ///             |b, &(size, threads)| b.iter(|| {
+    ///                 // Code to benchmark using size and threads goes here
+    ///             }),
+    ///             vec![1024u64, 2048, 4096],
+    ///             vec![1u32, 4]
+    ///         )
+    ///     );
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    pub fn new_product<S, F, I, J>(id: S, f: F, xs: I, ys: J) -> ParameterizedBenchmark<(X, Y)>
+    where
+        S: Into<String>,
+        F: FnMut(&mut Bencher, &(X, Y)) + 'static,
+        I: IntoIterator<Item = X>,
+        J: IntoIterator<Item = Y>,
+    {
+        let ys: Vec<Y> = ys.into_iter().collect();
+        let values: Vec<(X, Y)> = xs.into_iter()
+            .flat_map(|x| ys.iter().map(move |y| (x.clone(), y.clone())))
+            .collect();
+        let axes = values
+            .iter()
+            .map(|&(ref x, ref y)| vec![format!("{:?}", x), format!("{:?}", y)])
+            .collect();
+
+        ParameterizedBenchmark {
+            config: PartialBenchmarkConfig::default(),
+            values,
+            axes,
+            routines: vec![],
+            throughput: None,
+        }.with_function(id, f)
+    }
+}
 impl<T> BenchmarkDefinition for ParameterizedBenchmark<T>
 where
     T: Debug + 'static,
@@ -618,6 +867,8 @@ where
             plotting: c.plotting,
             plot_config: self.config.plot_config.clone(),
             test_mode: c.test_mode,
+            report_only_changed: c.report_only_changed,
+            run_label: c.run_label.clone(),
         };
 
         let config = self.config.to_complete(&c.config);
@@ -628,7 +879,12 @@ where
         let mut any_matched = false;
         let mut all_directories = HashSet::new();
 
+        // Shuffling the inputs only makes a difference to the measurements.
+        let shuffle = c.shuffle_inputs && !c.list_mode && !c.test_mode && c.profile_time.is_none();
+
         for routine in self.routines {
+            let mut axes = self.axes.iter();
+            let mut inputs = vec![];
             for value in &self.values {
                 let function_id = if num_routines == 1 && group_id == routine.id {
                     None
@@ -636,19 +892,28 @@ where
                     Some(routine.id.clone())
                 };
 
-                let value_str = if num_parameters == 1 {
-                    None
-                } else {
-                    Some(format!("{:?}", value))
-                };
-
                 let throughput = self.throughput.as_ref().map(|func| func(value));
-                let mut id = BenchmarkId::new(
-                    group_id.to_owned(),
-                    function_id,
-                    value_str,
-                    throughput.clone(),
-                );
+                let mut id = match axes.next() {
+                    Some(axes) => BenchmarkId::new_product(
+                        group_id.to_owned(),
+                        function_id,
+                        axes.clone(),
+                        throughput.clone(),
+                    ),
+                    None => {
+                        let value_str = if num_parameters == 1 {
+                            None
+                        } else {
+                            Some(format!("{:?}", value))
+                        };
+                        BenchmarkId::new(
+                            group_id.to_owned(),
+                            function_id,
+                            value_str,
+                            throughput.clone(),
+                        )
+                    }
+                };
 
                 id.ensure_directory_name_unique(&all_directories);
                 all_directories.insert(id.as_directory_name().to_owned());
@@ -656,25 +921,44 @@ where
                 if c.filter_matches(id.id()) {
                     any_matched = true;
 
-                    analysis::common(
-                        &id,
-                        &mut *routine.f.borrow_mut(),
-                        &config,
-                        c,
-                        &report_context,
-                        value,
-                        throughput,
-                    );
+                    if shuffle {
+                        inputs.push((id.clone(), value, throughput));
+                    } else {
+                        analysis::common(
+                            &id,
+                            &mut *routine.f.borrow_mut(),
+                            &config,
+                            c,
+                            &report_context,
+                            value,
+                            throughput,
+                        );
+                    }
                 }
 
                 all_ids.push(id);
             }
+
+            if !inputs.is_empty() {
+                analysis::shuffled(
+                    &inputs,
+                    &mut *routine.f.borrow_mut(),
+                    &config,
+                    c,
+                    &report_context,
+                );
+            }
         }
 
-        if all_ids.len() > 1 && any_matched && !c.measure_only && !c.test_mode {
+        if all_ids.len() > 1
+            && any_matched
+            && !c.measure_only
+            && !c.test_mode
+            && c.profile_time.is_none()
+        {
             c.report.summarize(&report_context, &all_ids);
         }
-        if any_matched {
+        if any_matched && !c.quiet {
             println!();
         }
     }
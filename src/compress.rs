@@ -0,0 +1,146 @@
+//! Minimal gzip (RFC 1952) encoding of the files Criterion.rs saves, on top of the raw deflate
+//! streams of `miniz_oxide`.
+
+use miniz_oxide::deflate::compress_to_vec;
+use miniz_oxide::inflate::decompress_to_vec;
+
+const MAGIC: [u8; 2] = [0x1f, 0x8b];
+const DEFLATE: u8 = 8;
+const LEVEL: u8 = 6;
+
+// Header flags
+const FHCRC: u8 = 1 << 1;
+const FEXTRA: u8 = 1 << 2;
+const FNAME: u8 = 1 << 3;
+const FCOMMENT: u8 = 1 << 4;
+
+/// Whether `data` starts like a gzip file.
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.len() >= 2 && data[..2] == MAGIC
+}
+
+/// Compresses `data` into a single gzip member.
+pub fn gzip(data: &[u8]) -> Vec<u8> {
+    // No flags, no modification time, default compression, unknown OS
+    let mut out = vec![MAGIC[0], MAGIC[1], DEFLATE, 0, 0, 0, 0, 0, 0, 255];
+    out.extend(compress_to_vec(data, LEVEL));
+    out.extend(&u32_le(crc32(data)));
+    out.extend(&u32_le(data.len() as u32));
+    out
+}
+
+/// Decompresses the first member of the gzip file `data`, describing the problem if it's not a
+/// valid gzip file.
+pub fn gunzip(data: &[u8]) -> Result<Vec<u8>, String> {
+    if !is_gzip(data) || data.len() < 18 {
+        return Err("not a gzip file".to_owned());
+    }
+    if data[2] != DEFLATE {
+        return Err(format!("unsupported compression method {}", data[2]));
+    }
+
+    // Skip the optional fields of the header
+    let flags = data[3];
+    let mut start = 10;
+    if flags & FEXTRA != 0 {
+        let len = data.get(start..start + 2).ok_or("truncated header")?;
+        start += 2 + (usize::from(len[0]) | usize::from(len[1]) << 8);
+    }
+    for &flag in &[FNAME, FCOMMENT] {
+        if flags & flag != 0 {
+            let len = data[start.min(data.len())..]
+                .iter()
+                .position(|&b| b == 0)
+                .ok_or("truncated header")?;
+            start += len + 1;
+        }
+    }
+    if flags & FHCRC != 0 {
+        start += 2;
+    }
+    if start > data.len() - 8 {
+        return Err("truncated header".to_owned());
+    }
+
+    let out = decompress_to_vec(&data[start..])
+        .map_err(|e| format!("invalid deflate stream: {:?}", e.status))?;
+
+    let trailer = &data[data.len() - 8..];
+    if trailer[..4] != u32_le(crc32(&out)) || trailer[4..] != u32_le(out.len() as u32) {
+        return Err("checksum mismatch".to_owned());
+    }
+    Ok(out)
+}
+
+fn u32_le(n: u32) -> [u8; 4] {
+    [n as u8, (n >> 8) as u8, (n >> 16) as u8, (n >> 24) as u8]
+}
+
+// CRC-32 as used by gzip, bit by bit. The files are small enough that a table isn't worth it.
+fn crc32(data: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for &byte in data {
+        crc ^= u32::from(byte);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use fs;
+    use std::env;
+    use std::process;
+
+    #[test]
+    fn crc32_check_value() {
+        assert_eq!(0xCBF4_3926, crc32(b"123456789"));
+    }
+
+    #[test]
+    fn gzip_round_trip() {
+        let data: Vec<u8> = (0..10_000).map(|i| (i % 251) as u8).collect();
+        let compressed = gzip(&data);
+
+        assert!(is_gzip(&compressed));
+        assert!(compressed.len() < data.len());
+        assert_eq!(Ok(data), gunzip(&compressed));
+
+        let mut corrupted = compressed.clone();
+        let last = corrupted.len() - 5;
+        corrupted[last] ^= 1;
+        assert!(gunzip(&corrupted).is_err());
+        assert!(gunzip(b"{\"not\": \"gzip\"}").is_err());
+    }
+
+    #[test]
+    fn compressed_samples_read_back_identically() {
+        let dir = env::temp_dir().join(format!("criterion-compress-{}", process::id()));
+        fs::mkdirp(&dir).unwrap();
+        let iters: Vec<f64> = (1..101).map(f64::from).collect();
+        let times: Vec<f64> = iters.iter().map(|i| i * 1234.25).collect();
+        let sample = (iters, times);
+
+        let plain = dir.join("plain.json");
+        let compressed = dir.join("compressed.json");
+        fs::save_sample(&sample, &plain, false).unwrap();
+        fs::save_sample(&sample, &compressed, true).unwrap();
+
+        let plain_bytes = ::std::fs::read(&plain).unwrap();
+        let compressed_bytes = ::std::fs::read(&compressed).unwrap();
+        assert!(!is_gzip(&plain_bytes));
+        assert!(is_gzip(&compressed_bytes));
+        assert!(compressed_bytes.len() < plain_bytes.len());
+
+        let from_plain: (Vec<f64>, Vec<f64>) = fs::load(&plain).unwrap();
+        let from_compressed: (Vec<f64>, Vec<f64>) = fs::load(&compressed).unwrap();
+        assert_eq!(sample, from_plain);
+        assert_eq!(from_plain, from_compressed);
+
+        ::std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,7 +1,9 @@
 use csv::Writer;
 use error::Result;
-use report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use estimate::Statistic;
+use report::{BenchmarkId, MeasurementData, ReportContext, Reporter};
 use std::io::Write;
+use std::path::Path;
 
 #[derive(Serialize)]
 struct CsvRow<'a> {
@@ -10,6 +12,11 @@ struct CsvRow<'a> {
     value: Option<&'a str>,
     sample_time_nanos: f64,
     iteration_count: u64,
+    // The estimates of the time per iteration, which are the same for every sample of a benchmark.
+    mean_nanos: f64,
+    median_nanos: f64,
+    std_dev_nanos: f64,
+    median_abs_dev_nanos: f64,
 }
 
 struct CsvReportWriter<W: Write> {
@@ -17,6 +24,7 @@ struct CsvReportWriter<W: Write> {
 }
 impl<W: Write> CsvReportWriter<W> {
     fn write_data(&mut self, id: &BenchmarkId, data: &MeasurementData) -> Result<()> {
+        let estimate = |statistic| data.absolute_estimates[&statistic].point_estimate;
         for (count, time) in data.iter_counts
             .as_slice()
             .iter()
@@ -28,6 +36,10 @@ impl<W: Write> CsvReportWriter<W> {
                 value: id.value_str.as_ref().map(String::as_str),
                 sample_time_nanos: *time,
                 iteration_count: (*count) as u64,
+                mean_nanos: estimate(Statistic::Mean),
+                median_nanos: estimate(Statistic::Median),
+                std_dev_nanos: estimate(Statistic::StdDev),
+                median_abs_dev_nanos: estimate(Statistic::MedianAbsDev),
             };
             self.writer.serialize(row)?;
         }
@@ -35,6 +47,39 @@ impl<W: Write> CsvReportWriter<W> {
     }
 }
 
+#[derive(Serialize)]
+struct SampleRow<'a> {
+    group: &'a str,
+    function_id: Option<&'a str>,
+    input: Option<f64>,
+    sample_index: usize,
+    value_ns: f64,
+}
+
+/// Saves the average iteration times of the benchmarks of a group to `path` as a CSV file with
+/// one row per measurement, for analyzing them with external tools. Inputs which aren't numbers
+/// are left empty.
+pub fn write_samples_csv(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    path: &Path,
+) -> Result<()> {
+    let mut writer = Writer::from_path(path)?;
+    for &&(ref id, ref sample) in all_curves {
+        for (sample_index, &value_ns) in sample.iter().enumerate() {
+            writer.serialize(SampleRow {
+                group: group_id,
+                function_id: id.function_id.as_ref().map(String::as_str),
+                input: id.as_number(),
+                sample_index,
+                value_ns,
+            })?;
+        }
+    }
+    writer.flush()?;
+    Ok(())
+}
+
 pub struct FileCsvReport;
 impl FileCsvReport {
     fn write_file(
@@ -50,8 +95,8 @@ impl FileCsvReport {
     }
 }
 
-impl Report for FileCsvReport {
-    fn measurement_complete(
+impl Reporter for FileCsvReport {
+    fn measured(
         &self,
         id: &BenchmarkId,
         context: &ReportContext,
@@ -64,4 +109,105 @@ impl Report for FileCsvReport {
         );
         log_if_err!(self.write_file(path, id, measurements));
     }
+
+    fn saves_files(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use csv::Reader;
+    use stats::univariate::outliers::tukey;
+    use stats::univariate::Sample;
+    use std::collections::BTreeMap;
+    use std::{env, process};
+    use {ConfidenceInterval, Estimate};
+
+    #[test]
+    fn rows_include_estimates() {
+        let id = BenchmarkId::new("group".to_owned(), Some("function".to_owned()), None, None);
+        let iters = [1.0, 2.0];
+        let times = [10.0, 22.0];
+        let avg_times = [10.0, 11.0];
+        let mut estimates = BTreeMap::new();
+        for &(statistic, value) in &[
+            (Statistic::Mean, 10.5),
+            (Statistic::Median, 10.25),
+            (Statistic::MedianAbsDev, 0.75),
+            (Statistic::Slope, 10.75),
+            (Statistic::StdDev, 0.5),
+        ] {
+            let estimate = Estimate {
+                confidence_interval: ConfidenceInterval {
+                    confidence_level: 0.95,
+                    lower_bound: value,
+                    upper_bound: value,
+                },
+                point_estimate: value,
+                standard_error: 0.0,
+            };
+            estimates.insert(statistic, estimate);
+        }
+        let data = MeasurementData {
+            iter_counts: Sample::new(&iters),
+            sample_times: Sample::new(&times),
+            avg_times: tukey::classify(Sample::new(&avg_times)),
+            absolute_estimates: estimates,
+            distributions: BTreeMap::new(),
+            comparison: None,
+            throughput: None,
+        };
+
+        let mut writer = CsvReportWriter {
+            writer: Writer::from_writer(vec![]),
+        };
+        writer.write_data(&id, &data).unwrap();
+        let csv = String::from_utf8(writer.writer.into_inner().unwrap()).unwrap();
+
+        assert_eq!(
+            "group,function,value,sample_time_nanos,iteration_count,\
+             mean_nanos,median_nanos,std_dev_nanos,median_abs_dev_nanos\n\
+             group,function,,10.0,1,10.5,10.25,0.5,0.75\n\
+             group,function,,22.0,2,10.5,10.25,0.5,0.75\n",
+            csv
+        );
+    }
+
+    #[test]
+    fn samples_csv_has_a_row_per_measurement() {
+        let curve = |function: &str, input: &str, times: Vec<f64>| {
+            let id = BenchmarkId::new(
+                "group".to_owned(),
+                Some(function.to_owned()),
+                Some(input.to_owned()),
+                None,
+            );
+            (id, times)
+        };
+        let small = curve("sort", "10", vec![1.0, 2.0, 3.0]);
+        let large = curve("sort", "100", vec![10.0, 20.0, 30.0, 40.0]);
+        let named = curve("sort", "random", vec![5.0, 6.0]);
+
+        let dir = env::temp_dir().join(format!("criterion-samples-csv-{}", process::id()));
+        ::fs::mkdirp(&dir).unwrap();
+        let path = dir.join("samples.csv");
+        write_samples_csv("group", &[&small, &large, &named], &path).unwrap();
+
+        let mut reader = Reader::from_path(&path).unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let rows: Vec<_> = reader.records().map(|row| row.unwrap()).collect();
+        ::std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(
+            vec!["group", "function_id", "input", "sample_index", "value_ns"],
+            headers.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(3 + 4 + 2, rows.len());
+        let count = |input: &str| rows.iter().filter(|row| &row[2] == input).count();
+        assert_eq!(3, count("10.0"));
+        assert_eq!(4, count("100.0"));
+        assert_eq!(2, count(""));
+        assert_eq!(vec!["group", "sort", "100.0", "3", "40.0"], rows[6].iter().collect::<Vec<_>>());
+    }
 }
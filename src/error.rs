@@ -20,6 +20,36 @@ pub struct CopyError {
     pub inner: io::Error,
 }
 
+#[cfg(feature = "compression")]
+#[derive(Debug, Fail)]
+#[fail(display = "Failed to decompress file {:?}: {}", path, reason)]
+pub struct DecompressError {
+    pub path: PathBuf,
+    pub reason: String,
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "Benchmark {} did not complete a single iteration within the warm-up time of {}; skipping it. Increase the warm-up time or reduce the work done by the routine.",
+    id,
+    warm_up
+)]
+pub struct TooSlowError {
+    pub id: String,
+    pub warm_up: String,
+}
+
+#[derive(Debug, Fail)]
+#[fail(
+    display = "Benchmark {} took longer than the timeout of {} per iteration; skipping it.",
+    id,
+    timeout
+)]
+pub struct TimeoutError {
+    pub id: String,
+    pub timeout: String,
+}
+
 pub type Result<T> = ::std::result::Result<T, Error>;
 
 pub(crate) fn log_error(e: &Error) {
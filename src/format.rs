@@ -1,55 +1,133 @@
-use Throughput;
+use stats::univariate::Sample;
+use {Estimate, Throughput};
 
-pub fn change(pct: f64, signed: bool) -> String {
-    if signed {
-        format!("{:>+6}%", signed_short(pct * 1e2))
+const HISTOGRAM_BINS: usize = 10;
+
+/// Formats a relative change as a signed percentage with two decimals, eg. ` -5.23%` for -0.0523.
+/// Changes which round to zero are shown as `+0.00%`, never as `-0.00%`.
+pub fn change(pct: f64) -> String {
+    let pct = pct * 1e2;
+    if pct.abs() < 0.005 {
+        format!("{:>+6.2}%", 0.0)
     } else {
-        format!("{:>6}%", short(pct * 1e2))
+        format!("{:>+6.2}%", pct)
     }
 }
 
+/// Formats a relative change together with its confidence interval, eg. `+3.4000% [+1.1000%,
+/// +5.7000%]`. Changes of exactly zero are shown as `0%`.
+pub fn change_interval(estimate: &Estimate) -> String {
+    let pct = |change: f64| {
+        if change == 0.0 {
+            "0".to_owned()
+        } else {
+            signed_short(change * 1e2)
+        }
+    };
+    format!(
+        "{}% [{}%, {}%]",
+        pct(estimate.point_estimate),
+        pct(estimate.confidence_interval.lower_bound),
+        pct(estimate.confidence_interval.upper_bound)
+    )
+}
+
 fn short(n: f64) -> String {
-    if n < 10.0 {
-        format!("{:.4}", n)
-    } else if n < 100.0 {
-        format!("{:.3}", n)
-    } else if n < 1000.0 {
-        format!("{:.2}", n)
-    } else if n < 10000.0 {
-        format!("{:.1}", n)
-    } else {
-        format!("{:.0}", n)
-    }
+    format!("{:.*}", decimals(n), n)
 }
 
 fn signed_short(n: f64) -> String {
-    let n_abs = n.abs();
-
-    if n_abs < 10.0 {
-        format!("{:+.4}", n)
-    } else if n_abs < 100.0 {
-        format!("{:+.3}", n)
-    } else if n_abs < 1000.0 {
-        format!("{:+.2}", n)
-    } else if n_abs < 10000.0 {
-        format!("{:+.1}", n)
-    } else {
-        format!("{:+.0}", n)
+    format!("{:+.*}", decimals(n), n)
+}
+
+// Numbers get fewer decimals as they grow, to keep them at most six digits long. Rounding may
+// carry into the next magnitude, eg. 9999.96 to 10000.0, which then gets one decimal less.
+fn decimals(n: f64) -> usize {
+    let rounded = |decimals: usize| -> f64 { format!("{:.*}", decimals, n.abs()).parse().unwrap() };
+    [(10.0, 4), (100.0, 3), (1000.0, 2), (10000.0, 1)]
+        .iter()
+        .find(|&&(limit, decimals)| rounded(decimals) < limit)
+        .map_or(0, |&(_, decimals)| decimals)
+}
+
+/// A unit which `time_in` formats times in.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TimeUnit {
+    Femtos,
+    Picos,
+    Nanos,
+    Micros,
+    Millis,
+    Secs,
+}
+
+impl TimeUnit {
+    /// The unit which suits the magnitude of the time `ns`. Femtoseconds are the smallest unit;
+    /// shorter times are fractions of a femtosecond.
+    pub fn of(ns: f64) -> TimeUnit {
+        if ns < 10f64.powi(-3) {
+            TimeUnit::Femtos
+        } else if ns < 1.0 {
+            TimeUnit::Picos
+        } else if ns < 10f64.powi(3) {
+            TimeUnit::Nanos
+        } else if ns < 10f64.powi(6) {
+            TimeUnit::Micros
+        } else if ns < 10f64.powi(9) {
+            TimeUnit::Millis
+        } else {
+            TimeUnit::Secs
+        }
+    }
+
+    // The time in this unit, and the suffix of the unit
+    fn scale(self, ns: f64) -> (f64, &'static str) {
+        match self {
+            TimeUnit::Femtos => (ns * 1e6, "fs"),
+            TimeUnit::Picos => (ns * 1e3, "ps"),
+            TimeUnit::Nanos => (ns, "ns"),
+            TimeUnit::Micros => (ns / 1e3, "us"),
+            TimeUnit::Millis => (ns / 1e6, "ms"),
+            TimeUnit::Secs => (ns / 1e9, "s"),
+        }
     }
 }
 
+/// Scales a time to the unit which suits its magnitude, and returns the scaled time with the
+/// suffix of the unit, eg. `(1.5, "ms")` for 1.5e6 ns, for those who format times themselves.
+pub fn scale_time_value(ns: f64) -> (f64, &'static str) {
+    TimeUnit::of(ns).scale(ns)
+}
+
 pub fn time(ns: f64) -> String {
-    if ns < 1.0 {
-        format!("{:>6} ps", short(ns * 1e3))
-    } else if ns < 10f64.powi(3) {
-        format!("{:>6} ns", short(ns))
-    } else if ns < 10f64.powi(6) {
-        format!("{:>6} us", short(ns / 1e3))
-    } else if ns < 10f64.powi(9) {
-        format!("{:>6} ms", short(ns / 1e6))
-    } else {
-        format!("{:>6} s", short(ns / 1e9))
-    }
+    let (value, unit) = scale_time_value(ns);
+    format!("{:>6} {}", short(value), unit)
+}
+
+/// Like `time`, but always in `unit` rather than in the unit which suits the magnitude of the
+/// time, so that a table of times can share a single unit.
+pub fn time_in(ns: f64, unit: TimeUnit) -> String {
+    let (value, unit) = unit.scale(ns);
+    format!("{:>6} {}", short(value), unit)
+}
+
+/// Like `time`, but padded to the same width whatever the unit, so that times line up in columns.
+pub fn time_column(ns: f64) -> String {
+    format!("{:<9}", time(ns))
+}
+
+/// Formats a time estimate together with its confidence interval, eg. `1.5000 us [1.2000 us
+/// 1.9000 us]`, in columns which line up across estimates. The bounds are shown in the unit of the
+/// point estimate, even if their own magnitude suits another unit.
+pub fn time_interval(estimate: &Estimate) -> String {
+    let unit = TimeUnit::of(estimate.point_estimate);
+    let column = |ns: f64| format!("{:<9}", time_in(ns, unit));
+    format!(
+        "{} [{} {}]",
+        column(estimate.point_estimate),
+        column(estimate.confidence_interval.lower_bound),
+        column(estimate.confidence_interval.upper_bound)
+    )
 }
 
 pub fn throughput(throughput: &Throughput, ns: f64) -> String {
@@ -59,9 +137,22 @@ pub fn throughput(throughput: &Throughput, ns: f64) -> String {
     }
 }
 
+// Like `short`, but rates too small to show with four decimals, eg. one byte every few hours, are
+// shown in scientific notation instead of as zero.
+fn short_rate(n: f64) -> String {
+    if n == 0.0 {
+        "0".to_owned()
+    } else if n < 1e-4 {
+        format!("{:.1e}", n)
+    } else {
+        short(n)
+    }
+}
+
+/// Formats a throughput in bytes per second, in powers of 1024.
 pub fn bytes_per_second(bytes_per_second: f64) -> String {
     if bytes_per_second < 1024.0 {
-        format!("{:>6}   B/s", short(bytes_per_second))
+        format!("{:>6}   B/s", short_rate(bytes_per_second))
     } else if bytes_per_second < 1024.0 * 1024.0 {
         format!("{:>6} KiB/s", short(bytes_per_second / 1024.0))
     } else if bytes_per_second < 1024.0 * 1024.0 * 1024.0 {
@@ -74,9 +165,10 @@ pub fn bytes_per_second(bytes_per_second: f64) -> String {
     }
 }
 
+/// Formats a throughput in elements per second, in powers of 1000.
 pub fn elements_per_second(elements_per_second: f64) -> String {
     if elements_per_second < 1000.0 {
-        format!("{:>6}  elem/s", short(elements_per_second))
+        format!("{:>6}  elem/s", short_rate(elements_per_second))
     } else if elements_per_second < 1000.0 * 1000.0 {
         format!("{:>6} Kelem/s", short(elements_per_second / 1000.0))
     } else if elements_per_second < 1000.0 * 1000.0 * 1000.0 {
@@ -84,17 +176,22 @@ pub fn elements_per_second(elements_per_second: f64) -> String {
             "{:>6} Melem/s",
             short(elements_per_second / (1000.0 * 1000.0))
         )
-    } else {
+    } else if elements_per_second < 1000.0 * 1000.0 * 1000.0 * 1000.0 {
         format!(
             "{:>6} Gelem/s",
             short(elements_per_second / (1000.0 * 1000.0 * 1000.0))
         )
+    } else {
+        format!(
+            "{:>6} Telem/s",
+            short(elements_per_second / (1000.0 * 1000.0 * 1000.0 * 1000.0))
+        )
     }
 }
 
 pub fn iter_count(iterations: u64) -> String {
     if iterations < 10_000 {
-        format!("{} iterations", iterations)
+        iter_count_full(iterations)
     } else if iterations < 1_000_000 {
         format!("{:.0}k iterations", (iterations as f64) / 1000.0)
     } else if iterations < 10_000_000 {
@@ -114,9 +211,44 @@ pub fn iter_count(iterations: u64) -> String {
     }
 }
 
+/// Formats the exact number of iterations, with the digits grouped in thousands, eg.
+/// `1,234,567 iterations`.
+pub fn iter_count_full(iterations: u64) -> String {
+    let digits = iterations.to_string();
+    let mut grouped = String::with_capacity(digits.len() * 4 / 3);
+    for (i, digit) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+    format!("{} iterations", grouped)
+}
+
+/// Renders the distribution of a sample as rows of `#` characters, one row per bin, labeled with
+/// the lower edge of the bin. The fullest bin is `width` characters wide.
+pub fn text_histogram(sample: &Sample<f64>, width: usize) -> String {
+    let counts = sample.histogram(HISTOGRAM_BINS);
+    let max_count = *counts.iter().max().unwrap();
+    let min = sample.min();
+    let bin_width = (sample.max() - min) / HISTOGRAM_BINS as f64;
+
+    counts
+        .iter()
+        .enumerate()
+        .map(|(i, &count)| {
+            // Round up, so that no bin with data points looks empty
+            let bar = (count * width + max_count - 1) / max_count;
+            format!("{} |{}", time(min + i as f64 * bin_width), "#".repeat(bar))
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use ConfidenceInterval;
 
     #[test]
     fn short_max_len() {
@@ -129,6 +261,24 @@ mod test {
         }
     }
 
+    #[test]
+    fn short_large_numbers() {
+        assert_eq!("9999.9", short(9999.9));
+        assert_eq!("10000", short(10000.0));
+        assert_eq!("1234567", short(1234567.0));
+        assert_eq!("54322", short(54321.7));
+
+        // Rounding up into the next magnitude drops a decimal.
+        assert_eq!("10000", short(9999.96));
+        assert_eq!("100.00", short(99.99996));
+        assert_eq!("+1000.0", signed_short(999.996));
+
+        // Values which are too long for the column aren't cut off.
+        assert_eq!(" 10000 s", time(1e13));
+        assert_eq!("1234567 s", time(1234567e9));
+        assert_eq!("1000.0 ns", time(999.99996));
+    }
+
     #[test]
     fn signed_short_max_len() {
         let mut float = -1.0;
@@ -139,4 +289,161 @@ mod test {
             float *= 2.0;
         }
     }
+
+    #[test]
+    fn change_percentages() {
+        assert_eq!(" +5.23%", change(0.0523));
+        assert_eq!(" -5.23%", change(-0.0523));
+        assert_eq!("+12.00%", change(0.12));
+        assert_eq!(" +0.00%", change(0.0));
+        assert_eq!(" +0.00%", change(-0.0));
+        assert_eq!(" +0.00%", change(-0.00004));
+        assert_eq!(" -0.01%", change(-0.00006));
+        assert_eq!("+1250.00%", change(12.5));
+        assert_eq!("-99.90%", change(-0.999));
+    }
+
+    #[test]
+    fn change_interval_brackets_point() {
+        let estimate = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: 0.011,
+                upper_bound: 0.057,
+            },
+            point_estimate: 0.034,
+            standard_error: 0.012,
+        };
+        let formatted = change_interval(&estimate);
+        assert_eq!("+3.4000% [+1.1000%, +5.7000%]", formatted);
+
+        let numbers: Vec<f64> = formatted
+            .split(&['%', '[', ']', ','][..])
+            .filter_map(|s| s.trim().parse().ok())
+            .collect();
+        let (point, lower, upper) = (numbers[0], numbers[1], numbers[2]);
+        assert!(lower <= point && point <= upper);
+    }
+
+    #[test]
+    fn time_interval_brackets_point() {
+        let estimate = |point_estimate: f64| Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: point_estimate * 0.8,
+                upper_bound: point_estimate * 1.25,
+            },
+            point_estimate,
+            standard_error: point_estimate * 0.1,
+        };
+        let formatted = time_interval(&estimate(1500.));
+        assert_eq!("1.5000 us [1.2000 us 1.8750 us]", formatted);
+
+        let numbers: Vec<f64> = formatted
+            .split(&[' ', '[', ']'][..])
+            .filter_map(|s| s.parse().ok())
+            .collect();
+        let (point, lower, upper) = (numbers[0], numbers[1], numbers[2]);
+        assert!(lower <= point && point <= upper);
+
+        // Seconds have a shorter unit than the others.
+        assert_eq!("2.0000 s ", time_column(2e9));
+        assert_eq!(formatted.len(), time_interval(&estimate(2e9)).len());
+        assert_eq!(formatted.len(), time_interval(&estimate(0.5)).len());
+
+        // The upper bound is in the unit of the point estimate, not in microseconds.
+        assert_eq!("900.00 ns [720.00 ns 1125.0 ns]", time_interval(&estimate(900.)));
+    }
+
+    #[test]
+    fn time_picosecond_femtosecond_boundary() {
+        assert_eq!("1.0000 ps", time(1e-3));
+        assert_eq!("999.00 fs", time(0.999e-3));
+        assert_eq!("1.0000 fs", time(1e-6));
+        assert_eq!("0.2500 fs", time(0.25e-6));
+        assert_eq!("999.00 ps", time(0.999));
+        assert_eq!("1.0000 ns", time(1.0));
+    }
+
+    #[test]
+    fn time_in_fixed_unit() {
+        assert_eq!("1.5000 us", time_in(1500.0, TimeUnit::Micros));
+        assert_eq!("1500.0 ns", time_in(1500.0, TimeUnit::Nanos));
+        assert_eq!("1500000 ps", time_in(1500.0, TimeUnit::Picos));
+        assert_eq!("0.0015 ms", time_in(1500.0, TimeUnit::Millis));
+        assert_eq!("2.5000 s", time_in(2.5e9, TimeUnit::Secs));
+        assert_eq!("2500.0 ms", time_in(2.5e9, TimeUnit::Millis));
+
+        assert_eq!(time(1500.0), time_in(1500.0, TimeUnit::Micros));
+    }
+
+    #[test]
+    fn scaled_time_values() {
+        assert_eq!((500.0, "ns"), scale_time_value(500.0));
+        assert_eq!((1.5, "ms"), scale_time_value(1.5e6));
+        assert_eq!((250.0, "ps"), scale_time_value(0.25));
+        assert_eq!((2.0, "s"), scale_time_value(2e9));
+        assert_eq!((0.25, "fs"), scale_time_value(0.25e-6));
+
+        // The units switch at the same magnitudes as in `time`.
+        assert_eq!((1.0, "us"), scale_time_value(1e3));
+        assert_eq!((999.0, "us"), scale_time_value(999e3));
+    }
+
+    #[test]
+    fn throughput_units() {
+        assert_eq!("     0   B/s", bytes_per_second(0.0));
+        assert_eq!("     0   B/s", throughput(&Throughput::Bytes(0), 1e9));
+        assert_eq!("0.2500   B/s", bytes_per_second(0.25));
+        assert_eq!("1.0e-5   B/s", bytes_per_second(1e-5));
+        assert_eq!("1023.0   B/s", bytes_per_second(1023.0));
+        assert_eq!("1.0000 KiB/s", bytes_per_second(1024.0));
+        assert_eq!("1.5000 MiB/s", bytes_per_second(1.5 * 1024.0 * 1024.0));
+        assert_eq!("2.0000 GiB/s", throughput(&Throughput::Bytes(2 << 30), 1e9));
+
+        assert_eq!("     0  elem/s", elements_per_second(0.0));
+        assert_eq!("1.0000 Kelem/s", elements_per_second(1e3));
+        assert_eq!("1.0000 Melem/s", elements_per_second(1e6));
+        assert_eq!("1.0000 Gelem/s", elements_per_second(1e9));
+        assert_eq!("2.5000 Telem/s", elements_per_second(2.5e12));
+
+        // The numbers line up with those of `time`.
+        assert_eq!(time(1.0).find(' '), bytes_per_second(1.0).find(' '));
+    }
+
+    #[test]
+    fn iteration_counts() {
+        assert_eq!("999 iterations", iter_count(999));
+        assert_eq!("1,000 iterations", iter_count(1000));
+        assert_eq!("9,999 iterations", iter_count(9999));
+        assert_eq!("10k iterations", iter_count(10_000));
+        assert_eq!("1.2M iterations", iter_count(1_234_567));
+
+        assert_eq!("0 iterations", iter_count_full(0));
+        assert_eq!("999 iterations", iter_count_full(999));
+        assert_eq!("1,000 iterations", iter_count_full(1000));
+        assert_eq!("10,000 iterations", iter_count_full(10_000));
+        assert_eq!("1,234,567 iterations", iter_count_full(1_234_567));
+        assert_eq!(
+            "18,446,744,073,709,551,615 iterations",
+            iter_count_full(u64::max_value())
+        );
+    }
+
+    #[test]
+    fn text_histogram_bimodal() {
+        let mut data: Vec<f64> = (0..40).map(|i| f64::from(100 + i % 5)).collect();
+        data.extend((0..40).map(|i| f64::from(200 - i % 5)));
+        data.extend(vec![150.0; 5]);
+
+        let histogram = text_histogram(Sample::new(&data), 40);
+        let bars: Vec<usize> = histogram.lines().map(|line| line.matches('#').count()).collect();
+
+        assert_eq!(10, bars.len());
+        assert_eq!(40, bars[0]);
+        assert_eq!(40, bars[9]);
+        assert_eq!(5, bars[5]);
+        assert!(bars[1..9].iter().all(|&bar| bar < 40));
+        assert!(histogram.starts_with(&time(100.0)));
+    }
 }
@@ -7,6 +7,10 @@ use std::io::Read;
 use std::path::Path;
 use walkdir::{DirEntry, WalkDir};
 
+#[cfg(feature = "compression")]
+use compress;
+#[cfg(feature = "compression")]
+use error::DecompressError;
 use error::{AccessError, CopyError, Result};
 use report::BenchmarkId;
 
@@ -19,9 +23,21 @@ where
         inner,
         path: path.as_ref().to_owned(),
     })?;
-    let mut string = String::new();
-    let _ = f.read_to_string(&mut string);
-    let result: A = serde_json::from_str(string.as_str())?;
+    let mut bytes = vec![];
+    let _ = f.read_to_end(&mut bytes);
+
+    // Compressed files are recognized by their contents, so that they keep their names
+    #[cfg(feature = "compression")]
+    {
+        if compress::is_gzip(&bytes) {
+            bytes = compress::gunzip(&bytes).map_err(|reason| DecompressError {
+                path: path.as_ref().to_owned(),
+                reason,
+            })?;
+        }
+    }
+
+    let result: A = serde_json::from_slice(&bytes)?;
 
     Ok(result)
 }
@@ -63,14 +79,39 @@ where
     save_string(&buf, path)
 }
 
+/// Saves raw samples, gzip-compressed if `compress` is set. `load` reads either.
+pub fn save_sample<D, P>(data: &D, path: &P, compress: bool) -> Result<()>
+where
+    D: Serialize,
+    P: AsRef<Path>,
+{
+    #[cfg(feature = "compression")]
+    {
+        if compress {
+            let buf = compress::gzip(&serde_json::to_vec(&data)?);
+            return save_bytes(&buf, path);
+        }
+    }
+    let _ = compress;
+
+    save(data, path)
+}
+
 pub fn save_string<P>(data: &str, path: &P) -> Result<()>
+where
+    P: AsRef<Path>,
+{
+    save_bytes(data.as_bytes(), path)
+}
+
+pub fn save_bytes<P>(data: &[u8], path: &P) -> Result<()>
 where
     P: AsRef<Path>,
 {
     use std::io::Write;
 
     File::create(path)
-        .and_then(|mut f| f.write_all(data.as_bytes()))
+        .and_then(|mut f| f.write_all(data))
         .map_err(|inner| AccessError {
             inner,
             path: path.as_ref().to_owned(),
@@ -79,6 +120,16 @@ where
     Ok(())
 }
 
+/// Checks whether files can be created in `directory`, which is created if necessary, by creating
+/// and removing an empty file.
+pub fn is_writable<P>(directory: &P) -> bool
+where
+    P: AsRef<Path>,
+{
+    let probe = directory.as_ref().join(".criterion-write-probe");
+    mkdirp(directory).is_ok() && File::create(&probe).is_ok() && fs::remove_file(&probe).is_ok()
+}
+
 pub fn list_existing_benchmarks<P>(directory: &P) -> Result<Vec<BenchmarkId>>
 where
     P: AsRef<Path>,
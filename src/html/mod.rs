@@ -1,16 +1,19 @@
-use report::{BenchmarkId, MeasurementData, Report, ReportContext};
+use report::{BenchmarkId, ComparisonResult, MeasurementData, ReportContext, Reporter, ValueType};
 use stats::bivariate::Data;
 use stats::bivariate::regression::Slope;
 
 use Estimate;
 use criterion_plot::Size;
-use estimate::Statistic;
-use format;
+use estimate::{Estimates, Statistic};
+use csv_report;
+use format::{self, TimeUnit};
 use fs;
 use handlebars::Handlebars;
+use json_report;
 use plot;
 use stats::univariate::Sample;
-use std::collections::BTreeSet;
+use std::cell::RefCell;
+use std::collections::{BTreeSet, HashSet};
 use std::path::{Path, PathBuf};
 use std::process::Child;
 
@@ -77,11 +80,22 @@ struct SummaryContext {
     thumbnail_height: usize,
 
     violin_plot: Option<String>,
+    violin_comparison: Option<String>,
+    change_overview: Option<String>,
     line_chart: Option<String>,
+    relative_line_chart: Option<String>,
+    axis_line_charts: Vec<AxisLineChart>,
 
     benchmarks: Vec<IndividualBenchmark>,
 }
 
+// Line chart of one parameter of a Cartesian product of parameters, with the others held fixed
+#[derive(Serialize)]
+struct AxisLineChart {
+    fixed: String,
+    file: String,
+}
+
 #[derive(Serialize)]
 struct ConfidenceInterval {
     lower: String,
@@ -142,10 +156,13 @@ impl IndexBenchmark {
 #[derive(Serialize)]
 struct IndexContext {
     benchmarks: Vec<IndexBenchmark>,
+    comparisons: Vec<Plot>,
 }
 
 pub struct Html {
     handlebars: Handlebars,
+    // Benchmarks left out of the report because they didn't change
+    hidden: RefCell<HashSet<String>>,
 }
 impl Html {
     pub fn new() -> Html {
@@ -162,11 +179,14 @@ impl Html {
         handlebars
             .register_template_string("index", include_str!("index.html.handlebars"))
             .expect("Unable to parse index report template.");
-        Html { handlebars }
+        Html {
+            handlebars,
+            hidden: RefCell::new(HashSet::new()),
+        }
     }
 }
-impl Report for Html {
-    fn measurement_complete(
+impl Reporter for Html {
+    fn measured(
         &self,
         id: &BenchmarkId,
         report_context: &ReportContext,
@@ -175,6 +195,10 @@ impl Report for Html {
         if !report_context.plotting.is_enabled() {
             return;
         }
+        if report_context.hides(measurements) {
+            self.hidden.borrow_mut().insert(id.id().to_owned());
+            return;
+        }
 
         try_else_return!(fs::mkdirp(&format!(
             "{}/{}/report/",
@@ -184,11 +208,13 @@ impl Report for Html {
 
         let slope_estimate = &measurements.absolute_estimates[&Statistic::Slope];
 
+        // Each row of the table shows its bounds in the unit of its estimate.
         fn time_interval(est: &Estimate) -> ConfidenceInterval {
+            let unit = TimeUnit::of(est.point_estimate);
             ConfidenceInterval {
-                lower: format::time(est.confidence_interval.lower_bound),
-                point: format::time(est.point_estimate),
-                upper: format::time(est.confidence_interval.upper_bound),
+                lower: format::time_in(est.confidence_interval.lower_bound, unit),
+                point: format::time_in(est.point_estimate, unit),
+                upper: format::time_in(est.confidence_interval.upper_bound, unit),
             }
         }
 
@@ -269,6 +295,7 @@ impl Report for Html {
             return;
         }
 
+        let hidden = self.hidden.borrow();
         let all_ids = all_ids
             .iter()
             .filter(|id| {
@@ -276,16 +303,24 @@ impl Report for Html {
                     "{}/{}",
                     context.output_directory,
                     id.as_directory_name()
-                ))
+                )) && !hidden.contains(id.id())
             })
             .cloned()
             .collect::<Vec<_>>();
+        if all_ids.is_empty() {
+            return;
+        }
 
         let mut all_plots = vec![];
         let group_id = all_ids[0].group_id.clone();
 
         let data: Vec<(BenchmarkId, Vec<f64>)> =
-            self.load_summary_data(&context.output_directory, &all_ids);
+            self.load_summary_data(&context.output_directory, &all_ids, "new/sample.json");
+        let baselines: Vec<(BenchmarkId, Vec<f64>)> = self.load_summary_data(
+            &context.output_directory,
+            &all_ids,
+            "change/base_sample.json",
+        );
 
         let mut function_ids = BTreeSet::new();
         for id in all_ids {
@@ -306,6 +341,7 @@ impl Report for Html {
                 all_plots.extend(self.generate_summary(
                     &subgroup_id,
                     &*samples_with_function,
+                    &baselines,
                     context,
                     false,
                 ));
@@ -315,6 +351,7 @@ impl Report for Html {
         all_plots.extend(self.generate_summary(
             &BenchmarkId::new(group_id, None, None, None),
             &*(data.iter().by_ref().collect::<Vec<_>>()),
+            &baselines,
             context,
             true,
         ));
@@ -347,6 +384,7 @@ impl Report for Html {
 
         let mut found_ids = try_else_return!(fs::list_existing_benchmarks(&output_directory));
         found_ids.sort_unstable_by_key(|id| id.id().to_owned());
+        let comparisons = self.generate_group_comparisons(report_context, &found_ids);
 
         let mut root_id = IndexBenchmark {
             name: "".to_owned(),
@@ -386,7 +424,10 @@ impl Report for Html {
 
         try_else_return!(fs::mkdirp(&format!("{}/report/", output_directory)));
 
-        let context = IndexContext { benchmarks };
+        let context = IndexContext {
+            benchmarks,
+            comparisons,
+        };
 
         let text = self.handlebars
             .render("index", &context)
@@ -396,6 +437,10 @@ impl Report for Html {
             &format!("{}/report/index.html", output_directory),
         ));
     }
+
+    fn saves_files(&self) -> bool {
+        true
+    }
 }
 impl Html {
     fn comparison(&self, measurements: &MeasurementData) -> Option<Comparison> {
@@ -407,8 +452,7 @@ impl Html {
             if !different_mean {
                 explanation_str = "No change in performance detected.".to_owned();
             } else {
-                let comparison = compare_to_threshold(&mean_est, comp.noise_threshold);
-                match comparison {
+                match comp.verdict {
                     ComparisonResult::Improved => {
                         explanation_str = "Performance has improved.".to_owned();
                     }
@@ -428,9 +472,9 @@ impl Html {
                 explanation: explanation_str,
 
                 change: ConfidenceInterval {
-                    point: format::change(mean_est.point_estimate, true),
-                    lower: format::change(mean_est.confidence_interval.lower_bound, true),
-                    upper: format::change(mean_est.confidence_interval.upper_bound, true),
+                    point: format::change(mean_est.point_estimate),
+                    lower: format::change(mean_est.confidence_interval.lower_bound),
+                    upper: format::change(mean_est.confidence_interval.upper_bound),
                 },
 
                 additional_plots: vec![
@@ -474,12 +518,15 @@ impl Html {
                 id.as_directory_name()
             ),
             None,
+            context.plot_config.fill_style,
+            context.plot_config.kde_kernel,
         ));
         gnuplots.extend(plot::abs_distributions(
             &measurements.distributions,
             &measurements.absolute_estimates,
             id,
             &context.output_directory,
+            context.plot_config.kde_kernel,
         ));
         gnuplots.push(plot::regression(
             data,
@@ -502,6 +549,8 @@ impl Html {
                 id.as_directory_name()
             ),
             Some(THUMBNAIL_SIZE),
+            context.plot_config.fill_style,
+            context.plot_config.kde_kernel,
         ));
         gnuplots.push(plot::regression(
             data,
@@ -556,12 +605,14 @@ impl Html {
                 ),
                 None,
                 false,
+                context.plot_config.kde_kernel,
             ));
             gnuplots.push(plot::t_test(
                 comp.t_value,
                 &comp.t_distribution,
                 id,
                 &context.output_directory,
+                context.plot_config.kde_kernel,
             ));
             gnuplots.extend(plot::rel_distributions(
                 &comp.relative_distributions,
@@ -569,6 +620,7 @@ impl Html {
                 id,
                 &context.output_directory,
                 comp.noise_threshold,
+                context.plot_config.kde_kernel,
             ));
             gnuplots.push(plot::both::regression(
                 base_data,
@@ -595,26 +647,78 @@ impl Html {
                 ),
                 Some(THUMBNAIL_SIZE),
                 true,
+                context.plot_config.kde_kernel,
             ));
         }
 
         wait_on_gnuplot(gnuplots);
     }
 
+    // Loads the average times of the `file` (relative to the benchmark directory) of each
+    // benchmark which has one.
+    // Draws the curves of each set of related groups (see `plot::summary::related_groups`) on one
+    // figure, and returns the plots for the index to link to.
+    fn generate_group_comparisons(
+        &self,
+        report_context: &ReportContext,
+        ids: &[BenchmarkId],
+    ) -> Vec<Plot> {
+        let output_directory = &report_context.output_directory;
+        let mut plots = vec![];
+        let mut gnuplots = vec![];
+
+        for (prefix, groups) in plot::summary::related_groups(ids) {
+            let data: Vec<Vec<(BenchmarkId, Vec<f64>)>> = groups
+                .iter()
+                .map(|ids| self.load_summary_data(output_directory, ids, "new/sample.json"))
+                .collect();
+            if data.iter().any(|curves| curves.is_empty()) {
+                continue;
+            }
+            let curves: Vec<Vec<&(BenchmarkId, Vec<f64>)>> =
+                data.iter().map(|curves| curves.iter().collect()).collect();
+            let curves: Vec<&[&(BenchmarkId, Vec<f64>)]> =
+                curves.iter().map(|curves| &curves[..]).collect();
+
+            try_else_return!(
+                fs::mkdirp(&format!("{}/report/comparisons/", output_directory)),
+                || plots
+            );
+            let url = format!(
+                "comparisons/{}.svg",
+                BenchmarkId::new(prefix.clone(), None, None, None).as_directory_name()
+            );
+            gnuplots.push(plot::summary::multi_group_comparison(
+                &prefix,
+                &curves,
+                &format!("{}/report/{}", output_directory, url),
+                &report_context.plot_config,
+            ));
+            plots.push(Plot::new(&prefix, &url));
+        }
+
+        wait_on_gnuplot(gnuplots);
+        plots
+    }
+
     fn load_summary_data(
         &self,
         output_directory: &str,
         all_ids: &[BenchmarkId],
+        file: &str,
     ) -> Vec<(BenchmarkId, Vec<f64>)> {
         let output_dir = Path::new(output_directory);
 
         all_ids
             .iter()
             .filter_map(|id| {
-                let entry = output_dir.join(id.as_directory_name()).join("new");
+                let path = output_dir.join(id.as_directory_name()).join(file);
+                if !path.exists() {
+                    return None;
+                }
 
                 let (iters, times): (Vec<f64>, Vec<f64>) =
-                    try_else_return!(fs::load(&entry.join("sample.json")), || None);
+                    try_else_return!(fs::load(&path), || None);
                 let avg_times = iters
                     .into_iter()
                     .zip(times.into_iter())
@@ -626,10 +730,41 @@ impl Html {
             .collect::<Vec<_>>()
     }
 
+    // Loads the change of the mean time and the verdict of each benchmark which was compared
+    // against a baseline.
+    fn load_changes(
+        &self,
+        output_directory: &str,
+        data: &[&(BenchmarkId, Vec<f64>)],
+    ) -> Vec<plot::summary::Change> {
+        let output_dir = Path::new(output_directory);
+
+        data.iter()
+            .filter_map(|&&(ref id, _)| {
+                let dir = output_dir.join(id.as_directory_name());
+                let estimates_path = dir.join("change").join("estimates.json");
+                let verdict_path = dir.join("new").join("verdict.json");
+                if !estimates_path.exists() || !verdict_path.exists() {
+                    return None;
+                }
+
+                let estimates: Estimates = try_else_return!(fs::load(&estimates_path), || None);
+                let verdict: ComparisonResult =
+                    try_else_return!(fs::load(&verdict_path), || None);
+                Some(plot::summary::Change {
+                    label: id.id().to_owned(),
+                    mean: estimates[&Statistic::Mean].point_estimate,
+                    verdict,
+                })
+            })
+            .collect()
+    }
+
     fn generate_summary(
         &self,
         id: &BenchmarkId,
         data: &[&(BenchmarkId, Vec<f64>)],
+        baselines: &[(BenchmarkId, Vec<f64>)],
         report_context: &ReportContext,
         full_summary: bool,
     ) -> Vec<Child> {
@@ -653,29 +788,143 @@ impl Html {
             id.id(),
             data,
             &violin_path,
-            report_context.plot_config.summary_scale,
+            &report_context.plot_config,
+        ));
+        log_if_err!(json_report::save_group_statistics(
+            id.id(),
+            data,
+            &format!(
+                "{}/{}/report/statistics.json",
+                report_context.output_directory,
+                id.as_directory_name()
+            ),
         ));
+        log_if_err!(csv_report::write_samples_csv(
+            id.id(),
+            data,
+            Path::new(&format!(
+                "{}/{}/report/samples.csv",
+                report_context.output_directory,
+                id.as_directory_name()
+            )),
+        ));
+
+        let baselines: Vec<_> = baselines
+            .iter()
+            .filter(|&&(ref id, _)| data.iter().any(|&&(ref current, _)| current.id() == id.id()))
+            .collect();
+        let violin_comparison_path = if baselines.is_empty() {
+            None
+        } else {
+            let path = format!(
+                "{}/{}/report/violin_comparison.svg",
+                report_context.output_directory,
+                id.as_directory_name()
+            );
+            gnuplots.push(plot::summary::violin_comparison(
+                id.id(),
+                data,
+                &baselines,
+                &path,
+                &report_context.plot_config,
+            ));
+            Some(path)
+        };
+
+        let changes = self.load_changes(&report_context.output_directory, data);
+        let change_overview_path = if changes.is_empty() {
+            None
+        } else {
+            let path = format!(
+                "{}/{}/report/change_overview.svg",
+                report_context.output_directory,
+                id.as_directory_name()
+            );
+            gnuplots.push(plot::summary::change_overview(
+                id.id(),
+                &changes,
+                &path,
+                &report_context.plot_config,
+            ));
+            Some(path)
+        };
 
-        let value_types: Vec<_> = data.iter().map(|&&(ref id, _)| id.value_type()).collect();
         let mut line_path = None;
+        let mut relative_line_path = None;
 
-        if value_types.iter().all(|x| x == &value_types[0]) {
-            if let Some(value_type) = value_types[0] {
+        if let Some(value_type) =
+            plot::summary::common_value_type(data, &report_context.plot_config)
+        {
+            let path = format!(
+                "{}/{}/report/lines.svg",
+                report_context.output_directory,
+                id.as_directory_name()
+            );
+
+            if let Some(gnuplot) = plot::summary::line_comparison(
+                id.id(),
+                data,
+                &path,
+                value_type,
+                &report_context.plot_config,
+                None,
+                report_context.plot_config.x_ticks.clone(),
+            ) {
+                gnuplots.push(gnuplot);
+                line_path = Some(path);
+            }
+
+            if let Some(ref baseline) = report_context.plot_config.line_baseline {
                 let path = format!(
-                    "{}/{}/report/lines.svg",
+                    "{}/{}/report/relative_lines.svg",
                     report_context.output_directory,
                     id.as_directory_name()
                 );
 
-                gnuplots.push(plot::summary::line_comparison(
+                if let Some(gnuplot) = plot::summary::relative_line_comparison(
                     id.id(),
                     data,
                     &path,
                     value_type,
-                    report_context.plot_config.summary_scale,
-                ));
+                    &report_context.plot_config,
+                    baseline,
+                ) {
+                    gnuplots.push(gnuplot);
+                    relative_line_path = Some(path);
+                }
+            }
+        }
 
-                line_path = Some(path);
+        let axis_count = data[0].0.axes.len();
+        let mut axis_line_charts = vec![];
+        if axis_count > 1 && data.iter().all(|&&(ref id, _)| id.axes.len() == axis_count) {
+            for axis in 0..axis_count {
+                for (fixed, curves) in plot::summary::hold_axes_fixed(data, axis) {
+                    let curves: Vec<_> = curves.iter().collect();
+                    if curves.len() < 2 || curves.iter().any(|c| c.0.value_type().is_none()) {
+                        continue;
+                    }
+
+                    let file = format!("lines_{}_{}.svg", axis, axis_line_charts.len());
+                    let gnuplot = plot::summary::line_comparison(
+                        &format!("{}/{}", id.id(), fixed),
+                        &curves,
+                        &format!(
+                            "{}/{}/report/{}",
+                            report_context.output_directory,
+                            id.as_directory_name(),
+                            file
+                        ),
+                        ValueType::Value,
+                        &report_context.plot_config,
+                        None,
+                        None,
+                    );
+                    if let Some(gnuplot) = gnuplot {
+                        gnuplots.push(gnuplot);
+                        axis_line_charts.push(AxisLineChart { fixed, file });
+                    }
+                }
             }
         }
 
@@ -691,7 +940,11 @@ impl Html {
             thumbnail_height: THUMBNAIL_SIZE.1,
 
             violin_plot: Some(violin_path),
+            violin_comparison: violin_comparison_path,
+            change_overview: change_overview_path,
             line_chart: line_path,
+            relative_line_chart: relative_line_path,
+            axis_line_charts,
 
             benchmarks,
         };
@@ -714,23 +967,3 @@ impl Html {
         gnuplots
     }
 }
-
-enum ComparisonResult {
-    Improved,
-    Regressed,
-    NonSignificant,
-}
-
-fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
-    let ci = estimate.confidence_interval;
-    let lb = ci.lower_bound;
-    let ub = ci.upper_bound;
-
-    if lb < -noise && ub < -noise {
-        ComparisonResult::Improved
-    } else if lb > noise && ub > noise {
-        ComparisonResult::Regressed
-    } else {
-        ComparisonResult::NonSignificant
-    }
-}
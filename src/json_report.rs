@@ -0,0 +1,463 @@
+use error::Result;
+use estimate::Statistic;
+use fs;
+use machine_info::{git_info, GitInfo};
+use report::{BenchmarkId, ComparisonResult, MeasurementData, ReportContext, Reporter};
+use stats::univariate::Sample;
+use {Estimate, Throughput};
+
+/// Version of the format of the `result.json` files (`result.<label>.json` for labeled runs).
+/// Fields may be added without changing the version, but it's incremented whenever a field is
+/// removed or changes its meaning.
+pub const FORMAT_VERSION: u32 = 1;
+
+/// JSON Schema describing the `result.json` files, printed by `--dump-schema`.
+pub const SCHEMA: &str = r##"{
+  "$schema": "http://json-schema.org/draft-07/schema#",
+  "$id": "https://github.com/japaric/criterion.rs/result-1.schema.json",
+  "title": "Criterion.rs benchmark result",
+  "type": "object",
+  "required": [
+    "format_version",
+    "id",
+    "group_id",
+    "function_id",
+    "value_str",
+    "throughput",
+    "iteration_count",
+    "sample_time_nanos",
+    "estimates",
+    "change"
+  ],
+  "properties": {
+    "format_version": { "type": "integer", "enum": [1] },
+    "id": { "type": "string" },
+    "group_id": { "type": "string" },
+    "function_id": { "type": ["string", "null"] },
+    "value_str": { "type": ["string", "null"] },
+    "throughput": {
+      "type": ["object", "null"],
+      "properties": {
+        "Bytes": { "type": "integer", "minimum": 0 },
+        "Elements": { "type": "integer", "minimum": 0 }
+      },
+      "additionalProperties": false,
+      "minProperties": 1,
+      "maxProperties": 1
+    },
+    "iteration_count": { "type": "array", "items": { "type": "integer", "minimum": 1 } },
+    "sample_time_nanos": { "type": "array", "items": { "type": "number" } },
+    "estimates": {
+      "type": "object",
+      "required": ["mean", "median", "median_abs_dev", "slope", "std_dev"],
+      "properties": {
+        "mean": { "$ref": "#/definitions/estimate" },
+        "median": { "$ref": "#/definitions/estimate" },
+        "median_abs_dev": { "$ref": "#/definitions/estimate" },
+        "slope": { "$ref": "#/definitions/estimate" },
+        "std_dev": { "$ref": "#/definitions/estimate" }
+      }
+    },
+    "run_label": { "type": ["string", "null"] },
+    "git": {
+      "type": ["object", "null"],
+      "required": ["commit", "branch"],
+      "properties": {
+        "commit": { "type": "string" },
+        "branch": { "type": ["string", "null"] }
+      }
+    },
+    "change": {
+      "type": ["object", "null"],
+      "required": ["mean", "median", "p_value", "verdict"],
+      "properties": {
+        "mean": { "$ref": "#/definitions/estimate" },
+        "median": { "$ref": "#/definitions/estimate" },
+        "p_value": { "type": "number" },
+        "verdict": { "type": "string", "enum": ["Improved", "Regressed", "NonSignificant"] }
+      }
+    }
+  },
+  "definitions": {
+    "estimate": {
+      "type": "object",
+      "required": ["confidence_interval", "point_estimate", "standard_error"],
+      "properties": {
+        "confidence_interval": {
+          "type": "object",
+          "required": ["confidence_level", "lower_bound", "upper_bound"],
+          "properties": {
+            "confidence_level": { "type": "number" },
+            "lower_bound": { "type": "number" },
+            "upper_bound": { "type": "number" }
+          }
+        },
+        "point_estimate": { "type": "number" },
+        "standard_error": { "type": "number" }
+      }
+    }
+  }
+}"##;
+
+#[derive(Serialize)]
+struct JsonEstimates<'a> {
+    mean: &'a Estimate,
+    median: &'a Estimate,
+    median_abs_dev: &'a Estimate,
+    slope: &'a Estimate,
+    std_dev: &'a Estimate,
+}
+
+#[derive(Serialize)]
+struct JsonChange<'a> {
+    mean: &'a Estimate,
+    median: &'a Estimate,
+    p_value: f64,
+    verdict: ComparisonResult,
+}
+
+#[derive(Serialize)]
+struct JsonResult<'a> {
+    format_version: u32,
+    id: &'a str,
+    group_id: &'a str,
+    function_id: Option<&'a str>,
+    value_str: Option<&'a str>,
+    throughput: Option<&'a Throughput>,
+    iteration_count: Vec<u64>,
+    sample_time_nanos: &'a [f64],
+    estimates: JsonEstimates<'a>,
+    change: Option<JsonChange<'a>>,
+    run_label: Option<&'a str>,
+    git: Option<GitInfo>,
+}
+impl<'a> JsonResult<'a> {
+    fn new(
+        id: &'a BenchmarkId,
+        context: &'a ReportContext,
+        data: &'a MeasurementData,
+    ) -> JsonResult<'a> {
+        let estimates = &data.absolute_estimates;
+
+        JsonResult {
+            format_version: FORMAT_VERSION,
+            id: id.id(),
+            group_id: id.group_id.as_str(),
+            function_id: id.function_id.as_ref().map(String::as_str),
+            value_str: id.value_str.as_ref().map(String::as_str),
+            throughput: data.throughput.as_ref(),
+            iteration_count: data
+                .iter_counts
+                .as_slice()
+                .iter()
+                .map(|&n| n as u64)
+                .collect(),
+            sample_time_nanos: data.sample_times.as_slice(),
+            estimates: JsonEstimates {
+                mean: &estimates[&Statistic::Mean],
+                median: &estimates[&Statistic::Median],
+                median_abs_dev: &estimates[&Statistic::MedianAbsDev],
+                slope: &estimates[&Statistic::Slope],
+                std_dev: &estimates[&Statistic::StdDev],
+            },
+            change: data.comparison.as_ref().map(|comp| JsonChange {
+                mean: &comp.relative_estimates[&Statistic::Mean],
+                median: &comp.relative_estimates[&Statistic::Median],
+                p_value: comp.p_value,
+                verdict: comp.verdict,
+            }),
+            run_label: context.run_label.as_ref().map(String::as_str),
+            git: git_info(),
+        }
+    }
+}
+
+/// Version of the format of the `statistics.json` files of the benchmark groups, with the same
+/// rules as `FORMAT_VERSION`.
+pub const STATISTICS_VERSION: u32 = 1;
+
+/// Summary statistics of the average iteration times of each benchmark in a group, in
+/// nanoseconds.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct GroupStatistics {
+    version: u32,
+    group_id: String,
+    benchmarks: Vec<BenchmarkStatistics>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct BenchmarkStatistics {
+    id: String,
+    mean: f64,
+    median: f64,
+    std_dev: f64,
+    median_abs_dev: f64,
+    min: f64,
+    max: f64,
+}
+
+impl GroupStatistics {
+    fn new(group_id: &str, all_curves: &[&(BenchmarkId, Vec<f64>)]) -> GroupStatistics {
+        GroupStatistics {
+            version: STATISTICS_VERSION,
+            group_id: group_id.to_owned(),
+            benchmarks: all_curves
+                .iter()
+                .map(|&&(ref id, ref sample)| {
+                    let sample = Sample::new(sample);
+                    let (mean, median) = (sample.mean(), sample.median());
+                    BenchmarkStatistics {
+                        id: id.id().to_owned(),
+                        mean,
+                        median,
+                        std_dev: sample.std_dev(Some(mean)),
+                        median_abs_dev: sample.median_abs_dev(Some(median)),
+                        min: sample.min(),
+                        max: sample.max(),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Saves the statistics of the average iteration times of the benchmarks of a group to `path`.
+pub fn save_group_statistics(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    path: &str,
+) -> Result<()> {
+    fs::save(&GroupStatistics::new(group_id, all_curves), &path)
+}
+
+/// Report which saves the results of each benchmark in a machine-readable JSON file, described by
+/// `SCHEMA`.
+pub struct FileJsonReport;
+impl FileJsonReport {
+    fn write_file(
+        &self,
+        path: String,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData,
+    ) -> Result<()> {
+        fs::save(&JsonResult::new(id, context, measurements), &path)
+    }
+}
+
+impl Reporter for FileJsonReport {
+    fn measured(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData,
+    ) {
+        let path = format!(
+            "{}/{}/new/{}",
+            context.output_directory,
+            id.as_directory_name(),
+            context.result_file_name()
+        );
+        log_if_err!(self.write_file(path, id, context, measurements));
+    }
+
+    fn saves_files(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::{self, Value};
+    use stats::univariate::outliers::tukey;
+    use std::collections::BTreeMap;
+    use {ConfidenceInterval, PlotConfiguration, Plotting};
+
+    // Validates `value` against the subset of JSON Schema used by `SCHEMA`. Returns a description
+    // of the first violation.
+    fn validate(
+        value: &Value,
+        schema: &Value,
+        root: &Value,
+        path: &str,
+    ) -> ::std::result::Result<(), String> {
+        if let Some(reference) = schema["$ref"].as_str() {
+            let name = reference.rsplit('/').next().unwrap();
+            return validate(value, &root["definitions"][name], root, path);
+        }
+
+        if let Some(types) = schema.get("type") {
+            let types: Vec<&str> = match *types {
+                Value::String(ref t) => vec![t.as_str()],
+                Value::Array(ref ts) => ts.iter().filter_map(Value::as_str).collect(),
+                _ => vec![],
+            };
+            let matches = |t: &&str| match *t {
+                "null" => value.is_null(),
+                "string" => value.is_string(),
+                "number" => value.is_number(),
+                "integer" => value.is_u64() || value.is_i64(),
+                "object" => value.is_object(),
+                "array" => value.is_array(),
+                _ => false,
+            };
+            if !types.iter().any(matches) {
+                return Err(format!("{}: {} is not of type {:?}", path, value, types));
+            }
+        }
+        if let Some(allowed) = schema["enum"].as_array() {
+            if !allowed.contains(value) {
+                return Err(format!("{}: {} is not one of {:?}", path, value, allowed));
+            }
+        }
+        if let (Some(minimum), Some(number)) = (schema["minimum"].as_f64(), value.as_f64()) {
+            if number < minimum {
+                return Err(format!("{}: {} is below {}", path, number, minimum));
+            }
+        }
+
+        if let Some(object) = value.as_object() {
+            for required in schema["required"].as_array().into_iter().flatten() {
+                let required = required.as_str().unwrap();
+                if !object.contains_key(required) {
+                    return Err(format!("{}: missing {}", path, required));
+                }
+            }
+            for (key, field) in object {
+                match schema["properties"].get(key) {
+                    Some(field_schema) => {
+                        validate(field, field_schema, root, &format!("{}.{}", path, key))?
+                    }
+                    None if schema["additionalProperties"] == Value::Bool(false) => {
+                        return Err(format!("{}: unexpected {}", path, key))
+                    }
+                    None => {}
+                }
+            }
+        }
+        if let (Some(items), Some(array)) = (schema.get("items"), value.as_array()) {
+            for (i, item) in array.iter().enumerate() {
+                validate(item, items, root, &format!("{}[{}]", path, i))?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn estimate(point_estimate: f64) -> Estimate {
+        Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: point_estimate * 0.9,
+                upper_bound: point_estimate * 1.1,
+            },
+            point_estimate,
+            standard_error: point_estimate * 0.05,
+        }
+    }
+
+    #[test]
+    fn result_matches_schema() {
+        let schema: Value = serde_json::from_str(SCHEMA).unwrap();
+        let id = BenchmarkId::new(
+            "group".to_owned(),
+            Some("function".to_owned()),
+            Some("1024".to_owned()),
+            Some(Throughput::Bytes(1024)),
+        );
+
+        let iters = [1.0, 2.0, 3.0, 4.0];
+        let times = [10.0, 21.0, 29.0, 40.0];
+        let avg_times = [10.0, 10.5, 9.667, 10.0];
+        let mut estimates = BTreeMap::new();
+        for &(statistic, value) in &[
+            (Statistic::Mean, 10.04),
+            (Statistic::Median, 10.0),
+            (Statistic::MedianAbsDev, 0.25),
+            (Statistic::Slope, 9.97),
+            (Statistic::StdDev, 0.34),
+        ] {
+            estimates.insert(statistic, estimate(value));
+        }
+        let data = MeasurementData {
+            iter_counts: Sample::new(&iters),
+            sample_times: Sample::new(&times),
+            avg_times: tukey::classify(Sample::new(&avg_times)),
+            absolute_estimates: estimates,
+            distributions: BTreeMap::new(),
+            comparison: None,
+            throughput: Some(Throughput::Bytes(1024)),
+        };
+
+        let mut context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+
+        let result = serde_json::to_value(JsonResult::new(&id, &context, &data)).unwrap();
+        assert_eq!(Ok(()), validate(&result, &schema, &schema, "result"));
+        assert!(result["run_label"].is_null());
+        assert_eq!("result.json", context.result_file_name());
+        let mut with_git = result.clone();
+        with_git["git"] = serde_json::from_str(r#"{"commit": "0123", "branch": null}"#).unwrap();
+        assert_eq!(Ok(()), validate(&with_git, &schema, &schema, "result"));
+        assert_eq!(
+            Some(u64::from(FORMAT_VERSION)),
+            result["format_version"].as_u64()
+        );
+        assert_eq!(Some(3), result["iteration_count"][2].as_u64());
+        assert_eq!(
+            Some(9.97),
+            result["estimates"]["slope"]["point_estimate"].as_f64()
+        );
+
+        // The validator itself has to catch violations.
+        let mut broken = result.clone();
+        broken["estimates"]["mean"]
+            .as_object_mut()
+            .unwrap()
+            .remove("point_estimate");
+        assert!(validate(&broken, &schema, &schema, "result").is_err());
+        broken = result.clone();
+        broken["throughput"] = serde_json::from_str(r#"{"Bits": 3}"#).unwrap();
+        assert!(validate(&broken, &schema, &schema, "result").is_err());
+
+        context.run_label = Some("turbo/off".to_owned());
+        let labeled = serde_json::to_value(JsonResult::new(&id, &context, &data)).unwrap();
+        assert_eq!(Ok(()), validate(&labeled, &schema, &schema, "result"));
+        assert_eq!(Some("turbo/off"), labeled["run_label"].as_str());
+        assert_eq!("result.turbo_off.json", context.result_file_name());
+    }
+
+    #[test]
+    fn group_statistics_round_trip() {
+        let curve = |function: &str, times: Vec<f64>| {
+            let id = BenchmarkId::new("group".to_owned(), Some(function.to_owned()), None, None);
+            (id, times)
+        };
+        let fast = curve("fast", vec![1.0, 2.0, 3.0, 6.0]);
+        let slow = curve("slow", vec![10.0, 30.0, 20.0]);
+
+        let statistics = GroupStatistics::new("group", &[&fast, &slow]);
+        let json = serde_json::to_string(&statistics).unwrap();
+        assert_eq!(statistics, serde_json::from_str(&json).unwrap());
+
+        let value: Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(Some(u64::from(STATISTICS_VERSION)), value["version"].as_u64());
+        assert_eq!(Some("group/slow"), value["benchmarks"][1]["id"].as_str());
+        assert_eq!(Some(3.0), value["benchmarks"][0]["mean"].as_f64());
+        assert_eq!(Some(20.0), value["benchmarks"][1]["median"].as_f64());
+        assert_eq!(Some(10.0), value["benchmarks"][1]["std_dev"].as_f64());
+        // The median absolute deviation is scaled to estimate the standard deviation.
+        let mad = |i: usize| value["benchmarks"][i]["median_abs_dev"].as_f64().unwrap();
+        assert!((mad(0) - 1.4826).abs() < 1e-6);
+        assert!((mad(1) - 14.826).abs() < 1e-5);
+        assert_eq!(Some(1.0), value["benchmarks"][0]["min"].as_f64());
+        assert_eq!(Some(30.0), value["benchmarks"][1]["max"].as_f64());
+    }
+}
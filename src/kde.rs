@@ -1,33 +1,71 @@
 use itertools_num;
 use stats::univariate::Sample;
-use stats::univariate::kde::kernel::Gaussian;
+use stats::univariate::kde::kernel::{Epanechnikov, Gaussian, Kernel, Triangular};
 use stats::univariate::kde::{Bandwidth, Kde};
+use {KdeBandwidth, KdeKernel};
 
-pub fn sweep(
+impl Kernel<f64> for KdeKernel {
+    fn evaluate(&self, x: f64) -> f64 {
+        match *self {
+            KdeKernel::Gaussian => Gaussian.evaluate(x),
+            KdeKernel::Epanechnikov => Epanechnikov.evaluate(x),
+            KdeKernel::Triangular => Triangular.evaluate(x),
+        }
+    }
+
+    fn support(&self) -> Option<(f64, f64)> {
+        match *self {
+            KdeKernel::Gaussian => Gaussian.support(),
+            KdeKernel::Epanechnikov => Epanechnikov.support(),
+            KdeKernel::Triangular => Triangular.support(),
+        }
+    }
+}
+
+pub fn sweep<K>(
     sample: &Sample<f64>,
     npoints: usize,
     range: Option<(f64, f64)>,
-) -> (Box<[f64]>, Box<[f64]>) {
-    let (xs, ys, _) = sweep_and_estimate(sample, npoints, range, sample.as_slice()[0]);
-    (xs, ys)
+    kernel: K,
+) -> (Box<[f64]>, Box<[f64]>)
+where
+    K: Kernel<f64>,
+{
+    sweep_with_bandwidth(sample, npoints, range, KdeBandwidth::Silverman, kernel)
 }
 
-pub fn sweep_and_estimate(
+pub fn sweep_with_bandwidth<K>(
     sample: &Sample<f64>,
     npoints: usize,
     range: Option<(f64, f64)>,
-    point_to_estimate: f64,
-) -> (Box<[f64]>, Box<[f64]>, f64) {
-    let x_min = sample.min();
-    let x_max = sample.max();
+    bandwidth: KdeBandwidth,
+    kernel: K,
+) -> (Box<[f64]>, Box<[f64]>)
+where
+    K: Kernel<f64>,
+{
+    let bandwidth = Bandwidth::Manual(bandwidth.estimate(sample.as_slice()));
+    let kde = Kde::new(sample, kernel, bandwidth);
+    let (start, end) = range.unwrap_or_else(|| default_range(sample, &kde));
 
-    let kde = Kde::new(sample, Gaussian, Bandwidth::Silverman);
-    let h = kde.bandwidth();
+    let xs: Vec<_> = itertools_num::linspace(start, end, npoints).collect();
+    let ys = kde.map(&xs);
 
-    let (start, end) = match range {
-        Some((start, end)) => (start, end),
-        None => (x_min - 3. * h, x_max + 3. * h),
-    };
+    (xs.into_boxed_slice(), ys)
+}
+
+pub fn sweep_and_estimate<K>(
+    sample: &Sample<f64>,
+    npoints: usize,
+    range: Option<(f64, f64)>,
+    point_to_estimate: f64,
+    kernel: K,
+) -> (Box<[f64]>, Box<[f64]>, f64)
+where
+    K: Kernel<f64>,
+{
+    let kde = Kde::new(sample, kernel, Bandwidth::Silverman);
+    let (start, end) = range.unwrap_or_else(|| default_range(sample, &kde));
 
     let xs: Vec<_> = itertools_num::linspace(start, end, npoints).collect();
     let ys = kde.map(&xs);
@@ -35,3 +73,59 @@ pub fn sweep_and_estimate(
 
     (xs.into_boxed_slice(), ys, point_estimate)
 }
+
+/// The range swept by default: the range of the sample, widened by three bandwidths on each side.
+pub fn range(sample: &Sample<f64>, bandwidth: KdeBandwidth) -> (f64, f64) {
+    let h = bandwidth.estimate(sample.as_slice());
+    (sample.min() - 3. * h, sample.max() + 3. * h)
+}
+
+fn default_range<K>(sample: &Sample<f64>, kde: &Kde<f64, K>) -> (f64, f64)
+where
+    K: Kernel<f64>,
+{
+    let h = kde.bandwidth();
+    (sample.min() - 3. * h, sample.max() + 3. * h)
+}
+
+/// Evaluates the kernel density estimate of `sample` at the single point `x`.
+pub fn pdf_at<K>(sample: &Sample<f64>, x: f64, bandwidth: Bandwidth<f64>, kernel: K) -> f64
+where
+    K: Kernel<f64>,
+{
+    Kde::new(sample, kernel, bandwidth).estimate(x)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn pdf_at_matches_sweep() {
+        let data = [1.0, 1.5, 2.0, 2.2, 3.1, 4.0, 4.5, 7.0];
+        let sample = Sample::new(&data);
+
+        for &kernel in &[
+            KdeKernel::Gaussian,
+            KdeKernel::Epanechnikov,
+            KdeKernel::Triangular,
+        ] {
+            let (xs, ys) = sweep(sample, 50, None, kernel);
+            for (&x, &y) in xs.iter().zip(ys.iter()) {
+                let pdf = pdf_at(sample, x, Bandwidth::Silverman, kernel);
+                assert!((pdf - y).abs() < 1e-12, "{} != {} at {}", pdf, y, x);
+            }
+        }
+    }
+
+    #[test]
+    fn epanechnikov_density_has_compact_support() {
+        let data = [1.0, 1.5, 2.0, 2.2, 3.1, 4.0, 4.5, 7.0];
+        let sample = Sample::new(&data);
+        let h = Kde::new(sample, Gaussian, Bandwidth::Silverman).bandwidth();
+
+        let beyond = sample.max() + 1.5 * h;
+        assert_eq!(0., pdf_at(sample, beyond, Bandwidth::Silverman, KdeKernel::Epanechnikov));
+        assert!(pdf_at(sample, beyond, Bandwidth::Silverman, KdeKernel::Gaussian) > 0.);
+    }
+}
@@ -27,6 +27,8 @@ extern crate csv;
 extern crate failure;
 extern crate itertools;
 extern crate itertools_num;
+extern crate num_cpus;
+extern crate rand;
 extern crate serde;
 extern crate serde_json;
 extern crate simplelog;
@@ -38,6 +40,9 @@ extern crate criterion_plot;
 #[cfg(feature = "html_reports")]
 extern crate handlebars;
 
+#[cfg(feature = "compression")]
+extern crate miniz_oxide;
+
 #[cfg(feature = "real_blackbox")]
 extern crate test;
 
@@ -57,15 +62,23 @@ mod macros_private;
 #[macro_use]
 mod analysis;
 mod benchmark;
+#[cfg(feature = "compression")]
+mod compress;
 mod csv_report;
 mod error;
 mod estimate;
 mod format;
 mod fs;
+mod json_report;
+mod machine_info;
 mod macros;
+#[cfg(feature = "parquet")]
+mod parquet_report;
+mod pr_comment;
 mod program;
 mod report;
 mod routine;
+mod run_time;
 
 #[cfg(feature = "html_reports")]
 mod kde;
@@ -77,25 +90,37 @@ mod plot;
 mod html;
 
 use std::cell::RefCell;
+use std::cmp;
 use std::collections::BTreeMap;
 use std::default::Default;
 use std::iter::{repeat_with, IntoIterator};
 use std::process::Command;
 use std::time::{Duration, Instant};
 use std::fmt;
+use std::thread;
 
 use benchmark::BenchmarkConfig;
 use benchmark::NamedRoutine;
 use csv_report::FileCsvReport;
 use estimate::{Distributions, Estimates, Statistic};
+use json_report::FileJsonReport;
+use machine_info::MachineInfoReport;
 use plotting::Plotting;
-use report::{CliReport, Report, ReportContext, Reports};
-use routine::Function;
+use pr_comment::PrCommentReport;
+use report::{BenchmarkId, CliReport, ComparisonData, QuietReport, ReportContext, Reports};
+use routine::{Function, Routine};
+use run_time::RunTimeReport;
+use stats::univariate::kde::kernel::Gaussian;
+use stats::univariate::kde::{Bandwidth, Kde};
+use stats::univariate::Sample;
 
 #[cfg(feature = "html_reports")]
 use html::Html;
+#[cfg(feature = "parquet")]
+use parquet_report::FileParquetReport;
 
 pub use benchmark::{Benchmark, BenchmarkDefinition, ParameterizedBenchmark};
+pub use report::{ComparisonResult, Reporter};
 
 fn debug_enabled() -> bool {
     std::env::vars().any(|(key, _)| key == "CRITERION_DEBUG")
@@ -173,12 +198,19 @@ where
 /// * If your routine requires some per-iteration setup that shouldn't be timed,
 ///   use `iter_with_setup` or (if the setup is expensive) use `iter_with_large_setup`
 ///   to construct a pool of input data ahead of time
+/// * If your routine has to be preceded by some per-iteration work which shouldn't be timed, but
+///   which doesn't produce its input, use `iter_with_preamble`
+/// * If your routine only needs mutable access to its input, use `iter_batched_ref` to avoid
+///   timing the creation and destruction of the input
+/// * If your routine processes a number of elements which is only known at runtime, use
+///   `iter_counted` to measure the throughput in elements per second
 /// * Otherwise, use `iter`.
 #[derive(Clone, Copy)]
 pub struct Bencher {
     iterated: bool,
     iters: u64,
     elapsed: Duration,
+    elements: Option<u64>,
 }
 
 impl Bencher {
@@ -325,6 +357,82 @@ impl Bencher {
         }
     }
 
+    /// Times a `routine` which has to be preceded by a `preamble` on each iteration, excluding the
+    /// time spent in the `preamble`.
+    ///
+    /// Unlike `iter_with_setup`, the `preamble` doesn't produce an input for the `routine`; it
+    /// only has to run right before it, eg. to reset some shared state.
+    ///
+    /// # Timing loop
+    ///
+    /// ```rust,no_run
+    /// # use std::time::{Instant, Duration};
+    /// # use std::mem;
+    /// # fn preamble() {}
+    /// # fn routine() {}
+    /// # let iters = 4_000_000;
+    /// let mut elapsed = Duration::new(0, 0);
+    /// for _ in 0..iters {
+    ///     preamble();
+    ///
+    ///     let start = Instant::now();
+    ///     let output = routine();
+    ///     let elapsed_in_iter = start.elapsed();
+    ///
+    ///     mem::drop(output);
+    ///
+    ///     elapsed = elapsed + elapsed_in_iter;
+    /// }
+    /// ```
+    ///
+    /// # Timing model
+    ///
+    /// ``` text
+    /// elapsed = iters * (Instant::now + routine)
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![macro_use] extern crate criterion;
+    ///
+    /// use criterion::*;
+    /// use std::cell::RefCell;
+    /// use std::collections::HashMap;
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     c.bench_function("with_preamble", move |b| {
+    ///         let cache = RefCell::new(HashMap::new());
+    ///         // This will avoid timing the clearing of the cache.
+    ///         b.iter_with_preamble(
+    ///             || cache.borrow_mut().clear(),
+    ///             || cache.borrow_mut().insert(1, 2),
+    ///         )
+    ///     });
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    #[inline(never)]
+    pub fn iter_with_preamble<O, P, R>(&mut self, mut preamble: P, mut routine: R)
+    where
+        P: FnMut(),
+        R: FnMut() -> O,
+    {
+        self.iterated = true;
+        self.elapsed = Duration::from_secs(0);
+        for _ in 0..self.iters {
+            preamble();
+
+            let start = Instant::now();
+            let output = routine();
+            self.elapsed += start.elapsed();
+
+            drop(black_box(output));
+        }
+    }
+
     /// Times a `routine` by collecting its output on each iteration. This avoids timing the
     /// destructor of the value returned by `routine`.
     ///
@@ -466,6 +574,262 @@ impl Bencher {
         }
     }
 
+    /// Times a `routine` that needs mutable access to its input, but shouldn't consume it. Inputs
+    /// are created by `setup` in batches of `batch_size` ahead of time, and each iteration borrows
+    /// one of them. Neither creating nor dropping the inputs is timed.
+    ///
+    /// Smaller batches use less memory, larger batches reduce the overhead of switching between
+    /// setup and measurement. A `batch_size` of 1 behaves like `iter_with_setup`.
+    ///
+    /// # Timing loop
+    ///
+    /// ```rust,no_run
+    /// # use std::time::{Instant, Duration};
+    /// # fn setup() -> u64 { 0 }
+    /// # fn routine(input: &mut u64) {}
+    /// # let iters = 4_000_000;
+    /// # let batch_size = 10;
+    /// let mut elapsed = Duration::new(0, 0);
+    /// let mut remaining = iters;
+    /// while remaining > 0 {
+    ///     let size = batch_size.min(remaining);
+    ///     let mut inputs: Vec<u64> = (0..size).map(|_| setup()).collect();
+    ///     let mut outputs = Vec::with_capacity(size);
+    ///
+    ///     let start = Instant::now();
+    ///     outputs.extend(inputs.iter_mut().map(|input| routine(input)));
+    ///     elapsed = elapsed + start.elapsed();
+    ///
+    ///     remaining -= size;
+    /// }
+    /// ```
+    ///
+    /// # Timing model
+    ///
+    /// ``` text
+    /// elapsed = (iters / batch_size) * Instant::now + iters * (routine + Vec::push)
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![macro_use] extern crate criterion;
+    ///
+    /// use criterion::*;
+    ///
+    /// fn create_scrambled_data() -> Vec<u64> {
+    ///     # vec![]
+    ///     // ...
+    /// }
+    ///
+    /// // The sorting algorithm to test
+    /// fn sort(data: &mut [u64]) {
+    ///     // ...
+    /// }
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     let data = create_scrambled_data();
+    ///
+    ///     c.bench_function("batched_ref", move |b| {
+    ///         // This will avoid timing the to_vec call and the drop of the sorted data.
+    ///         b.iter_batched_ref(|| data.to_vec(), |data| sort(data), 100)
+    ///     });
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    #[inline(never)]
+    pub fn iter_batched_ref<I, O, S, R>(&mut self, mut setup: S, mut routine: R, batch_size: u64)
+    where
+        S: FnMut() -> I,
+        R: FnMut(&mut I) -> O,
+    {
+        assert!(batch_size > 0, "batch_size must be at least 1");
+        self.iterated = true;
+        self.elapsed = Duration::from_secs(0);
+
+        let mut remaining = self.iters;
+        while remaining > 0 {
+            let size = cmp::min(batch_size, remaining);
+            let mut inputs = black_box((0..size).map(|_| setup()).collect::<Vec<_>>());
+            let mut outputs = Vec::with_capacity(size as usize);
+
+            let start = Instant::now();
+            outputs.extend(inputs.iter_mut().map(|input| routine(input)));
+            self.elapsed += start.elapsed();
+
+            drop(black_box(outputs));
+            drop(black_box(inputs));
+            remaining -= size;
+        }
+    }
+
+    /// Times the operations of a `routine` on a shared state with an expensive one-time setup, eg.
+    /// queries of an index which `setup` builds. Each state is created by `setup` and used for
+    /// `operations` iterations of `routine` before it's dropped, so that the cost of each
+    /// operation is amortized over the whole batch. Neither creating nor dropping the states is
+    /// timed.
+    ///
+    /// Unlike `iter_batched_ref`, which sets up a separate input for each iteration, the
+    /// iterations of a batch share one state. Each iteration is a single operation, so the
+    /// throughput of the benchmark is reported in operations (elements) per second. This takes
+    /// precedence over a throughput configured for the benchmark.
+    ///
+    /// # Timing loop
+    ///
+    /// ```rust,no_run
+    /// # use std::time::{Instant, Duration};
+    /// # fn setup() -> u64 { 0 }
+    /// # fn routine(state: &mut u64) {}
+    /// # let iters = 4_000_000;
+    /// # let operations = 1000;
+    /// let mut elapsed = Duration::new(0, 0);
+    /// let mut remaining = iters;
+    /// while remaining > 0 {
+    ///     let size = operations.min(remaining);
+    ///     let mut state = setup();
+    ///
+    ///     let start = Instant::now();
+    ///     for _ in 0..size {
+    ///         routine(&mut state);
+    ///     }
+    ///     elapsed = elapsed + start.elapsed();
+    ///
+    ///     remaining -= size;
+    /// }
+    /// ```
+    ///
+    /// # Timing model
+    ///
+    /// ``` text
+    /// elapsed = (iters / operations) * Instant::now + iters * (routine + Range::next)
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![macro_use] extern crate criterion;
+    ///
+    /// use criterion::*;
+    /// use std::collections::BTreeSet;
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     c.bench_function("amortized", move |b| {
+    ///         let mut key = 0;
+    ///         // Builds the set once for every 1000 lookups.
+    ///         b.iter_amortized(
+    ///             || (0..10_000).collect::<BTreeSet<u64>>(),
+    ///             |set| {
+    ///                 key = (key + 7919) % 10_000;
+    ///                 set.contains(&key)
+    ///             },
+    ///             1000,
+    ///         )
+    ///     });
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    #[inline(never)]
+    pub fn iter_amortized<I, O, S, R>(&mut self, mut setup: S, mut routine: R, operations: u64)
+    where
+        S: FnMut() -> I,
+        R: FnMut(&mut I) -> O,
+    {
+        assert!(operations > 0, "operations must be at least 1");
+        self.iterated = true;
+        self.elapsed = Duration::from_secs(0);
+
+        let mut remaining = self.iters;
+        while remaining > 0 {
+            let size = cmp::min(operations, remaining);
+            let mut state = black_box(setup());
+
+            let start = Instant::now();
+            for _ in 0..size {
+                black_box(routine(&mut state));
+            }
+            self.elapsed += start.elapsed();
+
+            drop(black_box(state));
+            remaining -= size;
+        }
+        self.elements = Some(self.iters);
+    }
+
+    /// Times a `routine` which returns the number of elements it processed, eg. by consuming an
+    /// iterator of unknown length.
+    ///
+    /// The throughput of the benchmark is reported in elements per second, using the average
+    /// number of elements per iteration (rounded to the nearest integer) over all iterations. This
+    /// takes precedence over a throughput configured for the benchmark.
+    ///
+    /// # Timing model
+    ///
+    /// ```text
+    /// elapsed = Instant::now + iters * (routine + Range::next)
+    /// ```
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// #![macro_use] extern crate criterion;
+    ///
+    /// use criterion::*;
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     let text = "the quick brown fox jumps over the lazy dog";
+    ///
+    ///     c.bench_function("words", move |b| {
+    ///         b.iter_counted(|| text.split_whitespace().count() as u64)
+    ///     });
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    #[inline(never)]
+    pub fn iter_counted<R>(&mut self, mut routine: R)
+    where
+        R: FnMut() -> u64,
+    {
+        self.iterated = true;
+        let mut elements = 0;
+        let start = Instant::now();
+        for _ in 0..self.iters {
+            elements += black_box(routine());
+        }
+        self.elapsed = start.elapsed();
+        self.elements = Some(elements);
+    }
+
+    // Runs each of the `tasks` `iters` times on its own thread and times the whole, including the
+    // spawning and joining of the threads, which the slope of the samples excludes.
+    fn iter_threads<T>(&mut self, tasks: Vec<T>)
+    where
+        T: FnMut() + Send + 'static,
+    {
+        self.iterated = true;
+        let iters = self.iters;
+        let start = Instant::now();
+        let handles: Vec<_> = tasks
+            .into_iter()
+            .map(|mut task| {
+                thread::spawn(move || {
+                    for _ in 0..iters {
+                        task();
+                    }
+                })
+            })
+            .collect();
+        for handle in handles {
+            handle.join().expect("Benchmark task panicked");
+        }
+        self.elapsed = start.elapsed();
+    }
+
     // Benchmarks must actually call one of the iter methods. This causes benchmarks to fail loudly
     // if they don't.
     fn assert_iterated(&mut self) {
@@ -486,6 +850,15 @@ pub enum Baseline {
     Save,
 }
 
+/// FilterMode describes how the benchmark filter is applied to benchmark names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FilterMode {
+    /// Include runs only the benchmarks whose names contain the filter.
+    Include,
+    /// Exclude runs every benchmark except those whose names contain the filter.
+    Exclude,
+}
+
 /// The benchmark manager
 ///
 /// `Criterion` lets you configure and execute benchmarks
@@ -504,13 +877,23 @@ pub struct Criterion {
     config: BenchmarkConfig,
     plotting: Plotting,
     filter: Option<String>,
-    report: Box<Report>,
+    filter_mode: FilterMode,
+    report: Reports,
+    pr_comment: Option<String>,
     output_directory: String,
     baseline_directory: String,
     baseline: Baseline,
     measure_only: bool,
+    profile_time: Option<Duration>,
     test_mode: bool,
     list_mode: bool,
+    report_only_changed: bool,
+    quiet: bool,
+    shuffle_inputs: bool,
+    compress_samples: bool,
+    run_label: Option<String>,
+    // Whether the output directory is writable, once that has been checked
+    writable_output: Option<bool>,
 }
 
 impl Default for Criterion {
@@ -529,9 +912,14 @@ impl Default for Criterion {
         #[allow(unused_mut, unused_assignments)]
         let mut plotting = Plotting::NotAvailable;
 
-        let mut reports: Vec<Box<Report>> = vec![];
+        let mut reports: Vec<Box<Reporter>> = vec![];
         reports.push(Box::new(CliReport::new(false, false, false)));
         reports.push(Box::new(FileCsvReport));
+        reports.push(Box::new(FileJsonReport));
+        #[cfg(feature = "parquet")]
+        reports.push(Box::new(FileParquetReport));
+        reports.push(Box::new(RunTimeReport::new()));
+        reports.push(Box::new(MachineInfoReport));
 
         #[cfg(feature = "html_reports")]
         {
@@ -558,22 +946,39 @@ impl Default for Criterion {
 
         Criterion {
             config: BenchmarkConfig {
+                change_hysteresis: 0.0,
                 confidence_level: 0.95,
+                max_measurement_time: Duration::from_secs(30),
+                measurement_repeats: 1,
                 measurement_time: Duration::new(5, 0),
+                min_iters_per_sample: 1,
                 noise_threshold: 0.01,
                 nresamples: 100_000,
                 sample_size: 100,
+                sample_weighting: SampleWeighting::Uniform,
                 significance_level: 0.05,
+                tail_percentile: None,
+                target_ci_width: None,
+                timeout: None,
                 warm_up_time: Duration::new(3, 0),
             },
             plotting,
             filter: None,
-            report: Box::new(Reports::new(reports)),
+            filter_mode: FilterMode::Include,
+            report: Reports::new(reports),
+            pr_comment: None,
             baseline_directory: "base".to_owned(),
             baseline: Baseline::Save,
             measure_only: false,
+            profile_time: None,
             test_mode: false,
             list_mode: false,
+            report_only_changed: false,
+            quiet: false,
+            shuffle_inputs: false,
+            compress_samples: false,
+            run_label: None,
+            writable_output: None,
             output_directory,
         }
     }
@@ -626,84 +1031,206 @@ impl Criterion {
         self
     }
 
-    /// Changes the default number of resamples for benchmarks run with this runner.
-    ///
-    /// Number of resamples to use for the
-    /// [bootstrap](http://en.wikipedia.org/wiki/Bootstrapping_(statistics)#Case_resampling)
+    /// Changes the number of times the whole measurement is repeated for benchmarks run with this
+    /// runner. Defaults to 1.
     ///
-    /// A larger number of resamples reduces the random sampling errors, which are inherent to the
-    /// bootstrap method, but also increases the analysis time
+    /// A routine which is measured over and over again runs with warm caches, which may not be
+    /// representative of how it's used. With more than one repeat, the caches are flushed by
+    /// writing to a large buffer before each repeat after the first, and the samples of all
+    /// repeats are analyzed together. Every repeat measures the same iteration counts, so each one
+    /// carries the same weight in the estimates. The measurement takes `n` times longer.
     ///
     /// # Panics
     ///
-    /// Panics if the number of resamples is set to zero
-    pub fn nresamples(mut self, n: usize) -> Criterion {
+    /// Panics if set to zero
+    pub fn measurement_repeats(mut self, n: usize) -> Criterion {
         assert!(n > 0);
 
-        self.config.nresamples = n;
+        self.config.measurement_repeats = n;
         self
     }
 
-    /// Changes the default noise threshold for benchmarks run with this runner.
+    /// Enables extending the measurement of noisy benchmarks run with this runner.
     ///
-    /// This threshold is used to decide if an increase of `X%` in the execution time is considered
-    /// significant or should be flagged as noise
+    /// If, after the measurement time, the confidence interval of the mean time per iteration is
+    /// wider than `width` relative to the mean, more samples are measured until it isn't, or
+    /// until the maximum measurement time is reached. This spends extra time only on the
+    /// benchmarks which need it.
     ///
     /// *Note:* A value of `0.02` is equivalent to `2%`
     ///
     /// # Panics
     ///
-    /// Panics is the threshold is set to a negative value
-    pub fn noise_threshold(mut self, threshold: f64) -> Criterion {
-        assert!(threshold >= 0.0);
+    /// Panics if the width is not positive
+    pub fn target_ci_width(mut self, width: f64) -> Criterion {
+        assert!(width > 0.0);
 
-        self.config.noise_threshold = threshold;
+        self.config.target_ci_width = Some(width);
         self
     }
 
-    /// Changes the default confidence level for benchmarks run with this runner
-    ///
-    /// The confidence level is used to calculate the
-    /// [confidence intervals](https://en.wikipedia.org/wiki/Confidence_interval) of the estimated
-    /// statistics
+    /// Changes the maximum time, including the measurement time, spent measuring a benchmark
+    /// whose measurement is extended because of `target_ci_width`. Defaults to 30 seconds.
     ///
     /// # Panics
     ///
-    /// Panics if the confidence level is set to a value outside the `(0, 1)` range
-    pub fn confidence_level(mut self, cl: f64) -> Criterion {
-        assert!(cl > 0.0 && cl < 1.0);
+    /// Panics if the input duration is zero
+    pub fn max_measurement_time(mut self, dur: Duration) -> Criterion {
+        assert!(dur.to_nanos() > 0);
 
-        self.config.confidence_level = cl;
+        self.config.max_measurement_time = dur;
         self
     }
 
-    /// Changes the default [significance level](https://en.wikipedia.org/wiki/Statistical_significance)
-    /// for benchmarks run with this runner
+    /// Changes the minimum number of iterations in each sample for benchmarks run with this
+    /// runner.
     ///
-    /// The significance level is used for
-    /// [hypothesis testing](https://en.wikipedia.org/wiki/Statistical_hypothesis_testing)
+    /// Very fast routines may be measured with so few iterations per sample that the overhead
+    /// and resolution of the timer dominate the measurement. Setting a floor ensures that every
+    /// sample runs the routine at least `n` times. This may increase the measurement time beyond
+    /// the target.
     ///
     /// # Panics
     ///
-    /// Panics if the significance level is set to a value outside the `(0, 1)` range
-    pub fn significance_level(mut self, sl: f64) -> Criterion {
-        assert!(sl > 0.0 && sl < 1.0);
+    /// Panics if set to zero
+    pub fn min_iters_per_sample(mut self, n: u64) -> Criterion {
+        assert!(n > 0);
 
-        self.config.significance_level = sl;
+        self.config.min_iters_per_sample = n;
         self
     }
 
-    /// Enables plotting
-    pub fn with_plots(mut self) -> Criterion {
-        match self.plotting {
-            Plotting::NotAvailable => {}
-            _ => self.plotting = Plotting::Enabled,
-        }
+    /// Enables detection of changes in the given percentile of the iteration times, in addition
+    /// to the mean, for benchmarks run with this runner.
+    ///
+    /// A change which only affects the tail latency - a few slow iterations - can be invisible in
+    /// the mean. With this set, the relative change in the percentile is bootstrapped as well, and
+    /// a separate verdict is reported for it using the same noise threshold as the mean. Typical
+    /// values are 95 or 99. Disabled by default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the percentile is not within the range `(0, 100)`
+    pub fn tail_percentile(mut self, percentile: f64) -> Criterion {
+        assert!(percentile > 0. && percentile < 100.);
 
+        self.config.tail_percentile = Some(percentile);
         self
     }
 
-    /// Disables plotting
+    /// Changes the weighting of the samples in the estimated time per iteration for benchmarks
+    /// run with this runner. Defaults to `SampleWeighting::Uniform`.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a down-weighting factor is not within the range `(0, 1]`
+    pub fn sample_weighting(mut self, weighting: SampleWeighting) -> Criterion {
+        if let SampleWeighting::DownweightFirst { weight, .. } = weighting {
+            assert!(weight > 0. && weight <= 1.);
+        }
+
+        self.config.sample_weighting = weighting;
+        self
+    }
+
+    /// Changes the default number of resamples for benchmarks run with this runner.
+    ///
+    /// Number of resamples to use for the
+    /// [bootstrap](http://en.wikipedia.org/wiki/Bootstrapping_(statistics)#Case_resampling)
+    ///
+    /// A larger number of resamples reduces the random sampling errors, which are inherent to the
+    /// bootstrap method, but also increases the analysis time
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of resamples is set to zero
+    pub fn nresamples(mut self, n: usize) -> Criterion {
+        assert!(n > 0);
+
+        self.config.nresamples = n;
+        self
+    }
+
+    /// Changes the default noise threshold for benchmarks run with this runner.
+    ///
+    /// This threshold is used to decide if an increase of `X%` in the execution time is considered
+    /// significant or should be flagged as noise
+    ///
+    /// *Note:* A value of `0.02` is equivalent to `2%`
+    ///
+    /// # Panics
+    ///
+    /// Panics is the threshold is set to a negative value
+    pub fn noise_threshold(mut self, threshold: f64) -> Criterion {
+        assert!(threshold >= 0.0);
+
+        self.config.noise_threshold = threshold;
+        self
+    }
+
+    /// Changes the hysteresis around the noise threshold for benchmarks run with this runner.
+    ///
+    /// Once a comparison has found a benchmark to be improved or regressed, the next comparison
+    /// against that baseline keeps the verdict unless the change moves outside the noise threshold
+    /// by more than this amount. Likewise, the verdict only changes to improved or regressed if
+    /// the change exceeds the noise threshold by more than this amount. This keeps the verdict of
+    /// changes close to the noise threshold from flapping between runs. Defaults to 0.
+    ///
+    /// *Note:* A value of `0.01` is equivalent to `1%`
+    ///
+    /// # Panics
+    ///
+    /// Panics if the hysteresis is set to a negative value
+    pub fn change_hysteresis(mut self, hysteresis: f64) -> Criterion {
+        assert!(hysteresis >= 0.0);
+
+        self.config.change_hysteresis = hysteresis;
+        self
+    }
+
+    /// Changes the default confidence level for benchmarks run with this runner
+    ///
+    /// The confidence level is used to calculate the
+    /// [confidence intervals](https://en.wikipedia.org/wiki/Confidence_interval) of the estimated
+    /// statistics
+    ///
+    /// # Panics
+    ///
+    /// Panics if the confidence level is set to a value outside the `(0, 1)` range
+    pub fn confidence_level(mut self, cl: f64) -> Criterion {
+        assert!(cl > 0.0 && cl < 1.0);
+
+        self.config.confidence_level = cl;
+        self
+    }
+
+    /// Changes the default [significance level](https://en.wikipedia.org/wiki/Statistical_significance)
+    /// for benchmarks run with this runner
+    ///
+    /// The significance level is used for
+    /// [hypothesis testing](https://en.wikipedia.org/wiki/Statistical_hypothesis_testing)
+    ///
+    /// # Panics
+    ///
+    /// Panics if the significance level is set to a value outside the `(0, 1)` range
+    pub fn significance_level(mut self, sl: f64) -> Criterion {
+        assert!(sl > 0.0 && sl < 1.0);
+
+        self.config.significance_level = sl;
+        self
+    }
+
+    /// Enables plotting
+    pub fn with_plots(mut self) -> Criterion {
+        match self.plotting {
+            Plotting::NotAvailable => {}
+            _ => self.plotting = Plotting::Enabled,
+        }
+
+        self
+    }
+
+    /// Disables plotting
     pub fn without_plots(mut self) -> Criterion {
         match self.plotting {
             Plotting::NotAvailable => {}
@@ -743,29 +1270,165 @@ impl Criterion {
         self
     }
 
+    /// Leaves the benchmarks which were compared against a baseline and found unchanged out of
+    /// the command-line output and the HTML report, and prints how many were left out instead.
+    /// This keeps the report of a large suite focused on the benchmarks that improved or
+    /// regressed. Benchmarks without a baseline are always reported. Defaults to disabled.
+    pub fn report_only_changed(mut self, enabled: bool) -> Criterion {
+        self.report_only_changed = enabled;
+        self
+    }
+
+    /// Measures the inputs of each function of a parameterized benchmark together, taking one
+    /// sample of each input after the other in a random order which changes from sample to
+    /// sample, instead of measuring one input after the other. This averages out the effects of
+    /// the order of the inputs, eg. on the caches and branch predictors, which otherwise favor
+    /// some of the inputs. The order is random, but the same on every run. Defaults to disabled.
+    ///
+    /// The measurements of shuffled inputs are neither repeated, nor extended, nor timed out, so
+    /// the `measurement_repeats`, `target_ci_width` and `timeout` settings have no effect on them.
+    pub fn shuffle_inputs(mut self, enabled: bool) -> Criterion {
+        self.shuffle_inputs = enabled;
+        self
+    }
+
+    /// Saves the raw samples of the benchmarks (`sample.json`) gzip-compressed, to save disk space
+    /// when keeping many baselines. The files keep their names, and compressed and uncompressed
+    /// files are read alike, so existing baselines remain usable. Defaults to disabled, as other
+    /// tools reading the files may not expect them to be compressed.
+    ///
+    /// Requires the `compression` feature.
+    #[cfg(feature = "compression")]
+    pub fn compress_samples(mut self, enabled: bool) -> Criterion {
+        self.compress_samples = enabled;
+        self
+    }
+
+    /// Labels the run with the conditions it runs under (eg. `"turbo-off"`), so that the results
+    /// of the same benchmarks run under different conditions can be told apart and grouped. The
+    /// label is saved in the JSON results of the benchmarks, which are named
+    /// `result.<label>.json` instead of `result.json`, so that differently labeled runs don't
+    /// overwrite each other's results.
+    pub fn run_label(mut self, label: &str) -> Criterion {
+        self.run_label = Some(label.to_owned());
+        self
+    }
+
+    /// Adds a reporter, which receives the results of the benchmarks in addition to the built-in
+    /// reports. Any number of reporters can be added; they are called in the order they were
+    /// added.
+    pub fn add_reporter(mut self, reporter: Box<Reporter>) -> Criterion {
+        self.report.add(reporter);
+        self
+    }
+
+    /// Saves the changes of the benchmarks since their baseline to `path` at the end of the run,
+    /// as GitHub-flavored Markdown ready to post as a pull request comment. The comment has a
+    /// summary line with the number of regressed, improved and unchanged benchmarks, and a
+    /// collapsible table of the benchmarks which changed, the largest changes first. Only the 100
+    /// largest changes are listed, to keep the comment within GitHub's size limit.
+    pub fn pr_comment(mut self, path: &str) -> Criterion {
+        self.report.push(Box::new(PrCommentReport::new(path)));
+        self.pr_comment = Some(path.to_owned());
+        self
+    }
+
+    /// Changes how the filter is applied. By default, only benchmarks matching the filter are
+    /// executed; with `FilterMode::Exclude`, the benchmarks matching the filter are skipped
+    /// instead.
+    ///
+    /// ```rust
+    /// # use criterion::{Criterion, FilterMode};
+    /// let criterion = Criterion::default()
+    ///     .with_filter("slow")
+    ///     .filter_mode(FilterMode::Exclude);
+    /// ```
+    pub fn filter_mode(mut self, mode: FilterMode) -> Criterion {
+        self.filter_mode = mode;
+
+        self
+    }
+
+    /// Enables profiling mode: instead of measuring and analyzing the benchmarks, each benchmark
+    /// is run continuously for the given time so that it can be profiled with an external
+    /// profiler. Pass `None` to measure the benchmarks as usual, which is the default.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the time is zero
+    pub fn profile_time(mut self, profile_time: Option<Duration>) -> Criterion {
+        if let Some(time) = profile_time {
+            assert!(time.to_nanos() > 0);
+        }
+
+        self.profile_time = profile_time;
+        self
+    }
+
     /// Set the output directory (currently for testing only)
     #[doc(hidden)]
     pub fn output_directory(mut self, path: &std::path::Path) -> Criterion {
         self.output_directory = path.to_string_lossy().into_owned();
+        self.writable_output = None;
 
         self
     }
 
+    // Checks whether the output directory is writable before the first benchmark. If it isn't,
+    // for example in a sandbox, the results are only printed, with a single warning, instead of
+    // logging an error for every file which can't be saved.
+    fn check_output_directory(&mut self) {
+        if self.writable_output.is_some()
+            || self.list_mode
+            || self.test_mode
+            || self.profile_time.is_some()
+        {
+            return;
+        }
+
+        let writable = fs::is_writable(&self.output_directory);
+        if !writable {
+            warn!(
+                "The output directory {} is not writable. The results will be printed, but not \
+                 saved, and there will be no comparison with this run later.",
+                self.output_directory
+            );
+            self.report.remove_file_reports();
+        }
+        self.writable_output = Some(writable);
+    }
+
+    pub(crate) fn saves_output(&self) -> bool {
+        self.writable_output != Some(false)
+    }
+
     /// Generate the final summary at the end of a run.
     #[doc(hidden)]
     pub fn final_summary(&self) {
-        if self.measure_only || self.test_mode {
+        // The reporters are told that the run is complete even when there is nothing to summarize.
+        if self.measure_only || self.test_mode || self.profile_time.is_some() {
+            report::complete_run();
             return;
         }
 
+        // The HTML index can't be saved to an existing output directory which isn't writable.
+        let writable = self.saves_output()
+            && (!fs::is_dir(&self.output_directory) || fs::is_writable(&self.output_directory));
         let report_context = ReportContext {
             output_directory: self.output_directory.clone(),
-            plotting: self.plotting,
+            plotting: if writable {
+                self.plotting
+            } else {
+                Plotting::Disabled
+            },
             plot_config: PlotConfiguration::default(),
             test_mode: self.test_mode,
+            report_only_changed: self.report_only_changed,
+            run_label: self.run_label.clone(),
         };
 
         self.report.final_summary(&report_context);
+        report::complete_run();
     }
 
     /// Configure this criterion struct based on the command-line arguments to
@@ -776,6 +1439,10 @@ impl Criterion {
             .arg(Arg::with_name("FILTER")
                 .help("Skip benchmarks whose names do not contain FILTER.")
                 .index(1))
+            .arg(Arg::with_name("exclude")
+                .long("exclude")
+                .requires("FILTER")
+                .help("Skip benchmarks whose names contain FILTER, instead of those that do not."))
             .arg(Arg::with_name("color")
                 .short("c")
                 .long("color")
@@ -788,6 +1455,11 @@ impl Criterion {
                 .short("v")
                 .long("verbose")
                 .help("Print additional statistical information."))
+            .arg(Arg::with_name("quiet")
+                .short("q")
+                .long("quiet")
+                .conflicts_with("verbose")
+                .help("Print only the benchmarks which regressed, errors, and how many benchmarks were measured and regressed at the end of the run."))
             .arg(Arg::with_name("noplot")
                 .short("n")
                 .long("noplot")
@@ -803,12 +1475,31 @@ impl Criterion {
                 .takes_value(true)
                 .conflicts_with("save-baseline")
                 .help("Compare to a named baseline."))
+            .arg(Arg::with_name("no-time-summary")
+                .long("no-time-summary")
+                .help("Don't print the total time spent warming up, measuring and analyzing at the end of the run."))
+            .arg(Arg::with_name("no-machine-info")
+                .long("no-machine-info")
+                .help("Don't print the description of the machine (CPU, cores, OS, compiler) before the first benchmark."))
             .arg(Arg::with_name("list")
                 .long("list")
                 .help("List all benchmarks"))
             .arg(Arg::with_name("measure-only")
                 .long("measure-only")
                 .help("Only perform measurements; do no analysis or storage of results. This is useful eg. when profiling the benchmarks, to reduce clutter in the profiling data."))
+            .arg(Arg::with_name("profile-time")
+                .long("profile-time")
+                .takes_value(true)
+                .value_name("SECONDS")
+                .conflicts_with_all(&["test", "list", "measure-only"])
+                .validator(|s| match s.parse::<f64>() {
+                    Ok(seconds) if seconds > 0.0 => Ok(()),
+                    _ => Err(format!("expected a positive number of seconds, found '{}'", s)),
+                })
+                .help("Run each benchmark continuously for the given number of seconds instead of measuring it, eg. to profile it with an external profiler. No analysis is done and no results are stored."))
+            .arg(Arg::with_name("dump-schema")
+                .long("dump-schema")
+                .help("Print the JSON Schema of the result.json files written for each benchmark, then exit."))
             .arg(Arg::with_name("test")
                 .long("test")
                 .help("Run the benchmarks once, to verify that they execute successfully, but do not measure or report the results."))
@@ -830,9 +1521,17 @@ scripts alongside the generated plots.
 ")
             .get_matches();
 
+        if matches.is_present("dump-schema") {
+            println!("{}", json_report::SCHEMA);
+            ::std::process::exit(0);
+        }
+
         if let Some(filter) = matches.value_of("FILTER") {
             self = self.with_filter(filter);
         }
+        if matches.is_present("exclude") {
+            self = self.filter_mode(FilterMode::Exclude);
+        }
 
         let verbose = matches.is_present("verbose");
         let stdout_isatty = atty::is(atty::Stream::Stdout);
@@ -865,15 +1564,34 @@ scripts alongside the generated plots.
             self.baseline_directory = dir.to_owned();
         }
 
-        let mut reports: Vec<Box<Report>> = vec![];
-        reports.push(Box::new(CliReport::new(
-            enable_text_overwrite,
-            enable_text_coloring,
-            verbose,
-        )));
+        self.quiet = matches.is_present("quiet");
+        let mut reports: Vec<Box<Reporter>> = vec![];
+        if self.quiet {
+            reports.push(Box::new(QuietReport::new()));
+        } else {
+            reports.push(Box::new(CliReport::new(
+                enable_text_overwrite,
+                enable_text_coloring,
+                verbose,
+            )));
+        }
         reports.push(Box::new(FileCsvReport));
+        reports.push(Box::new(FileJsonReport));
+        #[cfg(feature = "parquet")]
+        reports.push(Box::new(FileParquetReport));
+        if !matches.is_present("no-time-summary") && !self.quiet {
+            reports.push(Box::new(RunTimeReport::new()));
+        }
+        if !matches.is_present("no-machine-info") && !self.quiet {
+            reports.push(Box::new(MachineInfoReport));
+        }
 
         self.measure_only = matches.is_present("measure-only");
+        if let Some(seconds) = matches.value_of("profile-time") {
+            // Already validated by clap
+            let seconds: f64 = seconds.parse().unwrap();
+            self = self.profile_time(Some(Duration::from_nanos((seconds * 1e9) as u64)));
+        }
         self.test_mode = matches.is_present("test");
         if matches.is_present("list") {
             self.test_mode = true;
@@ -882,19 +1600,25 @@ scripts alongside the generated plots.
 
         #[cfg(feature = "html_reports")]
         {
-            if !self.measure_only {
+            if !self.measure_only && self.profile_time.is_none() {
                 reports.push(Box::new(Html::new()));
             }
         }
 
-        self.report = Box::new(Reports::new(reports));
+        if let Some(ref path) = self.pr_comment {
+            reports.push(Box::new(PrCommentReport::new(path)));
+        }
+        self.report.set_built_in(reports);
 
         self
     }
 
     fn filter_matches(&self, id: &str) -> bool {
         match self.filter {
-            Some(ref string) => id.contains(string),
+            Some(ref string) => match self.filter_mode {
+                FilterMode::Include => id.contains(string),
+                FilterMode::Exclude => !id.contains(string),
+            },
             None => true,
         }
     }
@@ -927,6 +1651,79 @@ scripts alongside the generated plots.
         self.bench(id, Benchmark::new(id, f))
     }
 
+    /// Measures a function and returns the estimated statistics of its time per iteration,
+    /// instead of reporting them. This is meant for embedding Criterion.rs in other programs,
+    /// such as a long-running service which profiles itself continuously.
+    ///
+    /// Unlike `bench_function`, this ignores the command-line options (filters, test mode, etc.),
+    /// prints nothing and saves nothing to the output directory, so there is no comparison
+    /// against a baseline either. Use `bench_function` to report and save the results.
+    ///
+    /// Returns `None` if the function is too slow to complete a single iteration within the
+    /// warm-up time.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # use criterion::Criterion;
+    /// # use std::time::Duration;
+    /// let criterion = Criterion::default()
+    ///     .warm_up_time(Duration::from_millis(100))
+    ///     .measurement_time(Duration::from_millis(200));
+    /// let result = criterion
+    ///     .run_benchmark("sum", |b| b.iter(|| (0..100u64).sum::<u64>()))
+    ///     .unwrap();
+    /// println!("{}: {} ns", result.id, result.mean.point_estimate);
+    /// ```
+    pub fn run_benchmark<F>(&self, id: &str, mut f: F) -> Option<BenchmarkResult>
+    where
+        F: FnMut(&mut Bencher),
+    {
+        let id = BenchmarkId::new(id.to_owned(), None, None, None);
+        let report_context = ReportContext {
+            output_directory: self.output_directory.clone(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+        let mut routine = Function::new(|b: &mut Bencher, _: &()| f(b));
+
+        let (iters, times) = match routine.sample(
+            &id,
+            &self.config,
+            &Reports::new(vec![]),
+            &report_context,
+            &(),
+        ) {
+            Ok(sample) => sample,
+            Err(e) => {
+                error::log_error(&e);
+                return None;
+            }
+        };
+        let avg_times = iters
+            .iter()
+            .zip(times.iter())
+            .map(|(&iters, &elapsed)| elapsed / iters)
+            .collect::<Vec<f64>>();
+        let (_, estimates) = analysis::absolute_estimates(
+            &id,
+            &iters,
+            &times,
+            Sample::new(&avg_times),
+            &self.config,
+        );
+
+        Some(BenchmarkResult::new(
+            &id,
+            Sample::new(&iters),
+            Sample::new(&times),
+            &estimates,
+        ))
+    }
+
     /// Benchmarks multiple functions
     ///
     /// All functions get the same input and are compared with the other implementations.
@@ -975,6 +1772,46 @@ scripts alongside the generated plots.
         self.bench(id, benchmark)
     }
 
+    /// Benchmarks two implementations against each other
+    ///
+    /// Rather than measuring the two functions one after the other, the samples of the two
+    /// alternate within the same measurement window, so that transient load on the machine
+    /// affects both of them alike. The paired samples are then compared with the Wilcoxon
+    /// signed-rank test. The warm-up and measurement time are split between the two functions.
+    ///
+    /// The paired measurements are neither repeated, nor extended, nor timed out, so the
+    /// `measurement_repeats` and `target_ci_width` settings have no effect on them, and there is no
+    /// timeout to stop a hung function.
+    ///
+    /// # Example
+    ///
+    /// ``` rust
+    /// # #![macro_use] extern crate criterion;
+    /// # use self::criterion::*;
+    /// # fn seq_fib(i: &u32) {}
+    /// # fn par_fib(i: &u32) {}
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     let sequential = Fun::new("Sequential", |b, i| b.iter(|| seq_fib(i)));
+    ///     let parallel = Fun::new("Parallel", |b, i| b.iter(|| par_fib(i)));
+    ///
+    ///     c.bench_paired("Fibonacci", sequential, parallel, 20);
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    pub fn bench_paired<I>(&mut self, id: &str, a: Fun<I>, b: Fun<I>, input: I) -> &mut Criterion
+    where
+        I: fmt::Debug + 'static,
+    {
+        self.check_output_directory();
+        // `Criterion` has no plot configuration of its own, so like the benchmarks of
+        // `bench_functions`, the pair is reported with the default one.
+        analysis::paired(id, a.f, b.f, self, &PlotConfiguration::default(), &input);
+        self
+    }
+
     /// Benchmarks a function under various inputs
     ///
     /// This is a convenience method to execute several related benchmarks. Each benchmark will
@@ -1007,6 +1844,61 @@ scripts alongside the generated plots.
         self.bench(id, ParameterizedBenchmark::new(id, f, inputs))
     }
 
+    /// Benchmarks a task running concurrently on each of the given numbers of threads
+    ///
+    /// For each thread count `n`, `make_task` is called `n` times per sample and every task runs on
+    /// its own thread. One iteration runs every task once, so the throughput is reported in tasks
+    /// per second, and the line chart of the summary plots it against the thread count to reveal
+    /// how the task scales, or contends, as threads are added. Each benchmark will receive the id:
+    /// `${id}/${threads}`.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// # #![macro_use] extern crate criterion;
+    /// # use self::criterion::*;
+    /// use std::sync::atomic::{AtomicUsize, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// fn bench(c: &mut Criterion) {
+    ///     let counter = Arc::new(AtomicUsize::new(0));
+    ///     c.bench_threads("shared_counter", &[1, 2, 4], move || {
+    ///         let counter = counter.clone();
+    ///         move || {
+    ///             counter.fetch_add(1, Ordering::SeqCst);
+    ///         }
+    ///     });
+    /// }
+    ///
+    /// criterion_group!(benches, bench);
+    /// criterion_main!(benches);
+    /// ```
+    pub fn bench_threads<F, T>(
+        &mut self,
+        id: &str,
+        counts: &[usize],
+        mut make_task: F,
+    ) -> &mut Criterion
+    where
+        F: FnMut() -> T + 'static,
+        T: FnMut() + Send + 'static,
+    {
+        let benchmark = ParameterizedBenchmark::new(
+            id,
+            move |b, &threads| {
+                let tasks = repeat_with(&mut make_task).take(threads).collect();
+                b.iter_threads(tasks)
+            },
+            counts.to_vec(),
+        );
+        self.bench(
+            id,
+            benchmark
+                .throughput(|&threads| Throughput::Elements(threads as u32))
+                .plot_config(PlotConfiguration::default().throughput_axis(true)),
+        )
+    }
+
     /// Benchmarks an external program
     ///
     /// The external program must:
@@ -1112,6 +2004,7 @@ scripts alongside the generated plots.
         group_id: &str,
         benchmark: B,
     ) -> &mut Criterion {
+        self.check_output_directory();
         benchmark.run(group_id, self);
         self
     }
@@ -1147,6 +2040,88 @@ impl DurationExt for Duration {
     }
 }
 
+/// Computes the bounds of the confidence interval of a statistic at `confidence_level` (eg. 0.95),
+/// from the bootstrap `distribution` of the statistic, using percentiles.
+///
+/// These are the bounds Criterion.rs reports for its estimates, and the "clean" region shaded
+/// under the distributions in the plots. The interval reported for each estimate uses the
+/// configured `Criterion::confidence_level`; this computes it for any other level.
+///
+/// # Panics
+///
+/// Panics if the `distribution` has less than two values or contains NaNs, or if the
+/// `confidence_level` is not in the `(0, 1)` range.
+pub fn confidence_bounds(distribution: &[f64], confidence_level: f64) -> (f64, f64) {
+    assert!(confidence_level > 0. && confidence_level < 1.);
+
+    let percentiles = Sample::new(distribution).percentiles();
+    (
+        percentiles.at(50. * (1. - confidence_level)),
+        percentiles.at(50. * (1. + confidence_level)),
+    )
+}
+
+/// Estimates the probability densities drawn by the violin plots, for rendering them without
+/// gnuplot, eg. in a dashboard. `samples` are the IDs of the benchmarks, each with the average
+/// times per iteration of its samples in nanoseconds. Returns the ID of each benchmark, in the
+/// same order, with the times at which its density was estimated and the density at those times,
+/// scaled so that the peak is one. The densities follow the kernel, bandwidth and resolution set
+/// in `plot_config`.
+///
+/// Like in the plots, non-finite times are left out, and so are the benchmarks with less than two
+/// finite times.
+///
+/// Requires the `html_reports` feature.
+#[cfg(feature = "html_reports")]
+pub fn violin_data(
+    samples: &[(String, Vec<f64>)],
+    plot_config: &PlotConfiguration,
+) -> Vec<(String, Vec<f64>, Vec<f64>)> {
+    let curves: Vec<_> = samples
+        .iter()
+        .map(|&(ref id, ref times)| {
+            (BenchmarkId::new(id.clone(), None, None, None), times.clone())
+        })
+        .collect();
+    let curves: Vec<_> = curves.iter().collect();
+
+    // The violins are listed from the bottom of the plot, where the last benchmark is drawn
+    plot::summary::violin_data("violin_data", &curves, plot_config)
+        .violins
+        .into_iter()
+        .rev()
+        .map(|violin| (violin.label, violin.xs, violin.ys))
+        .collect()
+}
+
+/// Plots the bootstrap distribution of the mean of the benchmark `id` to the SVG file `path`: the
+/// estimated probability density of `means`, the means of resamples of the average times of the
+/// benchmark in nanoseconds, with their 95% confidence interval and their mean marked. The means
+/// can be computed with `Sample::bootstrap_mean_distribution` from the `criterion-stats` crate.
+/// The density is estimated with the kernel set in `plot_config`.
+///
+/// Requires gnuplot and the `html_reports` feature. Errors of gnuplot are logged.
+///
+/// # Panics
+///
+/// Panics if there are less than two means or any of them is NaN.
+#[cfg(feature = "html_reports")]
+pub fn plot_bootstrap_distribution(
+    id: &str,
+    means: &[f64],
+    plot_config: &PlotConfiguration,
+    path: &str,
+) {
+    let id = BenchmarkId::new(id.to_owned(), None, None, None);
+    let means = stats::Distribution::from(means.to_vec().into_boxed_slice());
+    let child = plot::bootstrap_distribution(&id, &means, plot_config.kde_kernel, path);
+    match child.wait_with_output() {
+        Ok(ref out) if out.status.success() => {}
+        Ok(out) => error!("Error in Gnuplot: {}", String::from_utf8_lossy(&out.stderr)),
+        Err(e) => error!("Got IO error while waiting for Gnuplot to complete: {}", e),
+    }
+}
+
 #[derive(Clone, Copy, PartialEq, Deserialize, Serialize, Debug)]
 struct ConfidenceInterval {
     confidence_level: f64,
@@ -1170,7 +2145,7 @@ impl Estimate {
             .iter()
             .map(|(&statistic, distribution)| {
                 let point_estimate = points[&statistic];
-                let (lb, ub) = distribution.confidence_interval(cl);
+                let (lb, ub) = confidence_bounds(distribution.as_slice(), cl);
 
                 (
                     statistic,
@@ -1189,23 +2164,156 @@ impl Estimate {
     }
 }
 
-/// Enum representing different ways of measuring the throughput of benchmarked code.
-/// If the throughput setting is configured for a benchmark then the estimated throughput will
-/// be reported as well as the time per iteration.
-#[derive(Debug, Clone, Serialize, Deserialize)]
-pub enum Throughput {
-    /// Measure throughput in terms of bytes/second. The value should be the number of bytes
-    /// processed by one iteration of the benchmarked code. Typically, this would be the length of
-    /// an input string or `&[u8]`.
-    Bytes(u32),
+/// Results of a benchmark measured with `Criterion::run_benchmark`. All times are per iteration,
+/// in nanoseconds.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkResult {
+    /// Identifier of the benchmark
+    pub id: String,
+    /// Number of iterations of each sample
+    pub iteration_count: Vec<u64>,
+    /// Total time of each sample, in nanoseconds
+    pub sample_times: Vec<f64>,
+    /// Estimated mean time per iteration
+    pub mean: BenchmarkEstimate,
+    /// Estimated median time per iteration
+    pub median: BenchmarkEstimate,
+    /// Estimated median absolute deviation of the time per iteration
+    pub median_abs_dev: BenchmarkEstimate,
+    /// Estimated time per iteration, from the linear regression of the sample times on the
+    /// iteration counts
+    pub slope: BenchmarkEstimate,
+    /// Estimated standard deviation of the time per iteration
+    pub std_dev: BenchmarkEstimate,
+}
 
-    /// Measure throughput in terms of elements/second. The value should be the number of elements
-    /// processed by one iteration of the benchmarked code. Typically, this would be the size of a
-    /// collection, but could also be the number of lines of input text or the number of values to
-    /// parse.
+impl BenchmarkResult {
+    pub(crate) fn new(
+        id: &BenchmarkId,
+        iters: &Sample<f64>,
+        times: &Sample<f64>,
+        estimates: &Estimates,
+    ) -> BenchmarkResult {
+        BenchmarkResult {
+            id: id.id().to_owned(),
+            iteration_count: iters.iter().map(|&n| n as u64).collect(),
+            sample_times: times.as_slice().to_vec(),
+            mean: BenchmarkEstimate::from(&estimates[&Statistic::Mean]),
+            median: BenchmarkEstimate::from(&estimates[&Statistic::Median]),
+            median_abs_dev: BenchmarkEstimate::from(&estimates[&Statistic::MedianAbsDev]),
+            slope: BenchmarkEstimate::from(&estimates[&Statistic::Slope]),
+            std_dev: BenchmarkEstimate::from(&estimates[&Statistic::StdDev]),
+        }
+    }
+}
+
+/// Change of a benchmark relative to its baseline, as passed to `Reporter::comparison_complete`.
+/// The changes are fractions, eg. 0.05 for 5% slower.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkChange {
+    /// Identifier of the benchmark
+    pub id: String,
+    /// Estimated relative change of the mean time per iteration
+    pub mean: BenchmarkEstimate,
+    /// Estimated relative change of the median time per iteration
+    pub median: BenchmarkEstimate,
+    /// p-value of the hypothesis that the mean hasn't changed
+    pub p_value: f64,
+    /// Whether the benchmark improved, regressed or didn't change
+    pub verdict: ComparisonResult,
+}
+impl BenchmarkChange {
+    pub(crate) fn new(id: &BenchmarkId, comparison: &ComparisonData) -> BenchmarkChange {
+        BenchmarkChange {
+            id: id.id().to_owned(),
+            mean: BenchmarkEstimate::from(&comparison.relative_estimates[&Statistic::Mean]),
+            median: BenchmarkEstimate::from(&comparison.relative_estimates[&Statistic::Median]),
+            p_value: comparison.p_value,
+            verdict: comparison.verdict,
+        }
+    }
+}
+
+/// Estimate of a statistic, with its confidence interval.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkEstimate {
+    /// The value of the statistic in the measured sample
+    pub point_estimate: f64,
+    /// Lower bound of the confidence interval
+    pub lower_bound: f64,
+    /// Upper bound of the confidence interval
+    pub upper_bound: f64,
+    /// Confidence level of the confidence interval, eg. 0.95
+    pub confidence_level: f64,
+    /// The standard error of the estimate
+    pub standard_error: f64,
+}
+
+impl<'a> From<&'a Estimate> for BenchmarkEstimate {
+    fn from(estimate: &'a Estimate) -> BenchmarkEstimate {
+        BenchmarkEstimate {
+            point_estimate: estimate.point_estimate,
+            lower_bound: estimate.confidence_interval.lower_bound,
+            upper_bound: estimate.confidence_interval.upper_bound,
+            confidence_level: estimate.confidence_interval.confidence_level,
+            standard_error: estimate.standard_error,
+        }
+    }
+}
+
+/// Enum representing different ways of measuring the throughput of benchmarked code.
+/// If the throughput setting is configured for a benchmark then the estimated throughput will
+/// be reported as well as the time per iteration.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum Throughput {
+    /// Measure throughput in terms of bytes/second. The value should be the number of bytes
+    /// processed by one iteration of the benchmarked code. Typically, this would be the length of
+    /// an input string or `&[u8]`.
+    Bytes(u32),
+
+    /// Measure throughput in terms of elements/second. The value should be the number of elements
+    /// processed by one iteration of the benchmarked code. Typically, this would be the size of a
+    /// collection, but could also be the number of lines of input text or the number of values to
+    /// parse.
     Elements(u32),
 }
 
+/// How much each sample counts towards the estimated time per iteration.
+///
+/// The estimate is fit to all samples, giving more weight to samples with more iterations. A
+/// weighting multiplies that by a per-sample factor, eg. to discount the first samples, which may
+/// still be affected by the benchmark warming up (caches, branch predictors, CPU frequency) even
+/// after the warm-up period.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SampleWeighting {
+    /// Every sample counts the same.
+    Uniform,
+
+    /// The first `count` samples count with the given `weight`, which must be within `(0, 1]`,
+    /// and the remaining samples count fully.
+    DownweightFirst {
+        /// The number of samples to down-weight
+        count: usize,
+        /// The weight of the first samples
+        weight: f64,
+    },
+}
+
+impl SampleWeighting {
+    fn weight(self, index: usize) -> f64 {
+        match self {
+            SampleWeighting::Uniform => 1.,
+            SampleWeighting::DownweightFirst { count, weight } => {
+                if index < count {
+                    weight
+                } else {
+                    1.
+                }
+            }
+        }
+    }
+}
+
 /// Axis scaling type
 #[derive(Debug, Clone, Copy)]
 pub enum AxisScale {
@@ -1216,6 +2324,392 @@ pub enum AxisScale {
     Logarithmic,
 }
 
+/// How the shaded areas of the plots (such as probability densities) are filled
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillStyle {
+    /// Fill with a translucent solid color of the given opacity, between 0 and 1
+    Solid(f64),
+
+    /// Fill with one of gnuplot's hatch patterns, which is easier to tell apart in print and in
+    /// grayscale
+    Pattern(u8),
+}
+
+/// Kernel of the estimates of the probability density drawn in the plots, such as the PDF and
+/// violin plots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KdeKernel {
+    /// Gaussian kernel, whose densities are smooth but extend well beyond the measurements
+    Gaussian,
+
+    /// Epanechnikov kernel, whose densities are zero farther than one bandwidth from the
+    /// measurements
+    Epanechnikov,
+
+    /// Triangular kernel, whose densities are zero farther than one bandwidth from the
+    /// measurements, like those of the Epanechnikov kernel, but more peaked
+    Triangular,
+}
+
+/// Bandwidth of the estimates of the probability density drawn in the violin plots. Smaller
+/// bandwidths smooth the densities less, which shows the modes of multimodal measurements.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum KdeBandwidth {
+    /// Silverman's rule of thumb, which suits measurements with a single mode
+    Silverman,
+
+    /// Scott's rule, which smooths the densities slightly less than Silverman's rule of thumb
+    Scott,
+
+    /// Silverman's rule of thumb, multiplied by this factor
+    Scaled(f64),
+
+    /// This bandwidth, in nanoseconds like the measurements
+    Fixed(f64),
+}
+impl KdeBandwidth {
+    /// The bandwidth, in nanoseconds, of the density of the average times `sample`, eg. to print
+    /// the bandwidth chosen by Silverman's rule of thumb and pick a factor relative to it.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the sample has less than two measurements.
+    pub fn estimate(self, sample: &[f64]) -> f64 {
+        let sample = Sample::new(sample);
+        let silverman = || Kde::new(sample, Gaussian, Bandwidth::Silverman).bandwidth();
+        match self {
+            KdeBandwidth::Silverman => silverman(),
+            KdeBandwidth::Scott => Kde::new(sample, Gaussian, Bandwidth::Scott).bandwidth(),
+            KdeBandwidth::Scaled(factor) => factor * silverman(),
+            KdeBandwidth::Fixed(bandwidth) => bandwidth,
+        }
+    }
+}
+
+/// Quantiles of each benchmark marked on the center line of its violin in the violin plot
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViolinQuantiles {
+    /// No markers
+    None,
+
+    /// A point at the median
+    Median,
+
+    /// A point at the median, on a line from the 25th to the 75th percentile
+    Quartiles,
+}
+
+/// How the violin plot draws the distribution of the times of each benchmark
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViolinShape {
+    /// A smooth kernel density estimate
+    Kde,
+
+    /// A histogram of this many equally wide bins, which shows the discrete structure which the
+    /// density estimate smooths over, eg. times clustered on the ticks of a coarse clock
+    Histogram(usize),
+
+    /// A histogram of this many bins which are equally wide on a logarithmic scale, for times
+    /// which span orders of magnitude
+    LogHistogram(usize),
+}
+
+/// Which functions the line comparison plot labels and colors individually, when it only does so
+/// for some of them
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineSelection {
+    /// The functions which are fastest at the largest input
+    Fastest,
+
+    /// The functions which are slowest at the largest input
+    Slowest,
+
+    /// The functions with the largest values in the plot, at any input
+    Peak,
+}
+
+/// Which value the line comparison and violin plots pick the unit of their time axis for, eg.
+/// microseconds for a value of 1.2 us
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UnitAnchor {
+    /// The largest value in the plot, or the upper limit of the axis if it has fixed limits
+    Max,
+
+    /// The median of the median times of the benchmarks, so that a single slow benchmark doesn't
+    /// force the others onto a larger unit in which they are unreadably small
+    MedianOfMedians,
+
+    /// This value, in the unit of the measurements (eg. nanoseconds)
+    Value(f64),
+}
+
+/// Order of the benchmarks in the violin plot, from the top of the plot to the bottom
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ViolinOrder {
+    /// In the order in which the benchmarks were given
+    AsGiven,
+
+    /// From the fastest median time to the slowest, like a leaderboard
+    ByMedianAscending,
+
+    /// From the slowest median time to the fastest
+    ByMedianDescending,
+}
+
+/// Background of the plots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlotBackground {
+    /// Opaque white background
+    White,
+
+    /// No background, for embedding the plots in pages of any color. Shaded areas are drawn more
+    /// opaque, so that they remain visible on dark pages.
+    Transparent,
+
+    /// Opaque background of the given RGB color. Shaded areas are drawn more opaque, so that they
+    /// remain visible on dark backgrounds.
+    Rgb(u8, u8, u8),
+}
+
+/// Color theme of the summary plots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Theme {
+    /// Dark text and gridlines on a white background
+    Light,
+
+    /// Light text and gridlines on a dark gray background, with curves in lighter colors, for
+    /// embedding the plots in dark pages
+    Dark,
+}
+
+/// Image format of the summary plots
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlotFormat {
+    /// Scalable vector graphics, which stay crisp at any size
+    Svg,
+
+    /// PNG images
+    Png,
+}
+
+/// Built-in colors of the curves of the summary plots, which are used unless custom colors are
+/// set with `PlotConfiguration::palette`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Palette {
+    /// The default colors
+    Default,
+
+    /// The Okabe-Ito colors, which readers with red-green color blindness can still tell apart
+    ColorblindSafe,
+}
+
+/// Corner of the plotting area which a legend is placed at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegendCorner {
+    /// Top left corner
+    TopLeft,
+    /// Top right corner
+    TopRight,
+    /// Bottom left corner
+    BottomLeft,
+    /// Bottom right corner
+    BottomRight,
+}
+
+/// Where the legend of a plot is placed
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LegendPosition {
+    /// In a corner of the plotting area, over the plot
+    Inside(LegendCorner),
+
+    /// Beside a corner of the plotting area, which shrinks to make room for the legend
+    Outside(LegendCorner),
+
+    /// Below the plotting area, with the entries side by side, which leaves room for legends with
+    /// more entries than fit beside the plot
+    Below,
+
+    /// No legend at all
+    Hidden,
+}
+
+/// Placement and font of the legend of the line comparison plots.
+///
+/// By default, the legend is placed beside the top right corner of the plot, in the font of the
+/// plot. A plot with many functions reads better with a smaller legend below it:
+///
+/// ```rust
+/// use criterion::{Legend, LegendPosition, PlotConfiguration};
+///
+/// let legend = Legend::default()
+///     .position(LegendPosition::Below)
+///     .font_size(8.0);
+/// let plot_config = PlotConfiguration::default().line_legend(legend);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct Legend {
+    position: LegendPosition,
+    font: Option<String>,
+    font_size: Option<f64>,
+}
+
+impl Default for Legend {
+    fn default() -> Legend {
+        Legend {
+            position: LegendPosition::Outside(LegendCorner::TopRight),
+            font: None,
+            font_size: None,
+        }
+    }
+}
+
+impl Legend {
+    /// Set where the legend is placed, or hide it.
+    pub fn position(mut self, position: LegendPosition) -> Legend {
+        self.position = position;
+        self
+    }
+
+    /// Set the name of the font of the legend, eg. `"Helvetica"`.
+    pub fn font(mut self, name: &str) -> Legend {
+        self.font = Some(name.to_owned());
+        self
+    }
+
+    /// Set the size of the font of the legend, in points.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the size isn't positive.
+    pub fn font_size(mut self, size: f64) -> Legend {
+        assert!(size > 0.);
+        self.font_size = Some(size);
+        self
+    }
+}
+
+/// How the bars of the change overview plot, which shows the change of the mean time of each
+/// benchmark against its baseline, are colored.
+///
+/// By default, a change is highlighted as an improvement or a regression if the comparison found
+/// it to be statistically significant and larger than the noise threshold. Explicit thresholds
+/// override that verdict, eg. to highlight every regression above 5% in bright red:
+///
+/// ```rust
+/// use criterion::{ChangeHighlighting, PlotConfiguration};
+///
+/// let highlighting = ChangeHighlighting::default().regression(0.05, (255, 0, 0));
+/// let plot_config = PlotConfiguration::default().change_highlighting(highlighting);
+/// ```
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChangeHighlighting {
+    improvement_threshold: Option<f64>,
+    improvement_color: (u8, u8, u8),
+    regression_threshold: Option<f64>,
+    regression_color: (u8, u8, u8),
+    noise_color: (u8, u8, u8),
+}
+
+impl Default for ChangeHighlighting {
+    fn default() -> ChangeHighlighting {
+        ChangeHighlighting {
+            improvement_threshold: None,
+            improvement_color: (46, 139, 87),
+            regression_threshold: None,
+            regression_color: (227, 26, 28),
+            noise_color: (31, 120, 180),
+        }
+    }
+}
+
+impl ChangeHighlighting {
+    /// Highlight the benchmarks which got faster by more than `threshold` (eg. 0.05 for 5%) in
+    /// the given RGB color.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold is negative.
+    pub fn improvement(mut self, threshold: f64, color: (u8, u8, u8)) -> ChangeHighlighting {
+        assert!(threshold >= 0.0);
+        self.improvement_threshold = Some(threshold);
+        self.improvement_color = color;
+        self
+    }
+
+    /// Highlight the benchmarks which got slower by more than `threshold` (eg. 0.05 for 5%) in
+    /// the given RGB color.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the threshold is negative.
+    pub fn regression(mut self, threshold: f64, color: (u8, u8, u8)) -> ChangeHighlighting {
+        assert!(threshold >= 0.0);
+        self.regression_threshold = Some(threshold);
+        self.regression_color = color;
+        self
+    }
+
+    /// Set the RGB color of the benchmarks which are neither improvements nor regressions.
+    pub fn noise_color(mut self, color: (u8, u8, u8)) -> ChangeHighlighting {
+        self.noise_color = color;
+        self
+    }
+}
+
+/// Which gridlines a summary plot draws at the tics of each of its axes, eg. major and minor
+/// gridlines on both axes to read intermediate values off dense logarithmic plots:
+///
+/// ```rust
+/// use criterion::{AxisScale, GridConfig, PlotConfiguration};
+///
+/// let grid = GridConfig::none().x(true, true).y(true, true);
+/// let plot_config = PlotConfiguration::default()
+///     .summary_scale(AxisScale::Logarithmic)
+///     .line_grid(grid);
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct GridConfig {
+    x_major: bool,
+    x_minor: bool,
+    y_major: bool,
+    y_minor: bool,
+}
+
+impl GridConfig {
+    /// No gridlines at all.
+    pub fn none() -> GridConfig {
+        GridConfig {
+            x_major: false,
+            x_minor: false,
+            y_major: false,
+            y_minor: false,
+        }
+    }
+
+    /// Set whether the major and the minor gridlines are drawn at the tics of the X axis.
+    pub fn x(mut self, major: bool, minor: bool) -> GridConfig {
+        self.x_major = major;
+        self.x_minor = minor;
+        self
+    }
+
+    /// Set whether the major and the minor gridlines are drawn at the tics of the Y axis.
+    pub fn y(mut self, major: bool, minor: bool) -> GridConfig {
+        self.y_major = major;
+        self.y_minor = minor;
+        self
+    }
+}
+
+// What the plotted values measure, which decides how the summary plots scale and label them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum MeasuredUnit {
+    Nanoseconds,
+    // Nothing measures cycles yet; this is for the custom measurements.
+    #[allow(dead_code)]
+    Cycles,
+}
+
 /// Contains the configuration options for the plots generated by a particular benchmark
 /// or benchmark group.
 ///
@@ -1231,12 +2725,78 @@ pub enum AxisScale {
 #[derive(Debug, Clone)]
 pub struct PlotConfiguration {
     summary_scale: AxisScale,
+    summary_range: Option<(f64, f64)>,
+    unit_anchor: UnitAnchor,
+    throughput_axis: bool,
+    line_throughput: bool,
+    bytes_per_element: Option<f64>,
+    line_bands: bool,
+    line_differences: bool,
+    line_baseline: Option<String>,
+    line_aspect_ratio: Option<f64>,
+    line_trimmed_mean: Option<f64>,
+    line_legend: Legend,
+    line_highlighted: Option<(usize, LineSelection)>,
+    line_grid: GridConfig,
+    size: Option<(usize, Option<usize>)>,
+    fill_style: FillStyle,
+    violin_markers: bool,
+    violin_quantiles: ViolinQuantiles,
+    violin_order: ViolinOrder,
+    violin_outliers: bool,
+    violin_grid: GridConfig,
+    violin_shape: ViolinShape,
+    violin_range_quantiles: Option<(f64, f64)>,
+    adaptive_kde_points: bool,
+    kde_kernel: KdeKernel,
+    kde_bandwidth: KdeBandwidth,
+    x_ticks: Option<Vec<(f64, String)>>,
+    background: PlotBackground,
+    change_highlighting: ChangeHighlighting,
+    palette: Option<Vec<(u8, u8, u8)>>,
+    builtin_palette: Palette,
+    format: Option<PlotFormat>,
+    theme: Theme,
+    unit: MeasuredUnit,
 }
 
 impl Default for PlotConfiguration {
     fn default() -> PlotConfiguration {
         PlotConfiguration {
             summary_scale: AxisScale::Linear,
+            summary_range: None,
+            unit_anchor: UnitAnchor::Max,
+            throughput_axis: false,
+            line_throughput: false,
+            bytes_per_element: None,
+            line_bands: false,
+            line_differences: true,
+            line_baseline: None,
+            line_aspect_ratio: None,
+            line_trimmed_mean: None,
+            line_legend: Legend::default(),
+            line_highlighted: None,
+            line_grid: GridConfig::none().y(true, false),
+            size: None,
+            fill_style: FillStyle::Solid(0.25),
+            violin_markers: false,
+            violin_quantiles: ViolinQuantiles::None,
+            violin_order: ViolinOrder::AsGiven,
+            violin_outliers: false,
+            violin_grid: GridConfig::none().x(true, false),
+            violin_shape: ViolinShape::Kde,
+            violin_range_quantiles: None,
+            adaptive_kde_points: false,
+            kde_kernel: KdeKernel::Gaussian,
+            kde_bandwidth: KdeBandwidth::Silverman,
+            x_ticks: None,
+            background: PlotBackground::White,
+            change_highlighting: ChangeHighlighting::default(),
+            palette: None,
+            builtin_palette: Palette::Default,
+            format: None,
+            theme: Theme::Light,
+            unit: MeasuredUnit::Nanoseconds,
         }
     }
 }
@@ -1249,4 +2809,456 @@ impl PlotConfiguration {
         self.summary_scale = new_scale;
         self
     }
+
+    /// Set fixed limits, in nanoseconds, of the time axis of the line comparison and violin
+    /// plots, instead of fitting each plot to its data, so that plots of different groups can be
+    /// compared at a glance. The limits also decide the unit of the axis.
+    ///
+    /// The limits are kept even if some of the data falls outside of them; that data is cut off,
+    /// with a warning.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the lower limit isn't below the upper limit.
+    pub fn summary_range(mut self, lower: f64, upper: f64) -> PlotConfiguration {
+        assert!(lower < upper);
+        self.summary_range = Some((lower, upper));
+        self
+    }
+
+    /// Set which value the line comparison and violin plots pick the unit of their time axis
+    /// for. Defaults to the largest value in the plot.
+    ///
+    /// # Panics
+    ///
+    /// Panics if an explicit value isn't positive and finite.
+    pub fn unit_anchor(mut self, anchor: UnitAnchor) -> PlotConfiguration {
+        if let UnitAnchor::Value(value) = anchor {
+            assert!(value > 0. && value.is_finite());
+        }
+        self.unit_anchor = anchor;
+        self
+    }
+
+    /// Enable or disable plotting the throughput on a secondary (right) Y axis of the line
+    /// comparison plot, alongside the time on the left Y axis. This only has an effect if every
+    /// benchmark in the group has a throughput configured. Defaults to disabled.
+    pub fn throughput_axis(mut self, enabled: bool) -> PlotConfiguration {
+        self.throughput_axis = enabled;
+        self
+    }
+
+    /// Enable or disable plotting the throughput instead of the time on the Y axis of the line
+    /// comparison plot, so that faster functions are higher up. Like `throughput_axis`, this only
+    /// has an effect if every benchmark in the group has a throughput configured, and the fixed
+    /// limits of `summary_range` don't apply to it. Defaults to disabled.
+    pub fn line_throughput(mut self, enabled: bool) -> PlotConfiguration {
+        self.line_throughput = enabled;
+        self
+    }
+
+    /// Set how many bytes make up one element, so that groups which mix benchmarks with byte and
+    /// element throughputs get a line comparison plot, with the inputs and throughputs of the
+    /// benchmarks counting bytes converted to elements. Without it, such groups aren't plotted.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the number of bytes isn't positive and finite.
+    pub fn bytes_per_element(mut self, bytes: f64) -> PlotConfiguration {
+        assert!(bytes > 0. && bytes.is_finite());
+        self.bytes_per_element = Some(bytes);
+        self
+    }
+
+    /// Enable or disable shading a band around each line of the line comparison plot, between the
+    /// 2.5th and 97.5th percentiles of the measurements of each input, to tell real differences
+    /// from noise where lines cross. Defaults to disabled.
+    pub fn line_bands(mut self, enabled: bool) -> PlotConfiguration {
+        self.line_bands = enabled;
+        self
+    }
+
+    /// Enable or disable labeling each input of the line comparison plots of groups of exactly
+    /// two functions with the relative difference of the second function against the first, eg.
+    /// `+12.50%` if the second takes an eighth longer. Defaults to enabled.
+    pub fn line_differences(mut self, enabled: bool) -> PlotConfiguration {
+        self.line_differences = enabled;
+        self
+    }
+
+    /// Also draw a line comparison plot of the mean of each function relative to the mean of the
+    /// function `baseline` at the same input, in which the baseline is a flat line at 1.0 and
+    /// faster functions dip below it. Inputs which the baseline wasn't measured at are left out.
+    /// Groups without such a function only get the absolute plot. Defaults to none.
+    pub fn line_baseline(mut self, baseline: &str) -> PlotConfiguration {
+        self.line_baseline = Some(baseline.to_owned());
+        self
+    }
+
+    /// Set the ratio of the width to the height of the line comparison plots, which keep their
+    /// width. Wider plots read better when embedded in wide documents. A height set with `size`
+    /// takes precedence. Defaults to 16:9.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the ratio isn't positive and finite.
+    pub fn line_aspect_ratio(mut self, ratio: f64) -> PlotConfiguration {
+        assert!(ratio > 0. && ratio.is_finite());
+        self.line_aspect_ratio = Some(ratio);
+        self
+    }
+
+    /// Plot the trimmed mean of each benchmark in the line comparison plots instead of its mean,
+    /// discarding the smallest and the largest `proportion` of its samples first, so that a few
+    /// outliers (eg. from a noisy machine) don't distort the lines. Defaults to the plain mean.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the proportion isn't in the `[0, 0.5)` range.
+    pub fn line_trimmed_mean(mut self, proportion: f64) -> PlotConfiguration {
+        assert!(proportion >= 0. && proportion < 0.5);
+        self.line_trimmed_mean = Some(proportion);
+        self
+    }
+
+    /// Set where the legend of the line comparison plots is placed, and its font, eg. to place
+    /// the legends of plots with many functions below them instead of letting them overflow the
+    /// image. Defaults to beside the top right corner of the plot.
+    pub fn line_legend(mut self, legend: Legend) -> PlotConfiguration {
+        self.line_legend = legend;
+        self
+    }
+
+    /// Label and color only `count` functions of the line comparison plots, chosen by
+    /// `selection`, and draw the others in gray under a single "others" entry of the legend, so
+    /// that groups with dozens of functions stay legible. Defaults to labeling every function.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the count is zero.
+    pub fn line_highlighted(mut self, count: usize, selection: LineSelection) -> PlotConfiguration {
+        assert!(count > 0);
+        self.line_highlighted = Some((count, selection));
+        self
+    }
+
+    /// Set the gridlines of the line comparison plots. Defaults to the major gridlines of the Y
+    /// axis.
+    pub fn line_grid(mut self, grid: GridConfig) -> PlotConfiguration {
+        self.line_grid = grid;
+        self
+    }
+
+    /// Set the size in pixels of the line comparison, violin and change overview plots, eg. for
+    /// HiDPI displays or to embed them in documentation. With only a `width`, the line comparison
+    /// plots keep their aspect ratio, and the other plots keep growing by 25 pixels for each
+    /// benchmark they show. With a `height` as well, every plot has exactly that size. Defaults
+    /// to a width of 1280 pixels.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the width or height is zero.
+    pub fn size(mut self, width: usize, height: Option<usize>) -> PlotConfiguration {
+        assert!(width > 0 && height != Some(0));
+        self.size = Some((width, height));
+        self
+    }
+
+    /// Set how the probability densities in the violin and PDF plots are shaded. Defaults to a
+    /// solid fill with an opacity of 0.25.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the opacity of a solid fill is outside the range `[0, 1]`
+    pub fn fill_style(mut self, new_style: FillStyle) -> PlotConfiguration {
+        if let FillStyle::Solid(opacity) = new_style {
+            assert!(opacity >= 0. && opacity <= 1.);
+        }
+        self.fill_style = new_style;
+        self
+    }
+
+    /// Enable or disable marking the mean and median of each benchmark in the violin plot, so
+    /// that the central tendency is visible along with the shape. Defaults to disabled.
+    pub fn violin_markers(mut self, enabled: bool) -> PlotConfiguration {
+        self.violin_markers = enabled;
+        self
+    }
+
+    /// Set which quantiles are marked on the center line of each violin in the violin plot, which
+    /// shows where the typical times are and how skewed the distribution is. Defaults to none.
+    pub fn violin_quantiles(mut self, quantiles: ViolinQuantiles) -> PlotConfiguration {
+        self.violin_quantiles = quantiles;
+        self
+    }
+
+    /// Set the order of the benchmarks in the violin plot, eg. to rank them from the fastest to
+    /// the slowest. Defaults to the order in which they were given.
+    pub fn violin_order(mut self, order: ViolinOrder) -> PlotConfiguration {
+        self.violin_order = order;
+        self
+    }
+
+    /// Enable or disable marking the mild and severe outliers of each benchmark, by Tukey's
+    /// fences, on the center line of its violin in the violin plot, which shows how noisy its
+    /// measurements were. Defaults to disabled.
+    pub fn violin_outliers(mut self, enabled: bool) -> PlotConfiguration {
+        self.violin_outliers = enabled;
+        self
+    }
+
+    /// Set the gridlines of the violin plots. Defaults to the major gridlines of the X axis.
+    pub fn violin_grid(mut self, grid: GridConfig) -> PlotConfiguration {
+        self.violin_grid = grid;
+        self
+    }
+
+    /// Fit the time axis of the violin plots to the `lower` to `upper` quantiles of the times of
+    /// the benchmarks, eg. 0.01 to 0.99, rather than to the whole of their densities, so that a
+    /// few extreme times don't squash the detail of the rest. The axis spans the smallest of the
+    /// lower quantiles of the benchmarks to the largest of their upper quantiles. Fixed limits
+    /// set with `summary_range` take precedence. Defaults to fitting the whole densities.
+    ///
+    /// # Panics
+    ///
+    /// Panics unless `0 <= lower < upper <= 1`.
+    pub fn violin_range_quantiles(mut self, lower: f64, upper: f64) -> PlotConfiguration {
+        assert!(lower >= 0. && lower < upper && upper <= 1.);
+        self.violin_range_quantiles = Some((lower, upper));
+        self
+    }
+
+    /// Set how the violin plot draws the distribution of the times of each benchmark. Defaults
+    /// to a kernel density estimate.
+    ///
+    /// # Panics
+    ///
+    /// Panics if a histogram has no bins.
+    pub fn violin_shape(mut self, shape: ViolinShape) -> PlotConfiguration {
+        match shape {
+            ViolinShape::Histogram(nbins) | ViolinShape::LogHistogram(nbins) => assert!(nbins > 0),
+            ViolinShape::Kde => {}
+        }
+        self.violin_shape = shape;
+        self
+    }
+
+    /// Enable or disable scaling down the resolution of each density in the violin plot as the
+    /// number of benchmarks in the group grows, which keeps the size of the plots of large groups
+    /// reasonable. Defaults to disabled.
+    pub fn adaptive_kde_points(mut self, enabled: bool) -> PlotConfiguration {
+        self.adaptive_kde_points = enabled;
+        self
+    }
+
+    /// Set the kernel of the estimated probability densities in the plots. Defaults to Gaussian.
+    pub fn kde_kernel(mut self, kernel: KdeKernel) -> PlotConfiguration {
+        self.kde_kernel = kernel;
+        self
+    }
+
+    /// Set the bandwidth of the estimated probability densities in the violin plots. Defaults to
+    /// Silverman's rule of thumb, which can smooth away the modes of multimodal measurements.
+    ///
+    /// # Panics
+    ///
+    /// Panics if the factor or bandwidth isn't positive and finite.
+    pub fn kde_bandwidth(mut self, bandwidth: KdeBandwidth) -> PlotConfiguration {
+        match bandwidth {
+            KdeBandwidth::Silverman | KdeBandwidth::Scott => {}
+            KdeBandwidth::Scaled(x) | KdeBandwidth::Fixed(x) => {
+                assert!(x > 0. && x.is_finite())
+            }
+        }
+        self.kde_bandwidth = bandwidth;
+        self
+    }
+
+    /// Set the positions and labels of the tics of the X axis of the line comparison plot, eg.
+    /// `vec![(1024., "1K".to_owned()), (2048., "2K".to_owned())]`, instead of the numeric tics
+    /// which gnuplot chooses. Defaults to gnuplot's tics.
+    pub fn x_ticks(mut self, ticks: Vec<(f64, String)>) -> PlotConfiguration {
+        self.x_ticks = Some(ticks);
+        self
+    }
+
+    /// Set the background of the line comparison and violin plots. Defaults to white.
+    pub fn background(mut self, new_background: PlotBackground) -> PlotConfiguration {
+        self.background = new_background;
+        self
+    }
+
+    /// Set how the change overview plot distinguishes improvements, regressions and noise.
+    /// Defaults to the verdicts of the comparisons against the baseline.
+    pub fn change_highlighting(mut self, highlighting: ChangeHighlighting) -> PlotConfiguration {
+        self.change_highlighting = highlighting;
+        self
+    }
+
+    /// Set the RGB colors of the functions in the line comparison plot, and of the benchmarks in
+    /// the violin plot, which are otherwise all drawn in blue. If there are more functions or
+    /// benchmarks than colors, evenly spaced hues are generated instead of reusing colors.
+    ///
+    /// # Panics
+    ///
+    /// Panics if there are no colors.
+    pub fn palette(mut self, colors: Vec<(u8, u8, u8)>) -> PlotConfiguration {
+        assert!(!colors.is_empty());
+        self.palette = Some(colors);
+        self
+    }
+
+    /// Set which of the built-in palettes colors the functions in the line comparison plot, if
+    /// no custom colors are set with `palette`. Defaults to `Palette::Default`.
+    pub fn builtin_palette(mut self, palette: Palette) -> PlotConfiguration {
+        self.builtin_palette = palette;
+        self
+    }
+
+    /// Set the image format of the line comparison, violin and change overview plots, whatever
+    /// the extension of the files they are saved to. By default, plots saved to `.png` files are
+    /// PNG images and all others are SVG.
+    pub fn format(mut self, format: PlotFormat) -> PlotConfiguration {
+        self.format = Some(format);
+        self
+    }
+
+    /// Set the color theme of the line comparison, violin and change overview plots. The dark
+    /// theme draws light text and gridlines on a dark gray background, and the curves in lighter
+    /// colors which stand out from it. Choosing a theme also sets its background, so set a
+    /// different `background` after the theme. Defaults to the light theme.
+    pub fn theme(mut self, theme: Theme) -> PlotConfiguration {
+        self.theme = theme;
+        self.background = match theme {
+            Theme::Light => PlotBackground::White,
+            Theme::Dark => PlotBackground::Rgb(30, 30, 30),
+        };
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::thread;
+
+    struct Input<'a> {
+        value: u64,
+        dropped: &'a RefCell<Vec<u64>>,
+    }
+    impl<'a> Drop for Input<'a> {
+        fn drop(&mut self) {
+            self.dropped.borrow_mut().push(self.value);
+        }
+    }
+
+    #[test]
+    fn iter_batched_ref_mutates_inputs_and_excludes_setup() {
+        let dropped = RefCell::new(vec![]);
+        let mut setups = 0;
+        let mut bencher = Bencher {
+            iterated: false,
+            iters: 10,
+            elapsed: Duration::from_secs(0),
+            elements: None,
+        };
+
+        bencher.iter_batched_ref(
+            || {
+                setups += 1;
+                thread::sleep(Duration::from_millis(20));
+                Input {
+                    value: 0,
+                    dropped: &dropped,
+                }
+            },
+            |input| input.value += 1,
+            3,
+        );
+
+        assert!(bencher.iterated);
+        assert_eq!(10, setups);
+        assert_eq!(vec![1; 10], *dropped.borrow());
+        // 200ms were spent in setup; the routine itself takes next to no time.
+        assert!(bencher.elapsed < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn iter_with_preamble_excludes_preamble() {
+        let events = RefCell::new(vec![]);
+        let mut bencher = Bencher {
+            iterated: false,
+            iters: 10,
+            elapsed: Duration::from_secs(0),
+            elements: None,
+        };
+
+        bencher.iter_with_preamble(
+            || {
+                events.borrow_mut().push("preamble");
+                thread::sleep(Duration::from_millis(20));
+            },
+            || events.borrow_mut().push("routine"),
+        );
+
+        assert!(bencher.iterated);
+        let expected: Vec<_> = (0..10)
+            .flat_map(|_| vec!["preamble", "routine"])
+            .collect();
+        assert_eq!(expected, *events.borrow());
+        // 200ms were spent in the preamble; the routine itself takes next to no time.
+        assert!(bencher.elapsed < Duration::from_millis(100));
+    }
+
+    #[test]
+    fn confidence_bounds_match_reported_interval() {
+        let times: Vec<f64> = (0..100).map(|i| f64::from(100 + (i * 37) % 23)).collect();
+        let sample = Sample::new(&times);
+        let (distribution,) = sample.bootstrap(10_000, |s| (s.mean(),));
+
+        let mut distributions = Distributions::new();
+        distributions.insert(Statistic::Mean, distribution);
+        let mut points = BTreeMap::new();
+        points.insert(Statistic::Mean, sample.mean());
+        let ci = Estimate::new(&distributions, &points, 0.95)[&Statistic::Mean].confidence_interval;
+
+        let distribution = distributions[&Statistic::Mean].as_slice();
+        assert_eq!(
+            (ci.lower_bound, ci.upper_bound),
+            confidence_bounds(distribution, 0.95)
+        );
+
+        let (lb, ub) = confidence_bounds(distribution, 0.99);
+        assert!(lb < ci.lower_bound && ci.upper_bound < ub);
+    }
+
+    #[cfg(feature = "html_reports")]
+    #[test]
+    fn violin_data_matches_violin_plot() {
+        let samples = vec![
+            ("fast".to_owned(), vec![10., 11., 12., 10.5]),
+            ("broken".to_owned(), vec![::std::f64::NAN, 5.]),
+            ("slow".to_owned(), vec![20., 25., 21., ::std::f64::INFINITY, 22.]),
+        ];
+        let config = PlotConfiguration::default().kde_kernel(KdeKernel::Epanechnikov);
+        let curves = violin_data(&samples, &config);
+
+        let ids: Vec<_> = samples
+            .iter()
+            .map(|&(ref id, ref times)| {
+                (BenchmarkId::new(id.clone(), None, None, None), times.clone())
+            })
+            .collect();
+        let ids: Vec<_> = ids.iter().collect();
+        let plotted = plot::summary::violin_data("group", &ids, &config);
+
+        assert_eq!(2, curves.len());
+        for (&(ref id, ref xs, ref ys), violin) in curves.iter().zip(plotted.violins.iter().rev()) {
+            assert_eq!(&violin.label, id);
+            assert_eq!(&violin.xs, xs);
+            assert_eq!(&violin.ys, ys);
+            assert_eq!(1., ys.iter().cloned().fold(0., f64::max));
+        }
+        assert_eq!("slow", curves[1].0);
+    }
 }
@@ -0,0 +1,212 @@
+use std::cell::Cell;
+use std::env;
+use std::fs::File;
+use std::io::Read;
+use std::process::Command;
+
+use num_cpus;
+
+use report::{BenchmarkId, ReportContext, Reporter};
+
+/// Description of the machine and toolchain the benchmarks are running on.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MachineInfo {
+    pub cpu_model: Option<String>,
+    pub logical_cores: usize,
+    pub os: &'static str,
+    pub arch: &'static str,
+    pub rustc_version: Option<String>,
+    pub optimized: bool,
+    pub criterion_version: &'static str,
+    pub git: Option<GitInfo>,
+}
+impl MachineInfo {
+    pub fn collect() -> MachineInfo {
+        MachineInfo {
+            cpu_model: cpu_model(),
+            logical_cores: num_cpus::get(),
+            os: env::consts::OS,
+            arch: env::consts::ARCH,
+            rustc_version: rustc_version(),
+            // Benchmarks are built with the bench profile unless someone goes out of their way to
+            // change that, so debug assertions are a reasonable proxy for a missing optimizer.
+            optimized: !cfg!(debug_assertions),
+            criterion_version: env!("CARGO_PKG_VERSION"),
+            git: git_info(),
+        }
+    }
+
+    pub fn header(&self) -> String {
+        let unknown = "unknown";
+
+        format!(
+            "Machine information:\n  \
+             CPU:         {}\n  \
+             Cores:       {} logical\n  \
+             OS:          {} ({})\n  \
+             rustc:       {}\n  \
+             Build:       {}\n  \
+             Criterion:   {}\n  \
+             Commit:      {}",
+            self.cpu_model.as_ref().map_or(unknown, |s| s.as_str()),
+            self.logical_cores,
+            self.os,
+            self.arch,
+            self.rustc_version.as_ref().map_or(unknown, |s| s.as_str()),
+            if self.optimized {
+                "optimized"
+            } else {
+                "unoptimized (debug assertions enabled)"
+            },
+            self.criterion_version,
+            self.git.as_ref().map_or(unknown.to_owned(), |git| match git.branch {
+                Some(ref branch) => format!("{} ({})", git.commit, branch),
+                None => git.commit.clone(),
+            }),
+        )
+    }
+}
+
+/// The commit the benchmarks were built from, for correlating results with the history of the
+/// project.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub(crate) struct GitInfo {
+    pub commit: String,
+    pub branch: Option<String>,
+}
+impl GitInfo {
+    // CI services tell the commit they're building in environment variables, which also work
+    // where the checkout has no `.git` directory. Otherwise, git is asked.
+    pub fn collect() -> Option<GitInfo> {
+        GitInfo::from_env(|name| env::var(name).ok()).or_else(GitInfo::from_git)
+    }
+
+    fn from_env<F>(var: F) -> Option<GitInfo>
+    where
+        F: Fn(&str) -> Option<String>,
+    {
+        let commit = var("GITHUB_SHA").filter(|sha| !sha.is_empty())?;
+        // Pull requests are built from a merge ref, so the branch is the head of the request.
+        let branch = var("GITHUB_HEAD_REF")
+            .filter(|branch| !branch.is_empty())
+            .or_else(|| {
+                var("GITHUB_REF")
+                    .map(|reference| reference.trim_start_matches("refs/heads/").to_owned())
+            });
+        Some(GitInfo { commit, branch })
+    }
+
+    fn from_git() -> Option<GitInfo> {
+        let git = |args: &[&str]| {
+            let output = Command::new("git").args(args).output().ok()?;
+            if !output.status.success() {
+                return None;
+            }
+            String::from_utf8(output.stdout)
+                .ok()
+                .map(|out| out.trim().to_owned())
+        };
+
+        let commit = git(&["rev-parse", "HEAD"])?;
+        // A detached HEAD has no branch.
+        let branch = git(&["rev-parse", "--abbrev-ref", "HEAD"]).filter(|branch| branch != "HEAD");
+        Some(GitInfo { commit, branch })
+    }
+}
+
+thread_local! {
+    static GIT_INFO: Option<GitInfo> = GitInfo::collect();
+}
+
+/// The commit the benchmarks were built from, if it's known. It's only looked up once.
+pub(crate) fn git_info() -> Option<GitInfo> {
+    GIT_INFO.with(|info| info.clone())
+}
+
+fn cpu_model() -> Option<String> {
+    let mut cpuinfo = String::new();
+    File::open("/proc/cpuinfo")
+        .and_then(|mut file| file.read_to_string(&mut cpuinfo))
+        .ok()?;
+
+    cpuinfo
+        .lines()
+        .filter(|line| line.starts_with("model name"))
+        .filter_map(|line| line.splitn(2, ':').nth(1))
+        .map(|model| model.trim().to_owned())
+        .next()
+}
+
+// This is the compiler found at run time, which is usually - but not necessarily - the one which
+// built the benchmarks.
+fn rustc_version() -> Option<String> {
+    let rustc = env::var_os("RUSTC").unwrap_or_else(|| "rustc".into());
+    let output = Command::new(rustc).arg("--version").output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+
+    String::from_utf8(output.stdout)
+        .ok()
+        .map(|version| version.trim().to_owned())
+}
+
+// Every benchmark group is run by its own `Criterion` instance, but the header should only be
+// printed once per run.
+thread_local! {
+    static HEADER_PRINTED: Cell<bool> = Cell::new(false);
+}
+
+/// Report which prints a description of the machine before the first benchmark, so that captured
+/// benchmark logs can be compared with some confidence.
+pub(crate) struct MachineInfoReport;
+impl Reporter for MachineInfoReport {
+    fn started(&self, _: &BenchmarkId, context: &ReportContext) {
+        if context.test_mode || HEADER_PRINTED.with(|printed| printed.replace(true)) {
+            return;
+        }
+
+        println!("{}\n", MachineInfo::collect().header());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_header_contains_cores_and_version() {
+        let info = MachineInfo::collect();
+        assert!(info.logical_cores > 0);
+        assert_eq!(env!("CARGO_PKG_VERSION"), info.criterion_version);
+
+        let header = info.header();
+        assert!(header.contains(&format!("Cores:       {} logical", info.logical_cores)));
+        assert!(header.contains(&format!("Criterion:   {}", info.criterion_version)));
+        assert!(header.contains("Commit:      "));
+    }
+
+    #[test]
+    fn git_info_from_github_actions() {
+        let vars = |sha: &'static str, reference: &'static str, head: &'static str| {
+            move |name: &str| match name {
+                "GITHUB_SHA" => Some(sha.to_owned()),
+                "GITHUB_REF" => Some(reference.to_owned()),
+                "GITHUB_HEAD_REF" => Some(head.to_owned()),
+                _ => None,
+            }
+        };
+
+        let info = GitInfo::from_env(vars("0123abcd", "refs/heads/master", "")).unwrap();
+        assert_eq!("0123abcd", info.commit);
+        assert_eq!(Some("master".to_owned()), info.branch);
+        let json = ::serde_json::to_string(&info).unwrap();
+        assert_eq!(r#"{"commit":"0123abcd","branch":"master"}"#, json);
+
+        let info = GitInfo::from_env(vars("0123abcd", "refs/pull/7/merge", "feature")).unwrap();
+        assert_eq!(Some("feature".to_owned()), info.branch);
+
+        assert_eq!(None, GitInfo::from_env(vars("", "", "")));
+        assert_eq!(None, GitInfo::from_env(|_| None));
+    }
+}
@@ -0,0 +1,483 @@
+//! Report which saves the samples of each benchmark as a Parquet file, for loading them into
+//! analytics tools. The files are small and flat, so this writes the format directly rather than
+//! pulling in the arrow crates: a single row group with one uncompressed, PLAIN-encoded data page
+//! per column, described by a footer in the Thrift compact protocol.
+
+use error::Result;
+use fs;
+use report::{BenchmarkId, MeasurementData, ReportContext, Reporter};
+
+const MAGIC: &[u8] = b"PAR1";
+
+// Thrift compact protocol types
+const I32: u8 = 5;
+const I64: u8 = 6;
+const BINARY: u8 = 8;
+const LIST: u8 = 9;
+const STRUCT: u8 = 12;
+
+// Parquet enums
+const TYPE_INT64: i32 = 2;
+const TYPE_DOUBLE: i32 = 5;
+const TYPE_BYTE_ARRAY: i32 = 6;
+const REPETITION_REQUIRED: i32 = 0;
+const CONVERTED_UTF8: i32 = 0;
+const ENCODING_PLAIN: i32 = 0;
+const ENCODING_RLE: i32 = 3;
+const CODEC_UNCOMPRESSED: i32 = 0;
+const PAGE_DATA: i32 = 0;
+
+// Serializes Thrift structs with the compact protocol.
+struct Thrift {
+    buf: Vec<u8>,
+    last_id: i16,
+    outer_ids: Vec<i16>,
+}
+impl Thrift {
+    fn new() -> Thrift {
+        Thrift {
+            buf: vec![],
+            last_id: 0,
+            outer_ids: vec![],
+        }
+    }
+
+    fn varint(&mut self, mut n: u64) {
+        while n >= 0x80 {
+            self.buf.push((n as u8) | 0x80);
+            n >>= 7;
+        }
+        self.buf.push(n as u8);
+    }
+
+    fn field(&mut self, id: i16, field_type: u8) {
+        let delta = id - self.last_id;
+        if delta > 0 && delta <= 15 {
+            self.buf.push((delta as u8) << 4 | field_type);
+        } else {
+            self.buf.push(field_type);
+            self.varint(((id << 1) ^ (id >> 15)) as u16 as u64);
+        }
+        self.last_id = id;
+    }
+
+    fn i32_value(&mut self, n: i32) {
+        self.varint(((n << 1) ^ (n >> 31)) as u32 as u64);
+    }
+
+    fn binary_value(&mut self, bytes: &[u8]) {
+        self.varint(bytes.len() as u64);
+        self.buf.extend_from_slice(bytes);
+    }
+
+    fn i32(&mut self, id: i16, n: i32) {
+        self.field(id, I32);
+        self.i32_value(n);
+    }
+
+    fn i64(&mut self, id: i16, n: i64) {
+        self.field(id, I64);
+        self.varint(((n << 1) ^ (n >> 63)) as u64);
+    }
+
+    fn string(&mut self, id: i16, string: &str) {
+        self.field(id, BINARY);
+        self.binary_value(string.as_bytes());
+    }
+
+    fn list(&mut self, id: i16, element_type: u8, len: usize) {
+        self.field(id, LIST);
+        if len < 15 {
+            self.buf.push((len as u8) << 4 | element_type);
+        } else {
+            self.buf.push(0xf0 | element_type);
+            self.varint(len as u64);
+        }
+    }
+
+    // Starts a struct which is a field of the current one, or an element of a list if `id` is
+    // `None`.
+    fn begin(&mut self, id: Option<i16>) {
+        if let Some(id) = id {
+            self.field(id, STRUCT);
+        }
+        self.outer_ids.push(self.last_id);
+        self.last_id = 0;
+    }
+
+    fn end(&mut self) {
+        self.buf.push(0);
+        self.last_id = self.outer_ids.pop().unwrap_or(0);
+    }
+}
+
+enum Values {
+    Strings(Vec<String>),
+    Int64(Vec<i64>),
+    Double(Vec<f64>),
+}
+
+struct Column {
+    name: &'static str,
+    values: Values,
+}
+impl Column {
+    fn physical_type(&self) -> i32 {
+        match self.values {
+            Values::Strings(_) => TYPE_BYTE_ARRAY,
+            Values::Int64(_) => TYPE_INT64,
+            Values::Double(_) => TYPE_DOUBLE,
+        }
+    }
+
+    fn len(&self) -> usize {
+        match self.values {
+            Values::Strings(ref values) => values.len(),
+            Values::Int64(ref values) => values.len(),
+            Values::Double(ref values) => values.len(),
+        }
+    }
+
+    // The values of a required column are stored back to back, without definition levels.
+    fn plain(&self) -> Vec<u8> {
+        let mut buf = vec![];
+        match self.values {
+            Values::Strings(ref values) => for value in values {
+                buf.extend_from_slice(&le_bytes(value.len() as u64, 4));
+                buf.extend_from_slice(value.as_bytes());
+            },
+            Values::Int64(ref values) => for &value in values {
+                buf.extend_from_slice(&le_bytes(value as u64, 8));
+            },
+            Values::Double(ref values) => for &value in values {
+                buf.extend_from_slice(&le_bytes(value.to_bits(), 8));
+            },
+        }
+        buf
+    }
+}
+
+fn le_bytes(n: u64, len: usize) -> Vec<u8> {
+    (0..len).map(|i| (n >> (8 * i)) as u8).collect()
+}
+
+fn page_header(num_values: usize, size: usize) -> Vec<u8> {
+    let mut t = Thrift::new();
+    t.i32(1, PAGE_DATA);
+    t.i32(2, size as i32);
+    t.i32(3, size as i32);
+    t.begin(Some(5));
+    t.i32(1, num_values as i32);
+    t.i32(2, ENCODING_PLAIN);
+    t.i32(3, ENCODING_RLE);
+    t.i32(4, ENCODING_RLE);
+    t.end();
+    t.end();
+    t.buf
+}
+
+// Writes the columns, which must have the same length, as a Parquet file.
+fn write(columns: &[Column]) -> Vec<u8> {
+    let num_rows = columns.first().map_or(0, Column::len);
+
+    let mut out = MAGIC.to_vec();
+    let mut chunks = vec![];
+    for column in columns {
+        let data = column.plain();
+        let offset = out.len();
+        out.extend(page_header(column.len(), data.len()));
+        out.extend(data);
+        chunks.push((offset, out.len() - offset));
+    }
+
+    let mut t = Thrift::new();
+    t.i32(1, 1);
+    t.list(2, STRUCT, columns.len() + 1);
+    t.begin(None);
+    t.string(4, "schema");
+    t.i32(5, columns.len() as i32);
+    t.end();
+    for column in columns {
+        t.begin(None);
+        t.i32(1, column.physical_type());
+        t.i32(3, REPETITION_REQUIRED);
+        t.string(4, column.name);
+        if let Values::Strings(_) = column.values {
+            t.i32(6, CONVERTED_UTF8);
+        }
+        t.end();
+    }
+    t.i64(3, num_rows as i64);
+    t.list(4, STRUCT, 1);
+    t.begin(None);
+    t.list(1, STRUCT, columns.len());
+    for (column, &(offset, size)) in columns.iter().zip(&chunks) {
+        t.begin(None);
+        t.i64(2, offset as i64);
+        t.begin(Some(3));
+        t.i32(1, column.physical_type());
+        t.list(2, I32, 2);
+        t.i32_value(ENCODING_PLAIN);
+        t.i32_value(ENCODING_RLE);
+        t.list(3, BINARY, 1);
+        t.binary_value(column.name.as_bytes());
+        t.i32(4, CODEC_UNCOMPRESSED);
+        t.i64(5, column.len() as i64);
+        t.i64(6, size as i64);
+        t.i64(7, size as i64);
+        t.i64(9, offset as i64);
+        t.end();
+        t.end();
+    }
+    t.i64(2, chunks.iter().map(|&(_, size)| size as i64).sum());
+    t.i64(3, num_rows as i64);
+    t.end();
+    t.string(6, "criterion.rs");
+    t.end();
+
+    let footer_len = t.buf.len();
+    out.extend(t.buf);
+    out.extend(le_bytes(footer_len as u64, 4));
+    out.extend_from_slice(MAGIC);
+    out
+}
+
+fn columns(id: &BenchmarkId, measurements: &MeasurementData) -> Vec<Column> {
+    let iters = measurements.iter_counts.as_slice();
+    vec![
+        Column {
+            name: "id",
+            values: Values::Strings(vec![id.id().to_owned(); iters.len()]),
+        },
+        Column {
+            name: "sample",
+            values: Values::Int64((0..iters.len() as i64).collect()),
+        },
+        Column {
+            name: "iteration_count",
+            values: Values::Int64(iters.iter().map(|&n| n as i64).collect()),
+        },
+        Column {
+            name: "sample_time_nanos",
+            values: Values::Double(measurements.sample_times.as_slice().to_vec()),
+        },
+    ]
+}
+
+/// Report which saves the samples of each benchmark in a `raw.parquet` file, with the same data
+/// as `raw.csv`: one row per sample, with the id of the benchmark, the index of the sample, its
+/// iteration count and its total time in nanoseconds.
+pub struct FileParquetReport;
+impl FileParquetReport {
+    fn write_file(
+        &self,
+        path: String,
+        id: &BenchmarkId,
+        measurements: &MeasurementData,
+    ) -> Result<()> {
+        fs::save_bytes(&write(&columns(id, measurements)), &path)
+    }
+}
+
+impl Reporter for FileParquetReport {
+    fn measured(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        measurements: &MeasurementData,
+    ) {
+        let path = format!(
+            "{}/{}/new/raw.parquet",
+            context.output_directory,
+            id.as_directory_name()
+        );
+        log_if_err!(self.write_file(path, id, measurements));
+    }
+
+    fn saves_files(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use stats::univariate::outliers::tukey;
+    use stats::univariate::Sample;
+    use std::collections::BTreeMap;
+    use std::{env, process};
+
+    #[derive(Debug, PartialEq)]
+    enum Value {
+        Int(i64),
+        Bytes(Vec<u8>),
+        List(Vec<Value>),
+        Struct(BTreeMap<i16, Value>),
+    }
+    impl Value {
+        fn field(&self, id: i16) -> &Value {
+            match *self {
+                Value::Struct(ref fields) => &fields[&id],
+                _ => panic!("{:?} is not a struct", self),
+            }
+        }
+
+        fn int(&self) -> i64 {
+            match *self {
+                Value::Int(n) => n,
+                _ => panic!("{:?} is not an integer", self),
+            }
+        }
+
+        fn string(&self) -> &str {
+            match *self {
+                Value::Bytes(ref bytes) => ::std::str::from_utf8(bytes).unwrap(),
+                _ => panic!("{:?} is not a string", self),
+            }
+        }
+
+        fn list(&self) -> &[Value] {
+            match *self {
+                Value::List(ref values) => values,
+                _ => panic!("{:?} is not a list", self),
+            }
+        }
+    }
+
+    // Reads the subset of the Thrift compact protocol written above, independently of the writer.
+    struct Reader<'a> {
+        buf: &'a [u8],
+        pos: usize,
+    }
+    impl<'a> Reader<'a> {
+        fn byte(&mut self) -> u8 {
+            self.pos += 1;
+            self.buf[self.pos - 1]
+        }
+
+        fn varint(&mut self) -> u64 {
+            let mut n = 0;
+            let mut shift = 0;
+            loop {
+                let byte = self.byte();
+                n |= u64::from(byte & 0x7f) << shift;
+                if byte < 0x80 {
+                    return n;
+                }
+                shift += 7;
+            }
+        }
+
+        fn zigzag(&mut self) -> i64 {
+            let n = self.varint();
+            (n >> 1) as i64 ^ -((n & 1) as i64)
+        }
+
+        fn value(&mut self, value_type: u8) -> Value {
+            match value_type {
+                I32 | I64 => Value::Int(self.zigzag()),
+                BINARY => {
+                    let len = self.varint() as usize;
+                    self.pos += len;
+                    Value::Bytes(self.buf[self.pos - len..self.pos].to_vec())
+                }
+                LIST => {
+                    let header = self.byte();
+                    let len = match header >> 4 {
+                        15 => self.varint() as usize,
+                        len => len as usize,
+                    };
+                    Value::List((0..len).map(|_| self.value(header & 0xf)).collect())
+                }
+                STRUCT => {
+                    let mut fields = BTreeMap::new();
+                    let mut id = 0;
+                    loop {
+                        let header = self.byte();
+                        if header == 0 {
+                            return Value::Struct(fields);
+                        }
+                        id = match header >> 4 {
+                            0 => self.zigzag() as i16,
+                            delta => id + i16::from(delta),
+                        };
+                        fields.insert(id, self.value(header & 0xf));
+                    }
+                }
+                _ => panic!("unexpected type {}", value_type),
+            }
+        }
+    }
+
+    fn read_u32(bytes: &[u8]) -> usize {
+        (0..4).map(|i| (bytes[i] as usize) << (8 * i)).sum()
+    }
+
+    #[test]
+    fn samples_read_back() {
+        let id = BenchmarkId::new("group".to_owned(), Some("function".to_owned()), None, None);
+        let iters = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let times = [10.0, 21.0, 29.5, 40.0, 52.25];
+        let avg_times: Vec<f64> = iters.iter().zip(&times).map(|(i, t)| t / i).collect();
+        let data = MeasurementData {
+            iter_counts: Sample::new(&iters),
+            sample_times: Sample::new(&times),
+            avg_times: tukey::classify(Sample::new(&avg_times)),
+            absolute_estimates: BTreeMap::new(),
+            distributions: BTreeMap::new(),
+            comparison: None,
+            throughput: None,
+        };
+
+        let dir = env::temp_dir().join(format!("criterion-parquet-{}", process::id()));
+        fs::mkdirp(&dir).unwrap();
+        let path = dir.join("raw.parquet");
+        FileParquetReport
+            .write_file(path.to_str().unwrap().to_owned(), &id, &data)
+            .unwrap();
+        let file = ::std::fs::read(&path).unwrap();
+        ::std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(MAGIC, &file[..4]);
+        assert_eq!(MAGIC, &file[file.len() - 4..]);
+        let footer_len = read_u32(&file[file.len() - 8..]);
+        let mut reader = Reader {
+            buf: &file[file.len() - 8 - footer_len..file.len() - 8],
+            pos: 0,
+        };
+        let metadata = reader.value(STRUCT);
+        assert_eq!(footer_len, reader.pos);
+
+        assert_eq!(5, metadata.field(3).int());
+        let schema = metadata.field(2).list();
+        assert_eq!(4, schema[0].field(5).int());
+        let names: Vec<&str> = schema[1..].iter().map(|e| e.field(4).string()).collect();
+        assert_eq!(
+            vec!["id", "sample", "iteration_count", "sample_time_nanos"],
+            names
+        );
+
+        // Every column has a value for each row, which the data pages hold.
+        let chunks = metadata.field(4).list()[0].field(1).list();
+        assert_eq!(4, chunks.len());
+        for chunk in chunks {
+            assert_eq!(5, chunk.field(3).field(5).int());
+        }
+        let times_chunk = chunks[3].field(3);
+        let mut reader = Reader {
+            buf: &file,
+            pos: times_chunk.field(9).int() as usize,
+        };
+        let header = reader.value(STRUCT);
+        assert_eq!(5, header.field(5).field(1).int());
+        let values = &file[reader.pos..reader.pos + header.field(2).int() as usize];
+        let read_times: Vec<f64> = values
+            .chunks(8)
+            .map(|bytes| {
+                let bits = (0..8).map(|i| u64::from(bytes[i]) << (8 * i)).sum();
+                f64::from_bits(bits)
+            })
+            .collect();
+        assert_eq!(times.to_vec(), read_times);
+    }
+}
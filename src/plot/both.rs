@@ -12,7 +12,7 @@ use estimate::Statistic::Slope;
 use kde;
 use report::BenchmarkId;
 use std::process::Child;
-use {ConfidenceInterval, Estimate};
+use {ConfidenceInterval, Estimate, KdeKernel};
 
 #[cfg_attr(feature = "cargo-clippy", allow(too_many_arguments))]
 pub(crate) fn regression(
@@ -140,6 +140,7 @@ pub fn pdfs(
     path: String,
     size: Option<Size>,
     thumbnail_mode: bool,
+    kernel: KdeKernel,
 ) -> Child {
     let path = PathBuf::from(path);
 
@@ -147,8 +148,9 @@ pub fn pdfs(
     let new_mean = avg_times.mean();
 
     let (base_xs, base_ys, base_y_mean) =
-        kde::sweep_and_estimate(base_avg_times, KDE_POINTS, None, base_mean);
-    let (xs, ys, y_mean) = kde::sweep_and_estimate(avg_times, KDE_POINTS, None, new_mean);
+        kde::sweep_and_estimate(base_avg_times, KDE_POINTS, None, base_mean, kernel);
+    let (xs, ys, y_mean) =
+        kde::sweep_and_estimate(avg_times, KDE_POINTS, None, new_mean, kernel);
     let base_xs_ = Sample::new(&base_xs);
     let xs_ = Sample::new(&xs);
 
@@ -1,17 +1,23 @@
+use std::cmp;
 use std::iter;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Child;
 
+use criterion_plot::{axis, filledcurve, grid, key};
 use criterion_plot::prelude::*;
 use stats::Distribution;
 use stats::bivariate::Data;
 use stats::bivariate::regression::Slope;
 use stats::univariate::Sample;
+use stats::univariate::kde::Bandwidth;
 use stats::univariate::outliers::tukey::LabeledSample;
 
 use estimate::{Distributions, Estimates};
 use kde;
-use report::BenchmarkId;
+use report::{BenchmarkId, ValueType};
+use {confidence_bounds, ConfidenceInterval, Estimate};
+use {FillStyle, GridConfig, KdeKernel, Legend, LegendCorner, LegendPosition, MeasuredUnit};
+use {PlotBackground, PlotConfiguration, PlotFormat, Theme};
 
 pub mod both;
 pub mod summary;
@@ -21,7 +27,9 @@ fn escape_underscores(string: &str) -> String {
 }
 
 fn scale_time(ns: f64) -> (f64, &'static str) {
-    if ns < 10f64.powi(0) {
+    if ns < 10f64.powi(-3) {
+        (10f64.powi(6), "f")
+    } else if ns < 10f64.powi(0) {
         (10f64.powi(3), "p")
     } else if ns < 10f64.powi(3) {
         (10f64.powi(0), "n")
@@ -34,6 +42,116 @@ fn scale_time(ns: f64) -> (f64, &'static str) {
     }
 }
 
+impl MeasuredUnit {
+    // The factor which scales values of the order of `anchor`, and the label of the axis showing
+    // their `statistic`, eg. "Average time (us)".
+    fn scale(self, statistic: &str, anchor: f64) -> (f64, String) {
+        let (scale, unit) = self.unit(anchor);
+        (scale, format!("{} ({})", self.label(statistic), unit))
+    }
+
+    // The label of an axis showing the `statistic`, without a unit, eg. "Average time".
+    fn label(self, statistic: &str) -> String {
+        match self {
+            MeasuredUnit::Nanoseconds => format!("{} time", statistic),
+            MeasuredUnit::Cycles => format!("{} cycles", statistic),
+        }
+    }
+
+    // The unit for values of the order of `value`, eg. "us", and the factor which scales them to
+    // it.
+    fn unit(self, value: f64) -> (f64, String) {
+        match self {
+            MeasuredUnit::Nanoseconds => {
+                let (scale, prefix) = scale_time(value);
+                (scale, format!("{}s", prefix))
+            }
+            MeasuredUnit::Cycles => {
+                let (scale, prefix) = scale_si(value);
+                (scale, format!("{}cycles", prefix))
+            }
+        }
+    }
+
+    // Tics at the powers of ten from below `min` to above `max`, each labeled in its own unit, eg.
+    // "100 ns" and "1 us". No single scale factor fits a logarithmic axis spanning several units.
+    fn log_tics(self, min: f64, max: f64) -> Vec<(f64, String)> {
+        let first = min.log10().floor() as i32;
+        let last = cmp::max(max.log10().ceil() as i32, first);
+
+        (first..last + 1)
+            .map(|exponent| {
+                let value = 10f64.powi(exponent);
+                let (scale, unit) = self.unit(value);
+                // Rounded to get rid of the noise of the scaling, eg. 0.09999999999999999 fs.
+                let scaled = (value * scale * 1e3).round() / 1e3;
+                (value, format!("{} {}", scaled, unit))
+            })
+            .collect()
+    }
+}
+
+// The decimal prefix for quantities of the order of `value` (at least one unit), and the factor
+// which scales them to it.
+fn scale_si(value: f64) -> (f64, &'static str) {
+    if value < 10f64.powi(3) {
+        (1.0, "")
+    } else if value < 10f64.powi(6) {
+        (10f64.powi(-3), "K")
+    } else if value < 10f64.powi(9) {
+        (10f64.powi(-6), "M")
+    } else {
+        (10f64.powi(-9), "G")
+    }
+}
+
+// Like `scale_si`, with binary prefixes.
+fn scale_binary(value: f64) -> (f64, &'static str) {
+    if value < 1024.0 {
+        (1.0, "")
+    } else if value < 1024.0 * 1024.0 {
+        (1024f64.powi(-1), "Ki")
+    } else if value < 1024.0 * 1024.0 * 1024.0 {
+        (1024f64.powi(-2), "Mi")
+    } else {
+        (1024f64.powi(-3), "Gi")
+    }
+}
+
+fn scale_throughput(value_type: ValueType, per_second: f64) -> (f64, String) {
+    match value_type {
+        ValueType::Bytes => {
+            let (scale, prefix) = scale_binary(per_second);
+            (scale, format!("{}B/s", prefix))
+        }
+        ValueType::Elements | ValueType::Value => {
+            let (scale, prefix) = scale_si(per_second);
+            (scale, format!("{}elem/s", prefix))
+        }
+    }
+}
+
+// On a logarithmic axis the throughputs may span several units, so instead of scaling them all to
+// the unit of the largest one, each tic is labeled in its own unit: 1 KiB/s, 1 MiB/s, ... for
+// bytes and 1 Kelem/s, 10 Kelem/s, ... for elements. Returns the positions and labels of the tics
+// covering `min..max`.
+fn log_throughput_tics(value_type: ValueType, min: f64, max: f64) -> Vec<(f64, String)> {
+    let base: f64 = match value_type {
+        ValueType::Bytes => 1024.,
+        ValueType::Elements | ValueType::Value => 10.,
+    };
+    let first = cmp::max(min.log(base).floor() as i32, 0);
+    let last = cmp::max(max.log(base).ceil() as i32, first);
+
+    (first..last + 1)
+        .map(|exponent| {
+            let value = base.powi(exponent);
+            let (scale, unit) = scale_throughput(value_type, value);
+            (value, format!("{} {}", (value * scale).round(), unit))
+        })
+        .collect()
+}
+
 static DEFAULT_FONT: &'static str = "Helvetica";
 static KDE_POINTS: usize = 500;
 static SIZE: Size = Size(1280, 720);
@@ -45,10 +163,139 @@ const DARK_BLUE: Color = Color::Rgb(31, 120, 180);
 const DARK_ORANGE: Color = Color::Rgb(255, 127, 0);
 const DARK_RED: Color = Color::Rgb(227, 26, 28);
 
+const LIGHT_BLUE: Color = Color::Rgb(100, 170, 230);
+const LIGHT_GRAY: Color = Color::Rgb(220, 220, 220);
+const DIM_GRAY: Color = Color::Rgb(90, 90, 90);
+const MUTED_GRAY: Color = Color::Rgb(160, 160, 160);
+
+impl FillStyle {
+    fn apply(self, c: &mut filledcurve::Properties) -> &mut filledcurve::Properties {
+        match self {
+            FillStyle::Solid(opacity) => c.set(Opacity(opacity)),
+            FillStyle::Pattern(pattern) => c.set(FillPattern(pattern)),
+        }
+    }
+}
+
+// Below this, solid fills are hard to see on anything but a white background.
+const MIN_OPACITY_ON_BACKGROUND: f64 = 0.5;
+
+impl PlotBackground {
+    fn apply(self, f: &mut Figure) -> &mut Figure {
+        match self {
+            PlotBackground::White => f.set(Background(Color::White)),
+            // The SVG terminal draws no background unless asked to.
+            PlotBackground::Transparent => f,
+            PlotBackground::Rgb(r, g, b) => f.set(Background(Color::Rgb(r, g, b))),
+        }
+    }
+
+    fn legible(self, fill_style: FillStyle) -> FillStyle {
+        match (self, fill_style) {
+            (PlotBackground::White, _) | (_, FillStyle::Pattern(_)) => fill_style,
+            (_, FillStyle::Solid(opacity)) => {
+                FillStyle::Solid(opacity.max(MIN_OPACITY_ON_BACKGROUND))
+            }
+        }
+    }
+}
+
+impl Theme {
+    // Colors the text, the border and the tics, which gnuplot draws in black, for the dark theme.
+    fn apply(self, f: &mut Figure) -> &mut Figure {
+        match self {
+            Theme::Light => f,
+            Theme::Dark => f.set(Foreground(LIGHT_GRAY)),
+        }
+    }
+
+    // Dims the gridlines of the dark theme, which would otherwise outshine the curves.
+    fn grid(self, g: &mut grid::Properties) -> &mut grid::Properties {
+        match self {
+            Theme::Light => g,
+            Theme::Dark => g.set(DIM_GRAY),
+        }
+    }
+
+    // The color of the plots which only draw in one color
+    fn primary_color(self) -> Color {
+        match self {
+            Theme::Light => DARK_BLUE,
+            Theme::Dark => LIGHT_BLUE,
+        }
+    }
+}
+
+impl GridConfig {
+    // Shows the configured gridlines across the given axis, in the color of the theme.
+    fn apply(self, axis: Axis, a: &mut axis::Properties, theme: Theme) -> &mut axis::Properties {
+        let (major, minor) = match axis {
+            Axis::BottomX | Axis::TopX => (self.x_major, self.x_minor),
+            Axis::LeftY | Axis::RightY => (self.y_major, self.y_minor),
+        };
+        a.configure(Grid::Major, |g| if major { theme.grid(g.show()) } else { g.hide() })
+            .configure(Grid::Minor, |g| if minor { theme.grid(g.show()) } else { g.hide() })
+    }
+}
+
+impl Legend {
+    fn apply<'a>(&self, k: &'a mut key::Properties) -> &'a mut key::Properties {
+        let corner = |corner| match corner {
+            LegendCorner::TopLeft => (Vertical::Top, Horizontal::Left),
+            LegendCorner::TopRight => (Vertical::Top, Horizontal::Right),
+            LegendCorner::BottomLeft => (Vertical::Bottom, Horizontal::Left),
+            LegendCorner::BottomRight => (Vertical::Bottom, Horizontal::Right),
+        };
+        match self.position {
+            LegendPosition::Inside(c) => {
+                let (v, h) = corner(c);
+                k.set(Position::Inside(v, h));
+            }
+            LegendPosition::Outside(c) => {
+                let (v, h) = corner(c);
+                k.set(Position::Outside(v, h));
+            }
+            // Stacking the entries horizontally moves an outside key into the bottom margin.
+            LegendPosition::Below => {
+                k.set(Position::Outside(Vertical::Bottom, Horizontal::Center))
+                    .set(Stacked::Horizontally);
+            }
+            LegendPosition::Hidden => return k.hide(),
+        }
+        if let Some(ref font) = self.font {
+            k.set(Font(font.clone()));
+        }
+        if let Some(size) = self.font_size {
+            k.set(FontSize(size));
+        }
+        k
+    }
+}
+
+// The terminal which draws a plot saved to `path`, in the configured format or else in the one its
+// extension names.
+fn output_terminal(plot_config: &PlotConfiguration, path: &Path) -> Terminal {
+    let format = plot_config.format.unwrap_or_else(|| {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some(extension) if extension.eq_ignore_ascii_case("png") => PlotFormat::Png,
+            _ => PlotFormat::Svg,
+        }
+    });
+    match format {
+        PlotFormat::Svg => Terminal::Svg,
+        PlotFormat::Png => Terminal::Png,
+    }
+}
+
+// Saves the gnuplot script of a figure next to its plot, but only if the user asked for debug
+// output with `CRITERION_DEBUG`, so that ordinary runs don't litter the output directory.
 fn debug_script(path: &PathBuf, figure: &Figure) {
-    if ::debug_enabled() {
-        let mut script_path = path.clone();
-        script_path.set_extension("gnuplot");
+    save_script(path, figure, ::debug_enabled())
+}
+
+fn save_script(path: &Path, figure: &Figure, enabled: bool) {
+    if enabled {
+        let script_path = path.with_extension("gnuplot");
         println!("Writing gnuplot script to {:?}", script_path);
         let result = figure.save(script_path.as_path());
         if let Err(e) = result {
@@ -57,12 +304,18 @@ fn debug_script(path: &PathBuf, figure: &Figure) {
     }
 }
 
-pub fn pdf_small(sample: &Sample<f64>, path: String, size: Option<Size>) -> Child {
+pub fn pdf_small(
+    sample: &Sample<f64>,
+    path: String,
+    size: Option<Size>,
+    fill_style: FillStyle,
+    kernel: KdeKernel,
+) -> Child {
     let path = PathBuf::from(path);
     let (x_scale, prefix) = scale_time(sample.max());
     let mean = sample.mean();
 
-    let (xs, ys, mean_y) = kde::sweep_and_estimate(sample, KDE_POINTS, None, mean);
+    let (xs, ys, mean_y) = kde::sweep_and_estimate(sample, KDE_POINTS, None, mean, kernel);
     let xs_ = Sample::new(&xs);
     let ys_ = Sample::new(&ys);
 
@@ -91,10 +344,10 @@ pub fn pdf_small(sample: &Sample<f64>, path: String, size: Option<Size>) -> Chil
                 y2: zeros,
             },
             |c| {
-                c.set(Axes::BottomXRightY)
+                let c = c.set(Axes::BottomXRightY)
                     .set(DARK_BLUE)
-                    .set(Label("PDF"))
-                    .set(Opacity(0.25))
+                    .set(Label("PDF"));
+                fill_style.apply(c)
             },
         )
         .plot(
@@ -115,6 +368,8 @@ pub fn pdf(
     id: &BenchmarkId,
     path: String,
     size: Option<Size>,
+    fill_style: FillStyle,
+    kernel: KdeKernel,
 ) -> Child {
     let path = PathBuf::from(path);
     let (x_scale, prefix) = scale_time(labeled_sample.max());
@@ -134,7 +389,7 @@ pub fn pdf(
         format!("Iterations (x 10^{})", exponent)
     };
 
-    let (xs, ys) = kde::sweep(&labeled_sample, KDE_POINTS, None);
+    let (xs, ys) = kde::sweep(&labeled_sample, KDE_POINTS, None, kernel);
     let xs_ = Sample::new(&xs);
 
     let (lost, lomt, himt, hist) = labeled_sample.fences();
@@ -169,10 +424,10 @@ pub fn pdf(
                 y2: zeros,
             },
             |c| {
-                c.set(Axes::BottomXRightY)
+                let c = c.set(Axes::BottomXRightY)
                     .set(DARK_BLUE)
-                    .set(Label("PDF"))
-                    .set(Opacity(0.25))
+                    .set(Label("PDF"));
+                fill_style.apply(c)
             },
         )
         .plot(
@@ -412,6 +667,7 @@ pub(crate) fn abs_distributions(
     estimates: &Estimates,
     id: &BenchmarkId,
     output_directory: &str,
+    kernel: KdeKernel,
 ) -> Vec<Child> {
     distributions
         .iter()
@@ -422,92 +678,136 @@ pub(crate) fn abs_distributions(
                 id.as_directory_name(),
                 statistic
             ));
-            let estimate = estimates[&statistic];
+            let title = format!("{}: {}", escape_underscores(id.id()), statistic);
+            let mut figure =
+                distribution_figure(title, distribution, &estimates[&statistic], kernel);
+            debug_script(&path, &figure);
+            figure.set(Output(path)).draw().unwrap()
+        })
+        .collect::<Vec<_>>()
+}
 
-            let ci = estimate.confidence_interval;
-            let (lb, ub) = (ci.lower_bound, ci.upper_bound);
+/// Plots the bootstrap distribution of the mean of a benchmark, `means`, with its 95% confidence
+/// interval and the mean of the distribution marked.
+pub(crate) fn bootstrap_distribution(
+    id: &BenchmarkId,
+    means: &Distribution<f64>,
+    kernel: KdeKernel,
+    path: &str,
+) -> Child {
+    let path = PathBuf::from(path);
+    let mut figure = bootstrap_distribution_figure(id, means, kernel);
+    debug_script(&path, &figure);
+    figure.set(Output(path)).draw().unwrap()
+}
 
-            let start = lb - (ub - lb) / 9.;
-            let end = ub + (ub - lb) / 9.;
-            let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)));
-            let xs_ = Sample::new(&xs);
+fn bootstrap_distribution_figure(
+    id: &BenchmarkId,
+    means: &Distribution<f64>,
+    kernel: KdeKernel,
+) -> Figure {
+    let confidence_level = 0.95;
+    let (lower_bound, upper_bound) = confidence_bounds(means.as_slice(), confidence_level);
+    let estimate = Estimate {
+        confidence_interval: ConfidenceInterval {
+            confidence_level,
+            lower_bound,
+            upper_bound,
+        },
+        point_estimate: means.mean(),
+        standard_error: means.std_dev(None),
+    };
 
-            let (x_scale, prefix) = scale_time(xs_.max());
-            let y_scale = x_scale.recip();
+    let title = format!(
+        "{}: bootstrap distribution of the mean",
+        escape_underscores(id.id())
+    );
+    distribution_figure(title, means, &estimate, kernel)
+}
 
-            let p = estimate.point_estimate;
+// The estimated density of the bootstrap `distribution` of a statistic, with the confidence
+// interval and the point of the `estimate` marked.
+fn distribution_figure(
+    title: String,
+    distribution: &Distribution<f64>,
+    estimate: &Estimate,
+    kernel: KdeKernel,
+) -> Figure {
+    let ci = estimate.confidence_interval;
+    let (lb, ub) = (ci.lower_bound, ci.upper_bound);
 
-            let n_p = xs.iter().enumerate().find(|&(_, &x)| x >= p).unwrap().0;
-            let y_p =
-                ys[n_p - 1] + (ys[n_p] - ys[n_p - 1]) / (xs[n_p] - xs[n_p - 1]) * (p - xs[n_p - 1]);
+    let start = lb - (ub - lb) / 9.;
+    let end = ub + (ub - lb) / 9.;
+    let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)), kernel);
+    let xs_ = Sample::new(&xs);
 
-            let zero = iter::repeat(0);
+    let (x_scale, prefix) = scale_time(xs_.max());
+    let y_scale = x_scale.recip();
 
-            let start = xs.iter().enumerate().find(|&(_, &x)| x >= lb).unwrap().0;
-            let end = xs.iter()
-                .enumerate()
-                .rev()
-                .find(|&(_, &x)| x <= ub)
-                .unwrap()
-                .0;
-            let len = end - start;
+    let p = estimate.point_estimate;
 
-            let mut figure = Figure::new();
-            figure
-                .set(Font(DEFAULT_FONT))
-                .set(SIZE)
-                .set(Title(format!(
-                    "{}: {}",
-                    escape_underscores(id.id()),
-                    statistic
-                )))
-                .configure(Axis::BottomX, |a| {
-                    a.set(Label(format!("Average time ({}s)", prefix)))
-                        .set(Range::Limits(xs_.min() * x_scale, xs_.max() * x_scale))
-                        .set(ScaleFactor(x_scale))
-                })
-                .configure(Axis::LeftY, |a| {
-                    a.set(Label("Density (a.u.)")).set(ScaleFactor(y_scale))
-                })
-                .configure(Key, |k| {
-                    k.set(Justification::Left)
-                        .set(Order::SampleText)
-                        .set(Position::Outside(Vertical::Top, Horizontal::Right))
-                })
-                .plot(Lines { x: &*xs, y: &*ys }, |c| {
-                    c.set(DARK_BLUE)
-                        .set(LINEWIDTH)
-                        .set(Label("Bootstrap distribution"))
-                        .set(LineType::Solid)
-                })
-                .plot(
-                    FilledCurve {
-                        x: xs.iter().skip(start).take(len),
-                        y1: ys.iter().skip(start),
-                        y2: zero,
-                    },
-                    |c| {
-                        c.set(DARK_BLUE)
-                            .set(Label("Confidence interval"))
-                            .set(Opacity(0.25))
-                    },
-                )
-                .plot(
-                    Lines {
-                        x: &[p, p],
-                        y: &[0., y_p],
-                    },
-                    |c| {
-                        c.set(DARK_BLUE)
-                            .set(LINEWIDTH)
-                            .set(Label("Point estimate"))
-                            .set(LineType::Dash)
-                    },
-                );
-            debug_script(&path, &figure);
-            figure.set(Output(path)).draw().unwrap()
+    let y_p = kde::pdf_at(distribution, p, Bandwidth::Silverman, kernel);
+
+    let zero = iter::repeat(0);
+
+    let start = xs.iter().enumerate().find(|&(_, &x)| x >= lb).unwrap().0;
+    let end = xs.iter()
+        .enumerate()
+        .rev()
+        .find(|&(_, &x)| x <= ub)
+        .unwrap()
+        .0;
+    let len = end - start;
+
+    let mut figure = Figure::new();
+    figure
+        .set(Font(DEFAULT_FONT))
+        .set(SIZE)
+        .set(Title(title))
+        .configure(Axis::BottomX, |a| {
+            a.set(Label(format!("Average time ({}s)", prefix)))
+                .set(Range::Limits(xs_.min() * x_scale, xs_.max() * x_scale))
+                .set(ScaleFactor(x_scale))
         })
-        .collect::<Vec<_>>()
+        .configure(Axis::LeftY, |a| {
+            a.set(Label("Density (a.u.)")).set(ScaleFactor(y_scale))
+        })
+        .configure(Key, |k| {
+            k.set(Justification::Left)
+                .set(Order::SampleText)
+                .set(Position::Outside(Vertical::Top, Horizontal::Right))
+        })
+        .plot(Lines { x: &*xs, y: &*ys }, |c| {
+            c.set(DARK_BLUE)
+                .set(LINEWIDTH)
+                .set(Label("Bootstrap distribution"))
+                .set(LineType::Solid)
+        })
+        .plot(
+            FilledCurve {
+                x: xs.iter().skip(start).take(len),
+                y1: ys.iter().skip(start),
+                y2: zero,
+            },
+            |c| {
+                c.set(DARK_BLUE)
+                    .set(Label("Confidence interval"))
+                    .set(Opacity(0.25))
+            },
+        )
+        .plot(
+            Lines {
+                x: &[p, p],
+                y: &[0., y_p],
+            },
+            |c| {
+                c.set(DARK_BLUE)
+                    .set(LINEWIDTH)
+                    .set(Label("Point estimate"))
+                    .set(LineType::Dash)
+            },
+        );
+    figure
 }
 
 // TODO DRY: This is very similar to the `abs_distributions` method
@@ -517,6 +817,7 @@ pub(crate) fn rel_distributions(
     id: &BenchmarkId,
     output_directory: &str,
     nt: f64,
+    kernel: KdeKernel,
 ) -> Vec<Child> {
     let mut figure = Figure::new();
 
@@ -546,13 +847,11 @@ pub(crate) fn rel_distributions(
 
             let start = lb - (ub - lb) / 9.;
             let end = ub + (ub - lb) / 9.;
-            let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)));
+            let (xs, ys) = kde::sweep(distribution, KDE_POINTS, Some((start, end)), kernel);
             let xs_ = Sample::new(&xs);
 
             let p = estimate.point_estimate;
-            let n_p = xs.iter().enumerate().find(|&(_, &x)| x >= p).unwrap().0;
-            let y_p =
-                ys[n_p - 1] + (ys[n_p] - ys[n_p - 1]) / (xs[n_p] - xs[n_p - 1]) * (p - xs[n_p - 1]);
+            let y_p = kde::pdf_at(distribution, p, Bandwidth::Silverman, kernel);
 
             let one = iter::repeat(1);
             let zero = iter::repeat(0);
@@ -646,6 +945,7 @@ pub fn t_test(
     distribution: &Distribution<f64>,
     id: &BenchmarkId,
     output_directory: &str,
+    kernel: KdeKernel,
 ) -> Child {
     let path = PathBuf::from(format!(
         "{}/{}/report/change/t-test.svg",
@@ -653,7 +953,7 @@ pub fn t_test(
         id.as_directory_name()
     ));
 
-    let (xs, ys) = kde::sweep(distribution, KDE_POINTS, None);
+    let (xs, ys) = kde::sweep(distribution, KDE_POINTS, None, kernel);
     let zero = iter::repeat(0);
 
     let mut figure = Figure::new();
@@ -700,6 +1000,14 @@ pub fn t_test(
     figure.set(Output(path)).draw().unwrap()
 }
 
+// Renders the gnuplot script of a figure as text, for inspection in tests.
+#[cfg(test)]
+fn script(figure: &mut Figure) -> String {
+    let mut buffer = vec![];
+    figure.dump(&mut buffer).unwrap();
+    String::from_utf8_lossy(&buffer).into_owned()
+}
+
 /// Private
 trait Append<T> {
     /// Private
@@ -713,3 +1021,71 @@ impl<T> Append<T> for Vec<T> {
         self
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn output_format() {
+        let terminal = |config: &PlotConfiguration, path: &str| {
+            let mut figure = Figure::new();
+            figure.set(output_terminal(config, Path::new(path)));
+            script(&mut figure)
+                .lines()
+                .find(|line| line.starts_with("set terminal"))
+                .unwrap()
+                .to_owned()
+        };
+
+        let config = PlotConfiguration::default();
+        assert!(terminal(&config, "violin.svg").starts_with("set terminal svg dynamic"));
+        assert!(terminal(&config, "violin.PNG").starts_with("set terminal pngcairo"));
+        assert!(terminal(&config, "violin").starts_with("set terminal svg dynamic"));
+
+        let config = PlotConfiguration::default().format(PlotFormat::Svg);
+        assert!(terminal(&config, "violin.png").starts_with("set terminal svg dynamic"));
+        let config = PlotConfiguration::default().format(PlotFormat::Png);
+        assert!(terminal(&config, "violin.svg").starts_with("set terminal pngcairo"));
+    }
+
+    #[test]
+    fn scripts_are_only_saved_when_enabled() {
+        let dir = env::temp_dir().join(format!("criterion-scripts-{}", process::id()));
+        ::fs::mkdirp(&dir).unwrap();
+        let path = dir.join("plot.svg");
+        let script_path = path.with_extension("gnuplot");
+        let mut figure = Figure::new();
+        figure.set(Title("Debug"));
+        let expected = script(&mut figure);
+
+        save_script(&path, &figure, false);
+        assert!(!script_path.exists());
+
+        save_script(&path, &figure, true);
+        let saved = ::std::fs::read_to_string(&script_path).unwrap();
+        ::std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(expected, saved);
+        // Saving the script leaves the plot alone.
+        assert_eq!(expected, script(&mut figure));
+    }
+
+    #[test]
+    fn bootstrap_distribution_marks_interval() {
+        let times: Vec<f64> = (0..100).map(|i| 1000. + f64::from(i % 10)).collect();
+        let means = Sample::new(&times).bootstrap_mean_distribution(1000);
+        let id = BenchmarkId::new("group".to_owned(), Some("fn_a".to_owned()), None, None);
+
+        let script = script(&mut bootstrap_distribution_figure(&id, &means, KdeKernel::Gaussian));
+        assert!(script.contains("set title 'group/fn\\_a: bootstrap distribution of the mean'"));
+        assert!(script.contains("title 'Bootstrap distribution'"));
+        assert!(script.contains("title 'Confidence interval'"));
+        assert!(script.contains("title 'Point estimate'"));
+        assert!(script.contains("set xlabel 'Average time (us)'"));
+
+        // The density follows the configured kernel
+        let mut epanechnikov = bootstrap_distribution_figure(&id, &means, KdeKernel::Epanechnikov);
+        assert_ne!(script, super::script(&mut epanechnikov));
+    }
+}
@@ -1,18 +1,28 @@
-use std::cmp::Ordering;
+use std::cmp::{self, Ordering};
+use std::collections::BTreeMap;
 use std::path::PathBuf;
 use std::process::Child;
 
 use criterion_plot::prelude::*;
-use stats::univariate::Sample;
+use stats::univariate::{Binning, Sample};
 
-use kde;
-use report::{BenchmarkId, ValueType};
+use {format, kde};
+use report::{BenchmarkId, ComparisonResult, ValueType};
 
 use itertools::Itertools;
 
-use super::{DARK_BLUE, DEFAULT_FONT, KDE_POINTS, LINEWIDTH, POINT_SIZE, SIZE};
-use super::{debug_script, escape_underscores, scale_time};
-use AxisScale;
+use super::{DARK_ORANGE, DARK_RED, DEFAULT_FONT, KDE_POINTS, LINEWIDTH, MUTED_GRAY, POINT_SIZE};
+use super::SIZE;
+use super::{debug_script, escape_underscores, log_throughput_tics, output_terminal};
+use super::scale_throughput;
+use {AxisScale, ChangeHighlighting, LineSelection, Palette, PlotConfiguration, Theme, Throughput,
+     UnitAnchor, ViolinOrder, ViolinQuantiles, ViolinShape};
+
+// With adaptive KDE points, the violin plot evaluates this many points in total, split among the
+// benchmarks...
+const VIOLIN_POINTS_BUDGET: usize = 5000;
+// ...but never fewer than this many per benchmark, below which the densities look jagged.
+const MIN_KDE_POINTS: usize = 50;
 
 const NUM_COLORS: usize = 8;
 static COMPARISON_COLORS: [Color; NUM_COLORS] = [
@@ -26,6 +36,85 @@ static COMPARISON_COLORS: [Color; NUM_COLORS] = [
     Color::Rgb(0, 255, 127),
 ];
 
+// Lighter colors, which stand out from the background of the dark theme
+static DARK_THEME_COLORS: [Color; NUM_COLORS] = [
+    Color::Rgb(255, 107, 107),
+    Color::Rgb(102, 205, 170),
+    Color::Rgb(72, 209, 204),
+    Color::Rgb(255, 215, 0),
+    Color::Rgb(135, 206, 250),
+    Color::Rgb(255, 105, 180),
+    Color::Rgb(218, 112, 214),
+    Color::Rgb(0, 255, 127),
+];
+
+// The Okabe-Ito colors, which stay distinct under the common kinds of color blindness
+static COLORBLIND_SAFE_COLORS: [Color; NUM_COLORS] = [
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(240, 228, 66),
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(0, 0, 0),
+];
+
+// The same, but with white in place of black, which would vanish in the background of the dark
+// theme
+static DARK_THEME_COLORBLIND_SAFE_COLORS: [Color; NUM_COLORS] = [
+    Color::Rgb(230, 159, 0),
+    Color::Rgb(86, 180, 233),
+    Color::Rgb(0, 158, 115),
+    Color::Rgb(240, 228, 66),
+    Color::Rgb(0, 114, 178),
+    Color::Rgb(213, 94, 0),
+    Color::Rgb(204, 121, 167),
+    Color::Rgb(255, 255, 255),
+];
+
+// The colors of `n` curves: the palette of the plot configuration, or the built-in one, unless
+// there are more curves than colors, in which case every curve gets its own hue.
+fn curve_colors(plot_config: &PlotConfiguration, n: usize) -> Vec<Color> {
+    let palette: Vec<Color> = match plot_config.palette {
+        Some(ref colors) => colors.iter().map(|&(r, g, b)| Color::Rgb(r, g, b)).collect(),
+        None => match (plot_config.builtin_palette, plot_config.theme) {
+            (Palette::Default, Theme::Light) => COMPARISON_COLORS.to_vec(),
+            (Palette::Default, Theme::Dark) => DARK_THEME_COLORS.to_vec(),
+            (Palette::ColorblindSafe, Theme::Light) => COLORBLIND_SAFE_COLORS.to_vec(),
+            (Palette::ColorblindSafe, Theme::Dark) => DARK_THEME_COLORBLIND_SAFE_COLORS.to_vec(),
+        },
+    };
+    if n <= palette.len() {
+        palette
+    } else {
+        generate_palette(n)
+    }
+}
+
+// `n` colors of evenly spaced hues, of the same saturation and brightness.
+fn generate_palette(n: usize) -> Vec<Color> {
+    let (saturation, value) = (0.7, 0.75);
+    let chroma = saturation * value;
+    (0..n)
+        .map(|i| {
+            // The hue in sixths of the color wheel
+            let hue = i as f64 * 6. / n as f64;
+            let x = chroma * (1. - (hue % 2. - 1.).abs());
+            let (r, g, b) = match hue as usize {
+                0 => (chroma, x, 0.),
+                1 => (x, chroma, 0.),
+                2 => (0., chroma, x),
+                3 => (0., x, chroma),
+                4 => (x, 0., chroma),
+                _ => (chroma, 0., x),
+            };
+            let byte = |component: f64| ((component + value - chroma) * 255.).round() as u8;
+            Color::Rgb(byte(r), byte(g), byte(b))
+        })
+        .collect()
+}
+
 impl AxisScale {
     fn to_gnuplot(self) -> Scale {
         match self {
@@ -35,16 +124,195 @@ impl AxisScale {
     }
 }
 
-#[cfg_attr(feature = "cargo-clippy", allow(explicit_counter_loop))]
+/// The data shown in a line comparison plot, independent of how it is rendered.
+#[derive(Debug, Serialize)]
+pub struct LineComparisonData {
+    pub title: String,
+    pub x_label: String,
+    /// Labels of evenly-spaced inputs, if the inputs are ordered by a custom ordering rather than
+    /// placed at their numeric value.
+    pub x_tics: Option<Vec<String>>,
+    /// Positions and labels of the tics of the X axis chosen by the user, in place of gnuplot's.
+    /// Ignored if the inputs are ordered by a custom ordering.
+    pub x_tic_labels: Option<Vec<(f64, String)>>,
+    pub y_label: String,
+    pub y_scale: f64,
+    /// Fixed limits of the Y axis, in the unit of the measurements, instead of fitting the data.
+    pub y_range: Option<(f64, f64)>,
+    pub throughput_axis: Option<ThroughputAxis>,
+    pub series: Vec<LineSeries>,
+    /// The relative differences between the two functions of a group of two, at each input at
+    /// which both were measured.
+    pub differences: Vec<LineDifference>,
+    /// Number of non-finite measurements which were left out of the plot.
+    pub dropped_values: usize,
+    /// Number of points which fall outside of `y_range` and are cut off.
+    pub clipped_values: usize,
+}
+
+/// The secondary Y axis of a line comparison plot.
+#[derive(Debug, Serialize)]
+pub struct ThroughputAxis {
+    pub label: String,
+    pub scale: f64,
+    /// Positions and labels of the tics, if each tic is labeled with its own unit.
+    pub tics: Option<Vec<(f64, String)>>,
+}
+
+/// The points of one function in a line comparison plot.
+#[derive(Debug, Serialize)]
+pub struct LineSeries {
+    pub function_id: Option<String>,
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub throughputs: Option<Vec<f64>>,
+    /// The 2.5th and 97.5th percentiles of each sample, bounding the band around the line.
+    pub band: Option<(Vec<f64>, Vec<f64>)>,
+    /// Whether the function is drawn in gray under the single "others" entry of the legend,
+    /// rather than labeled and colored individually.
+    pub other: bool,
+}
+
+/// The relative difference of the second function of a line comparison plot against the first at
+/// one input, labeled above the higher of their two points.
+#[derive(Debug, Serialize)]
+pub struct LineDifference {
+    pub x: f64,
+    pub y: f64,
+    pub change: f64,
+}
+
+/// Draws the line comparison plot of a group, or returns `None` with a warning if the group can't
+/// be plotted because some of its inputs aren't numbers and no `x_order` places them.
 pub fn line_comparison(
     group_id: &str,
     all_curves: &[&(BenchmarkId, Vec<f64>)],
     path: &str,
     value_type: ValueType,
-    axis_scale: AxisScale,
-) -> Child {
+    plot_config: &PlotConfiguration,
+    x_order: Option<XOrdering>,
+    x_ticks: Option<Vec<(f64, String)>>,
+) -> Option<Child> {
+    if x_order.is_none() && !numeric_inputs(group_id, "line comparison", all_curves) {
+        return None;
+    }
+
     let path = PathBuf::from(path);
-    let mut f = Figure::new();
+    let mut data = line_comparison_data(group_id, all_curves, value_type, plot_config, x_order);
+    data.x_tic_labels = x_ticks;
+    let mut f = line_comparison_figure(&data, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
+    debug_script(&path, &f);
+    Some(f.set(Output(path)).draw().unwrap())
+}
+
+/// Like `line_comparison`, but plots the mean of each function divided by the mean of the
+/// `baseline` function at the same input, so that the baseline is a flat line at 1.0. Returns
+/// `None` if no function of the group is called `baseline`.
+pub fn relative_line_comparison(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    path: &str,
+    value_type: ValueType,
+    plot_config: &PlotConfiguration,
+    baseline: &str,
+) -> Option<Child> {
+    if !numeric_inputs(group_id, "relative line comparison", all_curves) {
+        return None;
+    }
+
+    let path = PathBuf::from(path);
+    let mut data =
+        relative_line_comparison_data(group_id, all_curves, value_type, plot_config, baseline)?;
+    data.x_tic_labels = plot_config.x_ticks.clone();
+    let mut f = line_comparison_figure(&data, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
+    debug_script(&path, &f);
+    Some(f.set(Output(path)).draw().unwrap())
+}
+
+// The line comparison plots place the inputs at their numeric value, so they are skipped with a
+// warning if any input isn't a number.
+fn numeric_inputs(group_id: &str, plot: &str, all_curves: &[&(BenchmarkId, Vec<f64>)]) -> bool {
+    match all_curves
+        .iter()
+        .find(|&&&(ref id, _)| id.as_number().is_none())
+    {
+        Some(&&(ref id, _)) => {
+            warn!(
+                "{}: the input of {} is not a number; skipping the {} plot",
+                group_id,
+                id.function_id.as_ref().map_or(id.id(), String::as_str),
+                plot
+            );
+            false
+        }
+        None => true,
+    }
+}
+
+/// The kind of inputs shared by all of the curves, which the line comparison plot needs to label
+/// its X axis. Curves which mix bytes and elements share elements if
+/// `PlotConfiguration::bytes_per_element` converts between them; otherwise they share none.
+pub fn common_value_type(
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    plot_config: &PlotConfiguration,
+) -> Option<ValueType> {
+    let value_types: Vec<_> = all_curves
+        .iter()
+        .map(|&&(ref id, _)| id.value_type())
+        .collect();
+    if value_types.iter().all(|x| x == &value_types[0]) {
+        value_types[0]
+    } else if plot_config.bytes_per_element.is_some() && value_types
+        .iter()
+        .all(|x| x == &Some(ValueType::Bytes) || x == &Some(ValueType::Elements))
+    {
+        Some(ValueType::Elements)
+    } else {
+        None
+    }
+}
+
+/// Splits the curves of a Cartesian product of parameters into one set of curves for each
+/// combination of the values of the axes other than `axis`, identified by the values of those
+/// axes. The IDs of the curves are replaced by IDs whose value is the value of `axis`, so that the
+/// sets can be drawn by `line_comparison`.
+pub fn hold_axes_fixed(
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    axis: usize,
+) -> Vec<(String, Vec<(BenchmarkId, Vec<f64>)>)> {
+    let mut sets: Vec<(String, Vec<(BenchmarkId, Vec<f64>)>)> = vec![];
+    for &&(ref id, ref sample) in all_curves {
+        let (fixed, id) = match id.vary_axis(axis) {
+            Some(varied) => varied,
+            None => continue,
+        };
+        match sets.iter().position(|&(ref other, _)| *other == fixed) {
+            Some(i) => sets[i].1.push((id, sample.clone())),
+            None => sets.push((fixed, vec![(id, sample.clone())])),
+        }
+    }
+    sets
+}
+
+/// Orders the points along the X axis of the line comparison plot.
+pub type XOrdering<'a> = &'a Fn(&BenchmarkId, &BenchmarkId) -> Ordering;
+
+// If `x_order` is given, the inputs are placed at evenly-spaced positions along the X axis in the
+// order it defines and labeled with their value strings, so that inputs which are not numbers can
+// still be plotted. Otherwise the inputs are placed at their numeric value.
+pub fn line_comparison_data(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    value_type: ValueType,
+    plot_config: &PlotConfiguration,
+    x_order: Option<XOrdering>,
+) -> LineComparisonData {
+    let (finite_curves, dropped_values) = finite_curves(group_id, "line comparison", all_curves);
+    let all_curves: &[_] = &finite_curves.iter().collect::<Vec<_>>();
 
     let input_suffix = match value_type {
         ValueType::Bytes => " Size (Bytes)",
@@ -52,162 +320,3031 @@ pub fn line_comparison(
         ValueType::Value => "",
     };
 
-    f.set(Font(DEFAULT_FONT))
-        .set(SIZE)
-        .configure(Key, |k| {
-            k.set(Justification::Left)
-                .set(Order::SampleText)
-                .set(Position::Outside(Vertical::Top, Horizontal::Right))
-        })
-        .set(Title(format!(
-            "{}: Comparison",
-            escape_underscores(group_id)
-        )))
-        .configure(Axis::BottomX, |a| {
-            a.set(Label(format!("Input{}", input_suffix)))
-                .set(axis_scale.to_gnuplot())
-        });
+    let x_positions = x_order.map(|x_order| {
+        let mut ids: Vec<&BenchmarkId> = all_curves.iter().map(|&&(ref id, _)| id).collect();
+        ids.sort_by(|a, b| x_order(a, b));
+        ids.dedup_by(|a, b| a.value_str == b.value_str);
+        ids
+    });
+
+    // Like the throughput axis, plotting the throughputs only makes sense if every benchmark has a
+    // throughput to divide by. The throughputs replace the times, so neither the throughput axis
+    // nor the fixed limits of the times apply to them.
+    let has_throughputs = all_curves
+        .iter()
+        .all(|&&(ref id, _)| id.throughput.is_some());
+    let plot_throughput = plot_config.line_throughput && has_throughputs;
+    let y_range = if plot_throughput {
+        None
+    } else {
+        plot_config.summary_range
+    };
 
-    let mut i = 0;
+    // The value plotted for a benchmark whose average time is `time`, or `None` if there is no
+    // sensible value because the time is zero.
+    let y_value = |id: &BenchmarkId, time: f64| -> Option<f64> {
+        if !plot_throughput {
+            return Some(time);
+        }
+        let throughput = to_elements(id, value_type, plot_config, throughput_per_second(id, time));
+        if time > 0. && throughput.is_finite() {
+            Some(throughput)
+        } else {
+            None
+        }
+    };
 
     let max = all_curves
         .iter()
-        .map(|&&(_, ref data)| Sample::new(data).mean())
+        .filter_map(|&&(ref id, ref data)| {
+            y_value(id, line_average(plot_config, Sample::new(data)))
+        })
         .fold(::std::f64::NAN, f64::max);
 
-    let (scale, prefix) = scale_time(max);
+    let (scale, y_label) = if plot_throughput {
+        let (scale, unit) = scale_throughput(value_type, max);
+        (scale, format!("Throughput ({})", unit))
+    } else {
+        // Fixed limits share their unit, so that plots with the same limits look alike.
+        let max = y_range.map_or(max, |(_, upper)| upper);
+        let samples = all_curves.iter().map(|&&(_, ref data)| data);
+        plot_config
+            .unit
+            .scale("Average", unit_anchor(plot_config, max, samples))
+    };
 
-    f.configure(Axis::LeftY, |a| {
-        a.configure(Grid::Major, |g| g.show())
-            .configure(Grid::Minor, |g| g.hide())
-            .set(Label(format!("Average time ({}s)", prefix)))
-            .set(axis_scale.to_gnuplot())
-            .set(ScaleFactor(scale))
-    });
+    let throughput_axis = if plot_config.throughput_axis && has_throughputs && !plot_throughput {
+        let throughputs: Vec<f64> = all_curves
+            .iter()
+            .map(|&&(ref id, ref data)| {
+                let average = line_average(plot_config, Sample::new(data));
+                let throughput = throughput_per_second(id, average);
+                to_elements(id, value_type, plot_config, throughput)
+            })
+            .collect();
+        let max_throughput = throughputs.iter().cloned().fold(::std::f64::NAN, f64::max);
+
+        Some(match plot_config.summary_scale {
+            AxisScale::Linear => {
+                let (scale, unit) = scale_throughput(value_type, max_throughput);
+                ThroughputAxis {
+                    label: format!("Throughput ({})", unit),
+                    scale,
+                    tics: None,
+                }
+            }
+            AxisScale::Logarithmic => {
+                let min_throughput = throughputs.iter().cloned().fold(::std::f64::NAN, f64::min);
+                ThroughputAxis {
+                    label: "Throughput".to_owned(),
+                    scale: 1.0,
+                    tics: Some(log_throughput_tics(
+                        value_type,
+                        min_throughput,
+                        max_throughput,
+                    )),
+                }
+            }
+        })
+    } else {
+        None
+    };
 
     // This assumes the curves are sorted. It also assumes that the benchmark IDs all have numeric
-    // values or throughputs and that value is sensible (ie. not a mix of bytes and elements
-    // or whatnot)
+    // values or throughputs and that value is sensible (ie. not a mix of bytes and elements,
+    // unless they are converted to elements, or whatnot)
+    let mut series = vec![];
     for (key, group) in &all_curves
         .into_iter()
         .group_by(|&&&(ref id, _)| &id.function_id)
     {
         let mut tuples: Vec<_> = group
-            .map(|&&(ref id, ref sample)| {
-                // Unwrap is fine here because it will only fail if the assumptions above are not true
-                // ie. programmer error.
-                let x = id.as_number().unwrap();
-                let y = Sample::new(sample).mean();
+            .filter_map(|&&(ref id, ref sample)| {
+                let x = match x_positions {
+                    Some(ref ids) => {
+                        let position = ids.iter()
+                            .position(|other| other.value_str == id.value_str)
+                            .unwrap();
+                        (position + 1) as f64
+                    }
+                    // Unwrap is fine here because `line_comparison` skips the groups whose inputs
+                    // aren't all numbers.
+                    None => to_elements(id, value_type, plot_config, id.as_number().unwrap()),
+                };
+                let sample = Sample::new(sample);
+                let average = line_average(plot_config, sample);
+                let y = y_value(id, average)?;
+                let percentiles = sample.percentiles();
+                // The throughput falls as the time rises, so the slowest times bound the band from
+                // below.
+                let band = if plot_throughput {
+                    let bound = |time| y_value(id, time).unwrap_or(y);
+                    (bound(percentiles.at(97.5)), bound(percentiles.at(2.5)))
+                } else {
+                    (percentiles.at(2.5), percentiles.at(97.5))
+                };
 
-                (x, y)
+                let throughput = throughput_per_second(id, average);
+                Some((x, y, to_elements(id, value_type, plot_config, throughput), band))
             })
             .collect();
-        tuples.sort_by(|&(ax, _), &(bx, _)| (ax.partial_cmp(&bx).unwrap_or(Ordering::Less)));
-        let (xs, ys): (Vec<_>, Vec<_>) = tuples.into_iter().unzip();
-
-        let function_name = key.as_ref().map(|string| escape_underscores(string));
-
-        f.plot(Lines { x: &xs, y: &ys }, |c| {
-            if let Some(name) = function_name {
-                c.set(Label(name));
-            }
-            c.set(LINEWIDTH)
-                .set(LineType::Solid)
-                .set(COMPARISON_COLORS[i % NUM_COLORS])
-        }).plot(Points { x: &xs, y: &ys }, |p| {
-            p.set(PointType::FilledCircle)
-                .set(POINT_SIZE)
-                .set(COMPARISON_COLORS[i % NUM_COLORS])
+        if tuples.is_empty() {
+            continue;
+        }
+        tuples.sort_by(|&(ax, _, _, _), &(bx, _, _, _)| nan_last(ax, bx));
+
+        series.push(LineSeries {
+            function_id: key.clone(),
+            xs: tuples.iter().map(|&(x, _, _, _)| x).collect(),
+            ys: tuples.iter().map(|&(_, y, _, _)| y).collect(),
+            throughputs: throughput_axis
+                .as_ref()
+                .map(|_| tuples.iter().map(|&(_, _, t, _)| t).collect()),
+            band: if plot_config.line_bands {
+                Some(tuples.iter().map(|&(_, _, _, band)| band).unzip())
+            } else {
+                None
+            },
+            other: false,
         });
+    }
+    if let Some((count, selection)) = plot_config.line_highlighted {
+        mark_others(&mut series, count, selection, plot_throughput);
+    }
+    let differences = match series.as_slice() {
+        [ref a, ref b] if plot_config.line_differences => differences(a, b),
+        _ => vec![],
+    };
+
+    let clipped_values = clipped(
+        group_id,
+        "line comparison",
+        y_range,
+        series.iter().flat_map(|series| series.ys.iter()),
+    );
 
-        i += 1;
+    LineComparisonData {
+        title: format!("{}: Comparison", group_id),
+        x_label: format!("Input{}", input_suffix),
+        x_tics: x_positions.map(|ids| {
+            ids.iter()
+                .map(|id| id.value_str.clone().unwrap_or_default())
+                .collect()
+        }),
+        x_tic_labels: None,
+        y_label,
+        y_scale: scale,
+        y_range,
+        throughput_axis,
+        series,
+        differences,
+        dropped_values,
+        clipped_values,
     }
+}
 
-    debug_script(&path, &f);
-    f.set(Output(path)).draw().unwrap()
+// The relative differences of `b` against `a`, at the inputs of `a` at which `b` was measured too.
+fn differences(a: &LineSeries, b: &LineSeries) -> Vec<LineDifference> {
+    a.xs
+        .iter()
+        .zip(&a.ys)
+        .filter(|&(_, &a_y)| a_y != 0.)
+        .filter_map(|(&x, &a_y)| {
+            let i = b.xs.iter().position(|&b_x| b_x == x)?;
+            let b_y = b.ys[i];
+            Some(LineDifference {
+                x,
+                y: a_y.max(b_y),
+                change: (b_y - a_y) / a_y,
+            })
+        })
+        .collect()
 }
 
-pub fn violin(
+// Marks all but `count` of the series as others, keeping the ones which `selection` ranks first.
+// The throughputs rise as the times fall, so the fastest functions have the largest throughputs.
+fn mark_others(
+    series: &mut [LineSeries],
+    count: usize,
+    selection: LineSelection,
+    plot_throughput: bool,
+) {
+    // The value at the largest input, which is last as the points are sorted by input
+    let last = |series: &LineSeries| {
+        series
+            .xs
+            .iter()
+            .zip(&series.ys)
+            .rev()
+            .find(|&(x, _)| !x.is_nan())
+            .map_or(::std::f64::NAN, |(_, &y)| y)
+    };
+    let key = |series: &LineSeries| match (selection, plot_throughput) {
+        (LineSelection::Fastest, false) | (LineSelection::Slowest, true) => last(series),
+        (LineSelection::Fastest, true) | (LineSelection::Slowest, false) => -last(series),
+        (LineSelection::Peak, _) => -series.ys.iter().cloned().fold(::std::f64::NAN, f64::max),
+    };
+
+    let mut ranked: Vec<(usize, f64)> = series.iter().map(key).enumerate().collect();
+    ranked.sort_by(|&(_, a), &(_, b)| nan_last(a, b));
+    for &(i, _) in &ranked[cmp::min(count, ranked.len())..] {
+        series[i].other = true;
+    }
+}
+
+// The ratios are dimensionless, so the unit, the fixed range and the throughput axis of the times
+// don't apply to them. Inputs which the baseline wasn't measured at are left out, as are functions
+// which share no inputs with the baseline.
+pub fn relative_line_comparison_data(
     group_id: &str,
     all_curves: &[&(BenchmarkId, Vec<f64>)],
-    path: &str,
-    axis_scale: AxisScale,
-) -> Child {
-    let path = PathBuf::from(&path);
-    let all_curves_vec = all_curves.iter().rev().map(|&t| t).collect::<Vec<_>>();
-    let all_curves: &[&(BenchmarkId, Vec<f64>)] = &*all_curves_vec;
+    value_type: ValueType,
+    plot_config: &PlotConfiguration,
+    baseline: &str,
+) -> Option<LineComparisonData> {
+    let mut plot_config = plot_config.clone();
+    plot_config.summary_range = None;
+    plot_config.throughput_axis = false;
+    plot_config.line_throughput = false;
+    plot_config.line_differences = false;
+    let mut data = line_comparison_data(group_id, all_curves, value_type, &plot_config, None);
 
-    let kdes = all_curves
+    let (baseline_xs, baseline_ys) = data
+        .series
         .iter()
-        .map(|&&(_, ref sample)| {
-            let (x, mut y) = kde::sweep(Sample::new(sample), KDE_POINTS, None);
-            let y_max = Sample::new(&y).max();
-            for y in y.iter_mut() {
-                *y /= y_max;
+        .find(|series| series.function_id.as_ref().map(String::as_str) == Some(baseline))
+        .map(|series| (series.xs.clone(), series.ys.clone()))?;
+
+    for series in &mut data.series {
+        // The index of each point which the baseline shares, and the baseline's mean there.
+        let shared: Vec<(usize, f64)> = series
+            .xs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, x)| {
+                let j = baseline_xs.iter().position(|other| other == x)?;
+                Some((i, baseline_ys[j]))
+            })
+            .collect();
+        let relative = |values: &[f64]| -> Vec<f64> {
+            shared.iter().map(|&(i, base)| values[i] / base).collect()
+        };
+
+        series.ys = relative(&series.ys);
+        series.band = series
+            .band
+            .as_ref()
+            .map(|&(ref lower, ref upper)| (relative(lower), relative(upper)));
+        series.xs = shared.iter().map(|&(i, _)| series.xs[i]).collect();
+    }
+    data.series.retain(|series| !series.xs.is_empty());
+
+    data.title = format!("{}: Relative to {}", group_id, baseline);
+    data.y_label = "Relative time (baseline = 1.0)".to_owned();
+    data.y_scale = 1.0;
+    Some(data)
+}
+
+// The average time which the line comparison plots show for a benchmark: its mean, or its
+// trimmed mean if the user asked for robust lines.
+fn line_average(plot_config: &PlotConfiguration, sample: &Sample<f64>) -> f64 {
+    match plot_config.line_trimmed_mean {
+        Some(proportion) => sample.trimmed_mean(proportion),
+        None => sample.mean(),
+    }
+}
+
+// Counts the `values` which fall outside of the fixed `range` of a plot, warning if there are any.
+fn clipped<'a, I>(group_id: &str, plot: &str, range: Option<(f64, f64)>, values: I) -> usize
+where
+    I: Iterator<Item = &'a f64>,
+{
+    let (lower, upper) = match range {
+        Some(range) => range,
+        None => return 0,
+    };
+    let clipped = values.filter(|&&value| value < lower || value > upper).count();
+    if clipped > 0 {
+        warn!(
+            "{}: {} values are outside of the range of the {} plot and are cut off",
+            group_id, clipped, plot
+        );
+    }
+    clipped
+}
+
+// The value which the unit of the time axis is picked for: `max` (the largest value, or the upper
+// limit of the axis), unless the configuration anchors the unit elsewhere.
+fn unit_anchor<'a, I>(plot_config: &PlotConfiguration, max: f64, samples: I) -> f64
+where
+    I: IntoIterator<Item = &'a Vec<f64>>,
+{
+    match plot_config.unit_anchor {
+        UnitAnchor::Max => max,
+        UnitAnchor::MedianOfMedians => {
+            let mut medians: Vec<f64> = samples
+                .into_iter()
+                .map(|sample| Sample::new(sample).median())
+                .collect();
+            if medians.is_empty() {
+                return max;
             }
+            medians.sort_by(|a, b| a.partial_cmp(b).unwrap_or(Ordering::Equal));
+            let middle = medians.len() / 2;
+            if medians.len() % 2 == 0 {
+                (medians[middle - 1] + medians[middle]) / 2.
+            } else {
+                medians[middle]
+            }
+        }
+        UnitAnchor::Value(value) => value,
+    }
+}
 
-            (x, y)
+// A single non-finite measurement would break the scaling of the whole plot, so those are left
+// out, with a warning for each benchmark which had any. Curves with fewer than two finite
+// measurements are left out entirely. Returns the remaining curves and the number of measurements
+// which were dropped.
+fn finite_curves(
+    group_id: &str,
+    plot: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+) -> (Vec<(BenchmarkId, Vec<f64>)>, usize) {
+    let mut dropped = 0;
+    let curves = all_curves
+        .iter()
+        .filter_map(|&&(ref id, ref sample)| {
+            let finite: Vec<f64> = sample.iter().cloned().filter(|x| x.is_finite()).collect();
+            let non_finite = sample.len() - finite.len();
+            if finite.len() < 2 {
+                warn!(
+                    "{}: {} has {} non-finite values and too few others; leaving it out of the {} \
+                     plot",
+                    group_id,
+                    id.id(),
+                    non_finite,
+                    plot
+                );
+                dropped += sample.len();
+                None
+            } else {
+                if non_finite > 0 {
+                    warn!(
+                        "{}: dropped {} non-finite values of {} from the {} plot",
+                        group_id,
+                        non_finite,
+                        id.id(),
+                        plot
+                    );
+                }
+                dropped += non_finite;
+                Some((id.clone(), finite))
+            }
         })
-        .collect::<Vec<_>>();
-    let mut xs = kdes.iter()
-        .flat_map(|&(ref x, _)| x.iter())
-        .filter(|&&x| x > 0.);
-    let (mut min, mut max) = {
-        let &first = xs.next().unwrap();
-        (first, first)
+        .collect();
+
+    (curves, dropped)
+}
+
+// Sorts the curves of a violin plot from the top of the plot down, which is the reverse of the
+// order the violins are built in. Their samples are all finite, so their medians can be compared.
+fn sort_violins(curves: &mut [(BenchmarkId, Vec<f64>)], order: ViolinOrder) {
+    let by_median = |a: &Vec<f64>, b: &Vec<f64>| {
+        let median = |sample: &[f64]| Sample::new(sample).percentiles().median();
+        median(a).partial_cmp(&median(b)).unwrap()
     };
-    for &e in xs {
-        if e < min {
-            min = e;
-        } else if e > max {
-            max = e;
+    match order {
+        ViolinOrder::AsGiven => {}
+        ViolinOrder::ByMedianAscending => {
+            curves.sort_by(|&(_, ref a), &(_, ref b)| by_median(a, b))
+        }
+        ViolinOrder::ByMedianDescending => {
+            curves.sort_by(|&(_, ref a), &(_, ref b)| by_median(b, a))
         }
     }
-    let (scale, prefix) = scale_time(max);
+}
 
-    let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
-    let size = Size(1280, 200 + (25 * all_curves.len()));
+// Orders the inputs along the X axis. An input which parses as NaN has no place on the axis, so
+// those are put after all of the others, in their original order because the sort is stable.
+fn nan_last(a: f64, b: f64) -> Ordering {
+    match (a.is_nan(), b.is_nan()) {
+        (false, false) => a.partial_cmp(&b).unwrap(),
+        (a_nan, b_nan) => a_nan.cmp(&b_nan),
+    }
+}
+
+// The configured size, or the configured width with the height of the configured aspect ratio,
+// or of the default one.
+fn line_size(plot_config: &PlotConfiguration) -> Size {
+    let Size(default_width, default_height) = SIZE;
+    match (plot_config.size, plot_config.line_aspect_ratio) {
+        (Some((width, Some(height))), _) => Size(width, height),
+        (size, Some(ratio)) => {
+            let width = size.map_or(default_width, |(width, _)| width);
+            Size(width, (width as f64 / ratio).round().max(1.) as usize)
+        }
+        (Some((width, None)), None) => Size(width, width * default_height / default_width),
+        (None, None) => SIZE,
+    }
+}
+
+// The configured size, or the configured width with a height which grows with the number of
+// `rows` of the plot.
+fn rows_size(plot_config: &PlotConfiguration, rows: usize) -> Size {
+    match plot_config.size {
+        Some((width, Some(height))) => Size(width, height),
+        Some((width, None)) => Size(width, 200 + (25 * rows)),
+        None => Size(1280, 200 + (25 * rows)),
+    }
+}
+
+fn line_comparison_figure(data: &LineComparisonData, plot_config: &PlotConfiguration) -> Figure {
+    let axis_scale = plot_config.summary_scale;
     let mut f = Figure::new();
+    plot_config.background.apply(&mut f);
+    plot_config.theme.apply(&mut f);
+
     f.set(Font(DEFAULT_FONT))
-        .set(size)
-        .set(Title(format!(
-            "{}: Violin plot",
-            escape_underscores(group_id)
-        )))
-        .configure(Axis::BottomX, |a| {
-            a.configure(Grid::Major, |g| g.show())
-                .configure(Grid::Minor, |g| g.hide())
-                .set(Label(format!("Average time ({}s)", prefix)))
-                .set(axis_scale.to_gnuplot())
-                .set(ScaleFactor(scale))
+        .set(line_size(plot_config))
+        .configure(Key, |k| {
+            plot_config
+                .line_legend
+                .apply(k.set(Justification::Left).set(Order::SampleText))
         })
-        .configure(Axis::LeftY, |a| {
-            a.set(Label("Input"))
-                .set(Range::Limits(0., all_curves.len() as f64))
+        .set(Title(escape_underscores(&data.title)))
+        .configure(Axis::BottomX, |a| a.set(Label(data.x_label.clone())));
+
+    if let Some(ref tics) = data.x_tics {
+        // Evenly-spaced positions have no meaningful logarithmic scale.
+        f.configure(Axis::BottomX, |a| {
+            a.set(Scale::Linear)
+                .set(Range::Limits(0.5, tics.len() as f64 + 0.5))
                 .set(TicLabels {
-                    positions: tics(),
-                    labels: all_curves
-                        .iter()
-                        .map(|&&(ref id, _)| escape_underscores(id.id())),
+                    positions: (1..).take(tics.len()).map(f64::from),
+                    labels: tics.iter().map(|tic| escape_underscores(tic)),
                 })
         });
+    } else if let Some(ref tics) = data.x_tic_labels {
+        f.configure(Axis::BottomX, |a| {
+            a.set(axis_scale.to_gnuplot()).set(TicLabels {
+                positions: tics.iter().map(|&(position, _)| position),
+                labels: tics.iter().map(|&(_, ref label)| escape_underscores(label)),
+            })
+        });
+    } else {
+        f.configure(Axis::BottomX, |a| a.set(axis_scale.to_gnuplot()));
+    }
 
-    let mut is_first = true;
-    for (i, &(ref x, ref y)) in kdes.iter().enumerate() {
-        let i = i as f64 + 0.5;
-        let y1 = y.iter().map(|&y| i + y * 0.5);
-        let y2 = y.iter().map(|&y| i - y * 0.5);
-
-        f.plot(FilledCurve { x: &**x, y1, y2 }, |c| {
-            if is_first {
-                is_first = false;
+    f.configure(Axis::BottomX, |a| {
+        plot_config
+            .line_grid
+            .apply(Axis::BottomX, a, plot_config.theme)
+    });
+    f.configure(Axis::LeftY, |a| {
+        plot_config
+            .line_grid
+            .apply(Axis::LeftY, a, plot_config.theme)
+            .set(Label(data.y_label.clone()))
+            .set(axis_scale.to_gnuplot())
+            .set(ScaleFactor(data.y_scale));
+        if let Some((lower, upper)) = data.y_range {
+            a.set(Range::Limits(lower * data.y_scale, upper * data.y_scale));
+        }
+        a
+    });
 
-                c.set(DARK_BLUE).set(Label("PDF")).set(Opacity(0.25))
-            } else {
-                c.set(DARK_BLUE).set(Opacity(0.25))
+    if let Some(ref axis) = data.throughput_axis {
+        f.configure(Axis::RightY, |a| {
+            a.configure(Grid::Major, |g| g.hide())
+                .configure(Grid::Minor, |g| g.hide())
+                .set(Label(axis.label.clone()))
+                .set(axis_scale.to_gnuplot())
+                .set(ScaleFactor(axis.scale));
+            if let Some(ref tics) = axis.tics {
+                a.set(TicLabels {
+                    positions: tics.iter().map(|&(position, _)| position),
+                    labels: tics.iter().map(|&(_, ref label)| label.as_str()),
+                });
             }
+            a
         });
     }
+
+    let highlighted = data.series.iter().filter(|series| !series.other).count();
+    let mut colors = curve_colors(plot_config, highlighted).into_iter();
+    let mut is_first_other = true;
+    for series in &data.series {
+        let (function_name, color) = if series.other {
+            let name = if is_first_other {
+                Some("others".to_owned())
+            } else {
+                None
+            };
+            is_first_other = false;
+            (name, MUTED_GRAY)
+        } else {
+            let name = series
+                .function_id
+                .as_ref()
+                .map(|string| escape_underscores(string));
+            (name, colors.next().unwrap())
+        };
+
+        if let Some((ref lower, ref upper)) = series.band {
+            f.plot(
+                FilledCurve {
+                    x: &series.xs,
+                    y1: lower,
+                    y2: upper,
+                },
+                |c| c.set(color).set(Opacity(0.25)),
+            );
+        }
+        f.plot(
+            Lines {
+                x: &series.xs,
+                y: &series.ys,
+            },
+            |c| {
+                if let Some(ref name) = function_name {
+                    c.set(Label(name.clone()));
+                }
+                c.set(LINEWIDTH)
+                    .set(LineType::Solid)
+                    .set(color)
+            },
+        ).plot(
+            Points {
+                x: &series.xs,
+                y: &series.ys,
+            },
+            |p| {
+                p.set(PointType::FilledCircle)
+                    .set(POINT_SIZE)
+                    .set(color)
+            },
+        );
+
+        if let Some(ref throughputs) = series.throughputs {
+            f.plot(
+                Lines {
+                    x: &series.xs,
+                    y: throughputs,
+                },
+                |c| {
+                    if let Some(ref name) = function_name {
+                        c.set(Label(format!("{} (throughput)", name)));
+                    } else if !series.other {
+                        c.set(Label("Throughput"));
+                    }
+                    c.set(Axes::BottomXRightY)
+                        .set(LINEWIDTH)
+                        .set(LineType::Dash)
+                        .set(color)
+                },
+            ).plot(
+                Points {
+                    x: &series.xs,
+                    y: throughputs,
+                },
+                |p| {
+                    p.set(Axes::BottomXRightY)
+                        .set(PointType::FilledCircle)
+                        .set(POINT_SIZE)
+                        .set(color)
+                },
+            );
+        }
+    }
+
+    for difference in &data.differences {
+        let label = format::change(difference.change).trim().to_owned();
+        f.annotate(difference.x, difference.y, label);
+    }
+
+    f
+}
+
+const GROUP_LINE_TYPES: [LineType; 5] = [
+    LineType::Solid,
+    LineType::Dash,
+    LineType::Dot,
+    LineType::DotDash,
+    LineType::DotDotDash,
+];
+
+// Groups whose ids share the part before the first `/`, eg. `sort/small` and `sort/large`, are
+// related. Returns the shared prefix of each set of at least two related groups with numeric
+// inputs, and the ids of each of its groups, to compare on one figure with
+// `multi_group_comparison`.
+pub fn related_groups(ids: &[BenchmarkId]) -> Vec<(String, Vec<Vec<BenchmarkId>>)> {
+    let mut prefixes: BTreeMap<&str, BTreeMap<&str, Vec<BenchmarkId>>> = BTreeMap::new();
+    for id in ids {
+        if let Some(slash) = id.group_id.find('/') {
+            prefixes
+                .entry(&id.group_id[..slash])
+                .or_default()
+                .entry(&id.group_id)
+                .or_default()
+                .push(id.clone());
+        }
+    }
+
+    prefixes
+        .into_iter()
+        .filter(|&(_, ref groups)| {
+            groups.len() > 1
+                && groups
+                    .values()
+                    .flat_map(|ids| ids.iter())
+                    .all(|id| id.as_number().is_some())
+        })
+        .map(|(prefix, groups)| {
+            // The curves of each function have to be next to each other.
+            let groups = groups
+                .into_values()
+                .map(|mut ids| {
+                    ids.sort_by(|a, b| a.id().cmp(b.id()));
+                    ids
+                })
+                .collect();
+            (prefix.to_owned(), groups)
+        })
+        .collect()
+}
+
+/// Draws the groups sharing the `prefix` of their ids on one figure, like `line_comparison` draws
+/// the functions of a group.
+pub fn multi_group_comparison(
+    prefix: &str,
+    groups: &[&[&(BenchmarkId, Vec<f64>)]],
+    path: &str,
+    plot_config: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(path);
+    let mut f = multi_group_comparison_figure(prefix, groups, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
     debug_script(&path, &f);
     f.set(Output(path)).draw().unwrap()
 }
+
+// Like `line_comparison`, but plots several groups on the same figure. The function is encoded in
+// the color and the group in the line type.
+fn multi_group_comparison_figure(
+    prefix: &str,
+    groups: &[&[&(BenchmarkId, Vec<f64>)]],
+    plot_config: &PlotConfiguration,
+) -> Figure {
+    let groups: Vec<Vec<(BenchmarkId, Vec<f64>)>> = groups
+        .iter()
+        .map(|curves| finite_curves(prefix, "multi-group comparison", curves).0)
+        .collect();
+    let group_ids: Vec<_> = groups
+        .iter()
+        .filter_map(|curves| curves.first())
+        .map(|&(ref id, _)| escape_underscores(&id.group_id))
+        .collect();
+    let axis_scale = plot_config.summary_scale;
+
+    let mut f = Figure::new();
+    plot_config.background.apply(&mut f);
+    plot_config.theme.apply(&mut f);
+
+    f.set(Font(DEFAULT_FONT))
+        .set(line_size(plot_config))
+        .configure(Key, |k| {
+            plot_config
+                .line_legend
+                .apply(k.set(Justification::Left).set(Order::SampleText))
+        })
+        .set(Title(format!("{}: Comparison", group_ids.join(" vs. "))))
+        .configure(Axis::BottomX, |a| {
+            plot_config
+                .line_grid
+                .apply(Axis::BottomX, a, plot_config.theme)
+                .set(Label("Input"))
+                .set(axis_scale.to_gnuplot())
+        });
+
+    let max = groups
+        .iter()
+        .flat_map(|curves| curves.iter())
+        .map(|&(_, ref data)| line_average(plot_config, Sample::new(data)))
+        .fold(0., f64::max);
+    // Fixed limits share their unit, so that plots with the same limits look alike.
+    let max = plot_config.summary_range.map_or(max, |(_, upper)| upper);
+    let samples = groups
+        .iter()
+        .flat_map(|curves| curves.iter())
+        .map(|&(_, ref data)| data);
+    let (scale, y_label) = plot_config
+        .unit
+        .scale("Average", unit_anchor(plot_config, max, samples));
+
+    f.configure(Axis::LeftY, |a| {
+        plot_config
+            .line_grid
+            .apply(Axis::LeftY, a, plot_config.theme)
+            .set(Label(y_label))
+            .set(axis_scale.to_gnuplot())
+            .set(ScaleFactor(scale));
+        if let Some((lower, upper)) = plot_config.summary_range {
+            a.set(Range::Limits(lower * scale, upper * scale));
+        }
+        a
+    });
+
+    // Functions keep the same color in every group.
+    let mut function_ids: Vec<&Option<String>> = vec![];
+    for &(ref id, _) in groups.iter().flat_map(|curves| curves.iter()) {
+        if !function_ids.contains(&&id.function_id) {
+            function_ids.push(&id.function_id);
+        }
+    }
+    let colors = curve_colors(plot_config, function_ids.len());
+
+    for (group_index, curves) in groups.iter().enumerate() {
+        let line_type = GROUP_LINE_TYPES[group_index % GROUP_LINE_TYPES.len()];
+
+        for (key, group) in &curves.iter().group_by(|&&(ref id, _)| &id.function_id) {
+            let color_index = function_ids.iter().position(|&id| id == key).unwrap();
+            let color = colors[color_index % colors.len()];
+
+            let mut tuples: Vec<_> = group
+                .map(|&(ref id, ref sample)| {
+                    let average = line_average(plot_config, Sample::new(sample));
+                    (id.as_number().unwrap(), average)
+                })
+                .collect();
+            tuples.sort_by(|&(ax, _), &(bx, _)| nan_last(ax, bx));
+            let (xs, ys): (Vec<_>, Vec<_>) = tuples.into_iter().unzip();
+
+            let label = match *key {
+                Some(ref function_id) => format!(
+                    "{}/{}",
+                    group_ids[group_index],
+                    escape_underscores(function_id)
+                ),
+                None => group_ids[group_index].clone(),
+            };
+
+            f.plot(Lines { x: &xs, y: &ys }, |c| {
+                c.set(Label(label))
+                    .set(LINEWIDTH)
+                    .set(line_type)
+                    .set(color)
+            }).plot(Points { x: &xs, y: &ys }, |p| {
+                p.set(PointType::FilledCircle)
+                    .set(POINT_SIZE)
+                    .set(color)
+            });
+        }
+    }
+
+    f
+}
+
+// Converts an average time per iteration into bytes or elements processed per second.
+fn throughput_per_second(id: &BenchmarkId, ns: f64) -> f64 {
+    match id.throughput {
+        Some(Throughput::Bytes(n)) | Some(Throughput::Elements(n)) => f64::from(n) * (1e9 / ns),
+        None => ::std::f64::NAN,
+    }
+}
+
+// Converts a number of bytes, or bytes per second, of a benchmark counting bytes into elements if
+// the plot counts elements, as it does for groups mixing the two (see `common_value_type`).
+fn to_elements(
+    id: &BenchmarkId,
+    value_type: ValueType,
+    plot_config: &PlotConfiguration,
+    bytes: f64,
+) -> f64 {
+    match (value_type, &id.throughput) {
+        (ValueType::Elements, &Some(Throughput::Bytes(_))) => {
+            bytes / plot_config.bytes_per_element.unwrap_or(1.)
+        }
+        _ => bytes,
+    }
+}
+
+/// The data shown in a violin plot, independent of how it is rendered.
+#[derive(Debug, Serialize)]
+pub struct ViolinData {
+    pub title: String,
+    pub x_label: String,
+    pub x_scale: f64,
+    /// Positions and labels of the tics of a logarithmic X axis, each labeled in its own unit,
+    /// which replace the scale factor.
+    pub x_tics: Option<Vec<(f64, String)>>,
+    /// Fixed limits of the X axis, in the unit of the measurements, instead of fitting the data.
+    pub x_range: Option<(f64, f64)>,
+    /// One violin per benchmark, from the bottom of the plot to the top.
+    pub violins: Vec<Violin>,
+    /// Number of non-finite measurements which were left out of the plot.
+    pub dropped_values: usize,
+    /// Number of measurements which fall outside of `x_range` and are cut off.
+    pub clipped_values: usize,
+}
+
+/// The estimated probability density of one benchmark, or the outline of its histogram,
+/// normalized so that its peak is at 1.
+#[derive(Debug, Serialize)]
+pub struct Violin {
+    pub label: String,
+    pub xs: Vec<f64>,
+    pub ys: Vec<f64>,
+    pub mean: f64,
+    pub median: f64,
+    /// The 25th and 75th percentiles.
+    pub quartiles: (f64, f64),
+    /// The measurements outside of the inner fences of Tukey's method, but within the outer ones.
+    pub mild_outliers: Vec<f64>,
+    /// The measurements outside of the outer fences of Tukey's method.
+    pub severe_outliers: Vec<f64>,
+}
+
+pub fn violin(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    path: &str,
+    plot_config: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(&path);
+    let data = violin_data(group_id, all_curves, plot_config);
+    let mut f = violin_figure(&data, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
+    debug_script(&path, &f);
+    f.set(Output(path)).draw().unwrap()
+}
+
+fn kde_points(ncurves: usize, plot_config: &PlotConfiguration) -> usize {
+    if !plot_config.adaptive_kde_points || ncurves == 0 {
+        return KDE_POINTS;
+    }
+
+    cmp::max(
+        MIN_KDE_POINTS,
+        cmp::min(KDE_POINTS, VIOLIN_POINTS_BUDGET / ncurves),
+    )
+}
+
+// The outline of the histogram of a sample, as steps which start and end at zero. A sample without
+// spread has no outline, so all of its steps are at zero.
+fn histogram_steps(
+    group_id: &str,
+    id: &BenchmarkId,
+    sample: &Sample<f64>,
+    nbins: usize,
+    binning: Binning,
+) -> (Vec<f64>, Vec<f64>) {
+    let binning = if binning == Binning::Logarithmic && sample.min() <= 0. {
+        warn!(
+            "{}: {} has times which aren't positive; its histogram has linear bins",
+            group_id,
+            id.id()
+        );
+        Binning::Linear
+    } else {
+        binning
+    };
+    let (edges, counts) = sample.binned_histogram(nbins, binning);
+    let has_spread = sample.min() < sample.max();
+
+    let mut xs = vec![edges[0]];
+    let mut ys = vec![0.];
+    for (edge, &count) in edges.windows(2).zip(&counts) {
+        let y = if has_spread { count as f64 } else { 0. };
+        xs.extend_from_slice(edge);
+        ys.extend_from_slice(&[y, y]);
+    }
+    xs.push(edges[nbins]);
+    ys.push(0.);
+    (xs, ys)
+}
+
+// The range of the violin plot when there are no times to fit it to, in nanoseconds.
+const DEFAULT_VIOLIN_RANGE: (f64, f64) = (1., 10.);
+
+pub fn violin_data(
+    group_id: &str,
+    all_curves: &[&(BenchmarkId, Vec<f64>)],
+    plot_config: &PlotConfiguration,
+) -> ViolinData {
+    let (mut finite_curves, dropped_values) = finite_curves(group_id, "violin", all_curves);
+    let npoints = kde_points(finite_curves.len(), plot_config);
+
+    sort_violins(&mut finite_curves, plot_config.violin_order);
+
+    let violins = finite_curves
+        .iter()
+        .rev()
+        .map(|&(ref id, ref sample)| {
+            let sample = Sample::new(sample);
+            let (x, mut y) = match plot_config.violin_shape {
+                ViolinShape::Kde => {
+                    let (x, y) = kde::sweep_with_bandwidth(
+                        sample,
+                        npoints,
+                        None,
+                        plot_config.kde_bandwidth,
+                        plot_config.kde_kernel,
+                    );
+                    (x.into_vec(), y.into_vec())
+                }
+                ViolinShape::Histogram(nbins) => {
+                    histogram_steps(group_id, id, sample, nbins, Binning::Linear)
+                }
+                ViolinShape::LogHistogram(nbins) => {
+                    histogram_steps(group_id, id, sample, nbins, Binning::Logarithmic)
+                }
+            };
+            // A sample without spread (eg. a constant one) has no density to normalize, so it's
+            // drawn as a flat line at its value instead.
+            let y_max = y.iter().cloned().fold(0., f64::max);
+            if y_max > 0. && y_max.is_finite() {
+                for y in y.iter_mut() {
+                    *y /= y_max;
+                }
+            } else {
+                warn!(
+                    "{}: the samples of {} have no spread; its violin is a flat line",
+                    group_id,
+                    id.id()
+                );
+                for y in y.iter_mut() {
+                    *y = 0.;
+                }
+            }
+
+            let percentiles = sample.percentiles();
+            let (mut mild_outliers, mut severe_outliers) = (vec![], vec![]);
+            for (x, label) in sample.classify_outliers().iter() {
+                if label.is_severe() {
+                    severe_outliers.push(x);
+                } else if label.is_mild() {
+                    mild_outliers.push(x);
+                }
+            }
+            Violin {
+                label: id.id().to_owned(),
+                xs: x,
+                ys: y,
+                mean: sample.mean(),
+                median: percentiles.median(),
+                quartiles: (percentiles.at(25.), percentiles.at(75.)),
+                mild_outliers,
+                severe_outliers,
+            }
+        })
+        .collect::<Vec<_>>();
+    let mut xs = violins
+        .iter()
+        .flat_map(|violin| violin.xs.iter())
+        .filter(|&&x| x > 0.);
+    let (min, max) = match xs.next() {
+        Some(&first) => xs.fold((first, first), |(min, max), &x| (min.min(x), max.max(x))),
+        None => {
+            warn!(
+                "{}: no positive times to plot in the violin plot; using a default range",
+                group_id
+            );
+            DEFAULT_VIOLIN_RANGE
+        }
+    };
+    // The lower quantiles which aren't positive don't fit on a logarithmic axis, which then starts
+    // at the smallest positive time instead.
+    let quantiles = match plot_config.summary_range {
+        Some(_) => None,
+        None => plot_config.violin_range_quantiles,
+    };
+    let quantile_range = match quantiles {
+        Some((lower, upper)) if !finite_curves.is_empty() => {
+            let (lower, upper) = finite_curves.iter().fold(
+                (::std::f64::INFINITY, ::std::f64::NEG_INFINITY),
+                |(lowest, highest), &(_, ref sample)| {
+                    let sample = Sample::new(sample);
+                    (
+                        lowest.min(sample.quantile(lower)),
+                        highest.max(sample.quantile(upper)),
+                    )
+                },
+            );
+            match plot_config.summary_scale {
+                AxisScale::Logarithmic if lower <= 0. => Some((min, upper)),
+                _ => Some((lower, upper)),
+            }
+        }
+        _ => None,
+    };
+    let (min, max) = quantile_range.unwrap_or((min, max));
+    let max = plot_config.summary_range.map_or(max, |(_, upper)| upper);
+    let (scale, x_label, x_tics) = match plot_config.summary_scale {
+        AxisScale::Linear => {
+            let samples = finite_curves.iter().map(|&(_, ref data)| data);
+            let anchor = unit_anchor(plot_config, max, samples);
+            let (scale, x_label) = plot_config.unit.scale("Average", anchor);
+            (scale, x_label, None)
+        }
+        AxisScale::Logarithmic => {
+            let min = match plot_config.summary_range {
+                Some((lower, _)) if lower > 0. => lower,
+                _ => min,
+            };
+            let tics = plot_config.unit.log_tics(min, max);
+            (1.0, plot_config.unit.label("Average"), Some(tics))
+        }
+    };
+    let clipped_values = clipped(
+        group_id,
+        "violin",
+        plot_config.summary_range,
+        finite_curves.iter().flat_map(|&(_, ref sample)| sample.iter()),
+    );
+
+    ViolinData {
+        title: format!("{}: Violin plot", group_id),
+        x_label,
+        x_scale: scale,
+        x_tics,
+        x_range: plot_config.summary_range.or(quantile_range),
+        violins,
+        dropped_values,
+        clipped_values,
+    }
+}
+
+fn violin_figure(data: &ViolinData, plot_config: &PlotConfiguration) -> Figure {
+    let axis_scale = plot_config.summary_scale;
+    let fill_style = plot_config.background.legible(plot_config.fill_style);
+    let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
+    let size = rows_size(plot_config, data.violins.len());
+    let mut f = Figure::new();
+    plot_config.background.apply(&mut f);
+    plot_config.theme.apply(&mut f);
+    f.set(Font(DEFAULT_FONT))
+        .set(size)
+        .set(Title(escape_underscores(&data.title)))
+        .configure(Axis::BottomX, |a| {
+            plot_config
+                .violin_grid
+                .apply(Axis::BottomX, a, plot_config.theme)
+                .set(Label(data.x_label.clone()))
+                .set(axis_scale.to_gnuplot());
+            match data.x_tics {
+                Some(ref tics) => a.set(TicLabels {
+                    positions: tics.iter().map(|&(position, _)| position),
+                    labels: tics.iter().map(|&(_, ref label)| label.as_str()),
+                }),
+                None => a.set(ScaleFactor(data.x_scale)),
+            };
+            if let Some((lower, upper)) = data.x_range {
+                a.set(Range::Limits(lower * data.x_scale, upper * data.x_scale));
+            }
+            a
+        })
+        .configure(Axis::LeftY, |a| {
+            plot_config
+                .violin_grid
+                .apply(Axis::LeftY, a, plot_config.theme)
+                .set(Label("Input"))
+                .set(Range::Limits(0., data.violins.len() as f64))
+                .set(TicLabels {
+                    positions: tics(),
+                    labels: data.violins
+                        .iter()
+                        .map(|violin| escape_underscores(&violin.label)),
+                })
+        });
+
+    let colors = match plot_config.palette {
+        Some(_) => curve_colors(plot_config, data.violins.len()),
+        None => vec![plot_config.theme.primary_color(); data.violins.len()],
+    };
+    let mut is_first = true;
+    // The outliers are only labeled where there are some, which may not be in the first violin
+    let (mut mild_labeled, mut severe_labeled) = (false, false);
+    for (i, (violin, &color)) in data.violins.iter().zip(&colors).enumerate() {
+        let i = i as f64 + 0.5;
+        let y1 = violin.ys.iter().map(|&y| i + y * 0.5);
+        let y2 = violin.ys.iter().map(|&y| i - y * 0.5);
+
+        f.plot(
+            FilledCurve {
+                x: &violin.xs,
+                y1,
+                y2,
+            },
+            |c| {
+                if is_first {
+                    is_first = false;
+
+                    fill_style.apply(c.set(color).set(Label("PDF")))
+                } else {
+                    fill_style.apply(c.set(color))
+                }
+            },
+        );
+
+        if plot_config.violin_markers {
+            // Ticks across the band of the violin, short of the neighboring bands
+            let band = [i - 0.45, i + 0.45];
+            let is_first = i == 0.5;
+
+            f.plot(
+                Lines {
+                    x: &[violin.mean, violin.mean],
+                    y: &band,
+                },
+                |c| {
+                    if is_first {
+                        c.set(Label("Mean"));
+                    }
+                    c.set(DARK_RED).set(LINEWIDTH).set(LineType::Solid)
+                },
+            ).plot(
+                Lines {
+                    x: &[violin.median, violin.median],
+                    y: &band,
+                },
+                |c| {
+                    if is_first {
+                        c.set(Label("Median"));
+                    }
+                    c.set(DARK_ORANGE).set(LINEWIDTH).set(LineType::Dash)
+                },
+            );
+        }
+
+        // Along the center line of the violin
+        let is_first = i == 0.5;
+        if plot_config.violin_quantiles == ViolinQuantiles::Quartiles {
+            let (q1, q3) = violin.quartiles;
+            f.plot(Lines { x: &[q1, q3], y: &[i, i] }, |c| {
+                if is_first {
+                    c.set(Label("Quartiles"));
+                }
+                c.set(DARK_ORANGE).set(LINEWIDTH).set(LineType::Solid)
+            });
+        }
+        if plot_config.violin_quantiles != ViolinQuantiles::None {
+            f.plot(
+                Points {
+                    x: &[violin.median],
+                    y: &[i],
+                },
+                |p| {
+                    // The median ticks have the label already
+                    if is_first && !plot_config.violin_markers {
+                        p.set(Label("Median"));
+                    }
+                    p.set(PointType::FilledCircle)
+                        .set(POINT_SIZE)
+                        .set(DARK_ORANGE)
+                },
+            );
+        }
+        if plot_config.violin_outliers {
+            let mut plot_outliers = |xs: &[f64], label, color, labeled: &mut bool| {
+                if xs.is_empty() {
+                    return;
+                }
+                let ys = vec![i; xs.len()];
+                f.plot(Points { x: xs, y: &ys }, |p| {
+                    if !*labeled {
+                        *labeled = true;
+                        p.set(Label(label));
+                    }
+                    // Hollow, unlike the median
+                    p.set(PointType::Circle).set(POINT_SIZE).set(color)
+                });
+            };
+            let (mild, severe) = (&violin.mild_outliers, &violin.severe_outliers);
+            plot_outliers(mild, "Mild outliers", DARK_ORANGE, &mut mild_labeled);
+            plot_outliers(severe, "Severe outliers", DARK_RED, &mut severe_labeled);
+        }
+    }
+    f
+}
+
+/// The data shown in a violin plot comparing each benchmark against its baseline, independent of
+/// how it is rendered.
+#[derive(Debug, Serialize)]
+pub struct ViolinComparisonData {
+    pub title: String,
+    pub x_label: String,
+    pub x_scale: f64,
+    /// One band per benchmark with a baseline, from the bottom of the plot to the top.
+    pub violins: Vec<ViolinComparison>,
+    /// Number of non-finite measurements which were left out of the plot.
+    pub dropped_values: usize,
+}
+
+/// The estimated probability densities of the current and baseline measurements of one benchmark,
+/// evaluated on the same points and normalized so that the higher of the two peaks is at 1. A
+/// benchmark which was only measured in one of the runs only has the density of that run.
+#[derive(Debug, Serialize)]
+pub struct ViolinComparison {
+    pub label: String,
+    pub xs: Vec<f64>,
+    pub current: Option<Vec<f64>>,
+    pub baseline: Option<Vec<f64>>,
+}
+
+pub fn violin_comparison(
+    group_id: &str,
+    current_curves: &[&(BenchmarkId, Vec<f64>)],
+    baseline_curves: &[&(BenchmarkId, Vec<f64>)],
+    path: &str,
+    plot_config: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(&path);
+    let data = violin_comparison_data(group_id, current_curves, baseline_curves, plot_config);
+    let mut f = violin_comparison_figure(&data, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
+    debug_script(&path, &f);
+    f.set(Output(path)).draw().unwrap()
+}
+
+pub fn violin_comparison_data(
+    group_id: &str,
+    current_curves: &[&(BenchmarkId, Vec<f64>)],
+    baseline_curves: &[&(BenchmarkId, Vec<f64>)],
+    plot_config: &PlotConfiguration,
+) -> ViolinComparisonData {
+    let plot = "violin comparison";
+    let (mut current_curves, dropped_current) = finite_curves(group_id, plot, current_curves);
+    let (baseline_curves, dropped_baseline) = finite_curves(group_id, plot, baseline_curves);
+    let dropped_values = dropped_current + dropped_baseline;
+    let npoints = kde_points(current_curves.len(), plot_config);
+    sort_violins(&mut current_curves, plot_config.violin_order);
+
+    // The benchmarks which only have a baseline are listed after those of the current run
+    let baseline_of = |id: &BenchmarkId| {
+        baseline_curves
+            .iter()
+            .find(|&&(ref base_id, _)| base_id.id() == id.id())
+            .map(|&(_, ref baseline)| baseline)
+    };
+    let mut rows: Vec<_> = current_curves
+        .iter()
+        .map(|&(ref id, ref current)| (id, Some(current), baseline_of(id)))
+        .collect();
+    rows.extend(
+        baseline_curves
+            .iter()
+            .filter(|&&(ref id, _)| !current_curves.iter().any(|&(ref cur, _)| cur.id() == id.id()))
+            .map(|&(ref id, ref baseline)| (id, None, Some(baseline))),
+    );
+
+    let violins = rows
+        .iter()
+        .rev()
+        .map(|&(id, current, baseline)| {
+            let current = current.map(|current| Sample::new(current));
+            let baseline = baseline.map(|baseline| Sample::new(baseline));
+
+            // Both densities are evaluated on a grid spanning both samples
+            let (kernel, bandwidth) = (plot_config.kde_kernel, plot_config.kde_bandwidth);
+            let range = current
+                .iter()
+                .chain(baseline.iter())
+                .map(|sample| kde::range(sample, bandwidth))
+                .fold((::std::f64::INFINITY, ::std::f64::NEG_INFINITY), |(start, end), range| {
+                    (start.min(range.0), end.max(range.1))
+                });
+            let sweep = |sample| {
+                kde::sweep_with_bandwidth(sample, npoints, Some(range), bandwidth, kernel)
+            };
+            let (current, baseline) = (current.map(sweep), baseline.map(sweep));
+            // Every row has at least one of the samples
+            let xs = current.iter().chain(baseline.iter()).next().unwrap().0.to_vec();
+            let mut current = current.map(|(_, ys)| ys.into_vec());
+            let mut baseline = baseline.map(|(_, ys)| ys.into_vec());
+
+            // As in `violin_data`, a sample without spread has no density to normalize, so its
+            // half is drawn as a flat line instead.
+            for ys in current.iter_mut().chain(baseline.iter_mut()) {
+                if ys.iter().any(|y| !y.is_finite()) {
+                    warn!(
+                        "{}: the samples of {} have no spread; its violin is a flat line",
+                        group_id,
+                        id.id()
+                    );
+                    for y in ys.iter_mut() {
+                        *y = 0.;
+                    }
+                }
+            }
+            let y_max = current
+                .iter()
+                .chain(baseline.iter())
+                .flat_map(|ys| ys.iter().cloned())
+                .fold(0., f64::max);
+            if y_max > 0. && y_max.is_finite() {
+                for ys in current.iter_mut().chain(baseline.iter_mut()) {
+                    for y in ys.iter_mut() {
+                        *y /= y_max;
+                    }
+                }
+            }
+
+            ViolinComparison {
+                label: id.id().to_owned(),
+                xs,
+                current,
+                baseline,
+            }
+        })
+        .collect::<Vec<_>>();
+    let max = violins
+        .iter()
+        .flat_map(|violin| violin.xs.iter())
+        .cloned()
+        .fold(0., f64::max);
+    let samples = current_curves.iter().map(|&(_, ref data)| data);
+    let anchor = unit_anchor(plot_config, max, samples);
+    let (scale, x_label) = plot_config.unit.scale("Average", anchor);
+
+    ViolinComparisonData {
+        title: format!("{}: Violin plot comparison", group_id),
+        x_label,
+        x_scale: scale,
+        violins,
+        dropped_values,
+    }
+}
+
+fn violin_comparison_figure(data: &ViolinComparisonData, plot_config: &PlotConfiguration) -> Figure {
+    let axis_scale = plot_config.summary_scale;
+    let fill_style = plot_config.background.legible(plot_config.fill_style);
+    let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
+    let size = rows_size(plot_config, data.violins.len());
+    let mut f = Figure::new();
+    plot_config.background.apply(&mut f);
+    plot_config.theme.apply(&mut f);
+    f.set(Font(DEFAULT_FONT))
+        .set(size)
+        .set(Title(escape_underscores(&data.title)))
+        .configure(Axis::BottomX, |a| {
+            plot_config
+                .violin_grid
+                .apply(Axis::BottomX, a, plot_config.theme)
+                .set(Label(data.x_label.clone()))
+                .set(axis_scale.to_gnuplot())
+                .set(ScaleFactor(data.x_scale))
+        })
+        .configure(Axis::LeftY, |a| {
+            plot_config
+                .violin_grid
+                .apply(Axis::LeftY, a, plot_config.theme)
+                .set(Label("Input"))
+                .set(Range::Limits(0., data.violins.len() as f64))
+                .set(TicLabels {
+                    positions: tics(),
+                    labels: data.violins
+                        .iter()
+                        .map(|violin| escape_underscores(&violin.label)),
+                })
+        })
+        .configure(Key, |k| {
+            k.set(Justification::Left)
+                .set(Order::SampleText)
+                .set(Position::Outside(Vertical::Top, Horizontal::Right))
+        });
+
+    // The current measurements fill the upper half of each band, the baseline the lower half. The
+    // halves are labeled where they first appear, which may not be in the first band.
+    let (mut current_labeled, mut baseline_labeled) = (false, false);
+    for (i, violin) in data.violins.iter().enumerate() {
+        let i = i as f64 + 0.5;
+
+        if let Some(ref current) = violin.current {
+            f.plot(
+                FilledCurve {
+                    x: &violin.xs,
+                    y1: current.iter().map(|&y| i + y * 0.5),
+                    y2: current.iter().map(|_| i),
+                },
+                |c| {
+                    if !current_labeled {
+                        current_labeled = true;
+                        c.set(Label("Current"));
+                    }
+                    fill_style.apply(c.set(plot_config.theme.primary_color()))
+                },
+            );
+        }
+        if let Some(ref baseline) = violin.baseline {
+            f.plot(
+                FilledCurve {
+                    x: &violin.xs,
+                    y1: baseline.iter().map(|_| i),
+                    y2: baseline.iter().map(|&y| i - y * 0.5),
+                },
+                |c| {
+                    if !baseline_labeled {
+                        baseline_labeled = true;
+                        c.set(Label("Baseline"));
+                    }
+                    fill_style.apply(c.set(DARK_RED))
+                },
+            );
+        }
+    }
+    f
+}
+
+/// The change of the mean time of one benchmark against its baseline.
+#[derive(Debug)]
+pub(crate) struct Change {
+    pub label: String,
+    /// Relative change, eg. 0.05 if the benchmark got 5% slower
+    pub mean: f64,
+    pub verdict: ComparisonResult,
+}
+
+impl ChangeHighlighting {
+    // Classifies a change as one of the verdicts, using the thresholds where configured.
+    fn classify(&self, change: &Change) -> ComparisonResult {
+        let improved = match self.improvement_threshold {
+            Some(threshold) => change.mean < -threshold,
+            None => change.verdict == ComparisonResult::Improved,
+        };
+        let regressed = match self.regression_threshold {
+            Some(threshold) => change.mean > threshold,
+            None => change.verdict == ComparisonResult::Regressed,
+        };
+
+        if regressed {
+            ComparisonResult::Regressed
+        } else if improved {
+            ComparisonResult::Improved
+        } else {
+            ComparisonResult::NonSignificant
+        }
+    }
+
+    fn color(&self, verdict: ComparisonResult) -> Color {
+        let (r, g, b) = match verdict {
+            ComparisonResult::Improved => self.improvement_color,
+            ComparisonResult::Regressed => self.regression_color,
+            ComparisonResult::NonSignificant => self.noise_color,
+        };
+        Color::Rgb(r, g, b)
+    }
+}
+
+pub(crate) fn change_overview(
+    group_id: &str,
+    changes: &[Change],
+    path: &str,
+    plot_config: &PlotConfiguration,
+) -> Child {
+    let path = PathBuf::from(&path);
+    let mut f = change_overview_figure(group_id, changes, plot_config);
+
+    f.set(output_terminal(plot_config, &path));
+    debug_script(&path, &f);
+    f.set(Output(path)).draw().unwrap()
+}
+
+fn change_overview_figure(
+    group_id: &str,
+    changes: &[Change],
+    plot_config: &PlotConfiguration,
+) -> Figure {
+    let highlighting = &plot_config.change_highlighting;
+    let fill_style = plot_config.background.legible(plot_config.fill_style);
+    let tics = || (0..).map(|x| (f64::from(x)) + 0.5);
+    let size = rows_size(plot_config, changes.len());
+    let mut f = Figure::new();
+    plot_config.background.apply(&mut f);
+    plot_config.theme.apply(&mut f);
+    f.set(Font(DEFAULT_FONT))
+        .set(size)
+        .set(Title(escape_underscores(&format!(
+            "{}: Change overview",
+            group_id
+        ))))
+        .configure(Axis::BottomX, |a| {
+            a.configure(Grid::Major, |g| plot_config.theme.grid(g.show()))
+                .set(Label("Change in mean time (%)"))
+        })
+        .configure(Axis::LeftY, |a| {
+            a.set(Label("Benchmark"))
+                .set(Range::Limits(0., changes.len() as f64))
+                .set(TicLabels {
+                    positions: tics(),
+                    labels: changes
+                        .iter()
+                        .map(|change| escape_underscores(&change.label)),
+                })
+        })
+        .configure(Key, |k| {
+            k.set(Justification::Left)
+                .set(Order::SampleText)
+                .set(Position::Outside(Vertical::Top, Horizontal::Right))
+        });
+
+    // Each verdict is labeled in the legend the first time it appears
+    let mut labeled = vec![];
+    for (i, change) in changes.iter().enumerate() {
+        let i = i as f64 + 0.5;
+        let verdict = highlighting.classify(change);
+        let label = if labeled.contains(&verdict) {
+            None
+        } else {
+            labeled.push(verdict);
+            Some(match verdict {
+                ComparisonResult::Improved => "Improved",
+                ComparisonResult::Regressed => "Regressed",
+                ComparisonResult::NonSignificant => "No change",
+            })
+        };
+
+        f.plot(
+            FilledCurve {
+                x: &[0., change.mean * 100.],
+                y1: &[i + 0.35, i + 0.35],
+                y2: &[i - 0.35, i - 0.35],
+            },
+            |c| {
+                if let Some(label) = label {
+                    c.set(Label(label));
+                }
+                fill_style.apply(c.set(highlighting.color(verdict)))
+            },
+        );
+    }
+    f
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use plot::script;
+    use {FillStyle, KdeBandwidth, MeasuredUnit, PlotBackground, UnitAnchor, ViolinQuantiles,
+         ViolinShape};
+    use {GridConfig, Legend, LineSelection, LegendCorner, LegendPosition, Theme};
+
+    fn curves(throughput: bool) -> Vec<(BenchmarkId, Vec<f64>)> {
+        let mut curves = vec![];
+        for function in &["a", "b"] {
+            for &size in &[1024u32, 2048] {
+                let id = BenchmarkId::new(
+                    "group".to_owned(),
+                    Some((*function).to_owned()),
+                    Some(size.to_string()),
+                    if throughput {
+                        Some(Throughput::Bytes(size))
+                    } else {
+                        None
+                    },
+                );
+                curves.push((id, vec![f64::from(size), f64::from(size) * 1.5]));
+            }
+        }
+        curves
+    }
+
+    #[test]
+    fn hold_axes_fixed_splits_product() {
+        let mut product = vec![];
+        for &size in &[1024, 2048, 4096] {
+            for &threads in &[1, 4] {
+                let id = BenchmarkId::new_product(
+                    "group".to_owned(),
+                    None,
+                    vec![size.to_string(), threads.to_string()],
+                    None,
+                );
+                product.push((id, vec![f64::from(size / threads)]));
+            }
+        }
+        let refs: Vec<_> = product.iter().collect();
+
+        let by_threads = hold_axes_fixed(&refs, 1);
+        let fixed: Vec<&str> = by_threads.iter().map(|&(ref f, _)| f.as_str()).collect();
+        assert_eq!(vec!["1024/*", "2048/*", "4096/*"], fixed);
+        let values: Vec<_> = by_threads[1]
+            .1
+            .iter()
+            .map(|&(ref id, ref sample)| (id.value_str.clone().unwrap(), sample[0]))
+            .collect();
+        assert_eq!(vec![("1".to_owned(), 2048.), ("4".to_owned(), 512.)], values);
+
+        let by_size = hold_axes_fixed(&refs, 0);
+        assert_eq!(2, by_size.len());
+        assert_eq!("*/4", by_size[1].0);
+        assert!(by_size[1].1.iter().all(|&(ref id, _)| id.as_number().is_some()));
+
+        // Benchmarks of a single parameter have no axes to hold fixed.
+        let plain = curves(false);
+        let refs: Vec<_> = plain.iter().collect();
+        assert!(hold_axes_fixed(&refs, 0).is_empty());
+    }
+
+    #[test]
+    fn line_comparison_throughput_axis() {
+        let curves = curves(true);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().throughput_axis(true);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let mut f = line_comparison_figure(&data, &config);
+        let script = script(&mut f);
+
+        assert!(script.contains("set ytics"));
+        assert!(script.contains("set y2tics"));
+        assert!(script.contains("set y2label 'Throughput (MiB/s)'"));
+        assert_eq!(script.matches("axes x1y2").count(), 4);
+        assert!(script.contains("title 'a (throughput)'"));
+        assert!(script.contains("title 'b (throughput)'"));
+    }
+
+    #[test]
+    fn line_throughput() {
+        let mut curves = curves(true);
+        curves[1].1 = vec![0., 0.];
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default()
+            .line_throughput(true)
+            .throughput_axis(true)
+            .summary_range(0., 1.);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        // The zero times of "a" at 2048 bytes have no throughput.
+        assert_eq!(vec![1024.], data.series[0].xs);
+        assert_eq!(vec![8e8, 8e8], data.series[1].ys);
+        assert_eq!("Throughput (MiB/s)", data.y_label);
+        assert!(data.throughput_axis.is_none());
+        assert_eq!(None, data.y_range);
+        assert_eq!(0, data.clipped_values);
+
+        let script = script(&mut line_comparison_figure(&data, &config));
+        assert!(script.contains("set ylabel 'Throughput (MiB/s)'"));
+        assert!(!script.contains("set y2tics"));
+
+        // Without a throughput to divide by, the times are plotted.
+        let curves = self::curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().line_throughput(true);
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        assert_eq!("Average time (us)", data.y_label);
+    }
+
+    #[test]
+    fn line_aspect_ratio() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+
+        let config = PlotConfiguration::default();
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        assert!(script(&mut line_comparison_figure(&data, &config)).contains(" size 1280, 720"));
+
+        let config = PlotConfiguration::default().line_aspect_ratio(3.2);
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        assert!(script(&mut line_comparison_figure(&data, &config)).contains(" size 1280, 400"));
+    }
+
+    #[test]
+    fn line_legend() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let key = |legend: Legend| {
+            let config = PlotConfiguration::default().line_legend(legend);
+            let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+            let script = script(&mut line_comparison_figure(&data, &config));
+            script
+                .lines()
+                .find(|line| line.starts_with("set key"))
+                .unwrap()
+                .to_owned()
+        };
+
+        assert_eq!(
+            "set key on outside top right Left reverse ",
+            key(Legend::default())
+        );
+        let inside = Legend::default().position(LegendPosition::Inside(LegendCorner::BottomLeft));
+        assert!(key(inside).contains(" inside bottom left "));
+        let below = key(
+            Legend::default()
+                .position(LegendPosition::Below)
+                .font("Helvetica")
+                .font_size(8.),
+        );
+        assert!(below.contains(" outside bottom center horizontal Left reverse "));
+        assert!(below.ends_with(" font 'Helvetica,8' "));
+        assert_eq!(
+            "set key off",
+            key(Legend::default().position(LegendPosition::Hidden))
+        );
+    }
+
+    #[test]
+    fn grid_config() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let grids = |script: String| -> Vec<String> {
+            script
+                .lines()
+                .filter(|line| line.starts_with("set grid") || line.starts_with("set m"))
+                .map(|line| line.to_owned())
+                .collect()
+        };
+        let line = |config: &PlotConfiguration| {
+            let data = line_comparison_data("group", &refs, ValueType::Value, config, None);
+            grids(script(&mut line_comparison_figure(&data, config)))
+        };
+        let violin = |config: &PlotConfiguration| {
+            let data = violin_data("group", &refs, config);
+            grids(script(&mut violin_figure(&data, config)))
+        };
+
+        let config = PlotConfiguration::default();
+        assert_eq!(vec!["set grid ytics"], line(&config));
+        assert_eq!(vec!["set grid xtics"], violin(&config));
+
+        let dense = GridConfig::none().x(true, true).y(true, true);
+        let config = PlotConfiguration::default()
+            .line_grid(dense)
+            .violin_grid(dense);
+        let expected = vec![
+            "set grid xtics",
+            "set mxtics",
+            "set grid mxtics",
+            "set grid ytics",
+            "set mytics",
+            "set grid mytics",
+        ];
+        assert_eq!(expected, line(&config));
+        assert_eq!(expected, violin(&config));
+
+        let config = PlotConfiguration::default()
+            .line_grid(GridConfig::none())
+            .violin_grid(GridConfig::none());
+        assert!(line(&config).is_empty());
+        assert!(violin(&config).is_empty());
+    }
+
+    #[test]
+    fn line_highlighted() {
+        // Function `fN` takes `N` times as long at the largest input, but is the fastest at the
+        // smallest one for large `N`
+        let mut curves = vec![];
+        for n in 1..7 {
+            for &(size, time) in &[(1u32, 100. - f64::from(n)), (2, 100. * f64::from(n))] {
+                let id = BenchmarkId::new(
+                    "group".to_owned(),
+                    Some(format!("f{}", n)),
+                    Some(size.to_string()),
+                    Some(Throughput::Elements(1)),
+                );
+                curves.push((id, vec![time, time]));
+            }
+        }
+        let refs: Vec<_> = curves.iter().collect();
+        let plot = |config: PlotConfiguration| {
+            let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+            let highlighted: Vec<String> = data
+                .series
+                .iter()
+                .filter(|series| !series.other)
+                .map(|series| series.function_id.clone().unwrap())
+                .collect();
+            let script = script(&mut line_comparison_figure(&data, &config));
+            // The titles of the curves, which make up the legend
+            let titles: Vec<String> = script
+                .lines()
+                .filter(|line| !line.starts_with("set "))
+                .flat_map(|line| line.split(" title '").skip(1))
+                .map(|title| title[..title.find('\'').unwrap()].to_owned())
+                .collect();
+            (highlighted, titles)
+        };
+
+        let (highlighted, titles) = plot(PlotConfiguration::default());
+        assert_eq!(6, highlighted.len());
+        assert_eq!(6, titles.len());
+
+        let (highlighted, titles) =
+            plot(PlotConfiguration::default().line_highlighted(3, LineSelection::Fastest));
+        assert_eq!(vec!["f1", "f2", "f3"], highlighted);
+        assert_eq!(vec!["f1", "f2", "f3", "others"], titles);
+
+        let (highlighted, _) =
+            plot(PlotConfiguration::default().line_highlighted(2, LineSelection::Slowest));
+        assert_eq!(vec!["f5", "f6"], highlighted);
+        let (highlighted, _) =
+            plot(PlotConfiguration::default().line_highlighted(1, LineSelection::Peak));
+        assert_eq!(vec!["f6"], highlighted);
+
+        // The fastest functions have the largest throughputs.
+        let config = PlotConfiguration::default()
+            .line_throughput(true)
+            .line_highlighted(2, LineSelection::Fastest);
+        assert_eq!(vec!["f1", "f2"], plot(config).0);
+
+        // Asking for more functions than there are leaves no others.
+        let (highlighted, titles) =
+            plot(PlotConfiguration::default().line_highlighted(10, LineSelection::Peak));
+        assert_eq!(6, highlighted.len());
+        assert!(!titles.contains(&"others".to_owned()));
+    }
+
+    #[test]
+    fn line_differences() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        // Both functions take the same time
+        assert_eq!(2, data.differences.len());
+        assert!(data.differences.iter().all(|d| d.change == 0.));
+
+        let curve = |function: &str, size: u32, time: f64| {
+            let id = BenchmarkId::new(
+                "group".to_owned(),
+                Some(function.to_owned()),
+                Some(size.to_string()),
+                None,
+            );
+            (id, vec![time, time])
+        };
+        let curves = vec![
+            curve("a", 1, 100.),
+            curve("a", 2, 200.),
+            curve("a", 3, 300.),
+            curve("b", 1, 112.5),
+            curve("b", 2, 150.),
+        ];
+        let refs: Vec<_> = curves.iter().collect();
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        let script = script(&mut line_comparison_figure(&data, &config));
+        let labels: Vec<_> = script
+            .lines()
+            .filter(|line| line.starts_with("set label"))
+            .collect();
+        // There is no label at the input which only one function was measured at.
+        assert_eq!(
+            vec![
+                "set label '+12.50%' at 1, 112.5 center offset 0, 1",
+                "set label '-25.00%' at 2, 200 center offset 0, 1",
+            ],
+            labels
+        );
+
+        let config = PlotConfiguration::default().line_differences(false);
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        assert!(data.differences.is_empty());
+
+        // Only groups of two functions are labeled.
+        let config = PlotConfiguration::default();
+        let curves = vec![curve("a", 1, 1.), curve("b", 1, 2.), curve("c", 1, 3.)];
+        let refs: Vec<_> = curves.iter().collect();
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        assert!(data.differences.is_empty());
+    }
+
+    #[test]
+    fn plot_size() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let sizes = |config: &PlotConfiguration| {
+            let data = line_comparison_data("group", &refs, ValueType::Value, config, None);
+            let line = script(&mut line_comparison_figure(&data, config));
+            let violin = script(&mut violin_figure(&violin_data("group", &refs, config), config));
+            (line, violin)
+        };
+
+        let (line, violin) = sizes(&PlotConfiguration::default());
+        assert!(line.contains(" size 1280, 720"));
+        assert!(violin.contains(" size 1280, 300"));
+
+        // A width alone keeps the aspect ratio and the growth with the number of benchmarks.
+        let (line, violin) = sizes(&PlotConfiguration::default().size(2560, None));
+        assert!(line.contains(" size 2560, 1440"));
+        assert!(violin.contains(" size 2560, 300"));
+        let config = PlotConfiguration::default()
+            .line_aspect_ratio(4.)
+            .size(2000, None);
+        assert!(sizes(&config).0.contains(" size 2000, 500"));
+
+        let (line, violin) = sizes(&config.size(800, Some(600)));
+        assert!(line.contains(" size 800, 600"));
+        assert!(violin.contains(" size 800, 600"));
+    }
+
+    #[test]
+    fn nan_inputs_sort_last() {
+        let mut curves = curves(false);
+        for &value in &["NaN", "512", "NaN"] {
+            let id = BenchmarkId::new(
+                "group".to_owned(),
+                Some("b".to_owned()),
+                Some(value.to_owned()),
+                None,
+            );
+            curves.push((id, vec![1.0, 2.0]));
+        }
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        let xs = &data.series[1].xs;
+        assert_eq!(vec![512., 1024., 2048.], xs[..3].to_vec());
+        assert!(xs[3..].iter().all(|x| x.is_nan()));
+        assert_eq!(vec![1.5, 1280., 2560.], data.series[1].ys[..3].to_vec());
+
+        assert_eq!(Ordering::Less, nan_last(1., ::std::f64::NAN));
+        assert_eq!(Ordering::Greater, nan_last(::std::f64::NAN, -1.));
+        assert_eq!(Ordering::Equal, nan_last(::std::f64::NAN, ::std::f64::NAN));
+    }
+
+    #[test]
+    fn relative_to_baseline() {
+        let mut curves = curves(false);
+        for &mut (_, ref mut sample) in curves.iter_mut().skip(2) {
+            for time in sample.iter_mut() {
+                *time /= 4.;
+            }
+        }
+        // The baseline wasn't measured at this input.
+        let id = BenchmarkId::new(
+            "group".to_owned(),
+            Some("b".to_owned()),
+            Some("4096".to_owned()),
+            None,
+        );
+        curves.push((id, vec![1.0, 2.0]));
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default()
+            .line_bands(true)
+            .summary_range(1000., 2000.);
+
+        let data =
+            relative_line_comparison_data("group", &refs, ValueType::Value, &config, "a").unwrap();
+        assert_eq!("Relative time (baseline = 1.0)", data.y_label);
+        assert_eq!(1.0, data.y_scale);
+        assert_eq!(None, data.y_range);
+        assert_eq!(0, data.clipped_values);
+        assert_eq!(vec![1024., 2048.], data.series[0].xs);
+        assert_eq!(vec![1., 1.], data.series[0].ys);
+        assert_eq!(vec![1024., 2048.], data.series[1].xs);
+        assert_eq!(vec![0.25, 0.25], data.series[1].ys);
+        let (ref lower, ref upper) = *data.series[1].band.as_ref().unwrap();
+        assert!(lower[0] < 0.25 && upper[0] > 0.25);
+
+        assert!(
+            relative_line_comparison_data("group", &refs, ValueType::Value, &config, "c").is_none()
+        );
+    }
+
+    #[test]
+    fn mixed_throughputs_are_normalized() {
+        // "a" counts 4-byte elements in bytes, "b" counts them directly.
+        let mut curves = curves(true);
+        for &mut (ref mut id, _) in curves.iter_mut().skip(2) {
+            let bytes = id.as_number().unwrap() as u32;
+            *id = BenchmarkId::new(
+                "group".to_owned(),
+                Some("b".to_owned()),
+                Some(bytes.to_string()),
+                Some(Throughput::Elements(bytes / 4)),
+            );
+        }
+        let refs: Vec<_> = curves.iter().collect();
+
+        let config = PlotConfiguration::default().throughput_axis(true);
+        assert_eq!(None, common_value_type(&refs, &config));
+
+        let config = config.bytes_per_element(4.);
+        let value_type = common_value_type(&refs, &config).unwrap();
+        assert_eq!(ValueType::Elements, value_type);
+        let data = line_comparison_data("group", &refs, value_type, &config, None);
+        assert_eq!(vec![256., 512.], data.series[0].xs);
+        assert_eq!(data.series[0].xs, data.series[1].xs);
+        assert_eq!(data.series[0].throughputs, data.series[1].throughputs);
+
+        let script = script(&mut line_comparison_figure(&data, &config));
+        assert!(script.contains("set xlabel 'Input Size (Elements)'"));
+        assert!(script.contains("Melem/s"));
+    }
+
+    #[test]
+    fn line_comparison_log_throughput_axis() {
+        let curves = curves(true);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default()
+            .throughput_axis(true)
+            .summary_scale(AxisScale::Logarithmic);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let mut f = line_comparison_figure(&data, &config);
+        let script = script(&mut f);
+
+        // The throughputs are all 8e8 B/s, between 1 MiB/s and 1 GiB/s.
+        assert!(script.contains("set logscale y2"));
+        assert!(script.contains("set y2label 'Throughput'"));
+        assert!(script.contains(&format!(
+            "set y2tics nomirror ('1 MiB/s' {}, '1 GiB/s' {})",
+            1024f64.powi(2),
+            1024f64.powi(3)
+        )));
+        assert!(!script.contains("MiB/s)"));
+
+        // Elements are labeled by decades.
+        let data = line_comparison_data("group", &refs, ValueType::Elements, &config, None);
+        let tics = data.throughput_axis.unwrap().tics.unwrap();
+        let labels: Vec<&str> = tics.iter().map(|&(_, ref label)| label.as_str()).collect();
+        assert_eq!(vec!["100 Melem/s", "1 Gelem/s"], labels);
+    }
+
+    #[test]
+    fn line_comparison_custom_x_ticks() {
+        let curves = curves(true);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let mut data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let default_tics = script(&mut line_comparison_figure(&data, &config));
+        assert!(!default_tics.contains("'1K'"));
+
+        data.x_tic_labels = Some(vec![(1024., "1K".to_owned()), (2048., "2K".to_owned())]);
+        let custom_tics = script(&mut line_comparison_figure(&data, &config));
+        assert!(custom_tics.contains("set xtics nomirror ('1K' 1024, '2K' 2048)"));
+    }
+
+    #[test]
+    fn line_comparison_throughput_axis_requires_throughput() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().throughput_axis(true);
+
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+        let mut f = line_comparison_figure(&data, &config);
+        let script = script(&mut f);
+
+        assert!(!script.contains("set y2tics"));
+        assert!(!script.contains("axes x1y2"));
+    }
+
+    #[test]
+    fn line_comparison_custom_x_order() {
+        fn version(id: &BenchmarkId) -> Vec<u32> {
+            id.value_str
+                .as_ref()
+                .unwrap()
+                .split('.')
+                .map(|part| part.parse().unwrap())
+                .collect()
+        }
+
+        let curves: Vec<_> = ["1.10.0", "1.2.0", "1.9.0"]
+            .iter()
+            .map(|v| {
+                let id = BenchmarkId::new(
+                    "group".to_owned(),
+                    Some("parse".to_owned()),
+                    Some((*v).to_owned()),
+                    None,
+                );
+                (id, vec![1.0, 2.0])
+            })
+            .collect();
+        let refs: Vec<_> = curves.iter().collect();
+        let by_version = |a: &BenchmarkId, b: &BenchmarkId| version(a).cmp(&version(b));
+
+        let config = PlotConfiguration::default();
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, Some(&by_version));
+        let mut f = line_comparison_figure(&data, &config);
+        let script = script(&mut f);
+
+        assert!(script.contains("set xtics nomirror ('1.2.0' 1, '1.9.0' 2, '1.10.0' 3)"));
+    }
+
+    #[test]
+    fn multi_group_comparison_series() {
+        let group_a: Vec<_> = curves(false)
+            .into_iter()
+            .map(|(id, data)| {
+                let id = BenchmarkId::new(
+                    "group_a".to_owned(),
+                    id.function_id,
+                    id.value_str,
+                    None,
+                );
+                (id, data)
+            })
+            .collect();
+        let group_b = curves(false);
+        let refs_a: Vec<_> = group_a.iter().collect();
+        let refs_b: Vec<_> = group_b.iter().collect();
+
+        let config = PlotConfiguration::default();
+        let mut f = multi_group_comparison_figure("group", &[&refs_a, &refs_b], &config);
+        let both = script(&mut f);
+
+        let red = "lc rgb '#b22222'";
+        let green = "lc rgb '#2e8b57'";
+        assert!(both.contains(&format!("lt 1 lw 2 {} title 'group\\_a/a'", red)));
+        assert!(both.contains(&format!("lt 1 lw 2 {} title 'group\\_a/b'", green)));
+        assert!(both.contains(&format!("lt 2 lw 2 {} title 'group/a'", red)));
+        assert!(both.contains(&format!("lt 2 lw 2 {} title 'group/b'", green)));
+        assert!(both.contains("set ylabel 'Average time (us)'"));
+
+        // A benchmark without finite times is left out, and doesn't break the scaling of the others
+        let group_c: Vec<_> = curves(false)
+            .into_iter()
+            .map(|(id, data)| {
+                let data = if id.function_id == Some("a".to_owned()) {
+                    vec![::std::f64::NAN; data.len()]
+                } else {
+                    data
+                };
+                let id = BenchmarkId::new("group_c".to_owned(), id.function_id, id.value_str, None);
+                (id, data)
+            })
+            .collect();
+        let refs_c: Vec<_> = group_c.iter().collect();
+        let mut f = multi_group_comparison_figure("group", &[&refs_b, &refs_c], &config);
+        let with_nan = script(&mut f);
+        assert!(!with_nan.contains("NaN"));
+        assert!(!with_nan.contains("title 'group\\_c/a'"));
+        assert!(with_nan.contains("title 'group\\_c/b'"));
+        assert!(with_nan.contains("set ylabel 'Average time (us)'"));
+
+        // The palette and the size are configured like for the other summary plots
+        let config = PlotConfiguration::default()
+            .palette(vec![(0, 0, 255), (255, 0, 0)])
+            .size(800, Some(600));
+        let mut f = multi_group_comparison_figure("group", &[&refs_a, &refs_b], &config);
+        let configured = script(&mut f);
+        assert!(configured.contains("lc rgb '#0000ff' title 'group\\_a/a'"));
+        assert!(configured.contains("lc rgb '#ff0000' title 'group/b'"));
+        assert!(configured.contains("size 800, 600"));
+    }
+
+    #[test]
+    fn related_groups_share_a_prefix() {
+        let id = |group: &str, function: &str, input: &str| {
+            BenchmarkId::new(
+                group.to_owned(),
+                Some(function.to_owned()),
+                Some(input.to_owned()),
+                None,
+            )
+        };
+        let ids = vec![
+            id("sort/large", "quick", "100"),
+            id("sort/small", "merge", "10"),
+            id("sort/small", "quick", "10"),
+            id("sort/large", "merge", "100"),
+            id("sort/small", "merge", "20"),
+            // A group without relatives
+            id("search/linear", "find", "10"),
+            // Related, but with an input which isn't a number
+            id("parse/json", "serde", "10"),
+            id("parse/toml", "serde", "large"),
+            // Not in a set of groups
+            id("hash", "fnv", "10"),
+        ];
+
+        let related = related_groups(&ids);
+        assert_eq!(1, related.len());
+        let (ref prefix, ref groups) = related[0];
+        assert_eq!("sort", prefix);
+        let ids: Vec<Vec<&str>> = groups
+            .iter()
+            .map(|group| group.iter().map(|id| id.id()).collect())
+            .collect();
+        assert_eq!(
+            vec![
+                vec!["sort/large/merge/100", "sort/large/quick/100"],
+                vec![
+                    "sort/small/merge/10",
+                    "sort/small/merge/20",
+                    "sort/small/quick/10",
+                ],
+            ],
+            ids
+        );
+    }
+
+    #[test]
+    fn line_comparison_data_matches_series() {
+        let curves = curves(true);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().throughput_axis(true);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+
+        assert_eq!(2, data.series.len());
+        assert_eq!(Some("a".to_owned()), data.series[0].function_id);
+        assert_eq!(vec![1024.0, 2048.0], data.series[0].xs);
+        assert_eq!(vec![1280.0, 2560.0], data.series[0].ys);
+        assert_eq!(Some(vec![8e8, 8e8]), data.series[0].throughputs);
+        assert_eq!("Average time (us)", data.y_label);
+
+        let json = ::serde_json::to_value(&data).unwrap();
+        assert_eq!(Some(2560.0), json["series"][1]["ys"][1].as_f64());
+    }
+
+    #[test]
+    fn line_trimmed_mean_ignores_outliers() {
+        let mut curves = curves(false);
+        for &mut (_, ref mut sample) in &mut curves {
+            *sample = vec![100., 100., 90., 100., 1e6];
+        }
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().line_trimmed_mean(0.2);
+
+        let data = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+
+        assert_eq!(vec![100.0, 100.0], data.series[0].ys);
+        assert_eq!("Average time (ns)", data.y_label);
+    }
+
+    #[test]
+    fn violin_of_constant_samples() {
+        let id = BenchmarkId::new("group".to_owned(), Some("constant".to_owned()), None, None);
+        let constant = (id, vec![5.0; 10]);
+
+        let data = violin_data("group", &[&constant], &PlotConfiguration::default());
+        assert!(data.violins[0].xs.iter().all(|&x| x == 5.0));
+        assert!(data.violins[0].ys.iter().all(|&y| y == 0.0));
+        assert_eq!(5.0, data.violins[0].median);
+        script(&mut violin_figure(&data, &PlotConfiguration::default()));
+
+        // Without positive times, the axes still get a range to span.
+        let id = BenchmarkId::new("group".to_owned(), Some("zero".to_owned()), None, None);
+        let zero = (id, vec![0.0; 10]);
+        let config = PlotConfiguration::default().summary_scale(AxisScale::Logarithmic);
+        let data = violin_data("group", &[&zero, &constant], &config);
+        assert_eq!(2, data.violins.len());
+        assert!(!data.x_tics.unwrap().is_empty());
+        let data = violin_data("group", &[&zero], &config);
+        script(&mut violin_figure(&data, &config));
+
+        // Nor without any samples left to plot.
+        let id = BenchmarkId::new("group".to_owned(), Some("nan".to_owned()), None, None);
+        let nan = (id, vec![::std::f64::NAN; 10]);
+        let data = violin_data("group", &[&nan], &config);
+        assert!(data.violins.is_empty());
+        script(&mut violin_figure(&data, &config));
+    }
+
+    #[test]
+    fn violin_data_matches_series() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+
+        // The violins are plotted from the bottom up, in reverse order.
+        let labels: Vec<_> = data.violins.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(
+            vec!["group/b/2048", "group/b/1024", "group/a/2048", "group/a/1024"],
+            labels
+        );
+        for violin in &data.violins {
+            assert_eq!(KDE_POINTS, violin.xs.len());
+            assert_eq!(1.0, Sample::new(&violin.ys).max());
+        }
+
+        let json = ::serde_json::to_value(&data).unwrap();
+        assert_eq!(Some("group/b/2048"), json["violins"][0]["label"].as_str());
+    }
+
+    #[test]
+    fn violin_fill_style() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+
+        let config = PlotConfiguration::default();
+        let solid = script(&mut violin_figure(&data, &config));
+        assert!(solid.contains("fillstyle solid 0.25 "));
+
+        let config = PlotConfiguration::default().fill_style(FillStyle::Pattern(4));
+        let pattern = script(&mut violin_figure(&data, &config));
+        assert!(pattern.contains("fillstyle pattern 4 "));
+        assert!(!pattern.contains("fillstyle solid"));
+    }
+
+    #[test]
+    fn plot_background() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        let terminal = |config: &PlotConfiguration| {
+            let script = script(&mut violin_figure(&data, config));
+            script
+                .lines()
+                .find(|line| line.starts_with("set terminal"))
+                .unwrap()
+                .to_owned()
+        };
+
+        let config = PlotConfiguration::default();
+        assert!(terminal(&config).contains(" background rgb 'white'"));
+
+        let config = PlotConfiguration::default().background(PlotBackground::Transparent);
+        assert!(!terminal(&config).contains("background"));
+        // The translucent default fill would be lost on a dark page.
+        let transparent = script(&mut violin_figure(&data, &config));
+        assert!(transparent.contains("fillstyle solid 0.5 "));
+
+        let config = PlotConfiguration::default().background(PlotBackground::Rgb(30, 30, 30));
+        assert!(terminal(&config).contains(" background rgb '#1e1e1e'"));
+        let line_comparison = script(&mut line_comparison_figure(
+            &line_comparison_data("group", &refs, ValueType::Value, &config, None),
+            &config,
+        ));
+        assert!(line_comparison.contains(" background rgb '#1e1e1e'"));
+    }
+
+    #[test]
+    fn dark_theme() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let scripts = |config: &PlotConfiguration| {
+            let data = line_comparison_data("group", &refs, ValueType::Value, config, None);
+            let line = script(&mut line_comparison_figure(&data, config));
+            let violin = script(&mut violin_figure(&violin_data("group", &refs, config), config));
+            (line, violin)
+        };
+
+        let (line, violin) = scripts(&PlotConfiguration::default().theme(Theme::Dark));
+        for script in &[&line, &violin] {
+            assert!(script.contains(" background rgb '#1e1e1e'"));
+            assert!(script.contains("set border lc rgb '#dcdcdc'\n"));
+            assert!(script.contains("set xtics textcolor rgb '#dcdcdc'\n"));
+            assert!(script.contains(" lc rgb '#5a5a5a'\n"));
+        }
+        assert!(line.contains("set key textcolor rgb '#dcdcdc'\n"));
+        assert!(line.contains("lc rgb '#ff6b6b'"));
+        assert!(violin.contains("lc rgb '#64aae6'"));
+
+        // The light theme leaves gnuplot's colors alone.
+        let (line, violin) = scripts(&PlotConfiguration::default().theme(Theme::Light));
+        for script in &[&line, &violin] {
+            assert!(script.contains(" background rgb 'white'"));
+            assert!(!script.contains("textcolor"));
+            assert!(!script.contains("set border"));
+        }
+        assert!(line.contains("lc rgb '#b22222'"));
+    }
+
+    #[test]
+    fn cycles_are_scaled_with_si_prefixes() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let mut config = PlotConfiguration::default();
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!("Average time (us)", data.y_label);
+
+        config.unit = MeasuredUnit::Cycles;
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!("Average cycles (Kcycles)", data.y_label);
+        assert_eq!(1e-3, data.y_scale);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("set ylabel 'Average cycles (Kcycles)'"));
+
+        let violins = violin_data("group", &refs, &config);
+        assert_eq!("Average cycles (Kcycles)", violins.x_label);
+        assert_eq!(1e-3, violins.x_scale);
+    }
+
+    #[test]
+    fn logarithmic_violin_labels_decades() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+
+        let config = PlotConfiguration::default();
+        let linear = script(&mut violin_figure(&violin_data("group", &refs, &config), &config));
+        assert!(linear.contains("set xlabel 'Average time (us)'"));
+        assert!(!linear.contains("set xtics ("));
+
+        let config = config.summary_scale(AxisScale::Logarithmic);
+        let data = violin_data("group", &refs, &config);
+        assert_eq!(1.0, data.x_scale);
+        assert_eq!("Average time", data.x_label);
+        let labels: Vec<&str> = data
+            .x_tics
+            .iter()
+            .flat_map(|tics| tics.iter().map(|&(_, ref label)| label.as_str()))
+            .collect();
+        assert!(labels.contains(&"1 us") && labels.contains(&"10 us"));
+
+        let log = script(&mut violin_figure(&data, &config));
+        assert!(log.contains("set logscale x"));
+        assert!(log.contains("'1 us' 1000"));
+        assert!(!log.contains("(us)"));
+
+        let tics = MeasuredUnit::Nanoseconds.log_tics(1e-7, 2e9);
+        assert_eq!((1e-7, "0.1 fs".to_owned()), tics[0]);
+        assert_eq!((1e3, "1 us".to_owned()), tics[10]);
+        assert_eq!((1e10, "10 s".to_owned()), tics[17]);
+    }
+
+    fn hex(colors: &[Color]) -> Vec<String> {
+        colors
+            .iter()
+            .map(|color| match *color {
+                Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+                _ => panic!("not an RGB color"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn generated_palette_has_distinct_colors() {
+        let palette = hex(&generate_palette(15));
+        assert_eq!(15, palette.len());
+        for (i, a) in palette.iter().enumerate() {
+            assert!(palette[i + 1..].iter().all(|b| b != a));
+        }
+        assert_eq!("#bf3939", palette[0]);
+
+        let config = PlotConfiguration::default();
+        assert_eq!(hex(&COMPARISON_COLORS), hex(&curve_colors(&config, 3)));
+        assert_eq!(palette, hex(&curve_colors(&config, 15)));
+    }
+
+    #[test]
+    fn custom_palette() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().palette(vec![(1, 2, 3), (4, 5, 6)]);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("#010203") && line.contains("#040506"));
+
+        // There are more violins than colors, so each gets its own hue instead.
+        let violin = script(&mut violin_figure(&violin_data("group", &refs, &config), &config));
+        assert!(!violin.contains("#010203"));
+        for color in hex(&generate_palette(4)) {
+            assert!(violin.contains(&color));
+        }
+    }
+
+    #[test]
+    fn colorblind_safe_palette() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default().builtin_palette(Palette::ColorblindSafe);
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("#e69f00") && line.contains("#56b4e9"));
+        assert!(!line.contains(&hex(&COMPARISON_COLORS)[0]));
+
+        // Custom colors take precedence over the built-in palette.
+        let config = config.palette(vec![(1, 2, 3), (4, 5, 6)]);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("#010203") && !line.contains("#e69f00"));
+
+        let config = PlotConfiguration::default()
+            .builtin_palette(Palette::ColorblindSafe)
+            .theme(Theme::Dark);
+        assert_eq!("#ffffff", hex(&curve_colors(&config, NUM_COLORS))[7]);
+    }
+
+    #[test]
+    fn line_bands() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+
+        let config = PlotConfiguration::default();
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert!(data.series.iter().all(|series| series.band.is_none()));
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(!line.contains("filledcurves"));
+
+        let config = config.line_bands(true);
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        let (ref lower, ref upper) = *data.series[0].band.as_ref().unwrap();
+        assert_eq!(data.series[0].xs.len(), lower.len());
+        for ((&lower, &upper), &y) in lower.iter().zip(upper).zip(&data.series[0].ys) {
+            assert!(lower < y && y < upper);
+        }
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert_eq!(2, line.matches("filledcurves").count());
+        assert!(line.contains("fillstyle solid 0.25"));
+    }
+
+    #[test]
+    fn summary_range_clips_data() {
+        // The means are 1.28 and 2.56 us, for each of the two functions.
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!((None, 0), (data.y_range, data.clipped_values));
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(!line.contains("set yrange"));
+
+        // The limits are kept even though some of the data is beyond them, and they decide the
+        // unit of the axis.
+        let config = config.summary_range(0., 2e3);
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!(Some((0., 2e3)), data.y_range);
+        assert_eq!(2, data.clipped_values);
+        assert_eq!("Average time (us)", data.y_label);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("set yrange [0:2]"));
+
+        let config = config.summary_range(1e3, 1e6);
+        let violins = violin_data("group", &refs, &config);
+        assert_eq!(0, violins.clipped_values);
+        assert_eq!("Average time (ms)", violins.x_label);
+        let violin = script(&mut violin_figure(&violins, &config));
+        assert!(violin.contains("set xrange [0.001:1]"));
+    }
+
+    #[test]
+    fn mixed_inputs_skip_line_comparison() {
+        let mut curves = curves(false);
+        let id = BenchmarkId::new(
+            "group".to_owned(),
+            Some("c".to_owned()),
+            Some("large".to_owned()),
+            None,
+        );
+        curves.push((id, vec![4096., 6144.]));
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let path = "target/mixed_inputs_skip_line_comparison.svg";
+        let plot = line_comparison("group", &refs, path, ValueType::Value, &config, None, None);
+        assert!(plot.is_none());
+        assert!(!PathBuf::from(path).exists());
+
+        // The violin plot doesn't need the inputs to be numbers.
+        let violin = script(&mut violin_figure(&violin_data("group", &refs, &config), &config));
+        assert!(violin.contains("group/c/large"));
+    }
+
+    #[test]
+    fn non_finite_values_are_dropped() {
+        let mut curves = curves(false);
+        curves[0].1.push(::std::f64::NAN);
+        curves[3].1.push(::std::f64::INFINITY);
+        let refs: Vec<_> = curves.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!(2, data.dropped_values);
+        assert_eq!(vec![1280.0, 2560.0], data.series[0].ys);
+        assert_eq!("Average time (us)", data.y_label);
+        let line = script(&mut line_comparison_figure(&data, &config));
+        assert!(line.contains("plot "));
+
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        assert_eq!(2, data.dropped_values);
+        assert_eq!(4, data.violins.len());
+        let violin = script(&mut violin_figure(&data, &config));
+        assert!(violin.contains("plot "));
+
+        // A benchmark without enough finite values is left out, but the others are still plotted.
+        curves[1].1 = vec![::std::f64::NAN, ::std::f64::INFINITY, ::std::f64::NEG_INFINITY];
+        let refs: Vec<_> = curves.iter().collect();
+
+        let data = line_comparison_data("group", &refs, ValueType::Bytes, &config, None);
+        assert_eq!(5, data.dropped_values);
+        assert_eq!(vec![1024.0], data.series[0].xs);
+        assert_eq!(2, data.series[1].xs.len());
+        assert!(script(&mut line_comparison_figure(&data, &config)).contains("plot "));
+
+        let data = violin_data("group", &refs, &config);
+        assert_eq!(5, data.dropped_values);
+        assert_eq!(3, data.violins.len());
+        assert!(data.violins.iter().all(|violin| violin.label != "group/a/2048"));
+        assert!(script(&mut violin_figure(&data, &config)).contains("plot "));
+    }
+
+    #[test]
+    fn violin_markers() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        assert_eq!(1280.0, data.violins[3].mean);
+        assert_eq!(1280.0, data.violins[3].median);
+
+        let config = PlotConfiguration::default();
+        let plain = script(&mut violin_figure(&data, &config));
+        assert_eq!(0, plain.matches("with lines").count());
+
+        let config = PlotConfiguration::default().violin_markers(true);
+        let marked = script(&mut violin_figure(&data, &config));
+        assert_eq!(2 * data.violins.len(), marked.matches("with lines").count());
+        assert_eq!(1, marked.matches("title 'Mean'").count());
+        assert_eq!(1, marked.matches("title 'Median'").count());
+    }
+
+    #[test]
+    fn violin_quantiles() {
+        let curves = curves(false);
+        let refs: Vec<_> = curves.iter().collect();
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        let (q1, q3) = data.violins[3].quartiles;
+        assert!(q1 <= data.violins[3].median && data.violins[3].median <= q3);
+
+        let plot = |quantiles| {
+            let config = PlotConfiguration::default().violin_quantiles(quantiles);
+            script(&mut violin_figure(&data, &config))
+        };
+        let none = plot(ViolinQuantiles::None);
+        assert_eq!(0, none.matches("with points").count());
+        assert_eq!(0, none.matches("with lines").count());
+
+        let median = plot(ViolinQuantiles::Median);
+        assert_eq!(data.violins.len(), median.matches("with points").count());
+        assert_eq!(0, median.matches("with lines").count());
+        assert_eq!(1, median.matches("title 'Median'").count());
+
+        let quartiles = plot(ViolinQuantiles::Quartiles);
+        assert_eq!(data.violins.len(), quartiles.matches("with points").count());
+        assert_eq!(data.violins.len(), quartiles.matches("with lines").count());
+        assert_eq!(1, quartiles.matches("title 'Quartiles'").count());
+    }
+
+    #[test]
+    fn violin_outliers() {
+        let curve = |function: &str, extra: &[f64]| {
+            let id = BenchmarkId::new("group".to_owned(), Some(function.to_owned()), None, None);
+            let mut times: Vec<f64> = (1..21).map(f64::from).collect();
+            times.extend_from_slice(extra);
+            (id, times)
+        };
+        let curves = vec![
+            curve("erratic", &[40.0, 100.0, -40.0]),
+            curve("noisy", &[40.0]),
+            curve("steady", &[]),
+        ];
+        // The violins are listed from the bottom of the plot, so "steady" is plotted first.
+        let refs: Vec<_> = curves.iter().collect();
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        let outliers: Vec<_> = data
+            .violins
+            .iter()
+            .map(|v| (v.mild_outliers.clone(), v.severe_outliers.clone()))
+            .collect();
+        assert_eq!(
+            vec![
+                (vec![], vec![]),
+                (vec![40.0], vec![]),
+                (vec![40.0], vec![100.0, -40.0]),
+            ],
+            outliers
+        );
+
+        let plot = |enabled| {
+            let config = PlotConfiguration::default()
+                .violin_quantiles(ViolinQuantiles::None)
+                .violin_outliers(enabled);
+            script(&mut violin_figure(&data, &config))
+        };
+        assert_eq!(0, plot(false).matches("with points").count());
+
+        // The violins without outliers of a kind have no points of that kind, and each kind is
+        // labeled once even though the first violin has none.
+        let marked = plot(true);
+        assert_eq!(3, marked.matches("with points").count());
+        assert_eq!(1, marked.matches("title 'Mild outliers'").count());
+        assert_eq!(1, marked.matches("title 'Severe outliers'").count());
+    }
+
+    #[test]
+    fn kde_bandwidth() {
+        // Two modes, 30ns apart, of measurements spread over 8ns each
+        let mut bimodal = vec![];
+        for &mode in &[100., 130.] {
+            for &(offset, count) in &[(-4., 5), (-2., 10), (0., 20), (2., 10), (4., 5)] {
+                bimodal.extend(::std::iter::repeat(mode + offset).take(count));
+            }
+        }
+        let id = BenchmarkId::new("group".to_owned(), None, None, None);
+        let curves = vec![(id, bimodal.clone())];
+        let refs: Vec<_> = curves.iter().collect();
+
+        let local_maxima = |bandwidth| {
+            let config = PlotConfiguration::default().kde_bandwidth(bandwidth);
+            let ys = violin_data("group", &refs, &config).violins[0].ys.clone();
+            ys.windows(3).filter(|w| w[0] < w[1] && w[1] >= w[2]).count()
+        };
+        let silverman = KdeBandwidth::Silverman.estimate(&bimodal);
+        assert_eq!(silverman / 4., KdeBandwidth::Scaled(0.25).estimate(&bimodal));
+        let scott = KdeBandwidth::Scott.estimate(&bimodal);
+        // The factor of Silverman's rule goes through an `f32`, so the ratio is only close.
+        assert!((scott / silverman - 0.75f64.powf(0.2)).abs() < 1e-6);
+        assert_eq!(10., KdeBandwidth::Fixed(10.).estimate(&bimodal));
+
+        assert_eq!(2, local_maxima(KdeBandwidth::Silverman));
+        assert_eq!(1, local_maxima(KdeBandwidth::Scaled(3.)));
+        assert_eq!(1, local_maxima(KdeBandwidth::Fixed(30.)));
+        assert_eq!(2, local_maxima(KdeBandwidth::Fixed(3.)));
+    }
+
+    #[test]
+    fn adaptive_kde_points() {
+        fn points_per_violin(ncurves: usize) -> usize {
+            let curves: Vec<_> = (0..ncurves)
+                .map(|i| {
+                    let id =
+                        BenchmarkId::new("group".to_owned(), Some(format!("f{}", i)), None, None);
+                    (id, vec![1.0, 2.0, 3.0, 4.0])
+                })
+                .collect();
+            let refs: Vec<_> = curves.iter().collect();
+            let config = PlotConfiguration::default().adaptive_kde_points(true);
+
+            let data = violin_data("group", &refs, &config);
+            assert_eq!(ncurves, data.violins.len());
+            data.violins[0].xs.len()
+        }
+
+        let small = points_per_violin(4);
+        let large = points_per_violin(200);
+        assert_eq!(KDE_POINTS, small);
+        assert_eq!(MIN_KDE_POINTS, large);
+        assert!(points_per_violin(20) < small);
+    }
+
+    #[test]
+    fn violin_histogram() {
+        let curve = |function: &str, times: Vec<f64>| {
+            let id = BenchmarkId::new("group".to_owned(), Some(function.to_owned()), None, None);
+            (id, times)
+        };
+        // Times on the ticks of a clock, which the density estimate would smooth over
+        let ticks: Vec<f64> = (0..40).map(|i| f64::from(100 + 25 * (i % 4))).collect();
+        let curves = vec![curve("ticks", ticks), curve("constant", vec![10.0; 5])];
+        let refs: Vec<_> = curves.iter().collect();
+
+        let config = PlotConfiguration::default().violin_shape(ViolinShape::Histogram(6));
+        let data = violin_data("group", &refs, &config);
+        let ticks = &data.violins[1];
+        assert_eq!(2 * 6 + 2, ticks.xs.len());
+        assert_eq!(ticks.xs.len(), ticks.ys.len());
+        assert!(ticks.xs.windows(2).all(|pair| pair[0] <= pair[1]));
+        assert_eq!((100.0, 175.0), (ticks.xs[0], ticks.xs[ticks.xs.len() - 1]));
+        // The empty bins between the ticks show as gaps
+        assert_eq!(
+            vec![0., 1., 1., 0., 0., 1., 1., 0., 0., 1., 1., 1., 1., 0.],
+            ticks.ys
+        );
+        let constant = &data.violins[0];
+        assert!(constant.ys.iter().all(|&y| y == 0.));
+
+        let config = PlotConfiguration::default().violin_shape(ViolinShape::LogHistogram(3));
+        let data = violin_data("group", &refs, &config);
+        let ticks = &data.violins[1];
+        assert!((100. * 1.75f64.powf(1. / 3.) - ticks.xs[2]).abs() < 1e-9);
+    }
+
+    #[test]
+    #[should_panic]
+    fn violin_histogram_needs_bins() {
+        PlotConfiguration::default().violin_shape(ViolinShape::Histogram(0));
+    }
+
+    #[test]
+    fn unit_anchor() {
+        let curve = |function: &str, time: f64| {
+            let id = BenchmarkId::new(
+                "group".to_owned(),
+                Some(function.to_owned()),
+                Some("1".to_owned()),
+                None,
+            );
+            (id, vec![time * 0.9, time, time * 1.1])
+        };
+        // One slow benchmark among fast ones
+        let curves = vec![
+            curve("a", 2e3),
+            curve("b", 3e3),
+            curve("c", 5e3),
+            curve("slow", 1.2e6),
+        ];
+        let refs: Vec<_> = curves.iter().collect();
+        let labels = |anchor: UnitAnchor| {
+            let config = PlotConfiguration::default().unit_anchor(anchor);
+            let line = line_comparison_data("group", &refs, ValueType::Value, &config, None);
+            let violin = violin_data("group", &refs, &config);
+            let comparison = violin_comparison_data("group", &refs, &refs, &config);
+            (line.y_label, violin.x_label, comparison.x_label)
+        };
+
+        let ms = "Average time (ms)".to_owned();
+        assert_eq!((ms.clone(), ms.clone(), ms), labels(UnitAnchor::Max));
+        let us = "Average time (us)".to_owned();
+        assert_eq!(
+            (us.clone(), us.clone(), us.clone()),
+            labels(UnitAnchor::MedianOfMedians)
+        );
+        assert_eq!(
+            (us.clone(), us.clone(), us),
+            labels(UnitAnchor::Value(1e3))
+        );
+    }
+
+    #[test]
+    fn violin_range_quantiles() {
+        let curve = |function: &str, extreme: f64| {
+            let id = BenchmarkId::new("group".to_owned(), Some(function.to_owned()), None, None);
+            let mut times: Vec<f64> = (0..99).map(|i| 1000. + f64::from(i)).collect();
+            times.push(extreme);
+            (id, times)
+        };
+        // One extreme time in each benchmark
+        let curves = vec![curve("a", 1e6), curve("b", 10.)];
+        let refs: Vec<_> = curves.iter().collect();
+
+        let data = violin_data("group", &refs, &PlotConfiguration::default());
+        assert_eq!(None, data.x_range);
+        assert_eq!("Average time (ms)", data.x_label);
+
+        let config = PlotConfiguration::default().violin_range_quantiles(0.05, 0.95);
+        let data = violin_data("group", &refs, &config);
+        let (lower, upper) = data.x_range.unwrap();
+        let sample = Sample::new(&curves[1].1);
+        assert_eq!(sample.quantile(0.05), lower);
+        assert!(lower > 1000. && upper < 1100.);
+        assert_eq!("Average time (us)", data.x_label);
+        let script = script(&mut violin_figure(&data, &config));
+        let (lower, upper) = (lower * data.x_scale, upper * data.x_scale);
+        assert!(script.contains(&format!("set xrange [{}:{}]", lower, upper)));
+
+        // Fixed limits take precedence.
+        let config = config.summary_range(1., 2e6);
+        assert_eq!(Some((1., 2e6)), violin_data("group", &refs, &config).x_range);
+    }
+
+    #[test]
+    fn violin_order() {
+        let curve = |function: &str, times: Vec<f64>| {
+            let id = BenchmarkId::new("group".to_owned(), Some(function.to_owned()), None, None);
+            (id, times)
+        };
+        let curves = vec![
+            curve("slow", vec![30.0, 31.0, 29.0]),
+            curve("fast", vec![10.0, 11.0, 9.0]),
+            curve("mid", vec![20.0, 21.0, 19.0]),
+        ];
+        let refs: Vec<_> = curves.iter().collect();
+        // The labels of the violins from the bottom of the plot to the top, in the data and in
+        // the tics of the plot.
+        let labels = |order: ViolinOrder| {
+            let config = PlotConfiguration::default().violin_order(order);
+            let data = violin_data("group", &refs, &config);
+            let labels: Vec<String> = data.violins.iter().map(|v| v.label.clone()).collect();
+
+            let script = script(&mut violin_figure(&data, &config));
+            let tics = script.lines().find(|line| line.starts_with("set ytics")).unwrap();
+            let positions: Vec<_> = labels.iter().map(|label| tics.find(label.as_str())).collect();
+            assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+
+            labels
+        };
+
+        assert_eq!(
+            vec!["group/mid", "group/fast", "group/slow"],
+            labels(ViolinOrder::AsGiven)
+        );
+        assert_eq!(
+            vec!["group/slow", "group/mid", "group/fast"],
+            labels(ViolinOrder::ByMedianAscending)
+        );
+        assert_eq!(
+            vec!["group/fast", "group/mid", "group/slow"],
+            labels(ViolinOrder::ByMedianDescending)
+        );
+
+        let config = PlotConfiguration::default().violin_order(ViolinOrder::ByMedianAscending);
+        let data = violin_comparison_data("group", &refs, &refs, &config);
+        let labels: Vec<_> = data.violins.iter().map(|v| v.label.as_str()).collect();
+        assert_eq!(vec!["group/slow", "group/mid", "group/fast"], labels);
+    }
+
+    #[test]
+    fn violin_comparison_of_constant_samples() {
+        let id = BenchmarkId::new("group".to_owned(), Some("constant".to_owned()), None, None);
+        let current = (id.clone(), vec![5.0; 10]);
+        let baseline = (id, vec![4.0; 10]);
+        let config = PlotConfiguration::default();
+
+        let data = violin_comparison_data("group", &[&current], &[&baseline], &config);
+        let violin = &data.violins[0];
+        assert!(violin.xs.iter().all(|x| x.is_finite()));
+        let densities = violin.current.iter().chain(violin.baseline.iter());
+        assert!(densities.flat_map(|ys| ys.iter()).all(|&y| y == 0.));
+        let script = script(&mut violin_comparison_figure(&data, &config));
+        assert!(!script.contains("NaN"));
+    }
+
+    #[test]
+    fn violin_comparison_halves() {
+        let current = curves(false);
+        // The baseline of each benchmark was a bit faster, one benchmark has no baseline, and one
+        // was only measured in the baseline.
+        let mut baseline: Vec<_> = current
+            .iter()
+            .skip(1)
+            .map(|&(ref id, ref sample)| (id.clone(), sample.iter().map(|x| x * 0.8).collect()))
+            .collect();
+        let removed = BenchmarkId::new("group".to_owned(), Some("c".to_owned()), None, None);
+        baseline.push((removed, vec![500., 600.]));
+        let current_refs: Vec<_> = current.iter().collect();
+        let baseline_refs: Vec<_> = baseline.iter().collect();
+        let config = PlotConfiguration::default();
+
+        let data = violin_comparison_data("group", &current_refs, &baseline_refs, &config);
+        let rows: Vec<_> = data
+            .violins
+            .iter()
+            .map(|v| (v.label.as_str(), v.current.is_some(), v.baseline.is_some()))
+            .collect();
+        assert_eq!(
+            vec![
+                ("group/c", false, true),
+                ("group/b/2048", true, true),
+                ("group/b/1024", true, true),
+                ("group/a/2048", true, true),
+                ("group/a/1024", true, false),
+            ],
+            rows
+        );
+        for violin in &data.violins {
+            assert_eq!(KDE_POINTS, violin.xs.len());
+            let densities: Vec<_> = violin.current.iter().chain(violin.baseline.iter()).collect();
+            assert!(densities.iter().all(|ys| ys.len() == KDE_POINTS));
+            let peak = densities
+                .iter()
+                .map(|ys| Sample::new(ys).max())
+                .fold(0., f64::max);
+            assert_eq!(1.0, peak);
+        }
+
+        let script = script(&mut violin_comparison_figure(&data, &config));
+        let blue = "lc rgb '#1f78b4'";
+        let red = "lc rgb '#e31a1c'";
+        assert_eq!(8, script.matches("with filledcurves").count());
+        assert_eq!(4, script.matches(blue).count());
+        assert_eq!(4, script.matches(red).count());
+        // The first row has no current half, which is labeled in the next one
+        assert_eq!(1, script.matches("title 'Current'").count());
+        assert_eq!(1, script.matches("title 'Baseline'").count());
+        assert!(script.contains("set key"));
+        // A tic for each row, including those with a single half
+        let tics = script.lines().find(|line| line.starts_with("set ytics")).unwrap();
+        let positions: Vec<_> = rows.iter().map(|&(label, _, _)| tics.find(label)).collect();
+        assert!(positions.iter().all(Option::is_some));
+        assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    }
+
+    #[test]
+    fn change_overview_highlighting() {
+        let change = |label: &str, mean: f64, verdict: ComparisonResult| Change {
+            label: label.to_owned(),
+            mean,
+            verdict,
+        };
+        let changes = vec![
+            change("faster", -0.10, ComparisonResult::Improved),
+            change("slightly_slower", 0.03, ComparisonResult::Regressed),
+            change("slower", 0.08, ComparisonResult::Regressed),
+        ];
+        let red = "lc rgb '#e31a1c'";
+        let green = "lc rgb '#2e8b57'";
+        let blue = "lc rgb '#1f78b4'";
+        let bright_red = "lc rgb '#ff0000'";
+
+        // By default, the verdicts decide.
+        let config = PlotConfiguration::default();
+        let default = script(&mut change_overview_figure("group", &changes, &config));
+        assert_eq!(3, default.matches("with filledcurves").count());
+        assert_eq!(1, default.matches(green).count());
+        assert_eq!(2, default.matches(red).count());
+        assert_eq!(1, default.matches("title 'Regressed'").count());
+
+        // Only the regression above the threshold is highlighted, the smaller one is noise.
+        let highlighting = ChangeHighlighting::default().regression(0.05, (255, 0, 0));
+        let config = PlotConfiguration::default().change_highlighting(highlighting);
+        let custom = script(&mut change_overview_figure("group", &changes, &config));
+        assert_eq!(1, custom.matches(bright_red).count());
+        assert!(custom.contains(&format!("{} title 'Regressed'", bright_red)));
+        assert_eq!(1, custom.matches(blue).count());
+        assert_eq!(1, custom.matches(green).count());
+        assert_eq!(0, custom.matches(red).count());
+    }
+}
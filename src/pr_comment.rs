@@ -0,0 +1,154 @@
+use std::cell::{Cell, RefCell};
+use std::cmp::Ordering;
+
+use estimate::Statistic;
+use report::{BenchmarkId, ComparisonResult, MeasurementData, ReportContext, Reporter};
+use {format, fs};
+
+// GitHub limits the size of comments, so large suites only list their largest changes.
+const MAX_ROWS: usize = 100;
+
+struct Change {
+    id: String,
+    mean: f64,
+    verdict: ComparisonResult,
+}
+
+/// Report which saves the changes of the benchmarks since their baseline as a Markdown snippet to
+/// post as a pull request comment: a summary line and a collapsible table of the benchmarks which
+/// improved or regressed, ordered by the magnitude of their change.
+pub(crate) struct PrCommentReport {
+    path: String,
+    changes: RefCell<Vec<Change>>,
+    unchanged: Cell<usize>,
+}
+impl PrCommentReport {
+    pub fn new(path: &str) -> PrCommentReport {
+        PrCommentReport {
+            path: path.to_owned(),
+            changes: RefCell::new(vec![]),
+            unchanged: Cell::new(0),
+        }
+    }
+}
+
+impl Reporter for PrCommentReport {
+    fn measured(&self, id: &BenchmarkId, _: &ReportContext, meas: &MeasurementData) {
+        let comp = match meas.comparison {
+            Some(ref comp) => comp,
+            None => return,
+        };
+
+        let verdict = if comp.p_value < comp.significance_threshold {
+            comp.verdict
+        } else {
+            ComparisonResult::NonSignificant
+        };
+        match verdict {
+            ComparisonResult::NonSignificant => self.unchanged.set(self.unchanged.get() + 1),
+            _ => self.changes.borrow_mut().push(Change {
+                id: id.id().to_owned(),
+                mean: comp.relative_estimates[&Statistic::Mean].point_estimate,
+                verdict,
+            }),
+        }
+    }
+
+    fn final_summary(&self, _: &ReportContext) {
+        let text = comment(&mut self.changes.borrow_mut(), self.unchanged.get());
+        log_if_err!(fs::save_string(&text, &self.path));
+    }
+}
+
+fn comment(changes: &mut [Change], unchanged: usize) -> String {
+    changes.sort_by(|a, b| {
+        b.mean
+            .abs()
+            .partial_cmp(&a.mean.abs())
+            .unwrap_or(Ordering::Equal)
+    });
+    let count = |verdict| changes.iter().filter(|c| c.verdict == verdict).count();
+
+    let mut text = format!(
+        "**Benchmark results:** {} regressed, {} improved, {} unchanged.\n",
+        count(ComparisonResult::Regressed),
+        count(ComparisonResult::Improved),
+        unchanged
+    );
+    if changes.is_empty() {
+        return text;
+    }
+
+    text.push_str("\n<details>\n<summary>Changed benchmarks</summary>\n\n");
+    text.push_str("| Benchmark | Change | |\n|:---|---:|:---|\n");
+    for change in changes.iter().take(MAX_ROWS) {
+        let verdict = match change.verdict {
+            ComparisonResult::Regressed => ":small_red_triangle: regressed",
+            _ => ":small_red_triangle_down: improved",
+        };
+        text.push_str(&format!(
+            "| `{}` | {} | {} |\n",
+            change.id.replace('|', "\\|"),
+            format::change(change.mean).trim(),
+            verdict
+        ));
+    }
+    if changes.len() > MAX_ROWS {
+        text.push_str(&format!(
+            "\n{} smaller changes are left out.\n",
+            changes.len() - MAX_ROWS
+        ));
+    }
+    text.push_str("\n</details>\n");
+    text
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn change(id: &str, mean: f64, verdict: ComparisonResult) -> Change {
+        Change {
+            id: id.to_owned(),
+            mean,
+            verdict,
+        }
+    }
+
+    #[test]
+    fn comment_lists_changes_by_magnitude() {
+        let mut changes = vec![
+            change("group/faster", -0.05, ComparisonResult::Improved),
+            change("group/slower", 0.25, ComparisonResult::Regressed),
+            change("group/a|b", 0.1, ComparisonResult::Regressed),
+        ];
+        let text = comment(&mut changes, 4);
+
+        assert!(text.starts_with("**Benchmark results:** 2 regressed, 1 improved, 4 unchanged.\n"));
+        assert!(text.contains("<details>"));
+        assert!(text.contains("</details>"));
+        let rows: Vec<&str> = text.lines().filter(|line| line.starts_with("| `")).collect();
+        assert_eq!(
+            vec![
+                "| `group/slower` | +25.00% | :small_red_triangle: regressed |",
+                "| `group/a\\|b` | +10.00% | :small_red_triangle: regressed |",
+                "| `group/faster` | -5.00% | :small_red_triangle_down: improved |",
+            ],
+            rows
+        );
+    }
+
+    #[test]
+    fn comment_is_size_bounded() {
+        let mut changes: Vec<Change> = (0..MAX_ROWS + 5)
+            .map(|i| change(&format!("group/{}", i), i as f64, ComparisonResult::Regressed))
+            .collect();
+        let text = comment(&mut changes, 0);
+
+        assert_eq!(MAX_ROWS, text.lines().filter(|line| line.starts_with("| `")).count());
+        assert!(text.contains("5 smaller changes are left out."));
+
+        // Without changes there is nothing to collapse.
+        assert!(!comment(&mut [], 3).contains("<details>"));
+    }
+}
@@ -2,10 +2,10 @@ use std::fmt;
 use std::io::BufReader;
 use std::marker::PhantomData;
 use std::process::{Child, ChildStderr, ChildStdin, ChildStdout, Command, Stdio};
-use std::time::{Duration, Instant};
+use std::time::Duration;
 
-use DurationExt;
-use routine::Routine;
+use routine::{self, Routine};
+use run_time::SystemClock;
 
 // A two-way channel to the standard streams of a child process
 pub struct Program {
@@ -92,21 +92,9 @@ impl Program {
     }
 
     fn warm_up(&mut self, how_long_ns: Duration) -> (u64, u64) {
-        let mut iters = 1;
-
-        let mut total_iters = 0;
-        let start = Instant::now();
-        loop {
+        routine::warm_up_loop(&SystemClock, how_long_ns, |iters| {
             self.send(iters).recv();
-
-            total_iters += iters;
-            let elapsed = start.elapsed();
-            if elapsed > how_long_ns {
-                return (elapsed.to_nanos(), total_iters);
-            }
-
-            iters *= 2;
-        }
+        })
     }
 }
 
@@ -1,5 +1,5 @@
 use stats::bivariate::Data;
-use stats::bivariate::regression::Slope;
+use stats::bivariate::regression::{Slope, StraightLine};
 use stats::univariate::outliers::tukey::LabeledSample;
 
 use Estimate;
@@ -7,13 +7,14 @@ use estimate::{Distributions, Estimates, Statistic};
 use format;
 use stats::Distribution;
 use stats::univariate::Sample;
-use std::cell::Cell;
+use std::cell::{Cell, RefCell};
 use std::cmp;
 use std::collections::HashSet;
 use std::fmt;
 use std::io::Write;
-use std::io::stdout;
-use {PlotConfiguration, Plotting, Throughput};
+use std::io::{stdout, Stdout};
+use std::rc::Rc;
+use {BenchmarkChange, BenchmarkResult, ConfidenceInterval, PlotConfiguration, Plotting, Throughput};
 
 const MAX_DIRECTORY_NAME_LEN: usize = 64;
 
@@ -29,16 +30,43 @@ pub(crate) struct ComparisonData {
     pub base_sample_times: Vec<f64>,
     pub base_avg_times: Vec<f64>,
     pub base_estimates: Estimates,
+    pub tail_change: Option<TailChange>,
+    pub verdict: ComparisonResult,
 }
 
-pub(crate) struct MeasurementData<'a> {
-    pub iter_counts: &'a Sample<f64>,
-    pub sample_times: &'a Sample<f64>,
-    pub avg_times: LabeledSample<'a, f64>,
-    pub absolute_estimates: Estimates,
-    pub distributions: Distributions,
-    pub comparison: Option<ComparisonData>,
-    pub throughput: Option<Throughput>,
+/// Relative change in a percentile of the iteration times, which captures changes in the tail
+/// latency that don't move the mean.
+pub(crate) struct TailChange {
+    pub percentile: f64,
+    pub estimate: Estimate,
+}
+
+/// Result of benchmarking two routines with interleaved samples.
+pub struct PairedComparison {
+    pub id_a: BenchmarkId,
+    pub id_b: BenchmarkId,
+    pub mean_a: f64,
+    pub mean_b: f64,
+    pub p_value: f64,
+    pub significance_threshold: f64,
+}
+
+pub struct MeasurementData<'a> {
+    pub(crate) iter_counts: &'a Sample<f64>,
+    pub(crate) sample_times: &'a Sample<f64>,
+    pub(crate) avg_times: LabeledSample<'a, f64>,
+    pub(crate) absolute_estimates: Estimates,
+    pub(crate) distributions: Distributions,
+    pub(crate) comparison: Option<ComparisonData>,
+    pub(crate) throughput: Option<Throughput>,
+}
+impl<'a> MeasurementData<'a> {
+    // Whether the benchmark was compared against a baseline and neither improved nor regressed
+    pub fn is_unchanged(&self) -> bool {
+        self.comparison
+            .as_ref()
+            .map_or(false, |comp| comp.verdict == ComparisonResult::NonSignificant)
+    }
 }
 
 #[derive(Debug, Clone, Copy, Eq, PartialEq)]
@@ -54,6 +82,10 @@ pub struct BenchmarkId {
     pub function_id: Option<String>,
     pub value_str: Option<String>,
     pub throughput: Option<Throughput>,
+    // Values of each parameter of a benchmark of a Cartesian product of parameters, which are
+    // joined into the value string. Empty for all other benchmarks.
+    #[serde(default)]
+    pub axes: Vec<String>,
     full_id: String,
     directory_name: String,
 }
@@ -118,11 +150,42 @@ impl BenchmarkId {
             function_id,
             value_str,
             throughput,
+            axes: vec![],
             full_id,
             directory_name,
         }
     }
 
+    pub fn new_product(
+        group_id: String,
+        function_id: Option<String>,
+        axes: Vec<String>,
+        throughput: Option<Throughput>,
+    ) -> BenchmarkId {
+        let mut id = BenchmarkId::new(group_id, function_id, Some(axes.join("/")), throughput);
+        id.axes = axes;
+        id
+    }
+
+    // For a benchmark of a product of parameters, returns the values of all axes but `axis`, with
+    // a `*` in place of the value of `axis`, and a copy of this id whose value string is the value
+    // of `axis` alone. The copy has no throughput, which usually depends on the other axes too.
+    pub fn vary_axis(&self, axis: usize) -> Option<(String, BenchmarkId)> {
+        let value = self.axes.get(axis)?;
+        let fixed: Vec<&str> = self.axes
+            .iter()
+            .enumerate()
+            .map(|(i, value)| if i == axis { "*" } else { value.as_str() })
+            .collect();
+        let id = BenchmarkId::new(
+            self.group_id.clone(),
+            self.function_id.clone(),
+            Some(value.clone()),
+            None,
+        );
+        Some((fixed.join("/"), id))
+    }
+
     pub fn id(&self) -> &str {
         &self.full_id
     }
@@ -131,6 +194,33 @@ impl BenchmarkId {
         &self.directory_name
     }
 
+    // Hash of the definition of the benchmark (its id, parameter and throughput), which is saved
+    // with its results. Only those fields are hashed, in a fixed encoding, so that the fingerprint
+    // of a benchmark doesn't change with the other fields of `BenchmarkId`. Uses FNV-1a rather
+    // than the std hasher, whose output may change between Rust versions.
+    pub fn fingerprint(&self) -> String {
+        let field = |string: Option<&String>| match string {
+            Some(string) => format!("+{}", string),
+            None => "-".to_owned(),
+        };
+        let throughput = match self.throughput {
+            Some(Throughput::Bytes(n)) => format!("+bytes {}", n),
+            Some(Throughput::Elements(n)) => format!("+elements {}", n),
+            None => "-".to_owned(),
+        };
+        let definition = [
+            field(Some(&self.group_id)),
+            field(self.function_id.as_ref()),
+            field(self.value_str.as_ref()),
+            throughput,
+        ].join("\0");
+
+        let hash = definition.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+        });
+        format!("{:016x}", hash)
+    }
+
     pub fn as_number(&self) -> Option<f64> {
         match self.throughput {
             Some(Throughput::Bytes(n)) | Some(Throughput::Elements(n)) => Some(f64::from(n)),
@@ -197,13 +287,68 @@ pub struct ReportContext {
     pub plotting: Plotting,
     pub plot_config: PlotConfiguration,
     pub test_mode: bool,
+    pub report_only_changed: bool,
+    pub run_label: Option<String>,
 }
+impl ReportContext {
+    // Whether the results of a benchmark are left out of the human-readable reports
+    pub fn hides(&self, measurements: &MeasurementData) -> bool {
+        self.report_only_changed && measurements.is_unchanged()
+    }
 
-pub(crate) trait Report {
-    fn benchmark_start(&self, _id: &BenchmarkId, _context: &ReportContext) {}
+    // The name of the JSON result file of each benchmark, which includes the label of the run
+    pub fn result_file_name(&self) -> String {
+        match self.run_label {
+            Some(ref label) => format!("result.{}.json", make_filename_safe(label)),
+            None => "result.json".to_owned(),
+        }
+    }
+}
+
+/// Receives the events of the benchmarks as they run. The built-in reports - the text output, the
+/// JSON and CSV files and the HTML report - are reporters, and more of them can be registered with
+/// `Criterion::add_reporter` to send the results somewhere the built-in ones don't, such as a
+/// database or a chat channel. Added reporters are called after the built-in ones. All methods do
+/// nothing by default.
+///
+/// # Example
+///
+/// ```rust
+/// # use criterion::{BenchmarkResult, Criterion, Reporter};
+/// struct Printer;
+/// impl Reporter for Printer {
+///     fn measurement_complete(&self, result: &BenchmarkResult) {
+///         println!("{}: {} ns", result.id, result.mean.point_estimate);
+///     }
+/// }
+///
+/// let criterion = Criterion::default().add_reporter(Box::new(Printer));
+/// ```
+pub trait Reporter {
+    /// Called before the benchmark `id` starts, also in test mode.
+    fn benchmark_start(&self, _id: &str) {}
+    /// Called when a benchmark has been measured and analyzed.
+    fn measurement_complete(&self, _result: &BenchmarkResult) {}
+    /// Called after `measurement_complete` if the benchmark was compared against a baseline.
+    fn comparison_complete(&self, _change: &BenchmarkChange) {}
+    /// Called once at the end of the run, after the benchmarks of all of the groups of
+    /// `criterion_main!` have run.
+    fn run_complete(&self) {}
+
+    // The built-in reports need more than the public events carry. They receive the crate's own
+    // representation of each event through the hidden methods below, which take types that can't
+    // be named outside of the crate.
+    #[doc(hidden)]
+    fn started(&self, _id: &BenchmarkId, _context: &ReportContext) {}
+    #[doc(hidden)]
     fn warmup(&self, _id: &BenchmarkId, _context: &ReportContext, _warmup_ns: f64) {}
+    #[doc(hidden)]
+    fn profile(&self, _id: &BenchmarkId, _context: &ReportContext, _profile_ns: f64) {}
+    #[doc(hidden)]
     fn terminated(&self, _id: &BenchmarkId, _context: &ReportContext) {}
+    #[doc(hidden)]
     fn analysis(&self, _id: &BenchmarkId, _context: &ReportContext) {}
+    #[doc(hidden)]
     fn measurement_start(
         &self,
         _id: &BenchmarkId,
@@ -213,51 +358,122 @@ pub(crate) trait Report {
         _iter_count: u64,
     ) {
     }
-    fn measurement_complete(
+    #[doc(hidden)]
+    fn measured(
         &self,
         _id: &BenchmarkId,
         _context: &ReportContext,
         _measurements: &MeasurementData,
     ) {
     }
+    #[doc(hidden)]
+    fn paired_comparison(
+        &self,
+        _id: &BenchmarkId,
+        _context: &ReportContext,
+        _comparison: &PairedComparison,
+    ) {
+    }
+    #[doc(hidden)]
     fn summarize(&self, _context: &ReportContext, _all_ids: &[BenchmarkId]) {}
+    #[doc(hidden)]
     fn final_summary(&self, _context: &ReportContext) {}
+
+    // Reports which save files to the output directory are left out when it's not writable.
+    #[doc(hidden)]
+    fn saves_files(&self) -> bool {
+        false
+    }
 }
 
+// Every benchmark group is run by its own `Criterion` instance, and the final summary by yet
+// another one, so the reporters which are told that the run is complete have to be collected
+// outside of them.
+thread_local! {
+    static RUN_REPORTERS: RefCell<Vec<Rc<Reporter>>> = RefCell::new(vec![]);
+}
+
+// Tells the reporters added during the run that it's complete, each of them exactly once.
+pub(crate) fn complete_run() {
+    let reporters = RUN_REPORTERS.with(|reporters| reporters.replace(vec![]));
+    for reporter in reporters {
+        reporter.run_complete();
+    }
+}
+
+// Sends the events of the benchmarks to the built-in reports and to the reporters added by the
+// user, in that order.
 pub(crate) struct Reports {
-    reports: Vec<Box<Report>>,
+    reports: Vec<Box<Reporter>>,
+    added: Vec<Rc<Reporter>>,
 }
 impl Reports {
-    pub fn new(reports: Vec<Box<Report>>) -> Reports {
-        Reports { reports }
+    pub fn new(reports: Vec<Box<Reporter>>) -> Reports {
+        Reports {
+            reports,
+            added: vec![],
+        }
     }
-}
-impl Report for Reports {
-    fn benchmark_start(&self, id: &BenchmarkId, context: &ReportContext) {
-        for report in &self.reports {
-            report.benchmark_start(id, context);
+
+    pub fn push(&mut self, report: Box<Reporter>) {
+        self.reports.push(report);
+    }
+
+    // Replaces the built-in reports, keeping the added reporters.
+    pub fn set_built_in(&mut self, reports: Vec<Box<Reporter>>) {
+        self.reports = reports;
+    }
+
+    // Adds a reporter of the user, which is also told when the run is complete.
+    pub fn add(&mut self, reporter: Box<Reporter>) {
+        let reporter: Rc<Reporter> = Rc::from(reporter);
+        RUN_REPORTERS.with(|reporters| reporters.borrow_mut().push(reporter.clone()));
+        self.added.push(reporter);
+    }
+
+    pub fn remove_file_reports(&mut self) {
+        self.reports.retain(|report| !report.saves_files());
+    }
+
+    fn all<'a>(&'a self) -> impl Iterator<Item = &'a Reporter> + 'a {
+        self.reports
+            .iter()
+            .map(|report| &**report)
+            .chain(self.added.iter().map(|reporter| &**reporter))
+    }
+
+    pub fn benchmark_start(&self, id: &BenchmarkId, context: &ReportContext) {
+        for report in self.all() {
+            report.started(id, context);
+            report.benchmark_start(id.id());
         }
     }
 
-    fn warmup(&self, id: &BenchmarkId, context: &ReportContext, warmup_ns: f64) {
-        for report in &self.reports {
+    pub fn warmup(&self, id: &BenchmarkId, context: &ReportContext, warmup_ns: f64) {
+        for report in self.all() {
             report.warmup(id, context, warmup_ns);
         }
     }
 
-    fn terminated(&self, id: &BenchmarkId, context: &ReportContext) {
-        for report in &self.reports {
+    pub fn profile(&self, id: &BenchmarkId, context: &ReportContext, profile_ns: f64) {
+        for report in self.all() {
+            report.profile(id, context, profile_ns);
+        }
+    }
+
+    pub fn terminated(&self, id: &BenchmarkId, context: &ReportContext) {
+        for report in self.all() {
             report.terminated(id, context);
         }
     }
 
-    fn analysis(&self, id: &BenchmarkId, context: &ReportContext) {
-        for report in &self.reports {
+    pub fn analysis(&self, id: &BenchmarkId, context: &ReportContext) {
+        for report in self.all() {
             report.analysis(id, context);
         }
     }
 
-    fn measurement_start(
+    pub fn measurement_start(
         &self,
         id: &BenchmarkId,
         context: &ReportContext,
@@ -265,30 +481,55 @@ impl Report for Reports {
         estimate_ns: f64,
         iter_count: u64,
     ) {
-        for report in &self.reports {
+        for report in self.all() {
             report.measurement_start(id, context, sample_count, estimate_ns, iter_count);
         }
     }
 
-    fn measurement_complete(
+    pub fn measurement_complete(
         &self,
         id: &BenchmarkId,
         context: &ReportContext,
         measurements: &MeasurementData,
     ) {
-        for report in &self.reports {
-            report.measurement_complete(id, context, measurements);
+        let result = BenchmarkResult::new(
+            id,
+            measurements.iter_counts,
+            measurements.sample_times,
+            &measurements.absolute_estimates,
+        );
+        let change = measurements
+            .comparison
+            .as_ref()
+            .map(|comp| BenchmarkChange::new(id, comp));
+        for report in self.all() {
+            report.measured(id, context, measurements);
+            report.measurement_complete(&result);
+            if let Some(ref change) = change {
+                report.comparison_complete(change);
+            }
+        }
+    }
+
+    pub fn paired_comparison(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        comparison: &PairedComparison,
+    ) {
+        for report in self.all() {
+            report.paired_comparison(id, context, comparison);
         }
     }
 
-    fn summarize(&self, context: &ReportContext, all_ids: &[BenchmarkId]) {
-        for report in &self.reports {
+    pub fn summarize(&self, context: &ReportContext, all_ids: &[BenchmarkId]) {
+        for report in self.all() {
             report.summarize(context, all_ids);
         }
     }
 
-    fn final_summary(&self, context: &ReportContext) {
-        for report in &self.reports {
+    pub fn final_summary(&self, context: &ReportContext) {
+        for report in self.all() {
             report.final_summary(context);
         }
     }
@@ -300,6 +541,8 @@ pub(crate) struct CliReport {
     pub verbose: bool,
 
     last_line_len: Cell<usize>,
+    group_medians: RefCell<Vec<(String, f64)>>,
+    unchanged: Cell<usize>,
 }
 impl CliReport {
     pub fn new(
@@ -313,6 +556,8 @@ impl CliReport {
             verbose,
 
             last_line_len: Cell::new(0),
+            group_medians: RefCell::new(vec![]),
+            unchanged: Cell::new(0),
         }
     }
 
@@ -338,6 +583,48 @@ impl CliReport {
         }
     }
 
+    fn throughput_change(&self, time_change: &Estimate, significant: bool, noise_threshold: f64) {
+        let estimate = throughput_change(time_change);
+        let mut change_str = format::change_interval(&estimate);
+        if significant {
+            match compare_throughput_to_threshold(&estimate, noise_threshold) {
+                ComparisonResult::Improved => {
+                    change_str = self.green(self.bold(change_str));
+                }
+                ComparisonResult::Regressed => {
+                    change_str = self.red(self.bold(change_str));
+                }
+                ComparisonResult::NonSignificant => {}
+            }
+        }
+
+        println!("{}thrpt:  {}", " ".repeat(24), change_str);
+    }
+
+    fn tail_change(&self, tail: &TailChange, noise_threshold: f64) {
+        let estimate = &tail.estimate;
+        let mut change_str = format::change_interval(estimate);
+        let explanation_str = match compare_to_threshold(estimate, noise_threshold) {
+            ComparisonResult::Improved => {
+                change_str = self.green(self.bold(change_str));
+                format!("Tail latency has {}.", self.green("improved".to_owned()))
+            }
+            ComparisonResult::Regressed => {
+                change_str = self.red(self.bold(change_str));
+                format!("Tail latency has {}.", self.red("regressed".to_owned()))
+            }
+            ComparisonResult::NonSignificant => "No change in tail latency detected.".to_owned(),
+        };
+
+        println!(
+            "{}{:<8}{}",
+            " ".repeat(24),
+            format!("p{}:", tail.percentile),
+            change_str
+        );
+        println!("{}{}", " ".repeat(24), explanation_str);
+    }
+
     fn green(&self, s: String) -> String {
         if self.enable_text_coloring {
             format!("\x1B[32m{}\x1B[39m", s)
@@ -410,9 +697,49 @@ impl CliReport {
         print(him, "high mild");
         print(his, "high severe");
     }
+
+    fn fit_advice(&self, data: Data<f64, f64>) {
+        let (r_squared, advice) = match fit_advice(data) {
+            Some(result) => result,
+            None => return,
+        };
+
+        let advice = match advice {
+            FitAdvice::IncreaseWarmUp => {
+                "the time per sample has a large fixed part, which suggests the benchmark was not \
+                 warmed up sufficiently. Consider increasing the warm-up time."
+            }
+            FitAdvice::IncreaseIterations => {
+                "the samples are noisy. Consider increasing the measurement time to run more \
+                 iterations per sample."
+            }
+        };
+        println!(
+            "{}",
+            self.yellow(format!(
+                "Poor fit of the measurements (R^2 = {:.4}): {}",
+                r_squared, advice
+            ))
+        );
+    }
+
+    fn discretization_advice(&self, avg_times: &[f64]) {
+        if let Some(distinct) = discretization(avg_times) {
+            println!(
+                "{}",
+                self.yellow(format!(
+                    "Only {} distinct times in {} measurements: the timer may be too coarse for \
+                     this benchmark. Consider increasing the measurement time to run more \
+                     iterations per sample.",
+                    distinct,
+                    avg_times.len()
+                ))
+            );
+        }
+    }
 }
-impl Report for CliReport {
-    fn benchmark_start(&self, id: &BenchmarkId, ctx: &ReportContext) {
+impl Reporter for CliReport {
+    fn started(&self, id: &BenchmarkId, ctx: &ReportContext) {
         if ctx.test_mode {
             println!("Testing {}", id);
         } else {
@@ -429,6 +756,15 @@ impl Report for CliReport {
         ));
     }
 
+    fn profile(&self, id: &BenchmarkId, _: &ReportContext, profile_ns: f64) {
+        self.text_overwrite();
+        self.print_overwritable(format!(
+            "Benchmarking {}: Profiling for {}",
+            id,
+            format::time(profile_ns)
+        ));
+    }
+
     fn terminated(&self, id: &BenchmarkId, ctx: &ReportContext) {
         if ctx.test_mode {
             println!("Success");
@@ -453,7 +789,7 @@ impl Report for CliReport {
     ) {
         self.text_overwrite();
         let iter_string = if self.verbose {
-            format!("{} iterations", iter_count)
+            format::iter_count_full(iter_count)
         } else {
             format::iter_count(iter_count)
         };
@@ -467,8 +803,17 @@ impl Report for CliReport {
         ));
     }
 
-    fn measurement_complete(&self, id: &BenchmarkId, _: &ReportContext, meas: &MeasurementData) {
+    fn measured(
+        &self,
+        id: &BenchmarkId,
+        context: &ReportContext,
+        meas: &MeasurementData,
+    ) {
         self.text_overwrite();
+        if context.hides(meas) {
+            self.unchanged.set(self.unchanged.get() + 1);
+            return;
+        }
 
         let slope_estimate = meas.absolute_estimates[&Statistic::Slope];
 
@@ -489,9 +834,9 @@ impl Report for CliReport {
                 "{}{}time:   [{} {} {}]",
                 self.green(id),
                 " ".repeat(24 - id_len),
-                self.faint(format::time(slope_estimate.confidence_interval.lower_bound)),
-                self.bold(format::time(slope_estimate.point_estimate)),
-                self.faint(format::time(slope_estimate.confidence_interval.upper_bound))
+                self.faint(format::time_column(slope_estimate.confidence_interval.lower_bound)),
+                self.bold(format::time_column(slope_estimate.point_estimate)),
+                self.faint(format::time_column(slope_estimate.confidence_interval.upper_bound))
             );
         }
 
@@ -517,22 +862,20 @@ impl Report for CliReport {
         if let Some(ref comp) = meas.comparison {
             let different_mean = comp.p_value < comp.significance_threshold;
             let mean_est = comp.relative_estimates[&Statistic::Mean];
-            let point_estimate = mean_est.point_estimate;
-            let mut point_estimate_str = format::change(point_estimate, true);
+            let mut change_str = format::change_interval(&mean_est);
             let explanation_str: String;
 
             if !different_mean {
                 explanation_str = "No change in performance detected.".to_owned();
             } else {
-                let comparison = compare_to_threshold(&mean_est, comp.noise_threshold);
-                match comparison {
+                match comp.verdict {
                     ComparisonResult::Improved => {
-                        point_estimate_str = self.green(self.bold(point_estimate_str));
+                        change_str = self.green(self.bold(change_str));
                         explanation_str =
                             format!("Performance has {}.", self.green("improved".to_owned()));
                     }
                     ComparisonResult::Regressed => {
-                        point_estimate_str = self.red(self.bold(point_estimate_str));
+                        change_str = self.red(self.bold(change_str));
                         explanation_str =
                             format!("Performance has {}.", self.red("regressed".to_owned()));
                     }
@@ -543,42 +886,49 @@ impl Report for CliReport {
             }
 
             println!(
-                "{}change: [{} {} {}] (p = {:.2} {} {:.2})",
+                "{}change: {} (p = {:.2} {} {:.2})",
                 " ".repeat(24),
-                self.faint(format::change(
-                    mean_est.confidence_interval.lower_bound,
-                    true
-                )),
-                point_estimate_str,
-                self.faint(format::change(
-                    mean_est.confidence_interval.upper_bound,
-                    true
-                )),
+                change_str,
                 comp.p_value,
                 if different_mean { "<" } else { ">" },
                 comp.significance_threshold
             );
+            if meas.throughput.is_some() {
+                self.throughput_change(&mean_est, different_mean, comp.noise_threshold);
+            }
             println!("{}{}", " ".repeat(24), explanation_str);
+
+            if let Some(ref tail) = comp.tail_change {
+                self.tail_change(tail, comp.noise_threshold);
+            }
         }
 
         self.outliers(&meas.avg_times);
+        self.fit_advice(Data::new(
+            meas.iter_counts.as_slice(),
+            meas.sample_times.as_slice(),
+        ));
+        self.discretization_advice(meas.avg_times.as_slice());
+
+        // Without gnuplot there are no plots of the distribution, so show a crude one here.
+        if let Plotting::NotAvailable = context.plotting {
+            for line in format::text_histogram(&meas.avg_times, 40).lines() {
+                println!("{}{}", " ".repeat(24), line);
+            }
+        }
+
+        self.group_medians
+            .borrow_mut()
+            .push((id.id().to_owned(), meas.avg_times.percentiles().median()));
 
         if self.verbose {
             let data = Data::new(meas.iter_counts.as_slice(), meas.sample_times.as_slice());
             let slope_estimate = &meas.absolute_estimates[&Statistic::Slope];
 
-            fn format_short_estimate(estimate: &Estimate) -> String {
-                format!(
-                    "[{} {}]",
-                    format::time(estimate.confidence_interval.lower_bound),
-                    format::time(estimate.confidence_interval.upper_bound)
-                )
-            }
-
             println!(
                 "{:<7}{} {:<15}[{:0.7} {:0.7}]",
                 "slope",
-                format_short_estimate(slope_estimate),
+                format::time_interval(slope_estimate),
                 "R^2",
                 Slope(slope_estimate.confidence_interval.lower_bound).r_squared(data),
                 Slope(slope_estimate.confidence_interval.upper_bound).r_squared(data),
@@ -586,28 +936,184 @@ impl Report for CliReport {
             println!(
                 "{:<7}{} {:<15}{}",
                 "mean",
-                format_short_estimate(&meas.absolute_estimates[&Statistic::Mean]),
+                format::time_interval(&meas.absolute_estimates[&Statistic::Mean]),
                 "std. dev.",
-                format_short_estimate(&meas.absolute_estimates[&Statistic::StdDev]),
+                format::time_interval(&meas.absolute_estimates[&Statistic::StdDev]),
             );
             println!(
                 "{:<7}{} {:<15}{}",
                 "median",
-                format_short_estimate(&meas.absolute_estimates[&Statistic::Median]),
+                format::time_interval(&meas.absolute_estimates[&Statistic::Median]),
                 "med. abs. dev.",
-                format_short_estimate(&meas.absolute_estimates[&Statistic::MedianAbsDev]),
+                format::time_interval(&meas.absolute_estimates[&Statistic::MedianAbsDev]),
+            );
+        }
+    }
+
+    fn paired_comparison(&self, id: &BenchmarkId, _: &ReportContext, comp: &PairedComparison) {
+        self.text_overwrite();
+        println!("{}", self.green(id.id().to_owned()));
+        for &(id, mean) in &[(&comp.id_a, comp.mean_a), (&comp.id_b, comp.mean_b)] {
+            println!(
+                "{}{}: {}",
+                " ".repeat(24),
+                id,
+                self.bold(format::time(mean))
+            );
+        }
+
+        let different = comp.p_value < comp.significance_threshold;
+        let explanation_str = if !different {
+            "No difference in performance detected.".to_owned()
+        } else {
+            let (faster, slower) = if comp.mean_a < comp.mean_b {
+                (&comp.id_a, &comp.id_b)
+            } else {
+                (&comp.id_b, &comp.id_a)
+            };
+            format!(
+                "{} is {} than {}.",
+                faster,
+                self.green("faster".to_owned()),
+                slower
+            )
+        };
+        println!(
+            "{}{} (p = {:.2} {} {:.2}, Wilcoxon signed-rank test)",
+            " ".repeat(24),
+            explanation_str,
+            comp.p_value,
+            if different { "<" } else { ">" },
+            comp.significance_threshold
+        );
+    }
+
+    fn summarize(&self, _: &ReportContext, all_ids: &[BenchmarkId]) {
+        let medians: Vec<(String, f64)> = self.group_medians
+            .borrow_mut()
+            .drain(..)
+            .filter(|&(ref id, _)| all_ids.iter().any(|other| other.id() == id))
+            .collect();
+
+        if let Some(summary) = GroupSummary::new(&medians) {
+            println!(
+                "{}: fastest {} ({}), slowest {} ({}), slowest/fastest = {:.2}x",
+                all_ids[0].group_id,
+                summary.fastest.0,
+                format::time(summary.fastest.1).trim(),
+                summary.slowest.0,
+                format::time(summary.slowest.1).trim(),
+                summary.ratio(),
             );
         }
     }
+
+    fn final_summary(&self, _: &ReportContext) {
+        match self.unchanged.get() {
+            0 => {}
+            1 => println!("1 unchanged benchmark was not reported."),
+            n => println!("{} unchanged benchmarks were not reported.", n),
+        }
+    }
+}
+
+/// Report which prints only the benchmarks which regressed and, at the end of the run, how many
+/// benchmarks were measured and how many of them regressed. Errors are logged separately, so they
+/// still show up.
+pub(crate) struct QuietReport<W: Write = Stdout> {
+    out: RefCell<W>,
+    measured: Cell<usize>,
+    regressed: Cell<usize>,
+}
+impl QuietReport {
+    pub fn new() -> QuietReport {
+        QuietReport::with_output(stdout())
+    }
+}
+impl<W: Write> QuietReport<W> {
+    fn with_output(out: W) -> QuietReport<W> {
+        QuietReport {
+            out: RefCell::new(out),
+            measured: Cell::new(0),
+            regressed: Cell::new(0),
+        }
+    }
+}
+impl<W: Write> Reporter for QuietReport<W> {
+    fn measured(&self, id: &BenchmarkId, _: &ReportContext, meas: &MeasurementData) {
+        self.measured.set(self.measured.get() + 1);
+
+        if let Some(ref comp) = meas.comparison {
+            if comp.p_value < comp.significance_threshold
+                && comp.verdict == ComparisonResult::Regressed
+            {
+                self.regressed.set(self.regressed.get() + 1);
+                writeln!(
+                    self.out.borrow_mut(),
+                    "{}: regressed, change: {}",
+                    id,
+                    format::change_interval(&comp.relative_estimates[&Statistic::Mean])
+                ).unwrap();
+            }
+        }
+    }
+
+    fn final_summary(&self, _: &ReportContext) {
+        if self.measured.get() > 0 {
+            writeln!(
+                self.out.borrow_mut(),
+                "{} benchmarks measured, {} regressed.",
+                self.measured.get(),
+                self.regressed.get()
+            ).unwrap();
+        }
+    }
+}
+
+/// The fastest and slowest benchmarks of a group, by median time.
+#[derive(Debug, PartialEq)]
+struct GroupSummary<'a> {
+    fastest: (&'a str, f64),
+    slowest: (&'a str, f64),
+}
+impl<'a> GroupSummary<'a> {
+    fn new(medians: &'a [(String, f64)]) -> Option<GroupSummary<'a>> {
+        if medians.len() < 2 {
+            return None;
+        }
+
+        let as_ref = |&(ref id, median): &'a (String, f64)| (id.as_str(), median);
+        let fastest = medians
+            .iter()
+            .min_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(cmp::Ordering::Equal))
+            .map(as_ref)
+            .unwrap();
+        let slowest = medians
+            .iter()
+            .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(cmp::Ordering::Equal))
+            .map(as_ref)
+            .unwrap();
+
+        Some(GroupSummary { fastest, slowest })
+    }
+
+    fn ratio(&self) -> f64 {
+        self.slowest.1 / self.fastest.1
+    }
 }
 
-enum ComparisonResult {
+/// Verdict of the comparison of a benchmark against its baseline.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ComparisonResult {
+    /// The benchmark got faster by more than the noise threshold
     Improved,
+    /// The benchmark got slower by more than the noise threshold
     Regressed,
+    /// The change is not statistically significant, or within the noise threshold
     NonSignificant,
 }
 
-fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
+pub(crate) fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
     let ci = estimate.confidence_interval;
     let lb = ci.lower_bound;
     let ub = ci.upper_bound;
@@ -621,9 +1127,157 @@ fn compare_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
     }
 }
 
+// Like `compare_to_threshold`, but a change has to clear the noise threshold by `hysteresis` to
+// move away from the previous verdict, and keeps the previous verdict unless it moves back inside
+// the noise threshold by more than `hysteresis`.
+pub(crate) fn compare_with_hysteresis(
+    estimate: &Estimate,
+    noise: f64,
+    hysteresis: f64,
+    previous: Option<ComparisonResult>,
+) -> ComparisonResult {
+    let previous = match previous {
+        Some(previous) => previous,
+        None => return compare_to_threshold(estimate, noise),
+    };
+    let threshold = |verdict| {
+        if previous == verdict {
+            (noise - hysteresis).max(0.)
+        } else {
+            noise + hysteresis
+        }
+    };
+
+    let ci = estimate.confidence_interval;
+    let (improved, regressed) = (
+        threshold(ComparisonResult::Improved),
+        threshold(ComparisonResult::Regressed),
+    );
+    if ci.lower_bound < -improved && ci.upper_bound < -improved {
+        ComparisonResult::Improved
+    } else if ci.lower_bound > regressed && ci.upper_bound > regressed {
+        ComparisonResult::Regressed
+    } else {
+        ComparisonResult::NonSignificant
+    }
+}
+
+// Below this, the time per sample is not explained well by the number of iterations.
+const R_SQUARED_THRESHOLD: f64 = 0.9;
+
+// A fixed part of the time per sample is significant if it is more than this fraction of the
+// fastest sample.
+const INTERCEPT_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, PartialEq)]
+enum FitAdvice {
+    IncreaseWarmUp,
+    IncreaseIterations,
+}
+
+// The time per sample should be proportional to the number of iterations. If it isn't, a line with
+// a large positive intercept means every sample paid some fixed overhead, which is typical of a
+// benchmark that was still warming up. Otherwise the samples are just noisy, which more iterations
+// per sample help to average out. Returns the R^2 of the fit along with the advice.
+fn fit_advice(data: Data<f64, f64>) -> Option<(f64, FitAdvice)> {
+    let slope = Slope::fit(data);
+    let r_squared = slope.r_squared(data);
+    if r_squared >= R_SQUARED_THRESHOLD {
+        return None;
+    }
+
+    let line = StraightLine::fit(data);
+    let fastest_sample = Sample::new(data.y().as_slice()).min();
+    let advice = if line.intercept > INTERCEPT_THRESHOLD * fastest_sample {
+        FitAdvice::IncreaseWarmUp
+    } else {
+        FitAdvice::IncreaseIterations
+    };
+
+    Some((r_squared, advice))
+}
+
+// Below this fraction of distinct values, a sample is too discretized for the kernel density
+// estimates and the bootstrap to be meaningful.
+const DISTINCT_VALUES_THRESHOLD: f64 = 0.2;
+
+// Measurements cluster on a few values when the timer is coarse compared to the time of a sample,
+// typically because the routine is trivial. Returns the number of distinct values if there are too
+// few of them.
+fn discretization(sample: &[f64]) -> Option<usize> {
+    let mut values = sample.to_vec();
+    values.sort_by(|a, b| a.partial_cmp(b).unwrap());
+    values.dedup();
+
+    let distinct = values.len();
+    if (distinct as f64) < DISTINCT_VALUES_THRESHOLD * sample.len() as f64 {
+        Some(distinct)
+    } else {
+        None
+    }
+}
+
+// Throughput is inversely proportional to time, so a relative change of `r` in the time is a
+// relative change of `1 / (1 + r) - 1` in the throughput, and the bounds trade places.
+fn throughput_change(time_change: &Estimate) -> Estimate {
+    let to_throughput = |r: f64| 1. / (1. + r) - 1.;
+    let ci = time_change.confidence_interval;
+    let point_estimate = to_throughput(time_change.point_estimate);
+
+    Estimate {
+        confidence_interval: ConfidenceInterval {
+            confidence_level: ci.confidence_level,
+            lower_bound: to_throughput(ci.upper_bound),
+            upper_bound: to_throughput(ci.lower_bound),
+        },
+        point_estimate,
+        // First-order approximation around the point estimate
+        standard_error: time_change.standard_error * (1. + point_estimate).powi(2),
+    }
+}
+
+// Unlike time, more throughput is better.
+fn compare_throughput_to_threshold(estimate: &Estimate, noise: f64) -> ComparisonResult {
+    match compare_to_threshold(estimate, noise) {
+        ComparisonResult::Improved => ComparisonResult::Regressed,
+        ComparisonResult::Regressed => ComparisonResult::Improved,
+        ComparisonResult::NonSignificant => ComparisonResult::NonSignificant,
+    }
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use stats::univariate::outliers::tukey;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn fingerprint_only_covers_the_definition() {
+        let id = BenchmarkId::new(
+            "group".to_owned(),
+            Some("function".to_owned()),
+            Some("1024/64".to_owned()),
+            Some(Throughput::Bytes(1024)),
+        );
+        // Saved baselines must keep matching the benchmarks which define them
+        assert_eq!("c6ecd3b6d2aaffe2", id.fingerprint());
+
+        let product = BenchmarkId::new_product(
+            "group".to_owned(),
+            Some("function".to_owned()),
+            vec!["1024".to_owned(), "64".to_owned()],
+            Some(Throughput::Bytes(1024)),
+        );
+        assert_eq!(id.fingerprint(), product.fingerprint());
+
+        let no_function = BenchmarkId::new(
+            "group".to_owned(),
+            None,
+            Some("function".to_owned()),
+            Some(Throughput::Bytes(1024)),
+        );
+        assert_ne!(id.fingerprint(), no_function.fingerprint());
+    }
 
     #[test]
     fn test_make_filename_safe_replaces_characters() {
@@ -647,6 +1301,22 @@ mod test {
         assert!(safe.len() < MAX_DIRECTORY_NAME_LEN);
     }
 
+    #[test]
+    fn test_group_summary_fastest_slowest_ratio() {
+        let medians = vec![
+            ("group/b".to_owned(), 20.0),
+            ("group/a".to_owned(), 5.0),
+            ("group/c".to_owned(), 12.5),
+        ];
+        let summary = GroupSummary::new(&medians).unwrap();
+
+        assert_eq!(("group/a", 5.0), summary.fastest);
+        assert_eq!(("group/b", 20.0), summary.slowest);
+        assert_eq!(4.0, summary.ratio());
+
+        assert_eq!(None, GroupSummary::new(&medians[..1]));
+    }
+
     #[test]
     fn test_benchmark_id_make_directory_name_unique() {
         let existing_id = BenchmarkId::new(
@@ -668,4 +1338,259 @@ mod test {
         assert_eq!("group/function/value_3", new_id.as_directory_name());
         directories.insert(new_id.as_directory_name().to_owned());
     }
+
+    #[test]
+    fn test_time_improvement_is_throughput_improvement() {
+        let time_change = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: -0.25,
+                upper_bound: -0.1,
+            },
+            point_estimate: -0.2,
+            standard_error: 0.05,
+        };
+        assert_eq!(
+            ComparisonResult::Improved,
+            compare_to_threshold(&time_change, 0.01)
+        );
+
+        let thrpt_change = throughput_change(&time_change);
+        assert!((thrpt_change.point_estimate - 0.25).abs() < 1e-9);
+        assert!((thrpt_change.confidence_interval.lower_bound - 1. / 9.).abs() < 1e-9);
+        assert!((thrpt_change.confidence_interval.upper_bound - 1. / 3.).abs() < 1e-9);
+        assert_eq!(
+            ComparisonResult::Improved,
+            compare_throughput_to_threshold(&thrpt_change, 0.01)
+        );
+    }
+
+    #[test]
+    fn test_hysteresis_retains_previous_verdict() {
+        let change = |lower_bound, upper_bound| Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound,
+                upper_bound,
+            },
+            point_estimate: (lower_bound + upper_bound) / 2.,
+            standard_error: 0.005,
+        };
+        let noise = 0.05;
+        let hysteresis = 0.02;
+
+        // Just outside the noise threshold, but inside the band.
+        let small_regression = change(0.055, 0.065);
+        assert_eq!(
+            ComparisonResult::Regressed,
+            compare_to_threshold(&small_regression, noise)
+        );
+        assert_eq!(
+            ComparisonResult::NonSignificant,
+            compare_with_hysteresis(
+                &small_regression,
+                noise,
+                hysteresis,
+                Some(ComparisonResult::NonSignificant)
+            )
+        );
+
+        // Just inside the noise threshold, but inside the band.
+        let smaller_regression = change(0.04, 0.045);
+        assert_eq!(
+            ComparisonResult::Regressed,
+            compare_with_hysteresis(
+                &smaller_regression,
+                noise,
+                hysteresis,
+                Some(ComparisonResult::Regressed)
+            )
+        );
+
+        // Outside the band, the verdict changes.
+        assert_eq!(
+            ComparisonResult::Regressed,
+            compare_with_hysteresis(
+                &change(0.08, 0.09),
+                noise,
+                hysteresis,
+                Some(ComparisonResult::NonSignificant)
+            )
+        );
+
+        // Without a previous verdict, there is no band.
+        assert_eq!(
+            ComparisonResult::Regressed,
+            compare_with_hysteresis(&small_regression, noise, hysteresis, None)
+        );
+    }
+
+    #[test]
+    fn test_fit_advice() {
+        let iters: Vec<f64> = (1..11).map(|i| f64::from(i * 10)).collect();
+
+        // Every sample pays a large fixed overhead.
+        let times: Vec<f64> = iters.iter().map(|&i| 1000. + 10. * i).collect();
+        let (r_squared, advice) = fit_advice(Data::new(&iters, &times)).unwrap();
+        assert!(r_squared < R_SQUARED_THRESHOLD);
+        assert_eq!(FitAdvice::IncreaseWarmUp, advice);
+
+        // A good fit needs no advice.
+        let times: Vec<f64> = iters.iter().map(|&i| 10. * i).collect();
+        assert_eq!(None, fit_advice(Data::new(&iters, &times)));
+    }
+
+    #[test]
+    fn test_discretization() {
+        let discrete: Vec<f64> = (0..100).map(|i| f64::from(10 + i % 3)).collect();
+        assert_eq!(Some(3), discretization(&discrete));
+
+        let continuous: Vec<f64> = (0..100).map(|i| 10. + f64::from(i) * 0.01).collect();
+        assert_eq!(None, discretization(&continuous));
+    }
+
+    fn estimates(point_estimate: f64) -> Estimates {
+        let estimate = Estimate {
+            confidence_interval: ConfidenceInterval {
+                confidence_level: 0.95,
+                lower_bound: point_estimate * 0.9,
+                upper_bound: point_estimate * 1.1,
+            },
+            point_estimate,
+            standard_error: point_estimate * 0.05,
+        };
+        let mut estimates = BTreeMap::new();
+        for &statistic in &[
+            Statistic::Mean,
+            Statistic::Median,
+            Statistic::MedianAbsDev,
+            Statistic::Slope,
+            Statistic::StdDev,
+        ] {
+            estimates.insert(statistic, estimate);
+        }
+        estimates
+    }
+
+    fn measurements<'a>(
+        iters: &'a Sample<f64>,
+        times: &'a Sample<f64>,
+        avg_times: &'a Sample<f64>,
+        verdict: ComparisonResult,
+    ) -> MeasurementData<'a> {
+        MeasurementData {
+            iter_counts: iters,
+            sample_times: times,
+            avg_times: tukey::classify(avg_times),
+            absolute_estimates: estimates(10.0),
+            distributions: BTreeMap::new(),
+            comparison: Some(ComparisonData {
+                p_value: 0.01,
+                t_distribution: Distribution::from(vec![0.0, 1.0].into_boxed_slice()),
+                t_value: 3.0,
+                relative_estimates: estimates(0.2),
+                relative_distributions: BTreeMap::new(),
+                significance_threshold: 0.05,
+                noise_threshold: 0.01,
+                base_iter_counts: iters.as_slice().to_vec(),
+                base_sample_times: times.as_slice().to_vec(),
+                base_avg_times: avg_times.as_slice().to_vec(),
+                base_estimates: estimates(8.0),
+                tail_change: None,
+                verdict,
+            }),
+            throughput: None,
+        }
+    }
+
+    #[test]
+    fn test_report_only_changed() {
+        let iters = [1.0, 2.0, 3.0, 4.0];
+        let times = [10.0, 21.0, 29.0, 40.0];
+        let avg_times = [10.0, 10.5, 9.667, 10.0];
+        let (iters, times, avg_times) = (
+            Sample::new(&iters),
+            Sample::new(&times),
+            Sample::new(&avg_times),
+        );
+        let mut context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: true,
+            run_label: None,
+        };
+        let benchmarks = [
+            ("group/regressed", ComparisonResult::Regressed),
+            ("group/unchanged", ComparisonResult::NonSignificant),
+            ("group/improved", ComparisonResult::Improved),
+            ("group/noise", ComparisonResult::NonSignificant),
+        ];
+
+        let report = CliReport::new(false, false, false);
+        for &(id, verdict) in &benchmarks {
+            let id = BenchmarkId::new(id.to_owned(), None, None, None);
+            let measurements = measurements(iters, times, avg_times, verdict);
+            report.measured(&id, &context, &measurements);
+        }
+        let reported: Vec<_> = report
+            .group_medians
+            .borrow()
+            .iter()
+            .map(|&(ref id, _)| id.clone())
+            .collect();
+        assert_eq!(vec!["group/regressed", "group/improved"], reported);
+        assert_eq!(2, report.unchanged.get());
+
+        // Without the option, every benchmark is reported.
+        context.report_only_changed = false;
+        let report = CliReport::new(false, false, false);
+        for &(id, verdict) in &benchmarks {
+            let id = BenchmarkId::new(id.to_owned(), None, None, None);
+            let measurements = measurements(iters, times, avg_times, verdict);
+            report.measured(&id, &context, &measurements);
+        }
+        assert_eq!(4, report.group_medians.borrow().len());
+        assert_eq!(0, report.unchanged.get());
+    }
+
+    #[test]
+    fn test_quiet_report() {
+        let iters = [1.0, 2.0, 3.0, 4.0];
+        let times = [10.0, 21.0, 29.0, 40.0];
+        let avg_times = [10.0, 10.5, 9.667, 10.0];
+        let (iters, times, avg_times) = (
+            Sample::new(&iters),
+            Sample::new(&times),
+            Sample::new(&avg_times),
+        );
+        let context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+
+        let report = QuietReport::with_output(vec![]);
+        for &(id, verdict) in &[
+            ("group/improved", ComparisonResult::Improved),
+            ("group/regressed", ComparisonResult::Regressed),
+            ("group/unchanged", ComparisonResult::NonSignificant),
+        ] {
+            let id = BenchmarkId::new(id.to_owned(), None, None, None);
+            let measurements = measurements(iters, times, avg_times, verdict);
+            report.measured(&id, &context, &measurements);
+        }
+        report.final_summary(&context);
+
+        let output = String::from_utf8(report.out.into_inner()).unwrap();
+        assert_eq!(
+            "group/regressed: regressed, change: +20.000% [+18.000%, +22.000%]\n\
+             3 benchmarks measured, 1 regressed.\n",
+            output
+        );
+    }
 }
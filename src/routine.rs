@@ -1,10 +1,17 @@
 use benchmark::BenchmarkConfig;
+use std::cmp;
+use std::process;
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::thread;
 use std::time::{Duration, Instant};
 
+use error::{Result, TimeoutError, TooSlowError};
 use program::Program;
-use report::{BenchmarkId, ReportContext};
+use report::{BenchmarkId, ReportContext, Reports};
+use run_time::{Clock, SystemClock};
+use stats::univariate::Sample;
 use std::marker::PhantomData;
-use {Bencher, Criterion, DurationExt};
+use {format, Bencher, Criterion, DurationExt};
 
 /// PRIVATE
 pub trait Routine<T> {
@@ -16,50 +23,338 @@ pub trait Routine<T> {
     fn warm_up(&mut self, m: &mut Option<Program>, how_long: Duration, parameter: &T)
         -> (u64, u64);
 
+    /// PRIVATE
+    fn elements_per_iteration(&self) -> Option<f64> {
+        None
+    }
+
+    /// PRIVATE
+    fn control_variate(&self) -> Option<(&[f64], f64)> {
+        None
+    }
+
+    /// PRIVATE
+    fn teardown(&mut self) {}
+
     /// PRIVATE
     fn test(&mut self, parameter: &T) {
         let mut m = self.start(parameter);
         self.bench(&mut m, &[1u64], parameter);
     }
 
+    /// PRIVATE
+    fn profile(
+        &mut self,
+        id: &BenchmarkId,
+        criterion: &Criterion,
+        report_context: &ReportContext,
+        how_long: Duration,
+        parameter: &T,
+    ) {
+        criterion
+            .report
+            .profile(id, report_context, how_long.to_nanos() as f64);
+
+        let mut m = self.start(parameter);
+
+        // The warm-up runs the routine continuously for the given time, which is exactly what a
+        // profiler needs to see.
+        self.warm_up(&mut m, how_long, parameter);
+    }
+
     fn sample(
         &mut self,
         id: &BenchmarkId,
         config: &BenchmarkConfig,
-        criterion: &Criterion,
+        report: &Reports,
         report_context: &ReportContext,
         parameter: &T,
-    ) -> (Box<[f64]>, Box<[f64]>) {
+    ) -> Result<(Box<[f64]>, Box<[f64]>)> {
         let wu = config.warm_up_time;
         let m_ns = config.measurement_time.to_nanos();
 
-        criterion
-            .report
-            .warmup(id, report_context, wu.to_nanos() as f64);
+        report.warmup(id, report_context, wu.to_nanos() as f64);
 
         let mut m = self.start(parameter);
 
+        let watchdog = config
+            .timeout
+            .map(|timeout| (timeout, Watchdog::start(id.id(), timeout)));
+        let timed_out = || TimeoutError {
+            id: id.id().to_owned(),
+            timeout: format::time(watchdog.as_ref().unwrap().0.to_nanos() as f64),
+        };
+
+        // The last doubling of the warm-up can take up to twice as long as everything before it.
+        if let Some((timeout, ref watchdog)) = watchdog {
+            watchdog.arm(wu * 3 + timeout);
+        }
         let (wu_elapsed, wu_iters) = self.warm_up(&mut m, wu, parameter);
 
+        // The warm-up only stops after the first iteration if that iteration alone took longer
+        // than the whole warm-up. Measuring a routine this slow would take practically forever.
+        if wu_iters <= 1 {
+            return Err(TooSlowError {
+                id: id.id().to_owned(),
+                warm_up: format::time(wu.to_nanos() as f64),
+            }.into());
+        }
+
         // Initial guess for the mean execution time
         let met = wu_elapsed as f64 / wu_iters as f64;
+        if let Some((timeout, _)) = watchdog {
+            if met > timeout.to_nanos() as f64 {
+                return Err(timed_out().into());
+            }
+        }
 
-        let n = config.sample_size as u64;
-        // Solve: [d + 2*d + 3*d + ... + n*d] * met = m_ns
-        let total_runs = n * (n + 1) / 2;
-        let d = (m_ns as f64 / met / total_runs as f64).ceil() as u64;
+        // With a timeout, the samples are measured one at a time under the watchdog. Once a sample
+        // takes too long per iteration, the rest are skipped, as the benchmark has failed anyway.
+        let mut failed = false;
+        let mut bench = |iters: &[u64]| match watchdog {
+            None => self.bench(&mut m, iters, parameter),
+            Some((timeout, ref watchdog)) => iters
+                .iter()
+                .map(|&n| {
+                    if failed {
+                        return 0.;
+                    }
+                    watchdog.arm(Duration::from_nanos(timeout.to_nanos().saturating_mul(n)));
+                    let elapsed = self.bench(&mut m, &[n], parameter)[0];
+                    failed = elapsed / n as f64 > timeout.to_nanos() as f64;
+                    elapsed
+                })
+                .collect(),
+        };
 
-        let m_iters = (1..(n + 1) as u64).map(|a| a * d).collect::<Vec<u64>>();
+        let repeats = config.measurement_repeats;
+        let n = (config.sample_size * repeats) as u64;
+        let ramp = iteration_counts(met, m_ns as f64, config);
 
-        let m_ns = total_runs as f64 * d as f64 * met;
-        criterion
-            .report
-            .measurement_start(id, report_context, n, m_ns, m_iters.iter().sum());
-        let m_elapsed = self.bench(&mut m, &m_iters, parameter);
+        let total_iters = ramp.iter().sum::<u64>() * repeats as u64;
+        let m_ns = total_iters as f64 * met;
+        report.measurement_start(id, report_context, n, m_ns, total_iters);
+        let (mut m_iters, mut m_elapsed) =
+            repeat_measurement(&ramp, repeats, &mut bench, perturb_caches);
+
+        let extension = extend_measurement(
+            &SystemClock,
+            config,
+            &mut m_iters,
+            &mut m_elapsed,
+            &mut bench,
+        );
+        if failed {
+            return Err(timed_out().into());
+        }
+        if extension > 0 {
+            info!(
+                "{}: measured {} additional samples to narrow the confidence interval",
+                id, extension
+            );
+        }
 
         let m_iters_f: Vec<f64> = m_iters.iter().map(|&x| x as f64).collect();
 
-        (m_iters_f.into_boxed_slice(), m_elapsed.into_boxed_slice())
+        Ok((m_iters_f.into_boxed_slice(), m_elapsed.into_boxed_slice()))
+    }
+}
+
+// A routine which takes this many times as long as its timeout allows is considered to hang. Slow
+// routines which do finish are failed by the caller instead, so this leaves them plenty of slack.
+const HANG_FACTOR: u32 = 10;
+
+// Exits the process with an error if the routine runs far past the time it's armed with. A thread
+// which doesn't cooperate can't be stopped, so a routine which hangs would otherwise keep the
+// benchmarks from ever finishing.
+struct Watchdog {
+    deadlines: Sender<Option<Instant>>,
+}
+impl Watchdog {
+    fn start(id: &str, timeout: Duration) -> Watchdog {
+        let id = id.to_owned();
+        let (deadlines, rx) = mpsc::channel();
+        thread::spawn(move || {
+            let mut deadline: Option<Instant> = None;
+            loop {
+                let next = match deadline {
+                    Some(deadline) => {
+                        let now = Instant::now();
+                        if now >= deadline {
+                            error!(
+                                "Benchmark {} seems to hang: it has run for {} times as long as \
+                                 its timeout of {} per iteration allows. Aborting the run.",
+                                id,
+                                HANG_FACTOR,
+                                format::time(timeout.to_nanos() as f64)
+                            );
+                            process::exit(1);
+                        }
+                        rx.recv_timeout(deadline - now)
+                    }
+                    None => rx.recv().map_err(|_| RecvTimeoutError::Disconnected),
+                };
+                match next {
+                    Ok(next) => deadline = next,
+                    Err(RecvTimeoutError::Timeout) => {}
+                    // The benchmark is over.
+                    Err(RecvTimeoutError::Disconnected) => return,
+                }
+            }
+        });
+        Watchdog { deadlines }
+    }
+
+    // Expects the routine to finish within `how_long` from now, replacing the previous deadline.
+    fn arm(&self, how_long: Duration) {
+        // A deadline too far away to represent can't be reached either.
+        let deadline = how_long
+            .checked_mul(HANG_FACTOR)
+            .and_then(|how_long| Instant::now().checked_add(how_long));
+        let _ = self.deadlines.send(deadline);
+    }
+}
+
+// Chooses the number of iterations of each sample, such that the samples take about `m_ns`
+// nanoseconds in total, given the mean execution time `met` in nanoseconds.
+pub(crate) fn iteration_counts(met: f64, m_ns: f64, config: &BenchmarkConfig) -> Vec<u64> {
+    let n = config.sample_size as u64;
+    // Solve: [d + 2*d + 3*d + ... + n*d] * met = m_ns
+    let total_runs = n * (n + 1) / 2;
+    let d = (m_ns / met / total_runs as f64).ceil() as u64;
+    // The smallest sample runs `d` iterations, so this is the floor for every sample
+    let d = cmp::max(d, config.min_iters_per_sample);
+
+    (1..(n + 1) as u64).map(|a| a * d).collect()
+}
+
+// Measures the samples with the iteration counts `ramp` `repeats` times, calling `perturb` between
+// the repeats. Returns the iteration counts and elapsed times of the samples of all repeats.
+fn repeat_measurement<F, P>(
+    ramp: &[u64],
+    repeats: usize,
+    mut bench: F,
+    mut perturb: P,
+) -> (Vec<u64>, Vec<f64>)
+where
+    F: FnMut(&[u64]) -> Vec<f64>,
+    P: FnMut(),
+{
+    let mut iters = Vec::with_capacity(ramp.len() * repeats);
+    let mut times = Vec::with_capacity(ramp.len() * repeats);
+    for repeat in 0..repeats {
+        if repeat > 0 {
+            perturb();
+        }
+        times.extend(bench(ramp));
+        iters.extend_from_slice(ramp);
+    }
+
+    (iters, times)
+}
+
+// Size of the buffer written between repeated measurements, which is larger than the last-level
+// cache of common processors.
+const CACHE_PERTURBATION_BYTES: usize = 64 * 1024 * 1024;
+
+// Evicts the data of the benchmarked routine from the caches by writing to every cache line of a
+// large buffer.
+fn perturb_caches() {
+    let mut buffer = vec![0u8; CACHE_PERTURBATION_BYTES];
+    for (i, byte) in buffer.iter_mut().enumerate().step_by(64) {
+        *byte = i as u8;
+    }
+    ::black_box(&buffer);
+}
+
+// Number of resamples for the estimate of the confidence interval which decides whether to extend
+// the measurement. This is far fewer than for the final estimates, as it only needs to be roughly
+// right.
+const EXTENSION_RESAMPLES: usize = 1000;
+
+// Width of the confidence interval of the mean time per iteration, relative to the mean
+fn relative_ci_width(iters: &[u64], times: &[f64], cl: f64) -> f64 {
+    let avg_times: Vec<f64> = iters
+        .iter()
+        .zip(times)
+        .map(|(&iters, &elapsed)| elapsed / iters as f64)
+        .collect();
+    let avg_times = Sample::new(&avg_times);
+
+    let (distribution,) = avg_times.bootstrap(EXTENSION_RESAMPLES, |s| (s.mean(),));
+    let (lb, ub) = distribution.confidence_interval(cl);
+    (ub - lb) / avg_times.mean()
+}
+
+// While the confidence interval is wider than the target, measures batches of additional samples
+// which continue the ramp of iteration counts, as long as the next batch is expected to fit into
+// the maximum measurement time. Returns the number of samples added.
+pub(crate) fn extend_measurement<C, F>(
+    clock: &C,
+    config: &BenchmarkConfig,
+    iters: &mut Vec<u64>,
+    times: &mut Vec<f64>,
+    mut bench: F,
+) -> usize
+where
+    C: Clock,
+    F: FnMut(&[u64]) -> Vec<f64>,
+{
+    let target = match config.target_ci_width {
+        Some(target) => target,
+        None => return 0,
+    };
+    let budget = match config
+        .max_measurement_time
+        .checked_sub(config.measurement_time)
+    {
+        Some(budget) => budget.to_nanos() as f64,
+        None => return 0,
+    };
+
+    let d = iters[0];
+    let batch_size = cmp::max(config.sample_size / 2, 1) as u64;
+    let start = clock.now();
+    let mut added = 0;
+    while relative_ci_width(iters, times, config.confidence_level) > target {
+        let n = iters.len() as u64;
+        let batch: Vec<u64> = (n + 1..n + batch_size + 1).map(|a| a * d).collect();
+
+        let met = times.iter().sum::<f64>() / iters.iter().sum::<u64>() as f64;
+        let elapsed = (clock.now() - start).to_nanos() as f64;
+        if elapsed + batch.iter().sum::<u64>() as f64 * met > budget {
+            break;
+        }
+
+        times.extend(bench(&batch));
+        added += batch.len();
+        iters.extend(batch);
+    }
+
+    added
+}
+
+// Runs the routine with doubling iteration counts until `how_long` has elapsed. Returns the
+// elapsed time in nanoseconds and the total number of iterations.
+pub(crate) fn warm_up_loop<C, F>(clock: &C, how_long: Duration, mut run: F) -> (u64, u64)
+where
+    C: Clock,
+    F: FnMut(u64),
+{
+    let mut iters = 1;
+
+    let mut total_iters = 0;
+    let start = clock.now();
+    loop {
+        run(iters);
+
+        total_iters += iters;
+        let elapsed = clock.now() - start;
+        if elapsed > how_long {
+            return (elapsed.to_nanos(), total_iters);
+        }
+
+        iters *= 2;
     }
 }
 
@@ -68,6 +363,8 @@ where
     F: FnMut(&mut Bencher, &T),
 {
     f: F,
+    // Elements and iterations counted by `Bencher::iter_counted` during the measurement
+    counted: Option<(u64, u64)>,
     _phantom: PhantomData<T>,
 }
 impl<F, T> Function<F, T>
@@ -77,6 +374,7 @@ where
     pub fn new(f: F) -> Function<F, T> {
         Function {
             f,
+            counted: None,
             _phantom: PhantomData,
         }
     }
@@ -90,21 +388,34 @@ where
         None
     }
 
+    fn elements_per_iteration(&self) -> Option<f64> {
+        self.counted
+            .map(|(elements, iters)| elements as f64 / iters as f64)
+    }
+
     fn bench(&mut self, _: &mut Option<Program>, iters: &[u64], parameter: &T) -> Vec<f64> {
         let f = &mut self.f;
+        let counted = &mut self.counted;
 
         let mut b = Bencher {
             iterated: false,
             iters: 0,
             elapsed: Duration::from_secs(0),
+            elements: None,
         };
 
         iters
             .iter()
             .map(|iters| {
                 b.iters = *iters;
+                b.elements = None;
                 (*f)(&mut b, parameter);
                 b.assert_iterated();
+                if let Some(elements) = b.elements {
+                    let totals = counted.get_or_insert((0, 0));
+                    totals.0 += elements;
+                    totals.1 += *iters;
+                }
                 b.elapsed.to_nanos() as f64
             })
             .collect()
@@ -121,22 +432,363 @@ where
             iterated: false,
             iters: 1,
             elapsed: Duration::from_secs(0),
+            elements: None,
         };
 
-        let mut total_iters = 0;
-        let start = Instant::now();
-        loop {
+        warm_up_loop(&SystemClock, how_long, |iters| {
+            b.iters = iters;
             (*f)(&mut b, parameter);
 
             b.assert_iterated();
+        })
+    }
+}
 
-            total_iters += b.iters;
-            let elapsed = start.elapsed();
-            if elapsed > how_long {
-                return (elapsed.to_nanos(), total_iters);
-            }
+// Routine which runs `setup` when it starts, and `teardown` once the benchmark is over
+pub struct Fixture<R, S, T>
+where
+    R: Routine<()>,
+    S: FnMut(),
+    T: FnMut(),
+{
+    routine: R,
+    setup: S,
+    teardown: T,
+}
+impl<R, S, T> Fixture<R, S, T>
+where
+    R: Routine<()>,
+    S: FnMut(),
+    T: FnMut(),
+{
+    pub fn new(routine: R, setup: S, teardown: T) -> Fixture<R, S, T> {
+        Fixture {
+            routine,
+            setup,
+            teardown,
+        }
+    }
+}
+
+impl<R, S, T> Routine<()> for Fixture<R, S, T>
+where
+    R: Routine<()>,
+    S: FnMut(),
+    T: FnMut(),
+{
+    fn start(&mut self, parameter: &()) -> Option<Program> {
+        (self.setup)();
+        self.routine.start(parameter)
+    }
+
+    fn bench(&mut self, m: &mut Option<Program>, iters: &[u64], parameter: &()) -> Vec<f64> {
+        self.routine.bench(m, iters, parameter)
+    }
+
+    fn warm_up(
+        &mut self,
+        m: &mut Option<Program>,
+        how_long: Duration,
+        parameter: &(),
+    ) -> (u64, u64) {
+        self.routine.warm_up(m, how_long, parameter)
+    }
+
+    fn elements_per_iteration(&self) -> Option<f64> {
+        self.routine.elements_per_iteration()
+    }
 
-            b.iters *= 2;
+    fn teardown(&mut self) {
+        (self.teardown)();
+        self.routine.teardown();
+    }
+}
+
+// Routine which runs a control routine after each sample of the main routine, with the same number
+// of iterations, so that both are affected alike by the state of the machine. The control's known
+// time per iteration is used to cancel out some of the noise of the main routine's samples.
+pub struct Controlled<R, C>
+where
+    R: Routine<()>,
+    C: Routine<()>,
+{
+    routine: R,
+    control: C,
+    control_ns: f64,
+    control_times: Vec<f64>,
+}
+impl<R, C> Controlled<R, C>
+where
+    R: Routine<()>,
+    C: Routine<()>,
+{
+    pub fn new(routine: R, control: C, control_ns: f64) -> Controlled<R, C> {
+        Controlled {
+            routine,
+            control,
+            control_ns,
+            control_times: vec![],
         }
     }
 }
+impl<R, C> Routine<()> for Controlled<R, C>
+where
+    R: Routine<()>,
+    C: Routine<()>,
+{
+    fn start(&mut self, parameter: &()) -> Option<Program> {
+        self.control_times.clear();
+        self.routine.start(parameter)
+    }
+
+    fn bench(&mut self, m: &mut Option<Program>, iters: &[u64], parameter: &()) -> Vec<f64> {
+        let mut control_m = None;
+        iters
+            .iter()
+            .map(|&iters| {
+                let elapsed = self.routine.bench(m, &[iters], parameter)[0];
+                let control_elapsed = self.control.bench(&mut control_m, &[iters], parameter)[0];
+                self.control_times.push(control_elapsed);
+                elapsed
+            })
+            .collect()
+    }
+
+    fn warm_up(
+        &mut self,
+        m: &mut Option<Program>,
+        how_long: Duration,
+        parameter: &(),
+    ) -> (u64, u64) {
+        self.routine.warm_up(m, how_long, parameter)
+    }
+
+    fn elements_per_iteration(&self) -> Option<f64> {
+        self.routine.elements_per_iteration()
+    }
+
+    fn control_variate(&self) -> Option<(&[f64], f64)> {
+        Some((&self.control_times, self.control_ns))
+    }
+
+    fn teardown(&mut self) {
+        self.routine.teardown();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use plotting::Plotting;
+    use run_time::FakeClock;
+    use PlotConfiguration;
+
+    // Routine with a fixed, fake timing which records the iteration counts it's asked to run.
+    struct FakeRoutine {
+        ns_per_iter: u64,
+        iters: Vec<u64>,
+    }
+    impl Routine<()> for FakeRoutine {
+        fn start(&mut self, _: &()) -> Option<Program> {
+            None
+        }
+
+        fn bench(&mut self, _: &mut Option<Program>, iters: &[u64], _: &()) -> Vec<f64> {
+            self.iters.extend_from_slice(iters);
+            iters
+                .iter()
+                .map(|&i| (i * self.ns_per_iter) as f64)
+                .collect()
+        }
+
+        fn warm_up(&mut self, _: &mut Option<Program>, how_long: Duration, _: &()) -> (u64, u64) {
+            let clock = FakeClock::new();
+            let ns_per_iter = self.ns_per_iter;
+            warm_up_loop(&clock, how_long, |iters| {
+                clock.advance(Duration::from_nanos(iters * ns_per_iter))
+            })
+        }
+    }
+
+    fn sample_iters(ns_per_iter: u64, min_iters_per_sample: u64) -> Result<Vec<u64>> {
+        let criterion = Criterion::default()
+            .sample_size(10)
+            .measurement_time(Duration::from_millis(1))
+            .min_iters_per_sample(min_iters_per_sample);
+        let context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+        let id = BenchmarkId::new("group".to_owned(), None, None, None);
+        let mut routine = FakeRoutine {
+            ns_per_iter,
+            iters: vec![],
+        };
+
+        routine.sample(&id, &criterion.config, &criterion.report, &context, &())?;
+        Ok(routine.iters)
+    }
+
+    #[test]
+    fn min_iters_per_sample_floor() {
+        // Without the floor, a routine this slow would run a single iteration in the first sample.
+        assert_eq!(1, sample_iters(1_000_000, 1).unwrap()[0]);
+
+        let iters = sample_iters(1_000_000, 50).unwrap();
+        assert!(iters.iter().all(|&i| i >= 50));
+        assert_eq!((1..11).map(|i| i * 50).collect::<Vec<_>>(), iters);
+    }
+
+    #[test]
+    fn every_repeat_contributes_samples() {
+        let mut criterion = Criterion::default()
+            .sample_size(10)
+            .measurement_time(Duration::from_millis(1))
+            .measurement_repeats(3);
+        criterion.config.warm_up_time = Duration::from_millis(1);
+        let context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+        let id = BenchmarkId::new("group".to_owned(), None, None, None);
+        let mut routine = FakeRoutine {
+            ns_per_iter: 1000,
+            iters: vec![],
+        };
+
+        let (iters, times) = routine
+            .sample(&id, &criterion.config, &criterion.report, &context, &())
+            .unwrap();
+        assert_eq!(30, iters.len());
+        assert_eq!(30, times.len());
+        let ramp: Vec<f64> = iters[..10].to_vec();
+        assert_eq!(&ramp[..], &iters[10..20]);
+        assert_eq!(&ramp[..], &iters[20..]);
+
+        let mut perturbations = 0;
+        let (iters, times) = repeat_measurement(
+            &[1, 2, 3],
+            4,
+            |iters| iters.iter().map(|&i| i as f64).collect(),
+            || perturbations += 1,
+        );
+        assert_eq!(3, perturbations);
+        assert_eq!(vec![1, 2, 3, 1, 2, 3, 1, 2, 3, 1, 2, 3], iters);
+        assert_eq!(12, times.len());
+    }
+
+    #[test]
+    fn too_slow_for_warm_up() {
+        // A single iteration takes longer than the default three second warm-up.
+        let error = sample_iters(10_000_000_000, 1).unwrap_err();
+        let error = error.downcast::<TooSlowError>().unwrap();
+        assert_eq!("group", error.id);
+    }
+
+    #[test]
+    fn counted_elements_are_averaged() {
+        let mut next = 0;
+        let mut routine = Function::new(|b: &mut Bencher, _: &()| {
+            // Processes 1, 2, 3, 4, 5, 1, 2, ... elements.
+            b.iter_counted(|| {
+                next = next % 5 + 1;
+                next
+            })
+        });
+        assert_eq!(None, routine.elements_per_iteration());
+
+        routine.bench(&mut None, &[5, 10, 15], &());
+        assert_eq!(Some(3.0), routine.elements_per_iteration());
+    }
+
+    #[test]
+    fn amortized_setup_runs_once_per_batch() {
+        let mut setups = 0;
+        let mut operations = 0;
+        let mut routine = Function::new(|b: &mut Bencher, _: &()| {
+            b.iter_amortized(
+                || {
+                    setups += 1;
+                    thread::sleep(Duration::from_millis(50));
+                    vec![0u64; 16]
+                },
+                |state| {
+                    operations += 1;
+                    state[0] += 1;
+                    thread::sleep(Duration::from_millis(1));
+                },
+                4,
+            )
+        });
+
+        let times = routine.bench(&mut None, &[8], &());
+        assert_eq!(Some(1.0), routine.elements_per_iteration());
+        drop(routine);
+        assert_eq!(2, setups);
+        assert_eq!(8, operations);
+
+        // Only the operations are timed, so the time per iteration is that of one operation.
+        let per_operation = times[0] / 8.;
+        assert!(per_operation >= 1e6 && per_operation < 50e6 / 8.);
+    }
+
+    // Measures ten samples of a routine taking 1us per iteration, which is alternately slower and
+    // faster by `noise`, and extends the measurement. Returns the number of samples added and the
+    // time spent on them.
+    fn extension(noise: f64) -> (usize, Duration) {
+        let criterion = Criterion::default()
+            .sample_size(10)
+            .measurement_time(Duration::from_millis(1))
+            .target_ci_width(0.05)
+            .max_measurement_time(Duration::from_millis(100));
+        let config = &criterion.config;
+        let clock = FakeClock::new();
+        let sample_time = |i: usize, iters: u64| {
+            let factor = if i % 2 == 0 { 1. + noise } else { 1. - noise };
+            iters as f64 * 1000. * factor
+        };
+
+        let mut iters: Vec<u64> = (1..11).collect();
+        let mut times: Vec<f64> = iters
+            .iter()
+            .enumerate()
+            .map(|(i, &iters)| sample_time(i, iters))
+            .collect();
+        let mut count = times.len();
+
+        let start = clock.now();
+        let added = extend_measurement(&clock, config, &mut iters, &mut times, |batch| {
+            batch
+                .iter()
+                .map(|&iters| {
+                    let time = sample_time(count, iters);
+                    count += 1;
+                    clock.advance(Duration::from_nanos(time as u64));
+                    time
+                })
+                .collect()
+        });
+
+        assert_eq!(10 + added, iters.len());
+        assert_eq!(iters.len(), times.len());
+        (added, clock.now() - start)
+    }
+
+    #[test]
+    fn noisy_measurement_is_extended() {
+        let (added, elapsed) = extension(0.5);
+        assert!(added > 0);
+        // The extension has to fit into the remaining 99ms.
+        assert!(elapsed <= Duration::from_millis(99));
+
+        assert_eq!(0, extension(0.001).0);
+    }
+}
@@ -0,0 +1,217 @@
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+
+use format;
+use report::{BenchmarkId, MeasurementData, PairedComparison, ReportContext, Reporter};
+use DurationExt;
+
+/// Source of the current time, so that the phase timings can be tested deterministically.
+pub(crate) trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub(crate) struct SystemClock;
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// Clock which only moves when told to.
+#[cfg(test)]
+pub(crate) struct FakeClock {
+    now: Cell<Instant>,
+}
+#[cfg(test)]
+impl FakeClock {
+    pub fn new() -> FakeClock {
+        FakeClock {
+            now: Cell::new(Instant::now()),
+        }
+    }
+
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+#[cfg(test)]
+impl Clock for FakeClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Phase {
+    WarmUp,
+    Measurement,
+    Analysis,
+}
+
+/// Wall time spent in each phase of benchmarking.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) struct PhaseTimes {
+    pub warm_up: Duration,
+    pub measurement: Duration,
+    pub analysis: Duration,
+}
+impl Default for PhaseTimes {
+    fn default() -> PhaseTimes {
+        PhaseTimes {
+            warm_up: Duration::from_secs(0),
+            measurement: Duration::from_secs(0),
+            analysis: Duration::from_secs(0),
+        }
+    }
+}
+impl PhaseTimes {
+    pub fn total(&self) -> Duration {
+        self.warm_up + self.measurement + self.analysis
+    }
+
+    fn add(&mut self, phase: Phase, elapsed: Duration) {
+        match phase {
+            Phase::WarmUp => self.warm_up += elapsed,
+            Phase::Measurement => self.measurement += elapsed,
+            Phase::Analysis => self.analysis += elapsed,
+        }
+    }
+}
+
+// Every benchmark group is run by its own `Criterion` instance, and the final summary is printed
+// by yet another one, so the totals for the whole run have to live outside of the reports.
+thread_local! {
+    static RUN_TIMES: Cell<PhaseTimes> = Cell::new(PhaseTimes::default());
+}
+
+/// Report which keeps track of the wall time spent warming up, measuring and analyzing the
+/// benchmarks, and prints the totals for the whole run in the final summary.
+pub(crate) struct RunTimeReport<C: Clock = SystemClock> {
+    clock: C,
+    current: Cell<Option<(Phase, Instant)>>,
+    times: Cell<PhaseTimes>,
+}
+impl RunTimeReport<SystemClock> {
+    pub fn new() -> RunTimeReport<SystemClock> {
+        RunTimeReport::with_clock(SystemClock)
+    }
+}
+impl<C: Clock> RunTimeReport<C> {
+    fn with_clock(clock: C) -> RunTimeReport<C> {
+        RunTimeReport {
+            clock,
+            current: Cell::new(None),
+            times: Cell::new(PhaseTimes::default()),
+        }
+    }
+
+    fn start(&self, phase: Phase) {
+        self.finish();
+        self.current.set(Some((phase, self.clock.now())));
+    }
+
+    fn finish(&self) {
+        if let Some((phase, start)) = self.current.take() {
+            let elapsed = self.clock.now() - start;
+
+            let mut times = self.times.get();
+            times.add(phase, elapsed);
+            self.times.set(times);
+
+            RUN_TIMES.with(|run_times| {
+                let mut times = run_times.get();
+                times.add(phase, elapsed);
+                run_times.set(times);
+            });
+        }
+    }
+}
+
+fn summary(times: &PhaseTimes) -> String {
+    let time = |duration: Duration| format::time(duration.to_nanos() as f64);
+
+    format!(
+        "Total time spent benchmarking: {}\n  warm-up:     {}\n  measurement: {}\n  analysis:    {}",
+        time(times.total()),
+        time(times.warm_up),
+        time(times.measurement),
+        time(times.analysis),
+    )
+}
+
+impl<C: Clock> Reporter for RunTimeReport<C> {
+    fn warmup(&self, _: &BenchmarkId, _: &ReportContext, _: f64) {
+        self.start(Phase::WarmUp);
+    }
+
+    fn terminated(&self, _: &BenchmarkId, _: &ReportContext) {
+        self.finish();
+    }
+
+    fn analysis(&self, _: &BenchmarkId, _: &ReportContext) {
+        self.start(Phase::Analysis);
+    }
+
+    fn measurement_start(&self, _: &BenchmarkId, _: &ReportContext, _: u64, _: f64, _: u64) {
+        self.start(Phase::Measurement);
+    }
+
+    fn measured(&self, _: &BenchmarkId, _: &ReportContext, _: &MeasurementData) {
+        self.finish();
+    }
+
+    fn paired_comparison(&self, _: &BenchmarkId, _: &ReportContext, _: &PairedComparison) {
+        self.finish();
+    }
+
+    fn final_summary(&self, _: &ReportContext) {
+        let times = RUN_TIMES.with(|run_times| run_times.get());
+
+        if times.total() > Duration::from_secs(0) {
+            println!("{}", summary(&times));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use plotting::Plotting;
+    use PlotConfiguration;
+
+    #[test]
+    fn test_total_is_sum_of_phases() {
+        let report = RunTimeReport::with_clock(FakeClock::new());
+        let id = BenchmarkId::new("group".to_owned(), None, None, None);
+        let context = ReportContext {
+            output_directory: "target/criterion".to_owned(),
+            plotting: Plotting::Disabled,
+            plot_config: PlotConfiguration::default(),
+            test_mode: false,
+            report_only_changed: false,
+            run_label: None,
+        };
+
+        for _ in 0..2 {
+            report.warmup(&id, &context, 0.0);
+            report.clock.advance(Duration::from_millis(300));
+            report.measurement_start(&id, &context, 100, 0.0, 0);
+            report.clock.advance(Duration::from_millis(500));
+            report.analysis(&id, &context);
+            report.clock.advance(Duration::from_millis(200));
+            report.terminated(&id, &context);
+            // Time between benchmarks doesn't belong to any phase.
+            report.clock.advance(Duration::from_secs(7));
+        }
+
+        let times = report.times.get();
+        assert_eq!(times.warm_up, Duration::from_millis(600));
+        assert_eq!(times.measurement, Duration::from_millis(1000));
+        assert_eq!(times.analysis, Duration::from_millis(400));
+        assert_eq!(times.total(), Duration::from_secs(2));
+
+        let summary = summary(&times);
+        assert!(summary.contains(&format::time(2e9)));
+        assert!(summary.contains(&format::time(6e8)));
+    }
+}
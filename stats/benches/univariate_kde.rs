@@ -11,7 +11,7 @@ macro_rules! bench {
         pub mod $ty {
             use criterion::Criterion;
             use stats::univariate::Sample;
-            use stats::univariate::kde::kernel::Gaussian;
+            use stats::univariate::kde::kernel::{Gaussian, TabulatedGaussian};
             use stats::univariate::kde::{Bandwidth, Kde};
 
             const KDE_POINTS: usize = 100;
@@ -43,10 +43,24 @@ macro_rules! bench {
                 );
             }
 
+            fn map_tabulated(c: &mut Criterion) {
+                let data = ::common_bench::vec_sized(SAMPLE_SIZE).unwrap();
+                let xs: Vec<_> = ::itertools_num::linspace::<$ty>(0., 1., KDE_POINTS).collect();
+
+                c.bench_function(
+                    &format!("univariate_kde_map_tabulated_{}", stringify!($ty)),
+                    move |b| {
+                        let kernel = TabulatedGaussian::new(1024, 6.);
+                        let kde = Kde::new(Sample::new(&data), kernel, Bandwidth::Silverman);
+                        b.iter(|| kde.map(&xs))
+                    },
+                );
+            }
+
             criterion_group!{
                 name = benches;
                 config = ::common_bench::reduced_samples();
-                targets = call, map
+                targets = call, map, map_tabulated
             }
         }
     };
@@ -3,12 +3,19 @@
 use float::Float;
 
 /// Kernel function
-pub trait Kernel<A>: Copy + Sync
+pub trait Kernel<A>: Clone + Sync
 where
     A: Float,
 {
     /// Apply the kernel function to the given x-value.
     fn evaluate(&self, x: A) -> A;
+
+    /// The interval outside of which the kernel function is zero, or `None` if it's nonzero
+    /// everywhere. Data points farther than this from an x-value are skipped when estimating the
+    /// density there.
+    fn support(&self) -> Option<(A, A)> {
+        None
+    }
 }
 
 /// Gaussian kernel
@@ -26,55 +33,249 @@ where
     }
 }
 
+/// Gaussian kernel, interpolated from a table of its values and zero beyond a cutoff
+///
+/// Evaluating the `Gaussian` kernel takes an `exp` and a `sqrt`, which dominate the time spent
+/// estimating the density of large samples. This kernel interpolates linearly between values of
+/// the Gaussian precomputed at evenly-spaced points instead, trading a little accuracy for speed.
+/// As it has compact support, data points beyond the cutoff are skipped altogether.
+#[derive(Clone)]
+pub struct TabulatedGaussian<A>
+where
+    A: Float,
+{
+    cutoff: A,
+    // Number of points of the table per unit of `x`
+    density: A,
+    table: Vec<A>,
+}
+
+impl<A> TabulatedGaussian<A>
+where
+    A: Float,
+{
+    /// Tabulates the Gaussian kernel at `resolution` evenly-spaced points of `[0, cutoff]`
+    ///
+    /// The error of the interpolation shrinks with the square of the resolution, and the error
+    /// beyond the cutoff is the value of the Gaussian there, eg. about `6e-9` for a cutoff of 6.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `resolution` is less than 2 or if `cutoff` isn't positive and finite.
+    pub fn new(resolution: usize, cutoff: A) -> TabulatedGaussian<A> {
+        assert!(resolution >= 2);
+        assert!(cutoff > A::cast(0) && cutoff.is_finite());
+
+        let density = A::cast(resolution - 1) / cutoff;
+        let table = (0..resolution)
+            .map(|i| Gaussian.evaluate(A::cast(i) / density))
+            .collect();
+
+        TabulatedGaussian {
+            cutoff,
+            density,
+            table,
+        }
+    }
+}
+
+impl<A> Kernel<A> for TabulatedGaussian<A>
+where
+    A: Float,
+{
+    fn evaluate(&self, x: A) -> A {
+        let x = x.abs();
+        if x > self.cutoff {
+            return A::cast(0);
+        }
+
+        let position = x * self.density;
+        let i = match position.to_usize() {
+            // The last point has no successor to interpolate towards, so the interval before it
+            // is used instead
+            Some(i) => i.min(self.table.len() - 2),
+            // NaN
+            None => return x,
+        };
+        let fraction = position - A::cast(i);
+
+        self.table[i] + (self.table[i + 1] - self.table[i]) * fraction
+    }
+
+    fn support(&self) -> Option<(A, A)> {
+        Some((-self.cutoff, self.cutoff))
+    }
+}
+
+/// Epanechnikov kernel, `0.75 * (1 - x^2)` within `[-1, 1]` and zero outside of it
+///
+/// Unlike the Gaussian kernel, it has compact support, so the estimated density is zero beyond
+/// one bandwidth of the data points instead of decaying slowly.
+#[derive(Clone, Copy)]
+pub struct Epanechnikov;
+
+impl<A> Kernel<A> for Epanechnikov
+where
+    A: Float,
+{
+    fn evaluate(&self, x: A) -> A {
+        if x.abs() <= A::cast(1) {
+            A::cast(0.75) * (A::cast(1) - x.powi(2))
+        } else {
+            A::cast(0)
+        }
+    }
+
+    fn support(&self) -> Option<(A, A)> {
+        Some((A::cast(-1.), A::cast(1.)))
+    }
+}
+
+/// Triangular kernel, `1 - |x|` within `[-1, 1]` and zero outside of it
+#[derive(Clone, Copy)]
+pub struct Triangular;
+
+impl<A> Kernel<A> for Triangular
+where
+    A: Float,
+{
+    fn evaluate(&self, x: A) -> A {
+        if x.abs() <= A::cast(1) {
+            A::cast(1) - x.abs()
+        } else {
+            A::cast(0)
+        }
+    }
+
+    fn support(&self) -> Option<(A, A)> {
+        Some((A::cast(-1.), A::cast(1.)))
+    }
+}
+
 #[cfg(test)]
 macro_rules! test {
-    ($ty:ident) => {
-        mod $ty {
-            mod gaussian {
-                use quickcheck::TestResult;
+    ($ty:ident, $name:ident, $kernel:ident, $support:expr) => {
+        mod $name {
+            use quickcheck::TestResult;
 
-                use univariate::kde::kernel::{Gaussian, Kernel};
+            use univariate::kde::kernel::{$kernel as Kernel_, Kernel};
 
-                quickcheck!{
-                    fn symmetric(x: $ty) -> bool {
-                        relative_eq!(Gaussian.evaluate(-x), Gaussian.evaluate(x))
-                    }
-                }
+            // Trapezoidal integral of the kernel over [a b]
+            fn trapezoid(a: $ty, b: $ty) -> $ty {
+                const DX: $ty = 1e-3;
+
+                let mut acc = 0.;
+                let mut x = a;
+                let mut y = Kernel_.evaluate(a);
 
-                // Any [a b] integral should be in the range [0 1]
-                quickcheck!{
-                    fn integral(a: $ty, b: $ty) -> TestResult {
-                        const DX: $ty = 1e-3;
+                while x < b {
+                    // The last step ends at b, which would otherwise be overshot
+                    let dx = DX.min(b - x);
+                    acc += dx * y / 2.;
 
-                        if a > b {
-                            TestResult::discard()
-                        } else {
-                            let mut acc = 0.;
-                            let mut x = a;
-                            let mut y = Gaussian.evaluate(a);
+                    x += dx;
+                    y = Kernel_.evaluate(x);
+
+                    acc += dx * y / 2.;
+                }
 
-                            while x < b {
-                                acc += DX * y / 2.;
+                acc
+            }
 
-                                x += DX;
-                                y = Gaussian.evaluate(x);
+            quickcheck!{
+                fn symmetric(x: $ty) -> bool {
+                    relative_eq!(Kernel_.evaluate(-x), Kernel_.evaluate(x))
+                }
+            }
 
-                                acc += DX * y / 2.;
-                            }
+            // Any [a b] integral should be in the range [0 1]
+            quickcheck!{
+                fn integral(a: $ty, b: $ty) -> TestResult {
+                    if a > b {
+                        TestResult::discard()
+                    } else {
+                        // Outside of the support the kernel is zero (or practically zero), so
+                        // only the part of [a b] within it needs to be integrated.
+                        let acc = trapezoid(a.max(-$support), b.min($support));
 
-                            TestResult::from_bool(
-                                (acc > 0. || relative_eq!(acc, 0.)) &&
-                                (acc < 1. || relative_eq!(acc, 1.)))
-                        }
+                        // Over the whole of a compact support, the integral is one give or take
+                        // rounding errors beyond the default tolerance.
+                        TestResult::from_bool(
+                            (acc > 0. || relative_eq!(acc, 0.)) &&
+                            (acc < 1. || relative_eq!(acc, 1., epsilon = 1e-3)))
                     }
                 }
             }
+
+            // The integral over the whole support should be one
+            #[test]
+            fn unit_integral() {
+                assert!(relative_eq!(trapezoid(-$support, $support), 1., epsilon = 1e-3));
+            }
         }
     };
 }
 
 #[cfg(test)]
 mod test {
-    test!(f32);
-    test!(f64);
+    mod f32 {
+        test!(f32, gaussian, Gaussian, 10.);
+        test!(f32, epanechnikov, Epanechnikov, 1.);
+        test!(f32, triangular, Triangular, 1.);
+    }
+
+    mod f64 {
+        test!(f64, gaussian, Gaussian, 10.);
+        test!(f64, epanechnikov, Epanechnikov, 1.);
+        test!(f64, triangular, Triangular, 1.);
+    }
+
+    mod tabulated_gaussian {
+        use univariate::kde::kernel::{Gaussian, Kernel, TabulatedGaussian};
+
+        // The interpolation error is bounded by `h^2 / 8` times the largest second derivative of
+        // the Gaussian, which is about 0.4, where `h` is the spacing of the table
+        fn tolerance(resolution: usize, cutoff: f64) -> f64 {
+            let h = cutoff / (resolution - 1) as f64;
+            (h.powi(2) / 8. * 0.4).max(Gaussian.evaluate(cutoff))
+        }
+
+        quickcheck!{
+            fn close_to_exact(x: f64) -> bool {
+                let kernel = TabulatedGaussian::new(1024, 6.);
+                let error = (kernel.evaluate(x) - Gaussian.evaluate(x)).abs();
+
+                error <= tolerance(1024, 6.)
+            }
+        }
+
+        #[test]
+        fn close_to_exact_within_cutoff() {
+            for &(resolution, cutoff) in &[(2, 1.), (64, 4.), (1024, 6.), (4096, 8.)] {
+                let kernel = TabulatedGaussian::new(resolution, cutoff);
+                for i in 0..10_000 {
+                    let x = -cutoff + 2. * cutoff * f64::from(i) / 9_999.;
+                    let error = (kernel.evaluate(x) - Gaussian.evaluate(x)).abs();
+                    assert!(error <= tolerance(resolution, cutoff), "error {} at {}", error, x);
+                }
+            }
+        }
+
+        #[test]
+        fn zero_beyond_cutoff() {
+            let kernel = TabulatedGaussian::new(128, 3.);
+
+            assert_eq!(Some((-3., 3.)), kernel.support());
+            assert_eq!(0., kernel.evaluate(3.5));
+            assert_eq!(0., kernel.evaluate(-1e300));
+            assert!(kernel.evaluate(3.) > 0.);
+            assert!(kernel.evaluate(::std::f64::NAN).is_nan());
+        }
+
+        #[test]
+        #[should_panic]
+        fn needs_two_points() {
+            TabulatedGaussian::new(1, 3.);
+        }
+    }
 }
@@ -2,6 +2,7 @@
 
 pub mod kernel;
 
+use std::cmp::Ordering;
 use std::ptr;
 
 use float::Float;
@@ -12,6 +13,9 @@ use univariate::Sample;
 
 use self::kernel::Kernel;
 
+// Below this many evaluations of the kernel, spawning threads takes longer than `map` itself
+const PARALLEL_THRESHOLD: usize = 100_000;
+
 /// Univariate kernel density estimator
 pub struct Kde<'a, A, K>
 where
@@ -21,6 +25,8 @@ where
     bandwidth: A,
     kernel: K,
     sample: &'a Sample<A>,
+    // Sorted copy of the sample, if the kernel has compact support
+    sorted: Option<Vec<A>>,
 }
 
 impl<'a, A, K> Kde<'a, A, K>
@@ -31,10 +37,19 @@ where
     /// Creates a new kernel density estimator from the `sample`, using a kernel and estimating
     /// the bandwidth using the method `bw`
     pub fn new(sample: &'a Sample<A>, kernel: K, bw: Bandwidth<A>) -> Kde<'a, A, K> {
+        // With a kernel of compact support, only the data points within the support of an
+        // x-value contribute to its density, which are found by binary search in a sorted copy
+        let sorted = kernel.support().map(|_| {
+            let mut sorted = sample.as_slice().to_vec();
+            sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+            sorted
+        });
+
         Kde {
             bandwidth: bw.estimate(sample),
             kernel,
             sample,
+            sorted,
         }
     }
 
@@ -45,50 +60,68 @@ where
 
     /// Maps the KDE over `xs`
     ///
-    /// - Multihreaded
+    /// - Multihreaded, unless there are few points or a small sample
     pub fn map(&self, xs: &[A]) -> Box<[A]> {
         let n = xs.len();
         let ncpus = num_cpus::get();
 
-        // TODO need some sensible threshold to trigger the multi-threaded path
-        if ncpus > 1 && n > ncpus {
-            let granularity = n / ncpus + 1;
-
-            unsafe {
-                let mut ys = Vec::with_capacity(n);
-                ys.set_len(n);
-
-                {
-                    let _ = ys.chunks_mut(granularity)
-                        .enumerate()
-                        .map(|(i, ys)| {
-                            let offset = i * granularity;
-
-                            thread::scoped(move || {
-                                for (i, y) in ys.iter_mut().enumerate() {
-                                    ptr::write(y, self.estimate(*xs.get_unchecked(offset + i)))
-                                }
-                            })
-                        })
-                        .collect::<Vec<_>>();
-                }
+        if ncpus > 1 && n > ncpus && n * self.sample.as_slice().len() >= PARALLEL_THRESHOLD {
+            self.map_parallel(xs, ncpus)
+        } else {
+            self.map_serial(xs)
+        }
+    }
 
-                ys.into_boxed_slice()
+    // Each thread estimates the density at a contiguous chunk of `xs`
+    fn map_parallel(&self, xs: &[A], nthreads: usize) -> Box<[A]> {
+        let n = xs.len();
+        let granularity = n / nthreads + 1;
+
+        unsafe {
+            let mut ys = Vec::with_capacity(n);
+            ys.set_len(n);
+
+            {
+                let _ = ys.chunks_mut(granularity)
+                    .enumerate()
+                    .map(|(i, ys)| {
+                        let offset = i * granularity;
+
+                        thread::scoped(move || {
+                            for (i, y) in ys.iter_mut().enumerate() {
+                                ptr::write(y, self.estimate(*xs.get_unchecked(offset + i)))
+                            }
+                        })
+                    })
+                    .collect::<Vec<_>>();
             }
-        } else {
-            xs.iter()
-                .map(|&x| self.estimate(x))
-                .collect::<Vec<_>>()
-                .into_boxed_slice()
+
+            ys.into_boxed_slice()
         }
     }
 
+    fn map_serial(&self, xs: &[A]) -> Box<[A]> {
+        xs.iter()
+            .map(|&x| self.estimate(x))
+            .collect::<Vec<_>>()
+            .into_boxed_slice()
+    }
+
     /// Estimates the probability density of `x`
     pub fn estimate(&self, x: A) -> A {
         let _0 = A::cast(0);
-        let slice = self.sample.as_slice();
         let h = self.bandwidth;
-        let n = A::cast(slice.len());
+        let n = A::cast(self.sample.as_slice().len());
+
+        let slice = match (self.kernel.support(), &self.sorted) {
+            // `(x - x_i) / h` is within `[lo, hi]` if `x_i` is within `[x - hi * h, x - lo * h]`
+            (Some((lo, hi)), &Some(ref sorted)) => {
+                let start = partition_point(sorted, |&x_i| x_i < x - hi * h);
+                let end = partition_point(sorted, |&x_i| x_i <= x - lo * h);
+                &sorted[start..end]
+            }
+            _ => self.sample.as_slice(),
+        };
 
         let sum = slice
             .iter()
@@ -98,6 +131,23 @@ where
     }
 }
 
+// Index of the first element of `sorted` for which `pred` is false, if it is true for all of the
+// elements before that one
+fn partition_point<A, P>(sorted: &[A], pred: P) -> usize
+where
+    P: Fn(&A) -> bool,
+{
+    sorted
+        .binary_search_by(|x| {
+            if pred(x) {
+                Ordering::Less
+            } else {
+                Ordering::Greater
+            }
+        })
+        .unwrap_err()
+}
+
 /// Method to estimate the bandwidth
 pub enum Bandwidth<A>
 where
@@ -107,6 +157,9 @@ where
     Manual(A),
     /// Use Silverman's rule of thumb to estimate the bandwidth from the sample
     Silverman,
+    /// Use Scott's rule to estimate the bandwidth from the sample, which gives a bandwidth
+    /// `(3/4)^(1/5)`, or about 0.94, times that of Silverman's rule
+    Scott,
 }
 
 impl<A> Bandwidth<A>
@@ -123,6 +176,13 @@ where
 
                 sigma * (factor / n).powf(exponent)
             }
+            Bandwidth::Scott => {
+                let exponent = A::cast(-1. / 5.);
+                let n = A::cast(sample.as_slice().len());
+                let sigma = sample.std_dev(None);
+
+                sigma * n.powf(exponent)
+            }
             Bandwidth::Manual(bw) => bw,
         }
     }
@@ -135,7 +195,7 @@ macro_rules! test {
             use quickcheck::TestResult;
 
             use univariate::Sample;
-            use univariate::kde::kernel::Gaussian;
+            use univariate::kde::kernel::{Gaussian, Kernel, Triangular};
             use univariate::kde::{Bandwidth, Kde};
 
             // The [-inf inf] integral of the estimated PDF should be one
@@ -170,6 +230,65 @@ macro_rules! test {
                         TestResult::discard()
                     }
                 }
+
+                // Skipping the data points outside of the support of a compact kernel leaves the
+                // density unchanged
+                fn compact_support(size: usize, start: usize) -> TestResult {
+                    if let Some(v) = ::test::vec::<$ty>(size, start) {
+                        let slice = &v[start..];
+                        let data = Sample::new(slice);
+                        let kde = Kde::new(data, Triangular, Bandwidth::Silverman);
+                        let h = kde.bandwidth();
+
+                        TestResult::from_bool((0..100).all(|i| {
+                            let x = data.min() - h + (data.max() - data.min() + 2. * h) * i as $ty
+                                / 99.;
+                            let sum: $ty = slice
+                                .iter()
+                                .map(|&x_i| Triangular.evaluate((x - x_i) / h))
+                                .sum();
+                            let all = sum / h / slice.len() as $ty;
+
+                            relative_eq!(kde.estimate(x), all, epsilon = 1e-5)
+                        }))
+                    } else {
+                        TestResult::discard()
+                    }
+                }
+
+                // Both rules scale the standard deviation by the size of the sample, so their
+                // bandwidths are in the same ratio for any sample
+                fn scott_to_silverman_ratio(size: usize, start: usize) -> TestResult {
+                    if let Some(v) = ::test::vec::<$ty>(size, start) {
+                        let data = Sample::new(&v[start..]);
+                        let silverman = Kde::new(data, Gaussian, Bandwidth::Silverman).bandwidth();
+                        let scott = Kde::new(data, Gaussian, Bandwidth::Scott).bandwidth();
+                        let ratio: $ty = 0.75;
+
+                        TestResult::from_bool(
+                            relative_eq!(scott / silverman, ratio.powf(0.2), epsilon = 1e-5)
+                        )
+                    } else {
+                        TestResult::discard()
+                    }
+                }
+
+                // Every point is estimated by the same code on either path, so the results are
+                // exactly equal
+                fn parallel_matches_serial(size: usize, start: usize) -> TestResult {
+                    if let Some(v) = ::test::vec::<$ty>(size, start) {
+                        let slice = &v[start..];
+                        let data = Sample::new(slice);
+                        let kde = Kde::new(data, Gaussian, Bandwidth::Silverman);
+                        let xs: Vec<$ty> = (0..100)
+                            .map(|i| data.min() + (data.max() - data.min()) * i as $ty / 99.)
+                            .collect();
+
+                        TestResult::from_bool(kde.map_parallel(&xs, 4) == kde.map_serial(&xs))
+                    } else {
+                        TestResult::discard()
+                    }
+                }
             }
         }
     };
@@ -4,6 +4,7 @@ mod bootstrap;
 mod percentiles;
 mod resamples;
 mod sample;
+mod student_t;
 
 pub mod kde;
 pub mod mixed;
@@ -19,7 +20,8 @@ use tuple::{Tuple, TupledDistributionsBuilder};
 use self::resamples::Resamples;
 
 pub use self::percentiles::Percentiles;
-pub use self::sample::Sample;
+pub use self::resamples::resample_indices;
+pub use self::sample::{Binning, Sample};
 
 /// Performs a two-sample bootstrap
 ///
@@ -2,16 +2,57 @@ use std::mem;
 
 use float::Float;
 use rand::distributions::{IndependentSample, Range};
-use rand::{Rng, XorShiftRng};
+use rand::{Rng, SeedableRng, XorShiftRng};
 
 use univariate::Sample;
 
+// Draws the indices of the elements that make up a resample, uniformly and with replacement
+struct Indices {
+    range: Range<usize>,
+    rng: XorShiftRng,
+}
+
+impl Indices {
+    fn new(n: usize, rng: XorShiftRng) -> Indices {
+        Indices {
+            range: Range::new(0, n),
+            rng,
+        }
+    }
+
+    fn next(&mut self) -> usize {
+        self.range.ind_sample(&mut self.rng)
+    }
+}
+
+/// Generates the indices of `nresamples` bootstrap resamples of a sample with `n` elements
+///
+/// Each resample is made of `n` indices into the sample, drawn uniformly and with replacement,
+/// exactly like the resamples used by `bootstrap`. This can be used to bootstrap statistics which
+/// don't fit `bootstrap` while staying consistent with it.
+///
+/// The indices are fully determined by `seed`: the same `n`, `nresamples` and `seed` produce the
+/// same index sets on every run and platform. Note that `bootstrap` itself draws a random seed on
+/// every call, so its resamples are only the same in distribution, not one for one.
+///
+/// # Panics
+///
+/// Panics if `n` is zero
+pub fn resample_indices(n: usize, nresamples: usize, seed: u64) -> impl Iterator<Item = Vec<usize>> {
+    assert!(n > 0);
+
+    // XorShift must not be seeded with all zeros, which the constant words rule out
+    let seed = [seed as u32, (seed >> 32) as u32, 0x9E37_79B9, 0x7F4A_7C15];
+    let mut indices = Indices::new(n, XorShiftRng::from_seed(seed));
+
+    (0..nresamples).map(move |_| (0..n).map(|_| indices.next()).collect())
+}
+
 pub struct Resamples<'a, A>
 where
     A: 'a + Float,
 {
-    range: Range<usize>,
-    rng: XorShiftRng,
+    indices: Indices,
     sample: &'a [A],
     stage: Option<Vec<A>>,
 }
@@ -25,8 +66,7 @@ where
         let slice = sample.as_slice();
 
         Resamples {
-            range: Range::new(0, slice.len()),
-            rng: ::rand::thread_rng().gen(),
+            indices: Indices::new(slice.len(), ::rand::thread_rng().gen()),
             sample: slice,
             stage: None,
         }
@@ -34,20 +74,20 @@ where
 
     pub fn next(&mut self) -> &Sample<A> {
         let n = self.sample.len();
-        let rng = &mut self.rng;
+        let indices = &mut self.indices;
 
         match self.stage {
             None => {
                 let mut stage = Vec::with_capacity(n);
 
                 for _ in 0..n {
-                    stage.push(self.sample[self.range.ind_sample(rng)])
+                    stage.push(self.sample[indices.next()])
                 }
 
                 self.stage = Some(stage);
             }
             Some(ref mut stage) => for elem in stage.iter_mut() {
-                *elem = self.sample[self.range.ind_sample(rng)]
+                *elem = self.sample[indices.next()]
             },
         }
 
@@ -65,7 +105,7 @@ mod test {
     use std::collections::HashSet;
 
     use univariate::Sample;
-    use univariate::resamples::Resamples;
+    use univariate::resamples::{resample_indices, Resamples};
 
     // FIXME
     // Check that the resample is a subset of the sample
@@ -92,5 +132,32 @@ mod test {
         }
     }
 
+    // Check that the indices are in range and determined by the seed
+    quickcheck!{
+        fn indices_reproducible(n: usize, nresamples: usize, seed: u64) -> TestResult {
+            if n > 0 {
+                let first: Vec<_> = resample_indices(n, nresamples, seed).collect();
+                let second: Vec<_> = resample_indices(n, nresamples, seed).collect();
+
+                TestResult::from_bool(
+                    first.len() == nresamples
+                        && first.iter().all(|resample| {
+                            resample.len() == n && resample.iter().all(|&i| i < n)
+                        })
+                        && first == second,
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    fn indices_depend_on_seed() {
+        let a: Vec<_> = resample_indices(100, 10, 1).collect();
+        let b: Vec<_> = resample_indices(100, 10, 2).collect();
+        assert!(a != b);
+    }
+
     // XXX Perhaps add a check that the resamples are different
 }
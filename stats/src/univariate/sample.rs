@@ -1,13 +1,28 @@
-use std::{cmp, mem};
+use std::{cmp, mem, slice};
 
+use Distribution;
 use cast;
 use float::Float;
 use num_cpus;
+use num_traits::NumCast;
 use thread_scoped as thread;
 
 use tuple::{Tuple, TupledDistributionsBuilder};
+use univariate::kde::kernel::Kernel;
+use univariate::kde::{Bandwidth, Kde};
 use univariate::Percentiles;
+use univariate::outliers::tukey::{self, LabeledSample};
 use univariate::resamples::Resamples;
+use univariate::student_t;
+
+/// How the bins of a histogram divide the range of a sample
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Binning {
+    /// Bins of equal width
+    Linear,
+    /// Bins of equal width on a logarithmic scale, for data which spans orders of magnitude
+    Logarithmic,
+}
 
 /// A collection of data points drawn from a population
 ///
@@ -27,8 +42,16 @@ impl<A> Sample<A> {
     pub fn as_slice(&self) -> &[A] {
         unsafe { mem::transmute(self) }
     }
+
+    /// Returns an iterator over the data points, in their original order
+    pub fn iter(&self) -> slice::Iter<'_, A> {
+        self.as_slice().iter()
+    }
 }
 
+// Number of points at which the density is evaluated in each pass of the search for the mode
+const MODE_GRID_POINTS: usize = 256;
+
 // TODO(rust-lang/rfcs#735) move this `impl` into a private percentiles module
 impl<A> Sample<A>
 where
@@ -45,6 +68,73 @@ where
         unsafe { mem::transmute(slice) }
     }
 
+    /// Counts the data points in each of `nbins` equally wide bins spanning `[min, max]`
+    ///
+    /// The last bin includes the maximum. If all the data points are equal, they all fall in the
+    /// first bin.
+    ///
+    /// - Time: `O(length)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nbins` is zero
+    pub fn histogram(&self, nbins: usize) -> Vec<usize>
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        self.binned_histogram(nbins, Binning::Linear).1
+    }
+
+    /// Counts the data points in each of `nbins` bins spanning `[min, max]`, divided as given by
+    /// `binning`, and returns the `nbins + 1` edges of the bins together with the counts
+    ///
+    /// The last bin includes the maximum. If all the data points are equal, they all fall in the
+    /// first bin, and all the edges are equal.
+    ///
+    /// - Time: `O(length)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `nbins` is zero, or if the binning is logarithmic and the sample has data
+    /// points which aren't positive
+    pub fn binned_histogram(&self, nbins: usize, binning: Binning) -> (Vec<A>, Vec<usize>)
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        assert!(nbins > 0);
+
+        let (min, max) = (self.min(), self.max());
+        // The bins are equally wide in terms of `scale(x)`
+        let (scale, unscale): (fn(A) -> A, fn(A) -> A) = match binning {
+            Binning::Linear => (|x| x, |x| x),
+            Binning::Logarithmic => {
+                assert!(min > A::cast(0));
+                (A::ln, A::exp)
+            }
+        };
+        let width = (scale(max) - scale(min)) / A::cast(nbins);
+
+        // The outer edges are exact, and rounding mustn't push the others past them
+        let mut edges = vec![min];
+        edges.extend((1..nbins).map(|i| {
+            let edge = unscale(scale(min) + A::cast(i) * width);
+            edge.max(min).min(max)
+        }));
+        edges.push(max);
+
+        let mut counts = vec![0; nbins];
+        for &x in self.as_slice() {
+            let bin = if width > A::cast(0) {
+                cast::usize(((scale(x) - scale(min)) / width).floor()).unwrap()
+            } else {
+                0
+            };
+            counts[cmp::min(bin, nbins - 1)] += 1;
+        }
+
+        (edges, counts)
+    }
+
     /// Returns the biggest element in the sample
     ///
     /// - Time: `O(length)`
@@ -67,6 +157,17 @@ where
         self.sum() / A::cast(n)
     }
 
+    /// Returns the median of the sample
+    ///
+    /// - Time: `O(N log N) where N = length`
+    /// - Memory: `O(length)`
+    pub fn median(&self) -> A
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        self.percentiles().median()
+    }
+
     /// Returns the median absolute deviation
     ///
     /// The `median` can be optionally passed along to speed up (2X) the computation
@@ -106,6 +207,52 @@ where
         (mad / median) * _100
     }
 
+    /// Returns the mode of the sample, the value at which its kernel density estimate peaks
+    ///
+    /// The density is estimated with the given `kernel` and `bandwidth`, or with a bandwidth
+    /// chosen by Silverman's rule of thumb if `bandwidth` is `None`. The mode depends strongly on
+    /// the bandwidth: a narrow one lets a few coincidentally close data points form the highest
+    /// peak, while a wide one merges distinct clusters into a single peak between them.
+    /// Silverman's rule assumes a roughly normal sample and tends to be too wide for multimodal
+    /// ones.
+    ///
+    /// - Multithreaded
+    /// - Time: `O(length)`
+    pub fn mode<K>(&self, bandwidth: Option<A>, kernel: K) -> A
+    where
+        K: Kernel<A>,
+    {
+        let (min, max) = (self.min(), self.max());
+        if min == max {
+            return min;
+        }
+
+        let bw = match bandwidth {
+            Some(bandwidth) => Bandwidth::Manual(bandwidth),
+            None => Bandwidth::Silverman,
+        };
+        let kde = Kde::new(self, kernel, bw);
+
+        // Scans the whole range of the sample, then the neighbourhood of the highest point again.
+        let mut center = min;
+        let mut step = (max - min) / A::cast(MODE_GRID_POINTS - 1);
+        let mut start = min;
+        for _ in 0..2 {
+            let xs: Vec<A> = (0..MODE_GRID_POINTS)
+                .map(|i| start + A::cast(i) * step)
+                .collect();
+            let ys = kde.map(&xs);
+            let best = (1..MODE_GRID_POINTS)
+                .fold(0, |best, i| if ys[i] > ys[best] { i } else { best });
+            center = xs[best];
+
+            start = center - step;
+            step = step * A::cast(2) / A::cast(MODE_GRID_POINTS - 1);
+        }
+
+        center
+    }
+
     /// Returns the smallest element in the sample
     ///
     /// - Time: `O(length)`
@@ -119,6 +266,67 @@ where
         }
     }
 
+    /// Returns the trimmed mean of the sample: the mean of the values left after discarding the
+    /// smallest and the largest `proportion` of them, which makes it robust against outliers
+    ///
+    /// A `proportion` of zero gives the mean, and the trimmed mean converges to the median as
+    /// `proportion` approaches one half.
+    ///
+    /// # Panics
+    ///
+    /// Panics if `proportion` is not in the `[0, 0.5)` range, or if the sample contains NaNs
+    ///
+    /// - Time: `O(N log N) where N = length`
+    /// - Memory: `O(length)`
+    pub fn trimmed_mean(&self, proportion: A) -> A
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        let _0 = A::cast(0);
+        let _05 = A::cast(0.5);
+
+        assert!(proportion >= _0 && proportion < _05);
+
+        let mut v = self.as_slice().to_vec();
+        v.sort_by(|a, b| a.partial_cmp(b).unwrap());
+
+        // Less than half of the values are discarded from each end, so at least one is kept
+        let n = v.len();
+        let trimmed = cast::usize((A::cast(n) * proportion).floor()).unwrap();
+        let kept = &v[trimmed..n - trimmed];
+        ::sum(kept) / A::cast(kept.len())
+    }
+
+    /// Classifies the data points of the sample as mild or severe, low or high outliers, or as
+    /// neither, using Tukey's fences (see `outliers::tukey`). The labeled data points keep their
+    /// order, so their indices are those of the sample.
+    ///
+    /// - Time: `O(N log N) where N = length`
+    pub fn classify_outliers(&self) -> LabeledSample<'_, A>
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        tukey::classify(self)
+    }
+
+    /// Returns the `q`-quantile of the sample, interpolating linearly between the data points
+    /// around it, eg. the minimum for a `q` of zero, the median for one half and the maximum for
+    /// one
+    ///
+    /// - Time: `O(N log N) where N = length`
+    ///
+    /// # Panics
+    ///
+    /// Panics if `q` is outside the closed `[0, 1]` range
+    pub fn quantile(&self, q: A) -> A
+    where
+        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+    {
+        assert!(q >= A::cast(0) && q <= A::cast(1));
+
+        self.percentiles().at(q * A::cast(100))
+    }
+
     /// Returns a "view" into the percentiles of the sample
     ///
     /// This "view" makes consecutive computations of percentiles much faster (`O(1)`)
@@ -161,6 +369,41 @@ where
         self.var(mean).sqrt()
     }
 
+    /// Returns the standard error of the mean, ie. the standard deviation of the means of samples
+    /// of this size
+    ///
+    /// The `mean` can be optionally passed along to speed up (2X) the computation
+    ///
+    /// - Time: `O(length)`
+    pub fn std_err(&self, mean: Option<A>) -> A {
+        self.std_dev(mean) / A::cast(self.as_slice().len()).sqrt()
+    }
+
+    /// Returns the confidence interval of the mean of the population, based on Student's
+    /// t-distribution, which assumes that the population is roughly normally distributed
+    ///
+    /// # Panics
+    ///
+    /// Panics if the `confidence_level` is not in the `(0, 1)` range.
+    ///
+    /// - Time: `O(length)`
+    pub fn mean_confidence_interval(&self, confidence_level: A) -> (A, A) {
+        let _0 = A::cast(0);
+        let _1 = A::cast(1);
+        let _2 = A::cast(2);
+
+        assert!(confidence_level > _0 && confidence_level < _1);
+
+        let mean = self.mean();
+        let std_err = self.std_err(Some(mean));
+        let p = (_1 + confidence_level) / _2;
+        let df = self.as_slice().len() - 1;
+        let t = student_t::quantile(p.to_f64().unwrap(), cast::f64(df));
+        let margin = <A as NumCast>::from(t).unwrap() * std_err;
+
+        (mean - margin, mean + margin)
+    }
+
     /// Returns the standard deviation as a percentage of the mean
     ///
     /// - Time: `O(length)`
@@ -212,6 +455,35 @@ where
         sum / A::cast(slice.len() - 1)
     }
 
+    /// Returns the average of the sample in which each data point counts with its weight, eg. the
+    /// number of iterations which a time per iteration was measured over, so that every iteration
+    /// counts equally
+    ///
+    /// - Time: `O(length)`
+    ///
+    /// # Panics
+    ///
+    /// Panics if there isn't a weight for each data point, if a weight is negative or if the
+    /// weights don't add up to a positive number
+    pub fn weighted_mean(&self, weights: &[A]) -> A {
+        use std::ops::Add;
+
+        let slice = self.as_slice();
+        assert_eq!(slice.len(), weights.len());
+        assert!(weights.iter().all(|&w| w >= A::cast(0)));
+
+        let total = weights.iter().cloned().fold(A::cast(0), Add::add);
+        assert!(total > A::cast(0));
+
+        let sum = slice
+            .iter()
+            .zip(weights)
+            .map(|(&x, &w)| x * w)
+            .fold(A::cast(0), Add::add);
+
+        sum / total
+    }
+
     // TODO Remove the `T` parameter in favor of `S::Output`
     /// Returns the bootstrap distributions of the parameters estimated by the 1-sample statistic
     ///
@@ -270,20 +542,25 @@ where
         }
     }
 
-    #[cfg(test)]
-    pub fn iqr(&self) -> A
+    /// Returns the bootstrap distribution of the mean: the means of `nresamples` resamples of this
+    /// sample, which show where the mean of the population likely lies
+    ///
+    /// - Multi-threaded
+    /// - Time: `O(nresamples)`
+    /// - Memory: `O(nresamples)`
+    pub fn bootstrap_mean_distribution(&self, nresamples: usize) -> Distribution<A>
     where
-        usize: cast::From<A, Output = Result<usize, cast::Error>>,
+        A: Send + Sync,
     {
-        self.percentiles().iqr()
+        self.bootstrap(nresamples, |sample| (sample.mean(),)).0
     }
 
     #[cfg(test)]
-    pub fn median(&self) -> A
+    pub fn iqr(&self) -> A
     where
         usize: cast::From<A, Output = Result<usize, cast::Error>>,
     {
-        self.percentiles().median()
+        self.percentiles().iqr()
     }
 }
 
@@ -297,3 +574,371 @@ where
 //}
 //}
 //}
+
+#[cfg(test)]
+mod test {
+    use quickcheck::TestResult;
+
+    use univariate::kde::kernel::Gaussian;
+    use univariate::{Binning, Sample};
+
+    #[test]
+    fn mode_finds_dominant_cluster() {
+        // 80 data points spread around 10 and 20 spread around 50
+        let mut v: Vec<f64> = (0..80).map(|i| 10. + f64::from(i % 9 - 4) * 0.1).collect();
+        v.extend((0..20).map(|i| 50. + f64::from(i % 5 - 2) * 0.1));
+        let sample = Sample::new(&v);
+
+        assert!((sample.mode(Some(0.5), Gaussian) - 10.).abs() < 0.1);
+        assert!((sample.mode(None, Gaussian) - 10.).abs() < 1.);
+
+        // A bandwidth much wider than the distance between the clusters merges them.
+        assert!(sample.mode(Some(100.), Gaussian) > 11.);
+
+        assert_eq!(3., Sample::new(&[3., 3., 3.]).mode(None, Gaussian));
+    }
+
+    #[test]
+    fn bootstrap_mean_distribution_surrounds_mean() {
+        let v: Vec<f64> = (0..100).map(|i| f64::from(i % 10)).collect();
+        let sample = Sample::new(&v);
+
+        let means = sample.bootstrap_mean_distribution(1000);
+        assert_eq!(1000, means.as_slice().len());
+        let means = Sample::new(means.as_slice());
+        assert!(means.min() >= sample.min() && means.max() <= sample.max());
+        assert!((means.mean() - sample.mean()).abs() < 0.1);
+        // The standard error of the mean is much smaller than the spread of the sample.
+        assert!(means.std_dev(None) < sample.std_dev(None) / 5.);
+    }
+
+    quickcheck!{
+        fn trimmed_mean_of_nothing_is_mean(size: usize, start: usize) -> TestResult {
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+
+                // Sorting changes the order of the summation, and so the rounding errors
+                TestResult::from_bool(relative_eq!(
+                    sample.trimmed_mean(0.),
+                    sample.mean(),
+                    max_relative = 1e-9
+                ))
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    quickcheck!{
+        fn trimmed_mean_converges_to_median(size: usize, start: usize) -> TestResult {
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+                // Close enough to one half to leave only the middle one or two values
+                let proportion = 0.5 - 0.5 / sample.as_slice().len() as f64;
+
+                TestResult::from_bool(
+                    relative_eq!(sample.trimmed_mean(proportion), sample.median())
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    quickcheck!{
+        fn trimmed_mean_is_within_range(size: usize, start: usize, proportion: f64) -> TestResult {
+            let proportion = proportion.abs() % 0.5;
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+                let trimmed_mean = sample.trimmed_mean(proportion);
+
+                TestResult::from_bool(
+                    (trimmed_mean > sample.min() || relative_eq!(trimmed_mean, sample.min())) &&
+                    (trimmed_mean < sample.max() || relative_eq!(trimmed_mean, sample.max()))
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    fn trimmed_mean_discards_outliers() {
+        let v = [10., 11., 9., 10., 1000., 10., 0.];
+        let sample = Sample::new(&v);
+
+        assert_eq!(10., sample.trimmed_mean(0.2));
+        assert_eq!(150., sample.trimmed_mean(0.));
+    }
+
+    #[test]
+    fn mean_confidence_interval_of_known_sample() {
+        let v: Vec<f64> = (1..11).map(f64::from).collect();
+        let sample = Sample::new(&v);
+
+        assert!(relative_eq!(0.957427, sample.std_err(None), max_relative = 1e-6));
+        let (lower, upper) = sample.mean_confidence_interval(0.95);
+        assert!(relative_eq!(3.334149, lower, max_relative = 1e-4));
+        assert!(relative_eq!(7.665851, upper, max_relative = 1e-4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn mean_confidence_interval_needs_confidence_below_one() {
+        Sample::new(&[1., 2., 3.]).mean_confidence_interval(1.);
+    }
+
+    quickcheck!{
+        fn mean_confidence_interval_brackets_mean(
+            size: usize,
+            start: usize,
+            confidence_level: f64
+        ) -> TestResult {
+            let confidence_level = confidence_level.abs() % 1.;
+            if confidence_level == 0. {
+                return TestResult::discard();
+            }
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+                let mean = sample.mean();
+                let (lower, upper) = sample.mean_confidence_interval(confidence_level);
+
+                TestResult::from_bool(lower <= mean && mean <= upper)
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    quickcheck!{
+        fn mean_confidence_interval_widens_with_confidence(
+            size: usize,
+            start: usize
+        ) -> TestResult {
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+                let width = |confidence_level| {
+                    let (lower, upper) = sample.mean_confidence_interval(confidence_level);
+                    upper - lower
+                };
+
+                TestResult::from_bool(
+                    width(0.5) <= width(0.9) && width(0.9) <= width(0.95)
+                        && width(0.95) <= width(0.99)
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    quickcheck!{
+        fn mean_confidence_interval_narrows_with_size(size: usize, start: usize) -> TestResult {
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                // Repeating the data points keeps their spread, with twice as many of them
+                let v = &v[start..];
+                let doubled: Vec<f64> = v.iter().chain(v).cloned().collect();
+                let width = |sample: &Sample<f64>| {
+                    let (lower, upper) = sample.mean_confidence_interval(0.95);
+                    upper - lower
+                };
+
+                TestResult::from_bool(width(Sample::new(&doubled)) <= width(Sample::new(v)))
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    fn classify_outliers_of_known_sample() {
+        let mut v: Vec<f64> = (1..21).map(f64::from).collect();
+        v.extend(&[40., 100., -40.]);
+        let sample = Sample::new(&v);
+
+        // The quartiles are 5.5 and 16.5, so the inner fences are at -11 and 33 and the outer
+        // fences at -27.5 and 49.5
+        let labeled = sample.classify_outliers();
+        assert_eq!((-27.5, -11., 33., 49.5), labeled.fences());
+        let severe: Vec<(usize, f64)> = labeled
+            .iter()
+            .enumerate()
+            .filter(|&(_, (_, ref label))| label.is_severe())
+            .map(|(i, (x, _))| (i, x))
+            .collect();
+        assert_eq!(vec![(21, 100.), (22, -40.)], severe);
+        let mild: Vec<f64> = labeled
+            .iter()
+            .filter(|&(_, ref label)| label.is_mild())
+            .map(|(x, _)| x)
+            .collect();
+        assert_eq!(vec![40.], mild);
+    }
+
+    quickcheck!{
+        fn binned_histogram_counts_every_data_point(
+            size: usize,
+            start: usize,
+            nbins: usize
+        ) -> TestResult {
+            let nbins = nbins % 100 + 1;
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                // Shifted away from zero, for the logarithmic bins
+                let v: Vec<f64> = v[start..].iter().map(|x| x + 1.).collect();
+                let sample = Sample::new(&v);
+
+                TestResult::from_bool([Binning::Linear, Binning::Logarithmic].iter().all(
+                    |&binning| {
+                        let (edges, counts) = sample.binned_histogram(nbins, binning);
+
+                        edges.len() == nbins + 1
+                            && counts.len() == nbins
+                            && counts.iter().sum::<usize>() == v.len()
+                            && edges.windows(2).all(|pair| pair[0] <= pair[1])
+                            && edges[0] == sample.min()
+                            && edges[nbins] == sample.max()
+                    },
+                ))
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    fn binned_histogram_of_known_sample() {
+        let sample = Sample::new(&[1., 2., 3., 10., 20., 30., 100.]);
+
+        let (edges, counts) = sample.binned_histogram(2, Binning::Logarithmic);
+        assert!(relative_eq!(10., edges[1]));
+        assert_eq!(vec![3, 4], counts);
+
+        let (edges, counts) = sample.binned_histogram(3, Binning::Linear);
+        assert_eq!(vec![1., 34., 67., 100.], edges);
+        assert_eq!(vec![6, 0, 1], counts);
+        assert_eq!(counts, sample.histogram(3));
+
+        // Without spread, everything falls in the first bin
+        let (edges, counts) = Sample::new(&[5., 5.]).binned_histogram(2, Binning::Logarithmic);
+        assert_eq!(vec![5., 5., 5.], edges);
+        assert_eq!(vec![2, 0], counts);
+    }
+
+    #[test]
+    #[should_panic]
+    fn logarithmic_bins_need_positive_data() {
+        Sample::new(&[0., 1.]).binned_histogram(2, Binning::Logarithmic);
+    }
+
+    quickcheck!{
+        fn weighted_mean_with_equal_weights_is_mean(
+            size: usize,
+            start: usize,
+            weight: f64
+        ) -> TestResult {
+            let weight = weight.abs() % 1e3 + 1e-3;
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+                let weights = vec![weight; sample.as_slice().len()];
+
+                TestResult::from_bool(relative_eq!(
+                    sample.weighted_mean(&weights),
+                    sample.mean(),
+                    max_relative = 1e-9
+                ))
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    fn weighted_mean_of_known_sample() {
+        // Batches of 1, 3 and 6 iterations at 10, 20 and 40 ns per iteration
+        let sample = Sample::new(&[10., 20., 40.]);
+        assert_eq!(
+            (10. + 3. * 20. + 6. * 40.) / 10.,
+            sample.weighted_mean(&[1., 3., 6.])
+        );
+        // Without weight, a data point doesn't count.
+        assert_eq!(15., sample.weighted_mean(&[1., 1., 0.]));
+    }
+
+    #[test]
+    #[should_panic]
+    fn weighted_mean_needs_a_weight_per_data_point() {
+        Sample::new(&[1., 2.]).weighted_mean(&[1.]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn weighted_mean_needs_positive_total_weight() {
+        Sample::new(&[1., 2.]).weighted_mean(&[0., 0.]);
+    }
+
+    // Linear interpolation between the closest ranks of the sorted data
+    fn reference_quantile(v: &[f64], q: f64) -> f64 {
+        let mut sorted = v.to_vec();
+        sorted.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let rank = q * (sorted.len() - 1) as f64;
+        let (floor, ceiling) = (rank.floor() as usize, rank.ceil() as usize);
+        sorted[floor] + (sorted[ceiling] - sorted[floor]) * (rank - rank.floor())
+    }
+
+    quickcheck!{
+        fn quantile_matches_sorted_reference(size: usize, start: usize, q: f64) -> TestResult {
+            let q = q.abs() % 1.;
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+
+                TestResult::from_bool(
+                    relative_eq!(
+                        sample.quantile(q),
+                        reference_quantile(&v[start..], q),
+                        epsilon = 1e-12,
+                        max_relative = 1e-9
+                    ) && sample.quantile(0.) == sample.min()
+                        && sample.quantile(1.) == sample.max()
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    quickcheck!{
+        fn quantile_is_monotonic(size: usize, start: usize, q1: f64, q2: f64) -> TestResult {
+            let (q1, q2) = (q1.abs() % 1., q2.abs() % 1.);
+            let (q1, q2) = (q1.min(q2), q1.max(q2));
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+
+                TestResult::from_bool(sample.quantile(q1) <= sample.quantile(q2))
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn quantile_needs_a_probability() {
+        Sample::new(&[1., 2.]).quantile(1.5);
+    }
+
+    // Check that the accessors return the original data points, in order
+    quickcheck!{
+        fn accessors_preserve_order(size: usize, start: usize) -> TestResult {
+            if let Some(v) = ::test::vec::<f64>(size, start) {
+                let sample = Sample::new(&v[start..]);
+
+                TestResult::from_bool(
+                    sample.as_slice() == &v[start..]
+                        && sample.iter().cloned().eq(v[start..].iter().cloned())
+                )
+            } else {
+                TestResult::discard()
+            }
+        }
+    }
+}
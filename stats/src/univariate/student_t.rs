@@ -0,0 +1,161 @@
+//! Quantiles of Student's t-distribution
+
+use std::f64::consts::PI;
+
+/// Returns the `p`-quantile of Student's t-distribution with `df` degrees of freedom, ie. the
+/// value which a t-distributed variable falls below with probability `p`
+///
+/// Uses the exact quantiles for one and two degrees of freedom, and Hill's approximation (ACM
+/// algorithm 396) otherwise, which is accurate to at least four significant digits.
+///
+/// # Panics
+///
+/// Panics if `p` is not in the `(0, 1)` range or if `df` is less than one.
+pub fn quantile(p: f64, df: f64) -> f64 {
+    assert!(p > 0. && p < 1.);
+    assert!(df >= 1.);
+
+    // The distribution is symmetric, so only the upper tail is computed, from the probability of
+    // falling outside of `[-t, t]`
+    let two_tailed = 2. * p.min(1. - p);
+    let t = upper(two_tailed, df);
+
+    if p < 0.5 {
+        -t
+    } else {
+        t
+    }
+}
+
+// The `t` for which a t-distributed variable falls outside of `[-t, t]` with probability `p`
+fn upper(p: f64, n: f64) -> f64 {
+    if n == 1. {
+        return (p * PI / 2.).tan().recip();
+    }
+    if n == 2. {
+        return (2. / (p * (2. - p)) - 2.).sqrt();
+    }
+
+    let a = 1. / (n - 0.5);
+    let b = 48. / (a * a);
+    let mut c = ((20700. * a / b - 98.) * a - 16.) * a + 96.36;
+    let d = ((94.5 / (b + c) - 3.) / b + 1.) * (a * PI / 2.).sqrt() * n;
+    let y = (d * p).powf(2. / n);
+
+    let y = if y > 0.05 + a {
+        // Asymptotic expansion about the normal distribution
+        let x = normal_quantile(0.5 * p);
+        let y = x * x;
+        if n < 5. {
+            c += 0.3 * (n - 4.5) * (x + 0.6);
+        }
+        c += (((0.05 * d * x - 5.) * x - 7.) * x - 2.) * x + b;
+        let y = (((((0.4 * y + 6.3) * y + 36.) * y + 94.5) / c - y - 3.) / b + 1.) * x;
+        (a * y * y).exp_m1()
+    } else {
+        ((1. / (((n + 6.) / (n * y) - 0.089 * d - 0.822) * (n + 2.) * 3.) + 0.5 / (n + 4.)) * y
+            - 1.) * (n + 1.) / (n + 2.) + 1. / y
+    };
+
+    (n * y).sqrt()
+}
+
+// The `p`-quantile of the standard normal distribution, using Acklam's rational approximation,
+// which has a relative error below 1.2e-9
+fn normal_quantile(p: f64) -> f64 {
+    const A: [f64; 6] = [
+        -3.969683028665376e+01,
+        2.209460984245205e+02,
+        -2.759285104469687e+02,
+        1.38357751867269e+02,
+        -3.066479806614716e+01,
+        2.506628277459239e+00,
+    ];
+    const B: [f64; 5] = [
+        -5.447609879822406e+01,
+        1.615858368580409e+02,
+        -1.556989798598866e+02,
+        6.680131188771972e+01,
+        -1.328068155288572e+01,
+    ];
+    const C: [f64; 6] = [
+        -7.784894002430293e-03,
+        -3.223964580411365e-01,
+        -2.400758277161838e+00,
+        -2.549732539343734e+00,
+        4.374664141464968e+00,
+        2.938163982698783e+00,
+    ];
+    const D: [f64; 4] = [
+        7.784695709041462e-03,
+        3.224671290700398e-01,
+        2.445134137142996e+00,
+        3.754408661907416e+00,
+    ];
+    const P_LOW: f64 = 0.02425;
+
+    // The tails are approximated in terms of `sqrt(-2 ln(p))`, the center in terms of `p - 0.5`
+    let tail = |q: f64| {
+        (((((C[0] * q + C[1]) * q + C[2]) * q + C[3]) * q + C[4]) * q + C[5])
+            / ((((D[0] * q + D[1]) * q + D[2]) * q + D[3]) * q + 1.)
+    };
+
+    if p < P_LOW {
+        tail((-2. * p.ln()).sqrt())
+    } else if p <= 1. - P_LOW {
+        let q = p - 0.5;
+        let r = q * q;
+        (((((A[0] * r + A[1]) * r + A[2]) * r + A[3]) * r + A[4]) * r + A[5]) * q
+            / (((((B[0] * r + B[1]) * r + B[2]) * r + B[3]) * r + B[4]) * r + 1.)
+    } else {
+        -tail((-2. * (1. - p).ln()).sqrt())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{normal_quantile, quantile};
+
+    #[test]
+    fn normal_quantiles() {
+        assert!(relative_eq!(0., normal_quantile(0.5)));
+        assert!(relative_eq!(1.959964, normal_quantile(0.975), max_relative = 1e-6));
+        assert!(relative_eq!(-2.326348, normal_quantile(0.01), max_relative = 1e-6));
+        assert!(relative_eq!(3.090232, normal_quantile(0.999), max_relative = 1e-6));
+    }
+
+    // Two-sided critical values from the tables of the t-distribution
+    #[test]
+    fn t_quantiles() {
+        for &(p, df, expected) in &[
+            (0.975, 1., 12.7062),
+            (0.975, 2., 4.3027),
+            (0.975, 3., 3.1824),
+            (0.975, 5., 2.5706),
+            (0.975, 10., 2.2281),
+            (0.975, 30., 2.0423),
+            (0.975, 1000., 1.9623),
+            (0.995, 3., 5.8409),
+            (0.995, 20., 2.8453),
+            (0.95, 4., 2.1318),
+            (0.75, 7., 0.7111),
+        ] {
+            let t = quantile(p, df);
+            assert!(
+                relative_eq!(expected, t, max_relative = 1e-4),
+                "t({}, {}) = {}, expected {}",
+                p,
+                df,
+                t,
+                expected
+            );
+            assert!(relative_eq!(-t, quantile(1. - p, df)));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn t_quantile_of_certainty() {
+        quantile(1., 10.);
+    }
+}
@@ -4,15 +4,21 @@ extern crate serde_json;
 extern crate tempdir;
 extern crate walkdir;
 
-use criterion::{Benchmark, Criterion, Fun, ParameterizedBenchmark, Throughput};
+use criterion::{
+    Benchmark, BenchmarkChange, BenchmarkResult, Criterion, FilterMode, Fun,
+    ParameterizedBenchmark, Reporter, Throughput,
+};
 use serde_json::value::Value;
 use std::cell::RefCell;
 use std::cmp::max;
-use std::fs::File;
+use std::fs::{self, File};
 use std::path::PathBuf;
 use std::process::{Command, Stdio};
 use std::rc::Rc;
-use std::time::{Duration, SystemTime};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 use tempdir::TempDir;
 use walkdir::WalkDir;
 
@@ -203,6 +209,27 @@ fn test_compare_baseline() {
         .bench_function("test_compare_baseline", |b| b.iter(|| 10));
 }
 
+#[test]
+fn test_run_label() {
+    let dir = temp_dir();
+    let run = |mut criterion: Criterion| {
+        criterion.bench_function("test_run_label", |b| b.iter(|| 10));
+    };
+    run(short_benchmark(&dir));
+    run(short_benchmark(&dir).run_label("turbo-off"));
+
+    // Labeled runs don't overwrite the results of other runs.
+    let dir = dir.path().join("test_run_label");
+    let label = |path: &str| {
+        let file = File::open(verify_file(&dir, path)).unwrap();
+        let result: Value = serde_json::from_reader(file).unwrap();
+        result["run_label"].as_str().map(str::to_owned)
+    };
+    assert_eq!(None, label("new/result.json"));
+    assert_eq!(Some("turbo-off".to_owned()), label("new/result.turbo-off.json"));
+    assert_eq!(Some("turbo-off".to_owned()), label("base/result.turbo-off.json"));
+}
+
 #[test]
 fn test_sample_size() {
     let dir = temp_dir();
@@ -282,6 +309,163 @@ fn test_bench_functions() {
     short_benchmark(&dir).bench_functions("test_bench_functions", functions, 20);
 }
 
+#[test]
+fn test_bench_paired() {
+    let dir = temp_dir();
+    let function_1 = Fun::new("times 10", |b, i| b.iter(|| *i * 10));
+    let function_2 = Fun::new("times 20", |b, i| b.iter(|| *i * 20));
+
+    short_benchmark(&dir).bench_paired("test_bench_paired", function_1, function_2, 20);
+}
+
+#[test]
+fn test_run_benchmark() {
+    let dir = temp_dir();
+    let result = short_benchmark(&dir)
+        .run_benchmark("sleep", |b| b.iter(|| thread::sleep(Duration::from_millis(1))))
+        .unwrap();
+
+    assert_eq!("sleep", result.id);
+    assert_eq!(100, result.iteration_count.len());
+    assert_eq!(100, result.sample_times.len());
+    for estimate in &[result.mean, result.median, result.slope] {
+        // Sleeping takes at least as long as requested, and not orders of magnitude longer.
+        assert!(estimate.point_estimate >= 1e6);
+        assert!(estimate.point_estimate < 1e8);
+        assert!(estimate.lower_bound <= estimate.point_estimate);
+        assert!(estimate.point_estimate <= estimate.upper_bound);
+    }
+    // Nothing is saved.
+    assert!(!dir.path().join("sleep").exists());
+}
+
+// Records the events it receives, in order.
+struct RecordingReporter {
+    events: Rc<RefCell<Vec<String>>>,
+}
+impl Reporter for RecordingReporter {
+    fn benchmark_start(&self, id: &str) {
+        self.events.borrow_mut().push(format!("start {}", id));
+    }
+
+    fn measurement_complete(&self, result: &BenchmarkResult) {
+        assert!(result.mean.lower_bound <= result.mean.upper_bound);
+        self.events.borrow_mut().push(format!("measured {}", result.id));
+    }
+
+    fn comparison_complete(&self, change: &BenchmarkChange) {
+        assert!(change.p_value >= 0. && change.p_value <= 1.);
+        self.events.borrow_mut().push(format!("compared {}", change.id));
+    }
+
+    fn run_complete(&self) {
+        self.events.borrow_mut().push("complete".to_owned());
+    }
+}
+
+#[test]
+fn test_custom_reporter() {
+    let dir = temp_dir();
+    let events = Rc::new(RefCell::new(vec![]));
+    let reporter = || {
+        Box::new(RecordingReporter {
+            events: events.clone(),
+        })
+    };
+
+    short_benchmark(&dir)
+        .add_reporter(reporter())
+        .add_reporter(reporter())
+        .bench_function("reported", |b| b.iter(|| 10))
+        .final_summary();
+    assert_eq!(
+        vec![
+            "start reported",
+            "start reported",
+            "measured reported",
+            "measured reported",
+            "complete",
+            "complete",
+        ],
+        *events.borrow()
+    );
+
+    // The second run is compared against the first.
+    events.borrow_mut().clear();
+    short_benchmark(&dir)
+        .add_reporter(reporter())
+        .bench_function("reported", |b| b.iter(|| 10))
+        .final_summary();
+    assert_eq!(
+        vec![
+            "start reported",
+            "measured reported",
+            "compared reported",
+            "complete",
+        ],
+        *events.borrow()
+    );
+}
+
+#[test]
+fn test_run_complete_once_per_run() {
+    let dir = temp_dir();
+    let events = Rc::new(RefCell::new(vec![]));
+
+    // Like `criterion_main!`, which runs each group with its own `Criterion`, and the final
+    // summary with yet another one.
+    for group in &["first", "second"] {
+        short_benchmark(&dir)
+            .add_reporter(Box::new(RecordingReporter {
+                events: events.clone(),
+            }))
+            .bench_function(group, |b| b.iter(|| 10));
+    }
+    short_benchmark(&dir).final_summary();
+    short_benchmark(&dir).final_summary();
+
+    // Each reporter is told once, after all groups have run.
+    assert_eq!(
+        vec![
+            "start first",
+            "measured first",
+            "start second",
+            "measured second",
+            "complete",
+            "complete",
+        ],
+        *events.borrow()
+    );
+}
+
+#[test]
+fn test_read_only_output_directory() {
+    let dir = temp_dir();
+    // Permissions don't stop root from writing, so the output directory is made unwritable by
+    // placing it below a regular file instead.
+    let file = dir.path().join("file");
+    File::create(&file).unwrap();
+    let events = Rc::new(RefCell::new(vec![]));
+
+    short_benchmark(&dir)
+        .output_directory(&file.join("criterion"))
+        .add_reporter(Box::new(RecordingReporter {
+            events: events.clone(),
+        }))
+        .bench_function("read_only", |b| b.iter(|| 10))
+        .final_summary();
+
+    assert_eq!(
+        vec!["start read_only", "measured read_only", "complete"],
+        *events.borrow()
+    );
+    let entries: Vec<PathBuf> = WalkDir::new(dir.path())
+        .into_iter()
+        .map(|entry| entry.unwrap().path().to_owned())
+        .collect();
+    assert_eq!(vec![dir.path().to_owned(), file], entries);
+}
+
 #[test]
 fn test_bench_function_over_inputs() {
     let dir = temp_dir();
@@ -332,6 +516,65 @@ fn test_bench_unparameterized() {
     short_benchmark(&dir).bench("test_bench_unparam", benchmark);
 }
 
+#[test]
+fn test_bench_with_setup() {
+    let dir = temp_dir();
+    let setups = Counter::default();
+    let teardowns = Counter::default();
+    let (setup_clone, teardown_clone) = (setups.clone(), teardowns.clone());
+    let iterations = Rc::new(RefCell::new(0u64));
+    let iterations_clone = iterations.clone();
+
+    let benchmark = Benchmark::new("return 10", |b| b.iter(|| 10)).with_setup_and_teardown(
+        "context",
+        move || {
+            setup_clone.count();
+            vec![1u64; 16]
+        },
+        |b, context: &mut Vec<u64>| {
+            b.iter(|| {
+                context[0] += 1;
+                context.iter().sum::<u64>()
+            })
+        },
+        move |context| {
+            teardown_clone.count();
+            *iterations_clone.borrow_mut() = context[0] - 1;
+        },
+    );
+    short_benchmark(&dir).bench("test_bench_with_setup", benchmark);
+
+    assert_eq!(1, setups.read());
+    assert_eq!(1, teardowns.read());
+    // Every iteration of the warm-up and the measurement used the same context.
+    assert!(*iterations.borrow() > 1000);
+}
+
+#[test]
+fn test_bench_with_control_variate() {
+    let dir = temp_dir();
+    let controls = Counter::default();
+    let control_clone = controls.clone();
+
+    let benchmark = Benchmark::new("return 10", |b| b.iter(|| 10)).with_control_variate(
+        "controlled",
+        |b| b.iter(|| (0..100u64).sum::<u64>()),
+        move |b| {
+            control_clone.count();
+            b.iter(|| 10)
+        },
+        1.0,
+    );
+    short_benchmark(&dir).bench("test_bench_with_control_variate", benchmark);
+
+    // The control runs once per sample of the controlled function, but not while warming up.
+    assert_eq!(100, controls.read());
+    verify_stats(
+        &dir.path().join("test_bench_with_control_variate/controlled"),
+        "base",
+    );
+}
+
 #[test]
 fn test_bench_parameterized() {
     let dir = temp_dir();
@@ -349,6 +592,141 @@ fn test_bench_parameterized() {
     short_benchmark(&dir).bench("test_bench_param", benchmark);
 }
 
+#[test]
+fn test_bench_product() {
+    let dir = temp_dir();
+    let events = Rc::new(RefCell::new(vec![]));
+    let benchmark = ParameterizedBenchmark::new_product(
+        "product",
+        |b, &(x, y)| b.iter(|| x * y),
+        vec![1, 2, 3],
+        vec![10, 20],
+    );
+
+    short_benchmark(&dir)
+        .warm_up_time(Duration::from_millis(10))
+        .measurement_time(Duration::from_millis(50))
+        .add_reporter(Box::new(RecordingReporter {
+            events: events.clone(),
+        }))
+        .bench("test_bench_product", benchmark);
+
+    let started: Vec<String> = events
+        .borrow()
+        .iter()
+        .filter(|event| event.starts_with("start "))
+        .cloned()
+        .collect();
+    assert_eq!(
+        vec![
+            "start test_bench_product/product/1/10",
+            "start test_bench_product/product/1/20",
+            "start test_bench_product/product/2/10",
+            "start test_bench_product/product/2/20",
+            "start test_bench_product/product/3/10",
+            "start test_bench_product/product/3/20",
+        ],
+        started
+    );
+    verify_stats(&dir.path().join("test_bench_product/product/2_20"), "new");
+}
+
+#[test]
+fn test_bench_threads() {
+    let dir = temp_dir();
+    let runs = Arc::new(AtomicUsize::new(0));
+    let task_runs = runs.clone();
+
+    short_benchmark(&dir)
+        .warm_up_time(Duration::from_millis(10))
+        .measurement_time(Duration::from_millis(50))
+        .bench_threads("test_bench_threads", &[1, 2, 4], move || {
+            let runs = task_runs.clone();
+            move || {
+                runs.fetch_add(1, Ordering::Relaxed);
+            }
+        });
+
+    assert!(runs.load(Ordering::Relaxed) > 0);
+    for threads in &["1", "2", "4"] {
+        let path = dir.path().join("test_bench_threads").join(threads);
+        verify_stats(&path, "new");
+        let f = File::open(path.join("new/benchmark.json")).unwrap();
+        let id: Value = serde_json::from_reader(f).unwrap();
+        assert_eq!(
+            Some(threads.parse().unwrap()),
+            id["throughput"]["Elements"].as_u64()
+        );
+    }
+}
+
+#[test]
+fn test_shuffle_inputs() {
+    let dir = temp_dir();
+    let calls = Rc::new(RefCell::new(vec![]));
+    let recorded = calls.clone();
+    let benchmark = ParameterizedBenchmark::new(
+        "shuffled",
+        move |b, i| {
+            recorded.borrow_mut().push(*i);
+            b.iter(|| *i * 10)
+        },
+        vec![1, 2, 3],
+    ).sample_size(10);
+
+    short_benchmark(&dir)
+        .warm_up_time(Duration::from_millis(10))
+        .measurement_time(Duration::from_millis(50))
+        .shuffle_inputs(true)
+        .bench("test_shuffle_inputs", benchmark);
+
+    // After the warm-up of each input, the samples take turns, one of each input at a time.
+    let calls = calls.borrow();
+    let (warm_up, samples) = calls.split_at(calls.len() - 30);
+    assert!(warm_up.windows(2).all(|pair| pair[0] <= pair[1]));
+    let rounds: Vec<&[u64]> = samples.chunks(3).collect();
+    assert_eq!(10, rounds.len());
+    for round in &rounds {
+        let mut inputs = round.to_vec();
+        inputs.sort();
+        assert_eq!(vec![1, 2, 3], inputs);
+    }
+    assert!(rounds.iter().any(|&round| round != rounds[0]));
+
+    for input in &["1", "2", "3"] {
+        verify_stats(&dir.path().join("test_shuffle_inputs/shuffled").join(input), "new");
+    }
+}
+
+#[test]
+fn test_timeout() {
+    let dir = temp_dir();
+    let events = Rc::new(RefCell::new(vec![]));
+    let benchmark = Benchmark::new("slow", |b| b.iter(|| thread::sleep(Duration::from_millis(2))))
+        .with_function("fast", |b| b.iter(|| 10))
+        .timeout(Duration::from_millis(1));
+
+    short_benchmark(&dir)
+        .warm_up_time(Duration::from_millis(10))
+        .measurement_time(Duration::from_millis(50))
+        .add_reporter(Box::new(RecordingReporter {
+            events: events.clone(),
+        }))
+        .bench("test_timeout", benchmark);
+
+    // The slow benchmark fails without a result, and the next one still runs.
+    assert_eq!(
+        vec![
+            "start test_timeout/slow",
+            "start test_timeout/fast",
+            "measured test_timeout/fast",
+        ],
+        *events.borrow()
+    );
+    verify_not_exists(&dir.path().join("test_timeout/slow"), "new/estimates.json");
+    verify_stats(&dir.path().join("test_timeout/fast"), "new");
+}
+
 #[test]
 fn test_filtering() {
     let dir = temp_dir();
@@ -363,6 +741,56 @@ fn test_filtering() {
     assert!(!dir.path().join("test_filtering").is_dir());
 }
 
+#[test]
+fn test_exclude_filtering() {
+    let dir = temp_dir();
+    let included = Counter::default();
+    let excluded = Counter::default();
+    let clone_1 = included.clone();
+    let clone_2 = included.clone();
+    let clone_3 = excluded.clone();
+
+    short_benchmark(&dir)
+        .with_filter("skipped")
+        .filter_mode(FilterMode::Exclude)
+        .bench(
+            "test_exclude_filtering",
+            Benchmark::new("run_1", move |b| b.iter(|| clone_1.count()))
+                .with_function("run_2", move |b| b.iter(|| clone_2.count()))
+                .with_function("skipped", move |b| b.iter(|| clone_3.count())),
+        );
+
+    assert!(included.read() > 0);
+    assert_eq!(excluded.read(), 0);
+    let dir = dir.path().join("test_exclude_filtering");
+    assert!(dir.join("run_1").is_dir());
+    assert!(dir.join("run_2").is_dir());
+    assert!(!dir.join("skipped").is_dir());
+}
+
+#[test]
+fn test_profile_time() {
+    let dir = temp_dir();
+    let counter = Counter::default();
+    let clone = counter.clone();
+
+    let start = Instant::now();
+    short_benchmark(&dir)
+        .profile_time(Some(Duration::from_millis(500)))
+        .bench_function("profile", move |b| {
+            clone.count();
+            b.iter(|| 10)
+        });
+    let elapsed = start.elapsed();
+
+    assert!(counter.read() > 0);
+    // The routine runs in doubling batches, so it may overshoot by up to one batch.
+    assert!(elapsed >= Duration::from_millis(500));
+    assert!(elapsed < Duration::from_millis(1500));
+    // No analysis means no estimates are saved.
+    assert!(!dir.path().join("profile").exists());
+}
+
 #[test]
 fn test_timing_loops() {
     let dir = temp_dir();
@@ -375,6 +803,19 @@ fn test_timing_loops() {
             .with_function("iter_with_large_setup", |b| {
                 b.iter_with_large_setup(|| vec![10], |v| v[0])
             })
+            .with_function("iter_with_preamble", |b| {
+                let mut v = vec![10];
+                b.iter_with_preamble(|| v.push(10), || 10)
+            })
+            .with_function("iter_batched_ref", |b| {
+                b.iter_batched_ref(|| vec![10], |v| v.push(20), 10)
+            })
+            .with_function("iter_amortized", |b| {
+                b.iter_amortized(|| vec![10], |v| v.push(20), 10)
+            })
+            .with_function("iter_counted", |b| {
+                b.iter_counted(|| (0..10).filter(|i| i % 3 == 0).count() as u64)
+            })
             .with_function("iter_with_large_drop", |b| {
                 b.iter_with_large_drop(|| vec![10; 100])
             }),
@@ -464,6 +905,25 @@ fn test_output_files() {
     }
 }
 
+#[test]
+fn test_related_groups_comparison() {
+    let tempdir = temp_dir();
+    for group in &["related/small", "related/large"] {
+        short_benchmark(&tempdir)
+            .warm_up_time(Duration::from_millis(10))
+            .measurement_time(Duration::from_millis(50))
+            .bench_function_over_inputs(group, |b, &&x| b.iter(|| x * 2), &[1, 2]);
+    }
+    short_benchmark(&tempdir).final_summary();
+
+    if short_benchmark(&tempdir).can_plot() && cfg!(feature = "html_reports") {
+        let dir = tempdir.path().to_owned();
+        verify_svg(&dir, "report/comparisons/related.svg");
+        let index = fs::read_to_string(dir.join("report/index.html")).unwrap();
+        assert!(index.contains("<a href=\"comparisons/related.svg\">related</a>"));
+    }
+}
+
 #[test]
 #[should_panic(expected = "Benchmark function must call Bencher::iter or related method.")]
 fn test_bench_with_no_iteration_panics() {